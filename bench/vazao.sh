@@ -0,0 +1,89 @@
+#!/bin/sh
+# vazao.sh -- Uma parte de Minerva.Lite
+# Copyright (C) 2022 Lucas S. Vieira
+#
+# This program is free software: you can redistribute it and/or modify
+# it under the terms of the GNU General Public License as published by
+# the Free Software Foundation, either version 3 of the License, or
+# (at your option) any later version.
+#
+# This program is distributed in the hope that it will be useful,
+# but WITHOUT ANY WARRANTY; without even the implied warranty of
+# MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+# GNU General Public License for more details.
+#
+# You should have received a copy of the GNU General Public License
+# along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+# Mede a vazão do servidor antes e depois da execução das consultas na pool
+# de threads bloqueantes do Tokio. Cada servidor é compilado em modo release
+# a partir de sua revisão, e recebe `liteclient 50 10` (50 conexões
+# simultâneas, 10 lotes de clientes por conexão) EXECUCOES vezes, com a
+# tabela de clientes esvaziada antes de cada execução. O mesmo cliente, da
+# revisão DEPOIS, é usado contra os dois servidores.
+#
+# Uso: BENCH_DATABASE_URL=postgres://... bench/vazao.sh
+#
+# O banco informado é APAGADO e recriado com o esquema da época das
+# revisões medidas. A saída completa de cada execução é impressa.
+
+set -eu
+
+BANCO=${BENCH_DATABASE_URL:?defina BENCH_DATABASE_URL com um banco descartável}
+ANTES=${ANTES:-ef7a887~1}
+DEPOIS=${DEPOIS:-ef7a887}
+EXECUCOES=${EXECUCOES:-3}
+PORTA=${PORTA:-50151}
+
+raiz=$(git rev-parse --show-toplevel)
+dir=$(mktemp -d)
+servidor=
+
+encerra() {
+    [ -n "$servidor" ] && kill "$servidor" 2>/dev/null || true
+    for rev in antes depois; do
+        git -C "$raiz" worktree remove --force "$dir/$rev" 2>/dev/null || true
+    done
+    rm -rf "$dir"
+}
+trap encerra EXIT
+
+compila() {
+    git -C "$raiz" worktree add --quiet --detach "$dir/$1" "$2"
+    cp "$raiz/Cargo.lock" "$dir/$1/" 2>/dev/null || true
+    (cd "$dir/$1" && cargo build --quiet --release --bins)
+}
+
+echo "# Revisões: antes=$(git rev-parse --short "$ANTES") depois=$(git rev-parse --short "$DEPOIS")"
+echo "# Máquina: $(uname -srm), $(nproc) núcleo(s)"
+grep -m1 "model name" /proc/cpuinfo 2>/dev/null || true
+rustc --version
+psql "$BANCO" -Atc "SELECT version()"
+
+compila antes "$ANTES"
+compila depois "$DEPOIS"
+
+psql "$BANCO" -q -c "DROP SCHEMA public CASCADE; CREATE SCHEMA public;"
+for m in 00000000000000_diesel_initial_setup 2022-03-01-000005_cria_cliente; do
+    psql "$BANCO" -q -f "$raiz/migrations/$m/up.sql"
+done
+
+for rev in antes depois; do
+    echo
+    echo "## Servidor: $rev"
+    (cd "$dir" && exec env DATABASE_URL="$BANCO" GRPC_PORT="$PORTA" \
+        "$dir/$rev/target/release/liteserver" >/dev/null 2>&1) &
+    servidor=$!
+    sleep 2
+    i=1
+    while [ "$i" -le "$EXECUCOES" ]; do
+        psql "$BANCO" -q -c "TRUNCATE cliente RESTART IDENTITY CASCADE"
+        echo "### Execução $i"
+        (cd "$dir" && GRPC_PORT=$PORTA \
+            "$dir/depois/target/release/liteclient" 50 10 </dev/null | tail -n 2)
+        i=$((i + 1))
+    done
+    kill "$servidor"
+    wait "$servidor" 2>/dev/null || true
+    servidor=
+done
@@ -14,12 +14,400 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use prost::Message;
+use prost_types::field_descriptor_proto::{Label, Type};
+use prost_types::{DescriptorProto, FieldDescriptorProto, FileDescriptorProto, FileDescriptorSet};
+use serde_json::{json, Map, Value};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// Rota do gateway REST, associada a um método de um serviço gRPC.
+struct Rota {
+    servico: &'static str,
+    metodo: &'static str,
+    verbo: &'static str,
+    caminho: &'static str,
+    /// Status HTTP retornado em caso de sucesso.
+    sucesso: &'static str,
+    /// Parâmetros de consulta que não fazem parte da mensagem de requisição.
+    consulta: &'static [(&'static str, &'static str)],
+}
+
+/// Rotas expostas pelo gateway REST (veja `src/service/rest.rs`). Os tipos e
+/// as descrições de cada rota são obtidos a partir do protobuf.
+const ROTAS: &[Rota] = &[
+    Rota {
+        servico: "MinervaClientes",
+        metodo: "Cadastra",
+        verbo: "post",
+        caminho: "/clientes",
+        sucesso: "201",
+        consulta: &[],
+    },
+    Rota {
+        servico: "MinervaClientes",
+        metodo: "Lista",
+        verbo: "get",
+        caminho: "/clientes",
+        sucesso: "200",
+        consulta: &[("pagina", "Número da página, a partir de 0.")],
+    },
+    Rota {
+        servico: "MinervaClientes",
+        metodo: "Consulta",
+        verbo: "get",
+        caminho: "/clientes/{id}",
+        sucesso: "200",
+        consulta: &[],
+    },
+    Rota {
+        servico: "MinervaClientes",
+        metodo: "Deleta",
+        verbo: "delete",
+        caminho: "/clientes/{id}",
+        sucesso: "204",
+        consulta: &[],
+    },
+];
+
 fn main() {
     let protobuf_file = "./proto/minerva.proto";
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+    let descriptor_path = out_dir.join("minerva_descriptor.bin");
 
     println!("cargo:rerun-if-changed={}", protobuf_file);
 
+    // O protobuf é compilado uma primeira vez apenas para obter seu
+    // descritor, a partir do qual são definidos os atributos de serialização
+    // de cada campo.
+    let rascunho = out_dir.join("descritor");
+    fs::create_dir_all(&rascunho).expect("Impossível criar diretório temporário");
     tonic_build::configure()
+        .build_client(false)
+        .build_server(false)
+        .out_dir(&rascunho)
+        .file_descriptor_set_path(&descriptor_path)
+        .compile(&[protobuf_file], &["."])
+        .unwrap_or_else(|e| panic!("Falha ao compilar protobuf: {:?}", e));
+
+    let descriptor = fs::read(&descriptor_path).expect("Impossível ler descritor do protobuf");
+    let descriptor =
+        FileDescriptorSet::decode(&descriptor[..]).expect("Descritor do protobuf inválido");
+
+    // As mensagens podem ser serializadas em JSON (mapeamento do proto3)
+    // quando a biblioteca é compilada com o serde.
+    let mut config = tonic_build::configure();
+    config = config.type_attribute(
+        ".Minerva",
+        "#[cfg_attr(feature = \"serde\", derive(serde::Serialize, serde::Deserialize), \
+         serde(rename_all = \"camelCase\", default))]",
+    );
+    for (caminho, atributo) in atributos_json(&descriptor) {
+        config = config.field_attribute(
+            caminho,
+            format!("#[cfg_attr(feature = \"serde\", serde({}))]", atributo),
+        );
+    }
+    config
         .compile(&[protobuf_file], &["."])
         .unwrap_or_else(|e| panic!("Falha ao compilar protobuf: {:?}", e));
+
+    fs::write(
+        out_dir.join("metodos.rs"),
+        format!("&{:?}", metodos_grpc(&descriptor)),
+    )
+    .expect("Impossível gravar lista de métodos gRPC");
+
+    let openapi = gera_openapi(&descriptor);
+    fs::write(
+        out_dir.join("openapi.json"),
+        serde_json::to_string_pretty(&openapi).unwrap(),
+    )
+    .expect("Impossível gravar documento OpenAPI");
+}
+
+/// Define os atributos do serde necessários para que os campos das mensagens
+/// do pacote `Minerva` sigam o mapeamento JSON do proto3: inteiros de 64 bits
+/// são serializados como strings (veja `src/json.rs`), e campos opcionais
+/// ausentes são omitidos. Retorna o caminho de cada campo e seu atributo.
+fn atributos_json(descriptor: &FileDescriptorSet) -> Vec<(String, String)> {
+    let arquivo = descriptor
+        .file
+        .iter()
+        .find(|f| f.package() == "Minerva")
+        .expect("Pacote Minerva não encontrado no protobuf");
+
+    let mut atributos = vec![];
+    for mensagem in &arquivo.message_type {
+        for campo in &mensagem.field {
+            let repetido = campo.label() == Label::Repeated;
+            let opcional =
+                !repetido && (campo.proto3_optional() || campo.r#type() == Type::Message);
+            let int64 = matches!(campo.r#type(), Type::Int64 | Type::Sint64 | Type::Sfixed64);
+            if matches!(campo.r#type(), Type::Uint64 | Type::Fixed64) || (int64 && repetido) {
+                panic!(
+                    "Campo {}.{} sem mapeamento JSON",
+                    mensagem.name(),
+                    campo.name()
+                );
+            }
+
+            let atributo = match (int64, opcional) {
+                (true, false) => "with = \"crate::json::int64\"",
+                (true, true) => {
+                    "with = \"crate::json::int64_opcional\", \
+                     skip_serializing_if = \"Option::is_none\""
+                }
+                (false, true) => "skip_serializing_if = \"Option::is_none\"",
+                (false, false) => continue,
+            };
+            atributos.push((
+                format!(".Minerva.{}.{}", mensagem.name(), campo.name()),
+                atributo.to_string(),
+            ));
+        }
+    }
+    atributos
+}
+
+/// Retorna os caminhos HTTP/2 de todos os métodos gRPC do pacote `Minerva`,
+/// no formato `/Minerva.<Serviço>/<Método>` (veja `src/service/metrics.rs`).
+fn metodos_grpc(descriptor: &FileDescriptorSet) -> Vec<String> {
+    let arquivo = descriptor
+        .file
+        .iter()
+        .find(|f| f.package() == "Minerva")
+        .expect("Pacote Minerva não encontrado no protobuf");
+
+    arquivo
+        .service
+        .iter()
+        .flat_map(|servico| {
+            servico
+                .method
+                .iter()
+                .map(move |metodo| format!("/Minerva.{}/{}", servico.name(), metodo.name()))
+        })
+        .collect()
+}
+
+/// Gera o documento OpenAPI do gateway REST, a partir das mensagens e dos
+/// serviços do pacote `Minerva`.
+fn gera_openapi(descriptor: &FileDescriptorSet) -> Value {
+    let arquivo = descriptor
+        .file
+        .iter()
+        .find(|f| f.package() == "Minerva")
+        .expect("Pacote Minerva não encontrado no protobuf");
+
+    let mut schemas = Map::new();
+    for (i, mensagem) in arquivo.message_type.iter().enumerate() {
+        schemas.insert(
+            mensagem.name().to_string(),
+            esquema_mensagem(arquivo, i, mensagem),
+        );
+    }
+    for (i, enumeracao) in arquivo.enum_type.iter().enumerate() {
+        let mut esquema = json!({
+            "type": "string",
+            "enum": enumeracao.value.iter().map(|v| v.name()).collect::<Vec<_>>(),
+        });
+        descreve(&mut esquema, comentario(arquivo, &[5, i as i32]));
+        schemas.insert(enumeracao.name().to_string(), esquema);
+    }
+    schemas.insert(
+        "Status".to_string(),
+        json!({
+            "type": "object",
+            "description": "Erro retornado pelo servidor, no formato de google.rpc.Status.",
+            "properties": {
+                "code": { "type": "integer", "format": "int32", "description": "Código de status gRPC." },
+                "message": { "type": "string", "description": "Mensagem de erro." },
+            },
+        }),
+    );
+
+    let mut paths = Map::new();
+    for rota in ROTAS {
+        let operacao = operacao(arquivo, rota);
+        paths
+            .entry(rota.caminho)
+            .or_insert_with(|| json!({}))
+            .as_object_mut()
+            .unwrap()
+            .insert(rota.verbo.to_string(), operacao);
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Minerva.Lite",
+            "description": "Gateway REST/JSON para os serviços gRPC do Minerva.Lite.",
+            "version": env::var("CARGO_PKG_VERSION").unwrap(),
+        },
+        "paths": paths,
+        "components": { "schemas": schemas },
+    })
+}
+
+/// Gera a operação OpenAPI de uma rota do gateway.
+fn operacao(arquivo: &FileDescriptorProto, rota: &Rota) -> Value {
+    let (s, servico) = arquivo
+        .service
+        .iter()
+        .enumerate()
+        .find(|(_, s)| s.name() == rota.servico)
+        .unwrap_or_else(|| panic!("Serviço {} não encontrado", rota.servico));
+    let (m, metodo) = servico
+        .method
+        .iter()
+        .enumerate()
+        .find(|(_, m)| m.name() == rota.metodo)
+        .unwrap_or_else(|| panic!("Método {}.{} não encontrado", rota.servico, rota.metodo));
+
+    let entrada = mensagem(arquivo, metodo.input_type());
+    let saida = nome_tipo(metodo.output_type());
+
+    let mut parametros = vec![];
+    if let Some(entrada) = entrada {
+        for campo in &entrada.field {
+            if rota.caminho.contains(&format!("{{{}}}", campo.json_name())) {
+                parametros.push(json!({
+                    "name": campo.json_name(),
+                    "in": "path",
+                    "required": true,
+                    "schema": esquema_campo(campo),
+                }));
+            }
+        }
+    }
+    for (nome, descricao) in rota.consulta {
+        parametros.push(json!({
+            "name": nome,
+            "in": "query",
+            "required": false,
+            "description": descricao,
+            "schema": { "type": "integer", "format": "int64", "minimum": 0, "default": 0 },
+        }));
+    }
+
+    let sucesso = if saida == "Empty" {
+        json!({ "description": "Operação realizada com sucesso." })
+    } else {
+        json!({
+            "description": "Operação realizada com sucesso.",
+            "content": { "application/json": { "schema": referencia(saida) } },
+        })
+    };
+
+    let mut operacao = json!({
+        "operationId": rota.metodo,
+        "tags": [rota.servico],
+        "parameters": parametros,
+        "responses": {
+            rota.sucesso: sucesso,
+            "default": {
+                "description": "Erro.",
+                "content": { "application/json": { "schema": referencia("Status") } },
+            },
+        },
+    });
+
+    if rota.verbo == "post" {
+        if let Some(entrada) = entrada {
+            operacao["requestBody"] = json!({
+                "required": true,
+                "content": { "application/json": { "schema": referencia(entrada.name()) } },
+            });
+        }
+    }
+
+    descreve(
+        &mut operacao,
+        comentario(arquivo, &[6, s as i32, 2, m as i32]),
+    );
+    operacao
+}
+
+/// Gera o esquema de uma mensagem, cujo índice no arquivo é `i`.
+fn esquema_mensagem(arquivo: &FileDescriptorProto, i: usize, mensagem: &DescriptorProto) -> Value {
+    let mut propriedades = Map::new();
+    for (j, campo) in mensagem.field.iter().enumerate() {
+        let mut esquema = esquema_campo(campo);
+        descreve(&mut esquema, comentario(arquivo, &[4, i as i32, 2, j as i32]));
+        propriedades.insert(campo.json_name().to_string(), esquema);
+    }
+
+    let mut esquema = json!({ "type": "object", "properties": propriedades });
+    descreve(&mut esquema, comentario(arquivo, &[4, i as i32]));
+    esquema
+}
+
+/// Gera o esquema de um campo, de acordo com o mapeamento JSON do proto3.
+fn esquema_campo(campo: &FieldDescriptorProto) -> Value {
+    let esquema = match campo.r#type() {
+        Type::Int32 | Type::Sint32 | Type::Sfixed32 => json!({ "type": "integer", "format": "int32" }),
+        Type::Uint32 | Type::Fixed32 => json!({ "type": "integer", "format": "int64", "minimum": 0 }),
+        Type::Int64 | Type::Sint64 | Type::Sfixed64 => json!({ "type": "string", "format": "int64" }),
+        Type::Uint64 | Type::Fixed64 => json!({ "type": "string", "format": "uint64" }),
+        Type::Float => json!({ "type": "number", "format": "float" }),
+        Type::Double => json!({ "type": "number", "format": "double" }),
+        Type::Bool => json!({ "type": "boolean" }),
+        Type::String => json!({ "type": "string" }),
+        Type::Bytes => json!({ "type": "string", "format": "byte" }),
+        Type::Message | Type::Enum | Type::Group => match campo.type_name() {
+            ".google.protobuf.Timestamp" => json!({ "type": "string", "format": "date-time" }),
+            tipo => referencia(nome_tipo(tipo)),
+        },
+    };
+
+    if campo.label() == Label::Repeated {
+        json!({ "type": "array", "items": esquema })
+    } else {
+        esquema
+    }
+}
+
+/// Busca uma mensagem do arquivo através de seu nome completo.
+fn mensagem<'a>(arquivo: &'a FileDescriptorProto, tipo: &str) -> Option<&'a DescriptorProto> {
+    let nome = tipo.strip_prefix(".Minerva.")?;
+    arquivo.message_type.iter().find(|m| m.name() == nome)
+}
+
+/// Retorna o nome simples de um tipo, sem o pacote.
+fn nome_tipo(tipo: &str) -> &str {
+    tipo.rsplit('.').next().unwrap()
+}
+
+/// Gera uma referência para um esquema do documento.
+fn referencia(nome: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", nome) })
+}
+
+/// Retorna o comentário que precede um elemento do protobuf, identificado
+/// pelo seu caminho no descritor.
+fn comentario(arquivo: &FileDescriptorProto, caminho: &[i32]) -> Option<String> {
+    let local = arquivo
+        .source_code_info
+        .as_ref()?
+        .location
+        .iter()
+        .find(|l| l.path == caminho)?;
+
+    let texto = local
+        .leading_comments()
+        .lines()
+        .map(|l| l.trim_start_matches('/').trim())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let texto = texto.trim();
+    (!texto.is_empty()).then(|| texto.to_string())
+}
+
+/// Adiciona uma descrição a um esquema ou operação, caso exista.
+fn descreve(valor: &mut Value, descricao: Option<String>) {
+    if let Some(descricao) = descricao {
+        valor["description"] = Value::String(descricao);
+    }
 }
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS usuario (
+    id         SERIAL PRIMARY KEY,
+    login      VARCHAR NOT NULL UNIQUE,
+    nome       VARCHAR NOT NULL,
+    email      VARCHAR,
+    senha_hash BYTEA NOT NULL
+);
@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS produto (
+    id        SERIAL PRIMARY KEY,
+    descricao VARCHAR NOT NULL,
+    unidsaida VARCHAR NOT NULL
+);
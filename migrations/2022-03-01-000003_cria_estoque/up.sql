@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS estoque (
+    produto_id    INTEGER PRIMARY KEY REFERENCES produto (id) ON DELETE CASCADE,
+    quantidade    NUMERIC NOT NULL DEFAULT 0,
+    precounitario NUMERIC NOT NULL DEFAULT 0
+);
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS mov_estoque (
+    id             SERIAL PRIMARY KEY,
+    produto_id     INTEGER NOT NULL REFERENCES produto (id) ON DELETE CASCADE,
+    docto          VARCHAR NOT NULL,
+    quantidade     NUMERIC NOT NULL,
+    preco_frete    NUMERIC NOT NULL DEFAULT 0,
+    datahora       TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    preco_unitario NUMERIC NOT NULL
+);
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS cliente (
+    id        SERIAL PRIMARY KEY,
+    tipo      SMALLINT NOT NULL DEFAULT 0,
+    nome      VARCHAR NOT NULL,
+    pj        BOOLEAN NOT NULL DEFAULT FALSE,
+    docto     VARCHAR NOT NULL,
+    ativo     BOOLEAN NOT NULL DEFAULT TRUE,
+    bloqueado BOOLEAN NOT NULL DEFAULT FALSE
+);
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS endereco (
+    id          SERIAL PRIMARY KEY,
+    cliente_id  INTEGER NOT NULL REFERENCES cliente (id) ON DELETE CASCADE,
+    tipo        SMALLINT NOT NULL DEFAULT 0,
+    logradouro  VARCHAR NOT NULL,
+    numero      VARCHAR NOT NULL,
+    complemento VARCHAR,
+    bairro      VARCHAR NOT NULL,
+    uf          VARCHAR NOT NULL,
+    cidade      VARCHAR NOT NULL
+);
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS logdb (
+    id        SERIAL PRIMARY KEY,
+    tabela    VARCHAR NOT NULL,
+    usuario   VARCHAR NOT NULL,
+    operacao  SMALLINT NOT NULL,
+    datahora  TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    descricao VARCHAR
+);
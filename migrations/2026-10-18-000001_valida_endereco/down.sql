@@ -0,0 +1,8 @@
+DROP INDEX IF EXISTS endereco_principal_idx;
+
+ALTER TABLE endereco
+    DROP CONSTRAINT IF EXISTS endereco_tipo_fkey,
+    DROP COLUMN IF EXISTS principal,
+    DROP COLUMN IF EXISTS cep;
+
+DROP TABLE IF EXISTS tipo_endereco;
@@ -0,0 +1,26 @@
+CREATE TABLE IF NOT EXISTS tipo_endereco (
+    id        SMALLINT PRIMARY KEY,
+    descricao VARCHAR NOT NULL UNIQUE
+);
+
+INSERT INTO tipo_endereco (id, descricao) VALUES
+    (0, 'Residencial'),
+    (1, 'Comercial'),
+    (2, 'Cobrança'),
+    (3, 'Entrega');
+
+-- Endereços existentes com tipos fora do catálogo recebem tipos provisórios,
+-- que podem ser renomeados posteriormente, para que a chave estrangeira possa
+-- ser criada.
+INSERT INTO tipo_endereco (id, descricao)
+    SELECT DISTINCT tipo, 'Tipo ' || tipo FROM endereco
+    WHERE tipo NOT IN (SELECT id FROM tipo_endereco);
+
+ALTER TABLE endereco
+    ADD COLUMN cep       VARCHAR,
+    ADD COLUMN principal BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD CONSTRAINT endereco_tipo_fkey
+        FOREIGN KEY (tipo) REFERENCES tipo_endereco (id);
+
+CREATE UNIQUE INDEX endereco_principal_idx
+    ON endereco (cliente_id, tipo) WHERE principal;
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS cep (
+    codigo     VARCHAR PRIMARY KEY,
+    logradouro VARCHAR NOT NULL DEFAULT '',
+    bairro     VARCHAR NOT NULL DEFAULT '',
+    cidade     VARCHAR NOT NULL,
+    uf         VARCHAR NOT NULL
+);
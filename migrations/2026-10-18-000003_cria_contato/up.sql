@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS contato (
+    id         SERIAL PRIMARY KEY,
+    cliente_id INTEGER NOT NULL REFERENCES cliente (id) ON DELETE CASCADE,
+    tipo       SMALLINT NOT NULL,
+    valor      VARCHAR NOT NULL,
+    principal  BOOLEAN NOT NULL DEFAULT FALSE,
+    observacao VARCHAR
+);
+
+CREATE INDEX contato_cliente_idx ON contato (cliente_id);
+
+CREATE UNIQUE INDEX contato_principal_idx
+    ON contato (cliente_id, tipo) WHERE principal;
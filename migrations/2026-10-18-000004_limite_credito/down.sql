@@ -0,0 +1,4 @@
+ALTER TABLE cliente
+    DROP COLUMN IF EXISTS bloqueio_automatico,
+    DROP COLUMN IF EXISTS saldo_devedor,
+    DROP COLUMN IF EXISTS limite_credito;
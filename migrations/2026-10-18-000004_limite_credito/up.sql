@@ -0,0 +1,5 @@
+-- Valores monetários são armazenados em centavos.
+ALTER TABLE cliente
+    ADD COLUMN limite_credito      BIGINT,
+    ADD COLUMN saldo_devedor       BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN bloqueio_automatico BOOLEAN NOT NULL DEFAULT FALSE;
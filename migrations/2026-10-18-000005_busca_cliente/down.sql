@@ -0,0 +1,4 @@
+-- As extensões são mantidas, já que podem ser usadas por outros objetos do
+-- banco de dados.
+DROP INDEX IF EXISTS cliente_nome_trgm_idx;
+DROP FUNCTION IF EXISTS f_unaccent(TEXT);
@@ -0,0 +1,11 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+CREATE EXTENSION IF NOT EXISTS unaccent;
+
+-- A função unaccent não é imutável, já que seu dicionário pode ser alterado,
+-- e portanto não pode ser usada em índices. Esta versão fixa o dicionário.
+CREATE OR REPLACE FUNCTION f_unaccent(TEXT) RETURNS TEXT AS $$
+    SELECT public.unaccent('public.unaccent', $1)
+$$ LANGUAGE SQL IMMUTABLE PARALLEL SAFE STRICT;
+
+CREATE INDEX cliente_nome_trgm_idx
+    ON cliente USING GIN (f_unaccent(lower(nome)) gin_trgm_ops);
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS logdb_registro_idx;
+ALTER TABLE logdb DROP COLUMN IF EXISTS registro;
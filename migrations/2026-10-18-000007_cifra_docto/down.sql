@@ -0,0 +1,4 @@
+-- Documentos cifrados devem ser decifrados com `liteserver recifra-doctos`
+-- antes da reversão, já que o índice cego deixa de existir.
+DROP INDEX IF EXISTS cliente_docto_indice_idx;
+ALTER TABLE cliente DROP COLUMN IF EXISTS docto_indice;
@@ -0,0 +1,7 @@
+-- O índice cego passa a ser calculado com uma chave própria, que não muda
+-- com a rotação das chaves de cifragem; os índices existentes, calculados
+-- com a chave de cifragem, são descartados, e devem ser recalculados com
+-- `liteserver recifra-doctos`. Documentos iguais passam a ser recusados.
+UPDATE cliente SET docto_indice = NULL;
+DROP INDEX IF EXISTS cliente_docto_indice_idx;
+CREATE UNIQUE INDEX cliente_docto_indice_idx ON cliente (docto_indice);
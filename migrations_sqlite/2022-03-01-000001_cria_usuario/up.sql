@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS usuario (
+    id         INTEGER PRIMARY KEY NOT NULL,
+    login      TEXT NOT NULL UNIQUE,
+    nome       TEXT NOT NULL,
+    email      TEXT,
+    senha_hash BLOB NOT NULL
+);
@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS produto (
+    id        INTEGER PRIMARY KEY NOT NULL,
+    descricao TEXT NOT NULL,
+    unidsaida TEXT NOT NULL
+);
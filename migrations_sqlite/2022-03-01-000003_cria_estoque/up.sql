@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS estoque (
+    produto_id    INTEGER PRIMARY KEY NOT NULL REFERENCES produto (id) ON DELETE CASCADE,
+    quantidade    REAL NOT NULL DEFAULT 0,
+    precounitario REAL NOT NULL DEFAULT 0
+);
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS mov_estoque (
+    id             INTEGER PRIMARY KEY NOT NULL,
+    produto_id     INTEGER NOT NULL REFERENCES produto (id) ON DELETE CASCADE,
+    docto          TEXT NOT NULL,
+    quantidade     REAL NOT NULL,
+    preco_frete    REAL NOT NULL DEFAULT 0,
+    datahora       TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    preco_unitario REAL NOT NULL
+);
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS cliente (
+    id        INTEGER PRIMARY KEY NOT NULL,
+    tipo      SMALLINT NOT NULL DEFAULT 0,
+    nome      TEXT NOT NULL,
+    pj        BOOLEAN NOT NULL DEFAULT FALSE,
+    docto     TEXT NOT NULL,
+    ativo     BOOLEAN NOT NULL DEFAULT TRUE,
+    bloqueado BOOLEAN NOT NULL DEFAULT FALSE
+);
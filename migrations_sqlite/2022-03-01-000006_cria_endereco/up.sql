@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS endereco (
+    id          INTEGER PRIMARY KEY NOT NULL,
+    cliente_id  INTEGER NOT NULL REFERENCES cliente (id) ON DELETE CASCADE,
+    tipo        SMALLINT NOT NULL DEFAULT 0,
+    logradouro  TEXT NOT NULL,
+    numero      TEXT NOT NULL,
+    complemento TEXT,
+    bairro      TEXT NOT NULL,
+    uf          TEXT NOT NULL,
+    cidade      TEXT NOT NULL
+);
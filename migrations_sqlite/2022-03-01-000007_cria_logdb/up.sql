@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS logdb (
+    id        INTEGER PRIMARY KEY NOT NULL,
+    tabela    TEXT NOT NULL,
+    usuario   TEXT NOT NULL,
+    operacao  SMALLINT NOT NULL,
+    datahora  TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    descricao TEXT
+);
@@ -0,0 +1,20 @@
+CREATE TABLE endereco_antigo (
+    id          INTEGER PRIMARY KEY NOT NULL,
+    cliente_id  INTEGER NOT NULL REFERENCES cliente (id) ON DELETE CASCADE,
+    tipo        SMALLINT NOT NULL DEFAULT 0,
+    logradouro  TEXT NOT NULL,
+    numero      TEXT NOT NULL,
+    complemento TEXT,
+    bairro      TEXT NOT NULL,
+    uf          TEXT NOT NULL,
+    cidade      TEXT NOT NULL
+);
+
+INSERT INTO endereco_antigo
+    SELECT id, cliente_id, tipo, logradouro, numero, complemento, bairro, uf, cidade
+    FROM endereco;
+
+DROP TABLE endereco;
+ALTER TABLE endereco_antigo RENAME TO endereco;
+
+DROP TABLE IF EXISTS tipo_endereco;
@@ -0,0 +1,44 @@
+CREATE TABLE IF NOT EXISTS tipo_endereco (
+    id        SMALLINT PRIMARY KEY NOT NULL,
+    descricao TEXT NOT NULL UNIQUE
+);
+
+INSERT INTO tipo_endereco (id, descricao) VALUES
+    (0, 'Residencial'),
+    (1, 'Comercial'),
+    (2, 'Cobrança'),
+    (3, 'Entrega');
+
+-- Endereços existentes com tipos fora do catálogo recebem tipos provisórios,
+-- que podem ser renomeados posteriormente, para que a chave estrangeira possa
+-- ser criada.
+INSERT INTO tipo_endereco (id, descricao)
+    SELECT DISTINCT tipo, 'Tipo ' || tipo FROM endereco
+    WHERE tipo NOT IN (SELECT id FROM tipo_endereco);
+
+-- O SQLite não permite adicionar chaves estrangeiras a uma tabela existente,
+-- e portanto a tabela é recriada.
+CREATE TABLE endereco_novo (
+    id          INTEGER PRIMARY KEY NOT NULL,
+    cliente_id  INTEGER NOT NULL REFERENCES cliente (id) ON DELETE CASCADE,
+    tipo        SMALLINT NOT NULL DEFAULT 0 REFERENCES tipo_endereco (id),
+    logradouro  TEXT NOT NULL,
+    numero      TEXT NOT NULL,
+    complemento TEXT,
+    bairro      TEXT NOT NULL,
+    uf          TEXT NOT NULL,
+    cidade      TEXT NOT NULL,
+    cep         TEXT,
+    principal   BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+INSERT INTO endereco_novo
+    (id, cliente_id, tipo, logradouro, numero, complemento, bairro, uf, cidade)
+    SELECT id, cliente_id, tipo, logradouro, numero, complemento, bairro, uf, cidade
+    FROM endereco;
+
+DROP TABLE endereco;
+ALTER TABLE endereco_novo RENAME TO endereco;
+
+CREATE UNIQUE INDEX endereco_principal_idx
+    ON endereco (cliente_id, tipo) WHERE principal;
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS cep (
+    codigo     TEXT PRIMARY KEY NOT NULL,
+    logradouro TEXT NOT NULL DEFAULT '',
+    bairro     TEXT NOT NULL DEFAULT '',
+    cidade     TEXT NOT NULL,
+    uf         TEXT NOT NULL
+);
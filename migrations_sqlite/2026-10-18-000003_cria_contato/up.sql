@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS contato (
+    id         INTEGER PRIMARY KEY NOT NULL,
+    cliente_id INTEGER NOT NULL REFERENCES cliente (id) ON DELETE CASCADE,
+    tipo       SMALLINT NOT NULL,
+    valor      TEXT NOT NULL,
+    principal  BOOLEAN NOT NULL DEFAULT FALSE,
+    observacao TEXT
+);
+
+CREATE INDEX contato_cliente_idx ON contato (cliente_id);
+
+CREATE UNIQUE INDEX contato_principal_idx
+    ON contato (cliente_id, tipo) WHERE principal;
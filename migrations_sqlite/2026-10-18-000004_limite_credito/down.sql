@@ -0,0 +1,5 @@
+-- A remoção de colunas requer o SQLite 3.35 ou superior. A tabela não é
+-- recriada, pois isso removeria os endereços e contatos dos clientes.
+ALTER TABLE cliente DROP COLUMN bloqueio_automatico;
+ALTER TABLE cliente DROP COLUMN saldo_devedor;
+ALTER TABLE cliente DROP COLUMN limite_credito;
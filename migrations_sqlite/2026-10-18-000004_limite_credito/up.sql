@@ -0,0 +1,4 @@
+-- Valores monetários são armazenados em centavos.
+ALTER TABLE cliente ADD COLUMN limite_credito BIGINT;
+ALTER TABLE cliente ADD COLUMN saldo_devedor BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE cliente ADD COLUMN bloqueio_automatico BOOLEAN NOT NULL DEFAULT FALSE;
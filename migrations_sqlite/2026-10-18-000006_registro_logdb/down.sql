@@ -0,0 +1,3 @@
+-- A remoção de colunas requer o SQLite 3.35 ou superior.
+DROP INDEX IF EXISTS logdb_registro_idx;
+ALTER TABLE logdb DROP COLUMN registro;
@@ -0,0 +1,4 @@
+-- Registro ao qual cada operação se refere, permitindo recuperar e anonimizar
+-- o histórico de um único cliente.
+ALTER TABLE logdb ADD COLUMN registro INTEGER;
+CREATE INDEX logdb_registro_idx ON logdb (tabela, registro);
@@ -0,0 +1,5 @@
+-- Documentos cifrados devem ser decifrados com `liteserver recifra-doctos`
+-- antes da reversão, já que o índice cego deixa de existir. A remoção de
+-- colunas requer o SQLite 3.35 ou superior.
+DROP INDEX IF EXISTS cliente_docto_indice_idx;
+ALTER TABLE cliente DROP COLUMN docto_indice;
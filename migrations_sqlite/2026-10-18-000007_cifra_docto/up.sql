@@ -0,0 +1,5 @@
+-- Índice cego do documento do cliente: um HMAC do documento normalizado,
+-- gravado quando os documentos são cifrados pela aplicação, que permite
+-- encontrar documentos iguais sem decifrá-los.
+ALTER TABLE cliente ADD COLUMN docto_indice VARCHAR;
+CREATE INDEX cliente_docto_indice_idx ON cliente (docto_indice);
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS cliente_docto_indice_idx;
+CREATE INDEX cliente_docto_indice_idx ON cliente (docto_indice);
@@ -0,0 +1,323 @@
+// cifra.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este módulo implementa a cifragem de dados sensíveis pela própria
+//! aplicação, antes de serem gravados no banco de dados.
+//!
+//! Os documentos dos clientes (CPF ou CNPJ) podem ser cifrados com
+//! AES-256-GCM, através de uma chave de 32 bytes definida, em base64, na
+//! variável `DOCTO_ENCRYPTION_KEY`. Junto com cada documento é gravado um
+//! índice cego: um HMAC-SHA256 do documento normalizado (veja
+//! [`normaliza_docto`]), que permite encontrar documentos iguais sem
+//! decifrá-los, e garante que um documento não seja cadastrado duas vezes.
+//!
+//! O índice cego é calculado com uma chave própria, definida na variável
+//! `DOCTO_INDEX_KEY`, que é obrigatória quando a cifragem está habilitada.
+//! Ao contrário das chaves de cifragem, esta chave nunca é trocada: assim, o
+//! índice de um documento é o mesmo independente da chave com que o mesmo foi
+//! cifrado, inclusive durante uma rotação de chaves.
+//!
+//! Valores cifrados possuem o prefixo [`PREFIXO`], seguido do identificador
+//! da chave usada. Valores sem o prefixo são lidos como estão, de forma que a
+//! cifragem possa ser habilitada em um banco de dados existente. Chaves
+//! anteriores podem ser informadas na variável `DOCTO_PREVIOUS_KEYS`,
+//! separadas por vírgulas, para que os valores cifrados com as mesmas
+//! continuem legíveis até que sejam recifrados com a chave atual (veja
+//! [`Cifra::recifra`]).
+
+use crate::model::duplicado::normaliza_docto;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+
+/// Prefixo dos valores cifrados, seguido do identificador da chave, de `:` e
+/// do nonce e do texto cifrado, em base64.
+pub const PREFIXO: &str = "cifra:v1:";
+
+/// Tamanho do nonce do AES-256-GCM, em bytes.
+const TAMANHO_NONCE: usize = 12;
+
+/// Erros de configuração ou de decifragem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErroCifra {
+    /// A chave informada não possui 32 bytes em base64.
+    ChaveInvalida,
+    /// O valor foi cifrado com uma chave que não foi configurada. Contém o
+    /// identificador da chave.
+    ChaveDesconhecida(String),
+    /// O valor cifrado está corrompido ou foi adulterado.
+    ValorInvalido,
+    /// A cifragem foi habilitada sem uma chave para o índice cego.
+    IndiceAusente,
+}
+
+impl fmt::Display for ErroCifra {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErroCifra::ChaveInvalida => {
+                write!(f, "Chave inválida: são esperados 32 bytes em base64")
+            }
+            ErroCifra::ChaveDesconhecida(id) => write!(f, "Chave {} não configurada", id),
+            ErroCifra::ValorInvalido => write!(f, "Valor cifrado inválido"),
+            ErroCifra::IndiceAusente => write!(
+                f,
+                "DOCTO_INDEX_KEY deve ser definida quando a cifragem está habilitada"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ErroCifra {}
+
+/// Calcula o HMAC-SHA256 de uma mensagem.
+fn hmac(chave: &[u8], mensagem: &[u8]) -> [u8; 32] {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(chave)
+        .expect("HMAC aceita chaves de qualquer tamanho");
+    mac.update(mensagem);
+    mac.finalize().into_bytes().into()
+}
+
+/// Codifica bytes em hexadecimal, com letras minúsculas.
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Lê 32 bytes codificados em base64.
+fn le_chave(s: &str) -> Result<[u8; 32], ErroCifra> {
+    let bytes = base64::decode(s.trim()).map_err(|_| ErroCifra::ChaveInvalida)?;
+    bytes.try_into().map_err(|_| ErroCifra::ChaveInvalida)
+}
+
+/// Chave de cifragem. O identificador da chave é derivado da mesma.
+#[derive(Clone)]
+pub struct Chave {
+    id: String,
+    aead: Aes256Gcm,
+}
+
+impl Chave {
+    /// Cria uma chave a partir de 32 bytes.
+    pub fn new(bytes: &[u8; 32]) -> Self {
+        Self {
+            id: hex(&hmac(bytes, b"minerva-lite:id")[..4]),
+            aead: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(bytes)),
+        }
+    }
+
+    /// Identificador da chave, gravado junto com os valores cifrados.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl FromStr for Chave {
+    type Err = ErroCifra;
+
+    /// Lê uma chave de 32 bytes codificada em base64.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::new(&le_chave(s)?))
+    }
+}
+
+// A chave em si nunca é mostrada.
+impl fmt::Debug for Chave {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Chave").field("id", &self.id).finish()
+    }
+}
+
+/// Chave do índice cego, independente das chaves de cifragem.
+#[derive(Clone)]
+pub struct ChaveIndice([u8; 32]);
+
+impl ChaveIndice {
+    /// Cria uma chave a partir de 32 bytes.
+    pub fn new(bytes: &[u8; 32]) -> Self {
+        Self(*bytes)
+    }
+}
+
+impl FromStr for ChaveIndice {
+    type Err = ErroCifra;
+
+    /// Lê uma chave de 32 bytes codificada em base64.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::new(&le_chave(s)?))
+    }
+}
+
+// A chave em si nunca é mostrada.
+impl fmt::Debug for ChaveIndice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ChaveIndice")
+    }
+}
+
+/// Configuração de cifragem dos dados sensíveis. Por padrão, nenhuma chave é
+/// definida, e os valores são gravados sem cifragem e sem índice cego.
+#[derive(Clone, Debug, Default)]
+pub struct Cifra {
+    atual: Option<Chave>,
+    antigas: Vec<Chave>,
+    indice: Option<ChaveIndice>,
+}
+
+impl Cifra {
+    /// Cria a configuração a partir da chave atual, usada para cifrar novos
+    /// valores, e de chaves anteriores, usadas apenas para decifrar.
+    pub fn new(atual: Option<Chave>, antigas: Vec<Chave>) -> Self {
+        Self {
+            atual,
+            antigas,
+            indice: None,
+        }
+    }
+
+    /// Define a chave do índice cego dos documentos.
+    pub fn com_indice(self, indice: ChaveIndice) -> Self {
+        Self {
+            indice: Some(indice),
+            ..self
+        }
+    }
+
+    /// Lê a configuração das variáveis `DOCTO_ENCRYPTION_KEY`,
+    /// `DOCTO_PREVIOUS_KEYS` e `DOCTO_INDEX_KEY`.
+    /// Retorna um erro caso alguma das chaves seja inválida, ou caso a
+    /// cifragem seja habilitada sem a chave do índice cego.
+    pub fn from_env() -> Result<Self, ErroCifra> {
+        let le = |var| match env::var(var) {
+            Ok(chave) if !chave.trim().is_empty() => Some(chave),
+            _ => None,
+        };
+        let atual = le("DOCTO_ENCRYPTION_KEY").map(|c| c.parse()).transpose()?;
+        let antigas = env::var("DOCTO_PREVIOUS_KEYS")
+            .unwrap_or_default()
+            .split(',')
+            .filter(|chave| !chave.trim().is_empty())
+            .map(str::parse)
+            .collect::<Result<_, _>>()?;
+        let cifra = Self::new(atual, antigas);
+        match le("DOCTO_INDEX_KEY") {
+            Some(indice) => Ok(cifra.com_indice(indice.parse()?)),
+            None if cifra.atual.is_some() => Err(ErroCifra::IndiceAusente),
+            None => Ok(cifra),
+        }
+    }
+
+    /// Retorna a chave atual, caso a cifragem esteja habilitada.
+    pub fn atual(&self) -> Option<&Chave> {
+        self.atual.as_ref()
+    }
+
+    /// Cifra um valor com a chave atual. Caso a cifragem não esteja
+    /// habilitada, ou o valor seja vazio, o mesmo é retornado sem alterações.
+    pub fn cifra(&self, texto: &str) -> String {
+        let chave = match &self.atual {
+            Some(chave) if !texto.is_empty() => chave,
+            _ => return texto.to_string(),
+        };
+
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let mut dados = nonce.to_vec();
+        dados.extend(
+            chave
+                .aead
+                .encrypt(&nonce, texto.as_bytes())
+                .expect("Impossível cifrar valor"),
+        );
+        format!("{}{}:{}", PREFIXO, chave.id, base64::encode(dados))
+    }
+
+    /// Decifra um valor com a chave usada para cifrá-lo, dentre as chaves
+    /// configuradas. Valores sem o prefixo [`PREFIXO`] são retornados sem
+    /// alterações.
+    pub fn decifra(&self, valor: &str) -> Result<String, ErroCifra> {
+        let resto = match valor.strip_prefix(PREFIXO) {
+            Some(resto) => resto,
+            None => return Ok(valor.to_string()),
+        };
+        let (id, dados) = resto.split_once(':').ok_or(ErroCifra::ValorInvalido)?;
+        let chave = self
+            .atual
+            .iter()
+            .chain(&self.antigas)
+            .find(|chave| chave.id == id)
+            .ok_or_else(|| ErroCifra::ChaveDesconhecida(id.to_string()))?;
+
+        let dados = base64::decode(dados).map_err(|_| ErroCifra::ValorInvalido)?;
+        if dados.len() < TAMANHO_NONCE {
+            return Err(ErroCifra::ValorInvalido);
+        }
+        let (nonce, cifrado) = dados.split_at(TAMANHO_NONCE);
+        let texto = chave
+            .aead
+            .decrypt(Nonce::from_slice(nonce), cifrado)
+            .map_err(|_| ErroCifra::ValorInvalido)?;
+        String::from_utf8(texto).map_err(|_| ErroCifra::ValorInvalido)
+    }
+
+    /// Indica se a chave do índice cego foi definida.
+    pub fn indexa(&self) -> bool {
+        self.indice.is_some()
+    }
+
+    /// Calcula o índice cego de um documento em texto puro, em hexadecimal.
+    /// Documentos iguais após a normalização possuem o mesmo índice,
+    /// independente da chave de cifragem. Retorna `None` caso a chave do
+    /// índice não tenha sido definida ou o documento seja vazio.
+    pub fn indice(&self, docto: &str) -> Option<String> {
+        let chave = self.indice.as_ref()?;
+        let docto = normaliza_docto(docto);
+        if docto.is_empty() {
+            return None;
+        }
+        Some(hex(&hmac(&chave.0, docto.as_bytes())))
+    }
+
+    /// Recifra um documento gravado com qualquer uma das chaves configuradas,
+    /// retornando o documento cifrado com a chave atual e seu índice cego,
+    /// que não depende da chave de cifragem. Caso a cifragem não esteja
+    /// habilitada, o documento é decifrado.
+    ///
+    /// Retorna `None` caso o documento já esteja cifrado com a chave atual
+    /// (ou sem cifragem, caso a mesma não esteja habilitada) e seu índice
+    /// esteja correto, não precisando ser regravado.
+    pub fn recifra(
+        &self,
+        docto: &str,
+        indice: Option<&str>,
+    ) -> Result<Option<(String, Option<String>)>, ErroCifra> {
+        let texto = self.decifra(docto)?;
+        let novo_indice = self.indice(&texto);
+        let atualizado = match &self.atual {
+            Some(chave) => {
+                texto.is_empty() || docto.starts_with(&format!("{}{}:", PREFIXO, chave.id))
+            }
+            None => docto == texto,
+        };
+
+        if atualizado && indice == novo_indice.as_deref() {
+            Ok(None)
+        } else {
+            Ok(Some((self.cifra(&texto), novo_indice)))
+        }
+    }
+}
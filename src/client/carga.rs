@@ -0,0 +1,488 @@
+// carga.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Gerador de carga para o servidor.
+//!
+//! Cada conexão executa uma sequência de operações sorteadas de acordo com
+//! uma distribuição (o "mix" de operações). Toda a aleatoriedade deriva de uma
+//! única semente, de forma que a sequência de operações de cada conexão seja
+//! reproduzível entre execuções. Ao final, é mostrado um relatório com a
+//! latência e os erros de cada operação.
+
+use crate::saida::Formato;
+use crate::{Clientes, ErrorImpl, Servidor};
+use clap::Args;
+use futures::StreamExt;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tonic::{Request, Status};
+
+use minerva_lite::*;
+
+/// Opções do gerador de carga.
+#[derive(Args)]
+pub struct OpcoesCarga {
+    /// Número de conexões simultâneas com o servidor.
+    #[clap(short, long, default_value = "16")]
+    concorrencia: usize,
+
+    /// Duração do teste, em segundos. Caso nem a duração nem o número de
+    /// operações sejam informados, o teste durará 10 segundos.
+    #[clap(short, long)]
+    duracao: Option<f64>,
+
+    /// Número máximo de operações executadas por cada conexão.
+    #[clap(short = 'n', long)]
+    operacoes: Option<usize>,
+
+    /// Pesos de cada operação, no formato `cadastra=40,consulta=40,lista=5,deleta=15`.
+    /// Operações omitidas não serão executadas.
+    #[clap(long, default_value = "cadastra=40,consulta=40,lista=5,deleta=15")]
+    mix: Mix,
+
+    /// Semente do gerador de números aleatórios. Caso não seja informada,
+    /// uma semente será sorteada e mostrada no relatório.
+    #[clap(long)]
+    seed: Option<u64>,
+
+    /// Taxa máxima de erros aceitável (entre 0 e 1). Caso seja excedida, o
+    /// programa encerrará com um código de saída diferente de zero.
+    #[clap(long, default_value = "0.01")]
+    max_erros: f64,
+}
+
+/// Operações executadas pelo gerador de carga.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Operacao {
+    Cadastra,
+    Consulta,
+    Lista,
+    Deleta,
+}
+
+impl Operacao {
+    /// Nome da operação, como usado na linha de comando e no relatório.
+    fn nome(self) -> &'static str {
+        match self {
+            Operacao::Cadastra => "cadastra",
+            Operacao::Consulta => "consulta",
+            Operacao::Lista => "lista",
+            Operacao::Deleta => "deleta",
+        }
+    }
+}
+
+impl FromStr for Operacao {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cadastra" => Ok(Operacao::Cadastra),
+            "consulta" => Ok(Operacao::Consulta),
+            "lista" => Ok(Operacao::Lista),
+            "deleta" => Ok(Operacao::Deleta),
+            _ => Err(format!("Operação desconhecida: {}", s)),
+        }
+    }
+}
+
+/// Distribuição de operações executadas por cada conexão.
+#[derive(Clone, Debug)]
+struct Mix {
+    operacoes: Vec<Operacao>,
+    pesos: Vec<u32>,
+}
+
+impl Mix {
+    /// Cria a distribuição para o sorteio das operações.
+    fn distribuicao(&self) -> WeightedIndex<u32> {
+        WeightedIndex::new(&self.pesos).expect("Pesos validados na criação")
+    }
+}
+
+impl FromStr for Mix {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut operacoes = vec![];
+        let mut pesos = vec![];
+        for par in s.split(',').filter(|p| !p.trim().is_empty()) {
+            let (operacao, peso) = par
+                .split_once('=')
+                .ok_or_else(|| format!("Esperado `operacao=peso`: {}", par))?;
+            operacoes.push(operacao.trim().parse()?);
+            pesos.push(
+                peso.trim()
+                    .parse()
+                    .map_err(|_| format!("Peso inválido: {}", peso))?,
+            );
+        }
+
+        if pesos.iter().all(|&p| p == 0) {
+            return Err("Ao menos uma operação deve ter peso positivo".to_string());
+        }
+        Ok(Self { operacoes, pesos })
+    }
+}
+
+/// Resultados de um único tipo de operação.
+#[derive(Default)]
+struct Resultados {
+    latencias: Vec<Duration>,
+    erros: BTreeMap<String, usize>,
+}
+
+impl Resultados {
+    /// Registra o resultado de uma operação.
+    fn registra(&mut self, latencia: Duration, result: Result<(), Status>) {
+        match result {
+            Ok(()) => self.latencias.push(latencia),
+            Err(status) => *self.erros.entry(format!("{:?}", status.code())).or_default() += 1,
+        }
+    }
+
+    /// Incorpora os resultados de outra conexão.
+    fn junta(&mut self, outro: Resultados) {
+        self.latencias.extend(outro.latencias);
+        for (codigo, n) in outro.erros {
+            *self.erros.entry(codigo).or_default() += n;
+        }
+    }
+}
+
+/// Resumo de um tipo de operação no relatório final. Latências em
+/// milissegundos.
+#[derive(Serialize)]
+struct ResumoOperacao {
+    sucessos: usize,
+    erros: usize,
+    erros_por_codigo: BTreeMap<String, usize>,
+    latencia_media_ms: f64,
+    latencia_p50_ms: f64,
+    latencia_p90_ms: f64,
+    latencia_p99_ms: f64,
+    latencia_max_ms: f64,
+}
+
+impl From<Resultados> for ResumoOperacao {
+    fn from(mut r: Resultados) -> Self {
+        r.latencias.sort();
+        let ms = |d: &Duration| d.as_secs_f64() * 1000.0;
+        let percentil = |p: f64| match r.latencias.len() {
+            0 => 0.0,
+            n => ms(&r.latencias[((n as f64 * p).ceil() as usize).clamp(1, n) - 1]),
+        };
+        let media = match r.latencias.len() {
+            0 => 0.0,
+            n => r.latencias.iter().map(ms).sum::<f64>() / n as f64,
+        };
+
+        Self {
+            sucessos: r.latencias.len(),
+            erros: r.erros.values().sum(),
+            latencia_media_ms: media,
+            latencia_p50_ms: percentil(0.50),
+            latencia_p90_ms: percentil(0.90),
+            latencia_p99_ms: percentil(0.99),
+            latencia_max_ms: r.latencias.last().map(ms).unwrap_or(0.0),
+            erros_por_codigo: r.erros,
+        }
+    }
+}
+
+/// Relatório final do teste de carga.
+#[derive(Serialize)]
+struct Relatorio {
+    seed: u64,
+    concorrencia: usize,
+    duracao_s: f64,
+    operacoes: usize,
+    erros: usize,
+    taxa_erros: f64,
+    vazao_ops: f64,
+    por_operacao: BTreeMap<&'static str, ResumoOperacao>,
+}
+
+impl Relatorio {
+    /// Imprime o resumo de cada operação em CSV.
+    fn imprime_csv(&self) {
+        println!("operacao,sucessos,erros,media_ms,p50_ms,p90_ms,p99_ms,max_ms");
+        for (nome, r) in &self.por_operacao {
+            println!(
+                "{},{},{},{:.3},{:.3},{:.3},{:.3},{:.3}",
+                nome,
+                r.sucessos,
+                r.erros,
+                r.latencia_media_ms,
+                r.latencia_p50_ms,
+                r.latencia_p90_ms,
+                r.latencia_p99_ms,
+                r.latencia_max_ms
+            );
+        }
+    }
+
+    /// Imprime o relatório em texto simples.
+    fn imprime(&self) {
+        println!("Semente: {}", self.seed);
+        println!("Conexões: {}", self.concorrencia);
+        println!(
+            "{} operações em {:.2}s ({:.1} op/s), {} erros ({:.2}%).",
+            self.operacoes,
+            self.duracao_s,
+            self.vazao_ops,
+            self.erros,
+            self.taxa_erros * 100.0
+        );
+        println!();
+        println!(
+            "{:<10} {:>9} {:>7} {:>10} {:>10} {:>10} {:>10} {:>10}",
+            "operação", "sucessos", "erros", "média", "p50", "p90", "p99", "máx"
+        );
+        for (nome, r) in &self.por_operacao {
+            println!(
+                "{:<10} {:>9} {:>7} {:>8.2}ms {:>8.2}ms {:>8.2}ms {:>8.2}ms {:>8.2}ms",
+                nome,
+                r.sucessos,
+                r.erros,
+                r.latencia_media_ms,
+                r.latencia_p50_ms,
+                r.latencia_p90_ms,
+                r.latencia_p99_ms,
+                r.latencia_max_ms
+            );
+            for (codigo, n) in &r.erros_por_codigo {
+                println!("{:<10} {:>9} {:>7} ({})", "", "", n, codigo);
+            }
+        }
+    }
+}
+
+/// Executa o teste de carga, imprimindo o relatório no formato informado.
+/// Retorna um erro caso a taxa de erros exceda o limite aceitável.
+pub async fn executa(
+    servidor: &Servidor,
+    opcoes: OpcoesCarga,
+    formato: Formato,
+) -> Result<(), ErrorImpl> {
+    let seed = opcoes.seed.unwrap_or_else(|| rand::thread_rng().gen());
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let duracao = match (opcoes.duracao, opcoes.operacoes) {
+        (None, None) => Some(Duration::from_secs(10)),
+        (duracao, _) => duracao.map(Duration::from_secs_f64),
+    };
+
+    let inicio = Instant::now();
+    let limite = duracao.map(|d| inicio + d);
+
+    // Cada conexão recebe uma semente própria, derivada da semente principal.
+    let mut tasks = vec![];
+    for _ in 0..opcoes.concorrencia {
+        let conexao = Conexao {
+            client: servidor.clientes().await?,
+            rng: StdRng::seed_from_u64(rng.gen()),
+            cadastrados: vec![],
+        };
+        let mix = opcoes.mix.clone();
+        let operacoes = opcoes.operacoes;
+        tasks.push(tokio::spawn(conexao.executa(mix, operacoes, limite)));
+    }
+
+    let mut resultados: BTreeMap<Operacao, Resultados> = BTreeMap::new();
+    let mut fim = inicio;
+    for task in tasks {
+        let (parciais, fim_conexao) = task.await??;
+        for (operacao, r) in parciais {
+            resultados.entry(operacao).or_default().junta(r);
+        }
+        fim = fim.max(fim_conexao);
+    }
+    let duracao_s = fim.duration_since(inicio).as_secs_f64();
+
+    let por_operacao: BTreeMap<_, ResumoOperacao> = resultados
+        .into_iter()
+        .map(|(operacao, r)| (operacao.nome(), r.into()))
+        .collect();
+    let sucessos: usize = por_operacao.values().map(|r| r.sucessos).sum();
+    let erros: usize = por_operacao.values().map(|r| r.erros).sum();
+    let operacoes = sucessos + erros;
+
+    let relatorio = Relatorio {
+        seed,
+        concorrencia: opcoes.concorrencia,
+        duracao_s,
+        operacoes,
+        erros,
+        taxa_erros: if operacoes > 0 {
+            erros as f64 / operacoes as f64
+        } else {
+            0.0
+        },
+        vazao_ops: operacoes as f64 / duracao_s,
+        por_operacao,
+    };
+
+    match formato {
+        Formato::Tabela => relatorio.imprime(),
+        Formato::Json => println!("{}", serde_json::to_string_pretty(&relatorio)?),
+        Formato::Csv => relatorio.imprime_csv(),
+    }
+
+    if relatorio.taxa_erros > opcoes.max_erros {
+        return Err(format!(
+            "Taxa de erros ({:.2}%) excede o limite de {:.2}%",
+            relatorio.taxa_erros * 100.0,
+            opcoes.max_erros * 100.0
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Gera um vetor de clientes de teste para serem cadastrados.
+fn gera_clientes() -> Vec<NovoClienteRequest> {
+    vec![
+        NovoClienteRequest {
+            nome: "Beltrano de Souza".to_string(),
+            pj: false,
+            docto: "999.999.999-99".to_string(),
+        },
+        NovoClienteRequest {
+            nome: "Fulano de Tal".to_string(),
+            pj: false,
+            docto: "888.888.888-88".to_string(),
+        },
+        NovoClienteRequest {
+            nome: "Empresa S/A".to_string(),
+            pj: true,
+            docto: "99.999.999/9999-99".to_string(),
+        },
+        NovoClienteRequest {
+            nome: "Ciclano da Silva".to_string(),
+            pj: false,
+            docto: "777.777.777-77".to_string(),
+        },
+        NovoClienteRequest {
+            nome: "Outra Empresa LTDA".to_string(),
+            pj: true,
+            docto: "99.999.999/9999-99".to_string(),
+        },
+    ]
+}
+
+/// Estado de uma única conexão do gerador de carga.
+struct Conexao {
+    client: Clientes,
+    rng: StdRng,
+    /// Clientes cadastrados por esta conexão e ainda não removidos.
+    cadastrados: Vec<i32>,
+}
+
+impl Conexao {
+    /// Executa operações sorteadas até que o número máximo de operações seja
+    /// atingido ou que o prazo termine, retornando os resultados e o instante
+    /// de término. Ao final, remove os clientes que ainda estejam cadastrados,
+    /// sem contabilizar estas remoções.
+    async fn executa(
+        mut self,
+        mix: Mix,
+        max_operacoes: Option<usize>,
+        limite: Option<Instant>,
+    ) -> Result<(BTreeMap<Operacao, Resultados>, Instant), ErrorImpl> {
+        let distribuicao = mix.distribuicao();
+        let clientes = gera_clientes();
+        let mut resultados: BTreeMap<Operacao, Resultados> = BTreeMap::new();
+
+        let mut executadas = 0;
+        while max_operacoes.map(|max| executadas < max).unwrap_or(true)
+            && limite.map(|l| Instant::now() < l).unwrap_or(true)
+        {
+            // Consultas e remoções exigem um cliente cadastrado por esta
+            // conexão; caso não haja nenhum, um cliente é cadastrado.
+            let operacao = match mix.operacoes[distribuicao.sample(&mut self.rng)] {
+                Operacao::Consulta | Operacao::Deleta if self.cadastrados.is_empty() => {
+                    Operacao::Cadastra
+                }
+                operacao => operacao,
+            };
+
+            let inicio = Instant::now();
+            let result = match operacao {
+                Operacao::Cadastra => {
+                    let dados = clientes.choose(&mut self.rng).unwrap().clone();
+                    self.cadastra(dados).await
+                }
+                Operacao::Consulta => {
+                    let id = *self.cadastrados.choose(&mut self.rng).unwrap();
+                    self.client
+                        .consulta(Request::new(IdClienteRequest { id }))
+                        .await
+                        .map(|_| ())
+                }
+                Operacao::Lista => self.lista().await,
+                Operacao::Deleta => {
+                    let indice = self.rng.gen_range(0..self.cadastrados.len());
+                    let id = self.cadastrados.swap_remove(indice);
+                    self.client
+                        .deleta(Request::new(IdClienteRequest { id }))
+                        .await
+                        .map(|_| ())
+                }
+            };
+
+            resultados
+                .entry(operacao)
+                .or_default()
+                .registra(inicio.elapsed(), result);
+            executadas += 1;
+        }
+        let fim = Instant::now();
+
+        for id in std::mem::take(&mut self.cadastrados) {
+            let _ = self
+                .client
+                .deleta(Request::new(IdClienteRequest { id }))
+                .await;
+        }
+
+        Ok((resultados, fim))
+    }
+
+    /// Cadastra um cliente, guardando seu ID para consultas e remoções.
+    async fn cadastra(&mut self, dados: NovoClienteRequest) -> Result<(), Status> {
+        let cliente = self.client.cadastra(Request::new(dados)).await?.into_inner();
+        self.cadastrados.push(cliente.id);
+        Ok(())
+    }
+
+    /// Recebe todas as páginas da listagem de clientes. A latência
+    /// contabilizada é a do stream completo.
+    async fn lista(&mut self) -> Result<(), Status> {
+        let mut stream = self.client.lista(Request::new(())).await?.into_inner();
+        while let Some(pagina) = stream.next().await {
+            pagina?;
+        }
+        Ok(())
+    }
+}
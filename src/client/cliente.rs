@@ -0,0 +1,268 @@
+// cliente.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Comandos administrativos sobre o CRUD de clientes.
+
+use crate::saida::{self, Formato, Registro};
+use crate::ErrorImpl;
+use clap::Subcommand;
+use futures::TryStreamExt;
+use minerva_lite::sdk::{self, MinervaLite, NovoCliente};
+use serde::Serialize;
+
+/// Operações sobre clientes.
+#[derive(Subcommand)]
+pub enum ComandoCliente {
+    /// Cadastra um novo cliente, mostrando o cliente cadastrado.
+    Cadastra {
+        /// Nome do cliente.
+        #[clap(long)]
+        nome: String,
+        /// Documento do cliente (CPF ou CNPJ).
+        #[clap(long)]
+        docto: String,
+        /// Indica que o cliente é uma pessoa jurídica.
+        #[clap(long, action)]
+        pj: bool,
+    },
+    /// Mostra os dados de um único cliente.
+    Consulta {
+        /// ID do cliente.
+        id: i32,
+    },
+    /// Mostra todos os clientes cadastrados.
+    Lista,
+    /// Remove um ou mais clientes.
+    Deleta {
+        /// IDs dos clientes.
+        #[clap(required = true)]
+        ids: Vec<i32>,
+    },
+    /// Busca clientes pelo nome, tolerando erros de digitação, acentos e
+    /// maiúsculas. Os clientes são mostrados da maior para a menor
+    /// similaridade com o termo informado.
+    Busca {
+        /// Termo a ser buscado.
+        termo: String,
+        /// Número máximo de clientes mostrados. Caso não seja informado, será
+        /// usado o padrão do servidor.
+        #[clap(long, default_value_t = 0)]
+        limite: i32,
+    },
+    /// Mostra os grupos de clientes possivelmente duplicados, com o mesmo
+    /// documento ou com nomes similares.
+    Duplicados,
+    /// Mescla um cliente duplicado em outro, mostrando o cliente mantido. Os
+    /// endereços e contatos da origem são transferidos, e a origem é
+    /// removida.
+    Mescla {
+        /// ID do cliente duplicado, que será removido.
+        origem: i32,
+        /// ID do cliente que será mantido.
+        destino: i32,
+    },
+    /// Exporta todos os dados armazenados a respeito de um cliente (LGPD),
+    /// como um documento JSON, independente do formato de saída.
+    Exporta {
+        /// ID do cliente.
+        id: i32,
+    },
+    /// Anonimiza um cliente de forma irreversível (LGPD), mostrando o cliente
+    /// anonimizado.
+    Anonimiza {
+        /// ID do cliente.
+        id: i32,
+    },
+}
+
+/// Dados de um cliente, como mostrados na saída do comando.
+#[derive(Serialize)]
+struct Cliente {
+    id: i32,
+    tipo: i32,
+    nome: String,
+    pj: bool,
+    docto: String,
+    ativo: bool,
+    bloqueado: bool,
+}
+
+impl From<sdk::Cliente> for Cliente {
+    fn from(c: sdk::Cliente) -> Self {
+        Self {
+            id: c.id,
+            tipo: c.tipo,
+            nome: c.nome,
+            pj: c.pj,
+            docto: c.docto,
+            ativo: c.ativo,
+            bloqueado: c.bloqueado,
+        }
+    }
+}
+
+impl Registro for Cliente {
+    const COLUNAS: &'static [&'static str] =
+        &["id", "tipo", "nome", "pj", "docto", "ativo", "bloqueado"];
+
+    fn campos(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            self.tipo.to_string(),
+            self.nome.clone(),
+            self.pj.to_string(),
+            self.docto.clone(),
+            self.ativo.to_string(),
+            self.bloqueado.to_string(),
+        ]
+    }
+}
+
+/// Cliente encontrado por uma busca, como mostrado na saída do comando.
+#[derive(Serialize)]
+struct ClienteEncontrado {
+    #[serde(flatten)]
+    cliente: Cliente,
+    similaridade: f32,
+}
+
+impl From<sdk::ClienteEncontrado> for ClienteEncontrado {
+    fn from(c: sdk::ClienteEncontrado) -> Self {
+        Self {
+            cliente: Cliente::from(c.cliente.unwrap_or_default()),
+            similaridade: c.similaridade,
+        }
+    }
+}
+
+impl Registro for ClienteEncontrado {
+    const COLUNAS: &'static [&'static str] = &[
+        "id",
+        "tipo",
+        "nome",
+        "pj",
+        "docto",
+        "ativo",
+        "bloqueado",
+        "similaridade",
+    ];
+
+    fn campos(&self) -> Vec<String> {
+        let mut campos = self.cliente.campos();
+        campos.push(format!("{:.2}", self.similaridade));
+        campos
+    }
+}
+
+/// Cliente de um grupo de duplicados, como mostrado na saída do comando.
+#[derive(Serialize)]
+struct ClienteDuplicado {
+    grupo: usize,
+    motivo: &'static str,
+    #[serde(flatten)]
+    cliente: Cliente,
+}
+
+impl Registro for ClienteDuplicado {
+    const COLUNAS: &'static [&'static str] = &[
+        "grupo",
+        "motivo",
+        "id",
+        "tipo",
+        "nome",
+        "pj",
+        "docto",
+        "ativo",
+        "bloqueado",
+    ];
+
+    fn campos(&self) -> Vec<String> {
+        let mut campos = vec![self.grupo.to_string(), self.motivo.to_string()];
+        campos.extend(self.cliente.campos());
+        campos
+    }
+}
+
+/// Executa uma operação sobre clientes.
+pub async fn executa(
+    minerva: &MinervaLite,
+    comando: ComandoCliente,
+    formato: Formato,
+) -> Result<(), ErrorImpl> {
+    match comando {
+        ComandoCliente::Cadastra { nome, docto, pj } => {
+            let cliente = minerva.cadastra(NovoCliente { nome, pj, docto }).await?;
+            saida::imprime_um(&Cliente::from(cliente), formato)?;
+        }
+        ComandoCliente::Consulta { id } => {
+            let cliente = minerva.consulta(id).await?;
+            saida::imprime_um(&Cliente::from(cliente), formato)?;
+        }
+        ComandoCliente::Lista => {
+            let clientes: Vec<Cliente> = minerva
+                .lista()
+                .await?
+                .map_ok(Cliente::from)
+                .try_collect()
+                .await?;
+            saida::imprime(&clientes, formato)?;
+        }
+        ComandoCliente::Deleta { ids } => {
+            for id in ids {
+                minerva.deleta(id).await?;
+                eprintln!("Cliente {} removido.", id);
+            }
+        }
+        ComandoCliente::Busca { termo, limite } => {
+            let clientes: Vec<ClienteEncontrado> = minerva
+                .busca(&termo, limite)
+                .await?
+                .into_iter()
+                .map(ClienteEncontrado::from)
+                .collect();
+            saida::imprime(&clientes, formato)?;
+        }
+        ComandoCliente::Duplicados => {
+            let mut clientes = vec![];
+            for (i, grupo) in minerva.detecta_duplicados().await?.into_iter().enumerate() {
+                let motivo = match grupo.motivo {
+                    0 => "documento",
+                    _ => "nome",
+                };
+                clientes.extend(grupo.clientes.into_iter().map(|c| ClienteDuplicado {
+                    grupo: i + 1,
+                    motivo,
+                    cliente: Cliente::from(c),
+                }));
+            }
+            saida::imprime(&clientes, formato)?;
+        }
+        ComandoCliente::Mescla { origem, destino } => {
+            let cliente = minerva.mescla(origem, destino).await?;
+            eprintln!("Cliente {} mesclado em {}.", origem, destino);
+            saida::imprime_um(&Cliente::from(cliente), formato)?;
+        }
+        ComandoCliente::Exporta { id } => {
+            println!("{}", minerva.exporta_dados(id).await?);
+        }
+        ComandoCliente::Anonimiza { id } => {
+            let cliente = minerva.anonimiza(id).await?;
+            eprintln!("Cliente {} anonimizado.", id);
+            saida::imprime_um(&Cliente::from(cliente), formato)?;
+        }
+    }
+    Ok(())
+}
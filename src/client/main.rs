@@ -0,0 +1,145 @@
+// client.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este aplicativo é um cliente de linha de comando para o servidor, útil
+//! tanto para a administração do backend quanto para avaliar seu desempenho.
+//!
+//! Os comandos disponíveis são:
+//!
+//! - `ping`: verifica se o servidor está respondendo;
+//! - `cliente`: operações sobre o CRUD de clientes;
+//! - `carga`: gerador de carga, que executa operações sorteadas em várias
+//!   conexões simultâneas e mostra um relatório de latências e erros.
+
+mod carga;
+mod cliente;
+mod saida;
+
+use clap::{Parser, Subcommand};
+use dotenv::dotenv;
+use std::env;
+use std::process::ExitCode;
+use std::time::Instant;
+use tonic::codegen::InterceptedService;
+use tonic::transport::{Channel, Endpoint};
+
+use minerva_lite::minerva_clientes_client::MinervaClientesClient;
+use minerva_lite::sdk::{Autenticacao, MinervaLite};
+
+type ErrorImpl = Box<dyn std::error::Error + Send + Sync>;
+
+/// Cliente do serviço de clientes, com autenticação.
+type Clientes = MinervaClientesClient<InterceptedService<Channel, Autenticacao>>;
+
+/// Cliente de linha de comando do Minerva.Lite.
+#[derive(Parser)]
+#[clap(name = "liteclient", version)]
+struct Cli {
+    /// Endereço do servidor gRPC. Caso não seja informado, será usado
+    /// `http://127.0.0.1:<GRPC_PORT>`.
+    #[clap(long, env = "MINERVA_ADDR", global = true)]
+    addr: Option<String>,
+
+    /// Token de acesso, enviado no metadado `authorization` de cada
+    /// requisição.
+    #[clap(long, env = "MINERVA_TOKEN", global = true, hide_env_values = true)]
+    token: Option<String>,
+
+    /// Formato de saída.
+    #[clap(long, arg_enum, default_value = "tabela", global = true)]
+    formato: saida::Formato,
+
+    #[clap(subcommand)]
+    comando: Comando,
+}
+
+/// Comandos do cliente.
+#[derive(Subcommand)]
+enum Comando {
+    /// Verifica se o servidor está respondendo, mostrando o tempo de resposta.
+    Ping,
+    /// Operações sobre clientes.
+    Cliente {
+        #[clap(subcommand)]
+        comando: cliente::ComandoCliente,
+    },
+    /// Executa um teste de carga no servidor.
+    Carga(carga::OpcoesCarga),
+}
+
+/// Dados de conexão com o servidor, usados pelo gerador de carga para abrir
+/// uma conexão própria para cada tarefa, sem novas tentativas.
+struct Servidor {
+    endpoint: Endpoint,
+    autenticacao: Autenticacao,
+}
+
+impl Servidor {
+    /// Abre uma conexão com o serviço de clientes.
+    async fn clientes(&self) -> Result<Clientes, ErrorImpl> {
+        let channel = self.endpoint.connect().await?;
+        Ok(MinervaClientesClient::with_interceptor(
+            channel,
+            self.autenticacao.clone(),
+        ))
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    dotenv().ok();
+
+    match executa(Cli::parse()).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Erro: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Executa o comando informado na linha de comando.
+async fn executa(cli: Cli) -> Result<(), ErrorImpl> {
+    let addr = cli.addr.unwrap_or_else(|| {
+        let port = env::var("GRPC_PORT").unwrap_or_else(|_| "50051".to_string());
+        format!("http://127.0.0.1:{}", port)
+    });
+
+    let mut builder = MinervaLite::builder(addr.clone());
+    if let Some(token) = &cli.token {
+        builder = builder.token(token.clone());
+    }
+    let minerva = builder.conecta().await?;
+
+    match cli.comando {
+        Comando::Ping => {
+            let inicio = Instant::now();
+            minerva.ping().await?;
+            println!("Pong ({:.2}ms).", inicio.elapsed().as_secs_f64() * 1000.0);
+        }
+        Comando::Cliente { comando } => cliente::executa(&minerva, comando, cli.formato).await?,
+        Comando::Carga(opcoes) => {
+            minerva.ping().await?;
+            let servidor = Servidor {
+                endpoint: Endpoint::from_shared(addr)?,
+                autenticacao: Autenticacao::new(cli.token.as_deref())?,
+            };
+            carga::executa(&servidor, opcoes, cli.formato).await?
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,109 @@
+// saida.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Formatos de saída dos comandos do cliente.
+
+use clap::ArgEnum;
+use serde::Serialize;
+
+/// Formatos de saída disponíveis.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ArgEnum)]
+pub enum Formato {
+    /// Tabela alinhada, para leitura no terminal.
+    Tabela,
+    /// JSON, para consumo por outras ferramentas.
+    Json,
+    /// CSV com cabeçalho, para planilhas.
+    Csv,
+}
+
+/// Um registro que pode ser mostrado em qualquer um dos formatos de saída.
+pub trait Registro: Serialize {
+    /// Nomes das colunas da tabela e do CSV.
+    const COLUNAS: &'static [&'static str];
+
+    /// Valores de cada coluna, na mesma ordem de [`Registro::COLUNAS`].
+    fn campos(&self) -> Vec<String>;
+}
+
+/// Imprime um único registro. Em JSON, o registro é impresso como um objeto.
+pub fn imprime_um<R: Registro>(registro: &R, formato: Formato) -> serde_json::Result<()> {
+    match formato {
+        Formato::Json => println!("{}", serde_json::to_string_pretty(registro)?),
+        _ => imprime(std::slice::from_ref(registro), formato)?,
+    }
+    Ok(())
+}
+
+/// Imprime uma lista de registros. Em JSON, os registros são impressos como
+/// um vetor.
+pub fn imprime<R: Registro>(registros: &[R], formato: Formato) -> serde_json::Result<()> {
+    match formato {
+        Formato::Tabela => imprime_tabela(R::COLUNAS, registros.iter().map(R::campos).collect()),
+        Formato::Json => println!("{}", serde_json::to_string_pretty(registros)?),
+        Formato::Csv => {
+            println!("{}", linha_csv(R::COLUNAS.iter().copied()));
+            for registro in registros {
+                println!("{}", linha_csv(registro.campos().iter().map(String::as_str)));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Imprime uma tabela com colunas alinhadas pelo maior valor de cada uma.
+fn imprime_tabela(colunas: &[&str], linhas: Vec<Vec<String>>) {
+    let mut larguras: Vec<usize> = colunas.iter().map(|c| c.chars().count()).collect();
+    for linha in &linhas {
+        for (largura, campo) in larguras.iter_mut().zip(linha) {
+            *largura = (*largura).max(campo.chars().count());
+        }
+    }
+
+    let formata = |campos: Vec<&str>| {
+        campos
+            .iter()
+            .zip(&larguras)
+            .map(|(campo, &largura)| format!("{:<largura$}", campo, largura = largura))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    let separador: Vec<String> = larguras.iter().map(|&l| "-".repeat(l)).collect();
+
+    println!("{}", formata(colunas.to_vec()));
+    println!("{}", formata(separador.iter().map(String::as_str).collect()));
+    for linha in &linhas {
+        println!("{}", formata(linha.iter().map(String::as_str).collect()));
+    }
+}
+
+/// Formata uma linha de CSV, escapando campos que contenham separadores,
+/// aspas ou quebras de linha.
+fn linha_csv<'a>(campos: impl Iterator<Item = &'a str>) -> String {
+    campos
+        .map(|campo| {
+            if campo.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", campo.replace('"', "\"\""))
+            } else {
+                campo.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
@@ -18,17 +18,50 @@
 //! O CRUD básico e a aplicação de regras de negócio do cliente poderão ser
 //! encontradas aqui.
 
+use crate::cifra::{Cifra, PREFIXO};
+use crate::controller::logdb;
+use crate::controller::regras::{self, MovimentoRecusado};
+use crate::model::busca::{ClienteEncontrado, SIMILARIDADE_MINIMA};
 use crate::model::cliente::*;
+use crate::model::contato::Contato;
+use crate::model::duplicado::{self, GrupoDuplicados, Mescla, SIMILARIDADE_DUPLICADOS};
+use crate::model::endereco::{ClienteDetalhado, Endereco};
+use crate::model::privacidade::{self, DadosCliente};
+use diesel::connection::SimpleConnection;
+use diesel::dsl::sql;
+use diesel::pg::Pg;
 use diesel::prelude::*;
 use diesel::result::Error;
+use diesel::sql_types::{Bool, Float, Integer, Text};
 use diesel::PgConnection;
 
 /// Número máximo de clientes mostrados em uma página de listagem.
 pub const CLIENTE_PAGE_SIZE: i64 = 100;
 
+/// Número máximo de clientes retornados por uma busca.
+pub const BUSCA_LIMITE_MAXIMO: i64 = 50;
+
+/// Número de clientes retornados por uma busca, caso não seja informado.
+pub const BUSCA_LIMITE_PADRAO: i64 = 10;
+
+sql_function!(fn lower(x: Text) -> Text);
+sql_function!(fn f_unaccent(x: Text) -> Text);
+sql_function!(fn word_similarity(termo: Text, texto: Text) -> Float);
+diesel_infix_operator!(SimilarPalavra, " <% ", backend: Pg);
+
+/// Par de clientes com nomes similares, encontrado pelo `pg_trgm`.
+#[derive(QueryableByName)]
+struct ParSimilar {
+    #[sql_type = "Integer"]
+    a: i32,
+    #[sql_type = "Integer"]
+    b: i32,
+}
+
 /// Realiza o cadastro de um único cliente, de acordo com os dados básicos
 /// necessários para cadastro. Requer uma conexão com o banco, e o cliente
 /// recém-cadastrado será retornado, em caso de sucesso.
+#[tracing::instrument(name = "cliente::cadastra", skip_all)]
 pub fn cadastra(conn: &PgConnection, dados: NovoCliente) -> Result<Cliente, Error> {
     diesel::insert_into(crate::model::schema::cliente::table)
         .values(&dados)
@@ -37,12 +70,38 @@ pub fn cadastra(conn: &PgConnection, dados: NovoCliente) -> Result<Cliente, Erro
 
 /// Consulta os dados de um único cliente, através do ID requisitado.
 /// Em caso de sucesso, retorna uma estrutura única contendo tais dados.
+#[tracing::instrument(name = "cliente::consulta", skip(conn))]
 pub fn consulta(conn: &PgConnection, req_id: i32) -> Result<Cliente, Error> {
     use crate::model::schema::cliente::dsl::*;
+    cliente.filter(id.eq(&req_id)).first::<Cliente>(conn)
+}
+
+/// Consulta um cliente através de seu documento, desconsiderando a
+/// pontuação. Caso a chave do índice cego esteja definida, o documento é
+/// comparado através do índice (veja [`duplicado::chave_texto`]); clientes
+/// ainda sem índice são comparados pela aplicação. Caso mais de um cliente
+/// possua o mesmo documento, retorna o de menor ID.
+#[tracing::instrument(name = "cliente::consulta_docto", skip_all)]
+pub fn consulta_docto(
+    conn: &PgConnection,
+    req_docto: &str,
+    cifra: &Cifra,
+) -> Result<Cliente, Error> {
+    use crate::model::schema::cliente::dsl::*;
+
+    let chave = duplicado::chave_texto(req_docto, cifra).ok_or(Error::NotFound)?;
+    let sem_indice = docto_indice.is_null().and(
+        sql::<Text>("upper(regexp_replace(docto, '[^[:alnum:]]', '', 'g'))")
+            .eq(duplicado::normaliza_docto(req_docto))
+            .or(docto.like(format!("{}%", PREFIXO))),
+    );
     cliente
-        .filter(id.eq(&req_id))
-        .load::<Cliente>(conn)
-        .map(|v| v.first().unwrap().clone())
+        .filter(docto_indice.eq(cifra.indice(req_docto)).or(sem_indice))
+        .order(id)
+        .load::<Cliente>(conn)?
+        .into_iter()
+        .find(|c| duplicado::chave_docto(c, cifra).as_deref() == Some(&chave))
+        .ok_or(Error::NotFound)
 }
 
 /// Retorna uma lista de clientes, por ordem de ID, de acordo com a página
@@ -50,20 +109,403 @@ pub fn consulta(conn: &PgConnection, req_id: i32) -> Result<Cliente, Error> {
 ///
 /// As páginas começam a serem contadas a partir de 0. Em caso de sucesso,
 /// retorna um `Vec` contendo um número `CLIENTE_PAGE_SIZE` de clientes.
+#[tracing::instrument(name = "cliente::lista", skip(conn))]
 pub fn lista(conn: &PgConnection, pagina: i64) -> Result<Vec<Cliente>, Error> {
     use crate::model::schema::cliente::dsl::*;
 
-    let offset = (pagina * CLIENTE_PAGE_SIZE) + 1;
-
     cliente
         .order(id)
         .limit(CLIENTE_PAGE_SIZE)
-        .offset(offset)
+        .offset(pagina * CLIENTE_PAGE_SIZE)
         .load::<Cliente>(conn)
 }
 
+/// Busca clientes cujos nomes sejam similares ao termo informado, através
+/// das extensões `pg_trgm` e `unaccent` (veja [`model::busca`]). Retorna no
+/// máximo `limite` clientes, da maior para a menor similaridade.
+///
+/// A comparação usa o índice de trigramas sobre os nomes normalizados, e o
+/// limiar de similaridade do `pg_trgm` é ajustado apenas para a transação.
+///
+/// [`model::busca`]: crate::model::busca
+#[tracing::instrument(name = "cliente::busca", skip(conn, termo))]
+pub fn busca(
+    conn: &PgConnection,
+    termo: &str,
+    limite: i64,
+) -> Result<Vec<ClienteEncontrado>, Error> {
+    use crate::model::schema::cliente::dsl::*;
+
+    let normalizado = || f_unaccent(lower(termo));
+    let nome_normalizado = || f_unaccent(lower(nome));
+
+    conn.transaction(|| {
+        // O limiar é uma constante, e portanto pode ser interpolado.
+        conn.batch_execute(&format!(
+            "SET LOCAL pg_trgm.word_similarity_threshold = {}",
+            SIMILARIDADE_MINIMA
+        ))?;
+
+        cliente
+            .select((
+                crate::model::schema::cliente::all_columns,
+                word_similarity(normalizado(), nome_normalizado()),
+            ))
+            .filter(SimilarPalavra::new(normalizado(), nome_normalizado()))
+            .order((
+                word_similarity(normalizado(), nome_normalizado()).desc(),
+                id,
+            ))
+            .limit(limite)
+            .load::<ClienteEncontrado>(conn)
+    })
+}
+
+/// Consulta os dados de um único cliente, junto com todos os seus endereços
+/// e contatos, por ordem de ID.
+#[tracing::instrument(name = "cliente::consulta_detalhada", skip(conn))]
+pub fn consulta_detalhada(conn: &PgConnection, req_id: i32) -> Result<ClienteDetalhado, Error> {
+    use crate::model::schema::{contato, endereco};
+
+    let c = consulta(conn, req_id)?;
+    let enderecos = Endereco::belonging_to(&c)
+        .order(endereco::id)
+        .load::<Endereco>(conn)?;
+    let contatos = Contato::belonging_to(&c)
+        .order(contato::id)
+        .load::<Contato>(conn)?;
+    Ok((c, enderecos, contatos))
+}
+
+/// Retorna uma página de clientes, como em [`lista`], incluindo opcionalmente
+/// os endereços e os contatos de cada cliente.
+///
+/// Os endereços e os contatos de todos os clientes da página são recuperados
+/// em uma consulta para cada entidade, e então agrupados por cliente.
+#[tracing::instrument(name = "cliente::lista_detalhada", skip(conn))]
+pub fn lista_detalhada(
+    conn: &PgConnection,
+    pagina: i64,
+    incluir_enderecos: bool,
+    incluir_contatos: bool,
+) -> Result<Vec<ClienteDetalhado>, Error> {
+    use crate::model::schema::{contato, endereco};
+
+    let clientes = lista(conn, pagina)?;
+    let enderecos = if incluir_enderecos && !clientes.is_empty() {
+        Endereco::belonging_to(&clientes)
+            .order(endereco::id)
+            .load::<Endereco>(conn)?
+            .grouped_by(&clientes)
+    } else {
+        vec![vec![]; clientes.len()]
+    };
+    let contatos = if incluir_contatos && !clientes.is_empty() {
+        Contato::belonging_to(&clientes)
+            .order(contato::id)
+            .load::<Contato>(conn)?
+            .grouped_by(&clientes)
+    } else {
+        vec![vec![]; clientes.len()]
+    };
+
+    Ok(clientes
+        .into_iter()
+        .zip(enderecos)
+        .zip(contatos)
+        .map(|((c, e), ct)| (c, e, ct))
+        .collect())
+}
+
+/// Grava os dados de crédito de um cliente, incluindo a situação de bloqueio
+/// definida pelo motor de regras.
+fn grava_credito(conn: &PgConnection, c: &Cliente) -> Result<Cliente, Error> {
+    use crate::model::schema::cliente::dsl::*;
+
+    diesel::update(cliente.find(c.id))
+        .set((
+            limite_credito.eq(c.limite_credito),
+            saldo_devedor.eq(c.saldo_devedor),
+            bloqueado.eq(c.bloqueado),
+            bloqueio_automatico.eq(c.bloqueio_automatico),
+        ))
+        .get_result::<Cliente>(conn)
+}
+
+/// Define o limite de crédito de um cliente, reavaliando as regras de
+/// crédito do mesmo. Um limite nulo remove o limite do cliente.
+#[tracing::instrument(name = "cliente::define_limite", skip(conn))]
+pub fn define_limite(
+    conn: &PgConnection,
+    req_id: i32,
+    limite: Option<i64>,
+) -> Result<Cliente, Error> {
+    use crate::model::schema::cliente::dsl::*;
+
+    conn.transaction(|| {
+        let mut c = cliente.find(req_id).for_update().first::<Cliente>(conn)?;
+        c.limite_credito = limite;
+        regras::aplica(&mut c);
+        grava_credito(conn, &c)
+    })
+}
+
+/// Registra um movimento de crédito em nome de um cliente (veja
+/// [`regras::movimenta`]), retornando o cliente atualizado. Caso o movimento
+/// seja recusado pelas regras de crédito, nada é gravado.
+#[tracing::instrument(name = "cliente::registra_movimento", skip(conn))]
+pub fn registra_movimento(
+    conn: &PgConnection,
+    req_id: i32,
+    valor: i64,
+    bloqueia_excedente: bool,
+) -> Result<Result<Cliente, MovimentoRecusado>, Error> {
+    use crate::model::schema::cliente::dsl::*;
+
+    conn.transaction(|| {
+        let mut c = cliente.find(req_id).for_update().first::<Cliente>(conn)?;
+        if let Err(recusa) = regras::movimenta(&mut c, valor, bloqueia_excedente) {
+            return Ok(Err(recusa));
+        }
+        grava_credito(conn, &c).map(Ok)
+    })
+}
+
+/// Detecta grupos de clientes possivelmente duplicados (veja
+/// [`model::duplicado`](crate::model::duplicado)).
+///
+/// Os clientes com documentos repetidos são encontrados por agrupamento,
+/// usando o índice cego quando existente (veja [`duplicado::chave_docto`]),
+/// e os pares de clientes com nomes similares através do índice de
+/// trigramas do `pg_trgm`; apenas estes clientes são recuperados do banco.
+/// Caso a chave do índice esteja definida, os clientes ainda sem índice têm
+/// seus índices calculados pela aplicação, e são comparados com os clientes
+/// indexados.
+#[tracing::instrument(name = "cliente::detecta_duplicados", skip_all)]
+pub fn detecta_duplicados(
+    conn: &PgConnection,
+    cifra: &Cifra,
+) -> Result<Vec<GrupoDuplicados>, Error> {
+    use crate::model::schema::cliente::dsl::*;
+
+    conn.transaction(|| {
+        // O limiar é uma constante, e portanto pode ser interpolado.
+        conn.batch_execute(&format!(
+            "SET LOCAL pg_trgm.similarity_threshold = {}",
+            SIMILARIDADE_DUPLICADOS
+        ))?;
+
+        let pares: Vec<(i32, i32)> = diesel::sql_query(
+            "SELECT a.id AS a, b.id AS b FROM cliente a JOIN cliente b \
+             ON a.id < b.id AND f_unaccent(lower(a.nome)) % f_unaccent(lower(b.nome))",
+        )
+        .load::<ParSimilar>(conn)?
+        .into_iter()
+        .map(|p| (p.a, p.b))
+        .collect();
+        let mut ids: Vec<i32> = pares.iter().flat_map(|&(a, b)| [a, b]).collect();
+
+        let mut indices = vec![];
+        if cifra.indexa() {
+            let sem_indice = cliente
+                .select((id, docto))
+                .filter(docto_indice.is_null())
+                .filter(docto.ne(""))
+                .load::<(i32, String)>(conn)?;
+            for (cliente_id, valor) in sem_indice {
+                if let Some(indice) = cifra.decifra(&valor).ok().and_then(|d| cifra.indice(&d)) {
+                    ids.push(cliente_id);
+                    indices.push(indice);
+                }
+            }
+        }
+
+        let candidatos = cliente
+            .filter(
+                id.eq_any(ids)
+                    .or(docto_indice.eq_any(indices))
+                    .or(sql::<Bool>(
+                        "COALESCE('#' || docto_indice, \
+                 upper(regexp_replace(docto, '[^[:alnum:]]', '', 'g'))) IN \
+                 (SELECT d FROM (SELECT COALESCE('#' || docto_indice, \
+                 upper(regexp_replace(docto, '[^[:alnum:]]', '', 'g'))) AS d FROM cliente) \
+                 AS doctos GROUP BY d HAVING d <> '' AND COUNT(*) > 1)",
+                    )),
+            )
+            .order(id)
+            .load::<Cliente>(conn)?;
+
+        Ok(duplicado::agrupa(candidatos, &pares, cifra))
+    })
+}
+
+/// Mescla um cliente duplicado em outro, em uma única transação.
+///
+/// Os endereços e os contatos da origem passam a pertencer ao destino; caso
+/// o destino já possua um endereço ou contato principal do mesmo tipo, o da
+/// origem deixa de ser principal. Os dados de crédito são combinados (veja
+/// [`regras::mescla`]), a origem é removida e a mescla é registrada no log de
+/// operações, em nome de `req_usuario`. Retorna o cliente de destino
+/// atualizado, ou [`Error::NotFound`] caso algum dos clientes não exista.
+#[tracing::instrument(name = "cliente::mescla", skip(conn))]
+pub fn mescla(conn: &PgConnection, dados: Mescla, req_usuario: &str) -> Result<Cliente, Error> {
+    use crate::model::schema::{cliente, contato, endereco};
+
+    conn.transaction(|| {
+        // Os clientes são travados por ordem de ID, evitando deadlocks entre
+        // mesclas concorrentes.
+        let travados = cliente::table
+            .filter(cliente::id.eq_any([dados.origem_id, dados.destino_id]))
+            .order(cliente::id)
+            .for_update()
+            .load::<Cliente>(conn)?;
+        let busca = |req_id| travados.iter().find(|c| c.id == req_id).cloned();
+        let origem = busca(dados.origem_id).ok_or(Error::NotFound)?;
+        let mut destino = busca(dados.destino_id).ok_or(Error::NotFound)?;
+
+        let principais = endereco::table
+            .select(endereco::tipo)
+            .filter(endereco::cliente_id.eq(destino.id))
+            .filter(endereco::principal)
+            .load::<i16>(conn)?;
+        diesel::update(
+            endereco::table
+                .filter(endereco::cliente_id.eq(origem.id))
+                .filter(endereco::principal)
+                .filter(endereco::tipo.eq_any(principais)),
+        )
+        .set(endereco::principal.eq(false))
+        .execute(conn)?;
+        diesel::update(endereco::table.filter(endereco::cliente_id.eq(origem.id)))
+            .set(endereco::cliente_id.eq(destino.id))
+            .execute(conn)?;
+
+        let principais = contato::table
+            .select(contato::tipo)
+            .filter(contato::cliente_id.eq(destino.id))
+            .filter(contato::principal)
+            .load::<i16>(conn)?;
+        diesel::update(
+            contato::table
+                .filter(contato::cliente_id.eq(origem.id))
+                .filter(contato::principal)
+                .filter(contato::tipo.eq_any(principais)),
+        )
+        .set(contato::principal.eq(false))
+        .execute(conn)?;
+        diesel::update(contato::table.filter(contato::cliente_id.eq(origem.id)))
+            .set(contato::cliente_id.eq(destino.id))
+            .execute(conn)?;
+
+        regras::mescla(&mut destino, &origem);
+        let destino = grava_credito(conn, &destino)?;
+        remove(conn, origem.id)?;
+
+        logdb::transfere(conn, "cliente", origem.id, destino.id)?;
+        logdb::registra(conn, dados.log(&origem, &destino, req_usuario))?;
+        Ok(destino)
+    })
+}
+
+/// Retorna todos os dados armazenados a respeito de um cliente (veja
+/// [`DadosCliente`]), ou [`Error::NotFound`] caso o mesmo não exista.
+#[tracing::instrument(name = "cliente::exporta", skip(conn))]
+pub fn exporta(conn: &PgConnection, req_id: i32) -> Result<DadosCliente, Error> {
+    conn.transaction(|| {
+        let (cliente, enderecos, contatos) = consulta_detalhada(conn, req_id)?;
+        let log = logdb::lista_registro(conn, "cliente", req_id)?;
+        Ok(DadosCliente {
+            cliente,
+            enderecos,
+            contatos,
+            log,
+        })
+    })
+}
+
+/// Anonimiza um cliente em uma única transação, de forma irreversível (veja
+/// [`model::privacidade`](crate::model::privacidade)). A anonimização é
+/// registrada no log de operações, em nome de `req_usuario`. Retorna o
+/// cliente anonimizado, ou [`Error::NotFound`] caso o mesmo não exista.
+#[tracing::instrument(name = "cliente::anonimiza", skip(conn))]
+pub fn anonimiza(conn: &PgConnection, req_id: i32, req_usuario: &str) -> Result<Cliente, Error> {
+    use crate::model::schema::{cliente, contato, endereco};
+
+    conn.transaction(|| {
+        let mut c = cliente::table
+            .find(req_id)
+            .for_update()
+            .first::<Cliente>(conn)?;
+        privacidade::anonimiza_cliente(&mut c);
+        let c = diesel::update(cliente::table.find(c.id))
+            .set((
+                cliente::nome.eq(&c.nome),
+                cliente::docto.eq(&c.docto),
+                cliente::docto_indice.eq(&c.docto_indice),
+                cliente::ativo.eq(c.ativo),
+            ))
+            .get_result::<Cliente>(conn)?;
+
+        for mut e in Endereco::belonging_to(&c).load::<Endereco>(conn)? {
+            privacidade::anonimiza_endereco(&mut e);
+            diesel::update(endereco::table.find(e.id))
+                .set((
+                    endereco::logradouro.eq(&e.logradouro),
+                    endereco::numero.eq(&e.numero),
+                    endereco::complemento.eq(&e.complemento),
+                    endereco::bairro.eq(&e.bairro),
+                    endereco::cep.eq(&e.cep),
+                ))
+                .execute(conn)?;
+        }
+        diesel::delete(contato::table.filter(contato::cliente_id.eq(c.id))).execute(conn)?;
+
+        logdb::anonimiza(conn, "cliente", c.id)?;
+        logdb::registra(conn, privacidade::log_anonimizacao(c.id, req_usuario))?;
+        Ok(c)
+    })
+}
+
+/// Retorna os documentos armazenados de até `limite` clientes, por ordem de
+/// ID, a partir do cliente seguinte a `apos_id`. Cada item contém o ID, o
+/// documento (possivelmente cifrado) e seu índice cego.
+#[tracing::instrument(name = "cliente::doctos", skip(conn))]
+pub fn doctos(
+    conn: &PgConnection,
+    apos_id: i32,
+    limite: i64,
+) -> Result<Vec<(i32, String, Option<String>)>, Error> {
+    use crate::model::schema::cliente::dsl::*;
+
+    cliente
+        .select((id, docto, docto_indice))
+        .filter(id.gt(apos_id))
+        .order(id)
+        .limit(limite)
+        .load(conn)
+}
+
+/// Grava os documentos recifrados de vários clientes, em uma única transação.
+/// Um documento só é sobrescrito caso não tenha sido modificado desde sua
+/// leitura. Retorna o número de clientes atualizados.
+#[tracing::instrument(name = "cliente::grava_doctos", skip_all)]
+pub fn grava_doctos(conn: &PgConnection, recifrados: Vec<DoctoRecifrado>) -> Result<usize, Error> {
+    use crate::model::schema::cliente::dsl::*;
+
+    conn.transaction(|| {
+        let mut total = 0;
+        for r in recifrados {
+            total += diesel::update(cliente.filter(id.eq(r.id)).filter(docto.eq(r.anterior)))
+                .set((docto.eq(r.docto), docto_indice.eq(r.docto_indice)))
+                .execute(conn)?;
+        }
+        Ok(total)
+    })
+}
+
 /// Remove um cliente, através do ID requisitado, caso o mesmo exista
 /// no banco de dados.
+#[tracing::instrument(name = "cliente::remove", skip(conn))]
 pub fn remove(conn: &PgConnection, req_id: i32) -> Result<(), Error> {
     use crate::model::schema::cliente::dsl::*;
     diesel::delete(cliente.filter(id.eq(&req_id)))
@@ -0,0 +1,62 @@
+// controller/contato.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este módulo engloba as estruturas do controller de contatos de clientes.
+//!
+//! A existência do cliente, assim como a unicidade do contato principal de
+//! cada tipo, são garantidas pelas restrições do banco de dados.
+
+use crate::model::contato::*;
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::PgConnection;
+
+/// Realiza o cadastro de um único contato, retornando o contato
+/// recém-cadastrado em caso de sucesso.
+#[tracing::instrument(name = "contato::cadastra", skip_all)]
+pub fn cadastra(conn: &PgConnection, dados: NovoContato) -> Result<Contato, Error> {
+    diesel::insert_into(crate::model::schema::contato::table)
+        .values(&dados)
+        .get_result::<Contato>(conn)
+}
+
+/// Retorna todos os contatos de um cliente, por ordem de ID. Caso o cliente
+/// não exista, retorna [`Error::NotFound`].
+#[tracing::instrument(name = "contato::lista", skip(conn))]
+pub fn lista(conn: &PgConnection, req_cliente_id: i32) -> Result<Vec<Contato>, Error> {
+    use crate::model::schema::contato::dsl::*;
+
+    let c = crate::controller::cliente::consulta(conn, req_cliente_id)?;
+    Contato::belonging_to(&c).order(id).load::<Contato>(conn)
+}
+
+/// Atualiza os dados de um contato, retornando o contato atualizado. Caso o
+/// contato não exista, retorna [`Error::NotFound`].
+#[tracing::instrument(name = "contato::atualiza", skip_all, fields(id = dados.id))]
+pub fn atualiza(conn: &PgConnection, dados: AlteraContato) -> Result<Contato, Error> {
+    diesel::update(&dados)
+        .set(&dados)
+        .get_result::<Contato>(conn)
+}
+
+/// Remove um contato, através do ID requisitado, caso o mesmo exista.
+#[tracing::instrument(name = "contato::remove", skip(conn))]
+pub fn remove(conn: &PgConnection, req_id: i32) -> Result<(), Error> {
+    use crate::model::schema::contato::dsl::*;
+    diesel::delete(contato.filter(id.eq(&req_id)))
+        .execute(conn)
+        .map(|_| ())
+}
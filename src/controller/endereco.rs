@@ -0,0 +1,60 @@
+// controller/endereco.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este módulo engloba as estruturas do controller de endereços de clientes,
+//! e do catálogo de tipos de endereço.
+//!
+//! A existência do cliente e do tipo de endereço, assim como a unicidade do
+//! endereço principal de cada tipo, são garantidas pelas restrições do banco
+//! de dados.
+
+use crate::model::endereco::*;
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::PgConnection;
+
+/// Realiza o cadastro de um único endereço, retornando o endereço
+/// recém-cadastrado em caso de sucesso.
+#[tracing::instrument(name = "endereco::cadastra", skip_all)]
+pub fn cadastra(conn: &PgConnection, dados: NovoEndereco) -> Result<Endereco, Error> {
+    diesel::insert_into(crate::model::schema::endereco::table)
+        .values(&dados)
+        .get_result::<Endereco>(conn)
+}
+
+/// Remove um endereço, através do ID requisitado, caso o mesmo exista.
+#[tracing::instrument(name = "endereco::remove", skip(conn))]
+pub fn remove(conn: &PgConnection, req_id: i32) -> Result<(), Error> {
+    use crate::model::schema::endereco::dsl::*;
+    diesel::delete(endereco.filter(id.eq(&req_id)))
+        .execute(conn)
+        .map(|_| ())
+}
+
+/// Retorna todos os tipos de endereço do catálogo, por ordem de código.
+#[tracing::instrument(name = "endereco::lista_tipos", skip(conn))]
+pub fn lista_tipos(conn: &PgConnection) -> Result<Vec<TipoEndereco>, Error> {
+    use crate::model::schema::tipo_endereco::dsl::*;
+    tipo_endereco.order(id).load::<TipoEndereco>(conn)
+}
+
+/// Cadastra um novo tipo de endereço no catálogo.
+#[tracing::instrument(name = "endereco::cadastra_tipo", skip(conn))]
+pub fn cadastra_tipo(conn: &PgConnection, tipo: TipoEndereco) -> Result<TipoEndereco, Error> {
+    diesel::insert_into(crate::model::schema::tipo_endereco::table)
+        .values(&tipo)
+        .get_result::<TipoEndereco>(conn)
+}
@@ -0,0 +1,86 @@
+// controller/estoque.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este módulo engloba as estruturas do controller de estoque de produtos.
+//!
+//! As regras de cálculo do estoque são definidas em
+//! [`Estoque::movimenta`]; o registro de estoque de cada produto é travado
+//! durante a movimentação, de forma que movimentações simultâneas sejam
+//! aplicadas em sequência.
+
+use crate::model::estoque::*;
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::PgConnection;
+
+/// Retorna o estoque de um produto. Produtos sem movimentações possuem
+/// estoque zerado; caso o produto não exista, retorna [`Error::NotFound`].
+#[tracing::instrument(name = "estoque::consulta", skip(conn))]
+pub fn consulta(conn: &PgConnection, req_produto_id: i32) -> Result<Estoque, Error> {
+    use crate::model::schema::estoque::dsl::*;
+
+    let p = crate::controller::produto::consulta(conn, req_produto_id)?;
+    Ok(estoque
+        .find(p.id)
+        .first::<Estoque>(conn)
+        .optional()?
+        .unwrap_or_else(|| Estoque::vazio(p.id)))
+}
+
+/// Registra uma movimentação de estoque, retornando o estoque atualizado do
+/// produto. Caso o produto não exista, a restrição de chave estrangeira da
+/// movimentação é violada.
+#[tracing::instrument(name = "estoque::movimenta", skip_all, fields(produto_id = dados.produto_id))]
+pub fn movimenta(conn: &PgConnection, dados: NovoMovEstoque) -> Result<Estoque, Error> {
+    use crate::model::schema::estoque::dsl::*;
+
+    conn.transaction(|| {
+        diesel::insert_into(crate::model::schema::mov_estoque::table)
+            .values(&dados)
+            .execute(conn)?;
+
+        let mut atual = estoque
+            .find(dados.produto_id)
+            .for_update()
+            .first::<Estoque>(conn)
+            .optional()?
+            .unwrap_or_else(|| Estoque::vazio(dados.produto_id));
+        atual.movimenta(&dados);
+
+        diesel::insert_into(estoque)
+            .values(&atual)
+            .on_conflict(produto_id)
+            .do_update()
+            .set(&atual)
+            .get_result::<Estoque>(conn)
+    })
+}
+
+/// Retorna as movimentações de estoque de um produto, por ordem de ID. Caso
+/// o produto não exista, retorna [`Error::NotFound`].
+#[tracing::instrument(name = "estoque::lista_movimentos", skip(conn))]
+pub fn lista_movimentos(
+    conn: &PgConnection,
+    req_produto_id: i32,
+) -> Result<Vec<MovEstoque>, Error> {
+    use crate::model::schema::mov_estoque::dsl::*;
+
+    let p = crate::controller::produto::consulta(conn, req_produto_id)?;
+    mov_estoque
+        .filter(produto_id.eq(p.id))
+        .order(id)
+        .load::<MovEstoque>(conn)
+}
@@ -18,4 +18,15 @@
 //! Cada submódulo possui estruturas e funções que possibilitam manipular e usar
 //! regras de negócio da aplicação.
 
+pub mod cep;
 pub mod cliente;
+pub mod contato;
+pub mod endereco;
+pub mod estoque;
+pub mod logdb;
+pub mod produto;
+pub mod regras;
+pub mod usuario;
+
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
@@ -0,0 +1,57 @@
+// controller/produto.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este módulo engloba as estruturas do controller de produtos.
+//!
+//! O estoque e as movimentações de um produto são removidos junto com o
+//! mesmo, através das restrições do banco de dados.
+
+use crate::model::produto::*;
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::PgConnection;
+
+/// Realiza o cadastro de um único produto, retornando o produto
+/// recém-cadastrado em caso de sucesso.
+#[tracing::instrument(name = "produto::cadastra", skip_all)]
+pub fn cadastra(conn: &PgConnection, dados: NovoProduto) -> Result<Produto, Error> {
+    diesel::insert_into(crate::model::schema::produto::table)
+        .values(&dados)
+        .get_result::<Produto>(conn)
+}
+
+/// Retorna um único produto, através do ID requisitado.
+#[tracing::instrument(name = "produto::consulta", skip(conn))]
+pub fn consulta(conn: &PgConnection, req_id: i32) -> Result<Produto, Error> {
+    use crate::model::schema::produto::dsl::*;
+    produto.find(req_id).first::<Produto>(conn)
+}
+
+/// Retorna todos os produtos, por ordem de ID.
+#[tracing::instrument(name = "produto::lista", skip(conn))]
+pub fn lista(conn: &PgConnection) -> Result<Vec<Produto>, Error> {
+    use crate::model::schema::produto::dsl::*;
+    produto.order(id).load::<Produto>(conn)
+}
+
+/// Remove um produto, através do ID requisitado, caso o mesmo exista.
+#[tracing::instrument(name = "produto::remove", skip(conn))]
+pub fn remove(conn: &PgConnection, req_id: i32) -> Result<(), Error> {
+    use crate::model::schema::produto::dsl::*;
+    diesel::delete(produto.filter(id.eq(&req_id)))
+        .execute(conn)
+        .map(|_| ())
+}
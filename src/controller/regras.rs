@@ -0,0 +1,196 @@
+// controller/regras.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este módulo implementa o motor de regras de crédito dos clientes.
+//!
+//! Cada [`Regra`] avalia a situação de um cliente, indicando se o mesmo deve
+//! ser bloqueado. As regras são reavaliadas sempre que os dados de crédito de
+//! um cliente são alterados, através de [`aplica`]: clientes que violam alguma
+//! regra são bloqueados automaticamente, e clientes bloqueados automaticamente
+//! são desbloqueados assim que voltam a respeitar todas as regras. Bloqueios
+//! feitos por outros meios nunca são removidos pelo motor de regras.
+//!
+//! Atualmente, os débitos de crédito são as únicas operações feitas em nome
+//! de um cliente: não há pedidos, e as movimentações de estoque não são
+//! associadas a clientes. Os débitos são recusados por [`movimenta`] enquanto
+//! o cliente estiver bloqueado, ou caso violem alguma regra, exceto quando o
+//! bloqueio do cliente for requisitado explicitamente. Novas operações em
+//! nome de clientes devem ser precedidas por [`verifica_operacao`], que as
+//! recusa enquanto o cliente estiver bloqueado. Como este módulo não depende
+//! do banco de dados, ele é usado da mesma forma por todos os repositórios.
+
+use crate::model::cliente::Cliente;
+use std::fmt;
+
+/// Regra de negócio que pode causar o bloqueio de um cliente.
+pub trait Regra: Sync {
+    /// Avalia a regra para um cliente, retornando o motivo do bloqueio caso
+    /// a mesma seja violada.
+    fn avalia(&self, cliente: &Cliente) -> Option<String>;
+}
+
+/// Regra que bloqueia clientes cujo saldo devedor excede o limite de crédito.
+/// Clientes sem limite definido nunca violam esta regra.
+pub struct LimiteCredito;
+
+impl Regra for LimiteCredito {
+    fn avalia(&self, cliente: &Cliente) -> Option<String> {
+        let limite = cliente.limite_credito?;
+        if cliente.saldo_devedor > limite {
+            Some(format!(
+                "Limite de crédito excedido (saldo devedor de {} centavos, limite de {} centavos)",
+                cliente.saldo_devedor, limite
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// Regras avaliadas pelo motor, em ordem.
+pub const REGRAS: &[&dyn Regra] = &[&LimiteCredito];
+
+/// Recusa de uma operação em nome de um cliente bloqueado.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClienteBloqueado {
+    /// Motivo do bloqueio do cliente.
+    pub motivo: String,
+}
+
+impl fmt::Display for ClienteBloqueado {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cliente bloqueado: {}", self.motivo)
+    }
+}
+
+impl std::error::Error for ClienteBloqueado {}
+
+/// Recusa de um movimento de crédito (veja [`movimenta`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MovimentoRecusado {
+    /// O cliente está bloqueado.
+    Bloqueado(ClienteBloqueado),
+    /// O débito violaria uma regra, como o limite de crédito; o motivo é o
+    /// mesmo retornado pela regra.
+    RegraViolada(String),
+}
+
+impl fmt::Display for MovimentoRecusado {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MovimentoRecusado::Bloqueado(e) => e.fmt(f),
+            MovimentoRecusado::RegraViolada(motivo) => write!(f, "Débito recusado: {}", motivo),
+        }
+    }
+}
+
+impl std::error::Error for MovimentoRecusado {}
+
+impl From<ClienteBloqueado> for MovimentoRecusado {
+    fn from(e: ClienteBloqueado) -> Self {
+        MovimentoRecusado::Bloqueado(e)
+    }
+}
+
+/// Retorna o motivo da primeira regra violada por um cliente, caso exista.
+pub fn avalia(cliente: &Cliente) -> Option<String> {
+    REGRAS.iter().find_map(|regra| regra.avalia(cliente))
+}
+
+/// Reavalia as regras para um cliente, bloqueando-o ou desbloqueando-o
+/// automaticamente. Retorna verdadeiro caso a situação do cliente tenha sido
+/// alterada, e deva ser gravada.
+pub fn aplica(cliente: &mut Cliente) -> bool {
+    match avalia(cliente) {
+        Some(motivo) if !cliente.bloqueado => {
+            tracing::info!(id = cliente.id, motivo = %motivo, "Cliente bloqueado automaticamente");
+            cliente.bloqueado = true;
+            cliente.bloqueio_automatico = true;
+            true
+        }
+        None if cliente.bloqueado && cliente.bloqueio_automatico => {
+            tracing::info!(id = cliente.id, "Cliente desbloqueado automaticamente");
+            cliente.bloqueado = false;
+            cliente.bloqueio_automatico = false;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Verifica se uma operação pode ser feita em nome de um cliente, recusando-a
+/// caso o mesmo esteja bloqueado.
+pub fn verifica_operacao(cliente: &Cliente) -> Result<(), ClienteBloqueado> {
+    if !cliente.bloqueado {
+        return Ok(());
+    }
+
+    let motivo = if cliente.bloqueio_automatico {
+        avalia(cliente)
+    } else {
+        None
+    };
+    Err(ClienteBloqueado {
+        motivo: motivo.unwrap_or_else(|| "Bloqueio manual".to_string()),
+    })
+}
+
+/// Aplica um movimento de crédito a um cliente, verificando se o mesmo pode
+/// ser feito e reavaliando as regras em seguida.
+///
+/// Débitos (valores positivos) são recusados caso o cliente esteja
+/// bloqueado, ou caso violem alguma regra, como ao exceder o limite de
+/// crédito; neste caso, o cliente não é alterado. Caso `bloqueia_excedente`
+/// seja verdadeiro, os débitos que violam as regras são aceitos, e o cliente
+/// é bloqueado. Pagamentos (valores negativos) são sempre aceitos.
+pub fn movimenta(
+    cliente: &mut Cliente,
+    valor: i64,
+    bloqueia_excedente: bool,
+) -> Result<(), MovimentoRecusado> {
+    if valor > 0 {
+        verifica_operacao(cliente)?;
+    }
+
+    let saldo_anterior = cliente.saldo_devedor;
+    cliente.saldo_devedor = saldo_anterior.saturating_add(valor);
+    if valor > 0 && !bloqueia_excedente {
+        if let Some(motivo) = avalia(cliente) {
+            cliente.saldo_devedor = saldo_anterior;
+            return Err(MovimentoRecusado::RegraViolada(motivo));
+        }
+    }
+    aplica(cliente);
+    Ok(())
+}
+
+/// Incorpora os dados de crédito de um cliente a outro, durante a mescla de
+/// clientes duplicados, reavaliando as regras em seguida.
+///
+/// Os saldos devedores são somados, e o limite de crédito do destino é
+/// mantido; caso o destino não possua limite, o limite da origem é usado.
+/// Um bloqueio manual da origem também é mantido no destino.
+pub fn mescla(destino: &mut Cliente, origem: &Cliente) {
+    destino.saldo_devedor = destino.saldo_devedor.saturating_add(origem.saldo_devedor);
+    if destino.limite_credito.is_none() {
+        destino.limite_credito = origem.limite_credito;
+    }
+    if origem.bloqueado && !origem.bloqueio_automatico {
+        destino.bloqueado = true;
+        destino.bloqueio_automatico = false;
+    }
+    aplica(destino);
+}
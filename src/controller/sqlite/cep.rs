@@ -0,0 +1,52 @@
+// cep.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este módulo engloba as estruturas do controller da base de CEPs para o
+//! SQLite.
+
+use crate::model::cep::Cep;
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::SqliteConnection;
+
+/// Consulta os dados de um CEP, no formato `00000-000`.
+#[tracing::instrument(name = "cep::consulta", skip(conn))]
+pub fn consulta(conn: &SqliteConnection, req_codigo: &str) -> Result<Cep, Error> {
+    use crate::model::schema_sqlite::cep::dsl::*;
+    cep.find(req_codigo).first::<Cep>(conn)
+}
+
+/// Importa um lote de CEPs para a base, substituindo os dados de CEPs já
+/// existentes. Retorna o número de CEPs importados.
+#[tracing::instrument(name = "cep::importa", skip_all, fields(quantidade = ceps.len()))]
+pub fn importa(conn: &SqliteConnection, ceps: &[Cep]) -> Result<usize, Error> {
+    use crate::model::schema_sqlite::cep::dsl::*;
+
+    conn.transaction(|| {
+        for novo in ceps {
+            diesel::replace_into(cep)
+                .values((
+                    codigo.eq(&novo.codigo),
+                    logradouro.eq(&novo.logradouro),
+                    bairro.eq(&novo.bairro),
+                    cidade.eq(&novo.cidade),
+                    uf.eq(&novo.uf),
+                ))
+                .execute(conn)?;
+        }
+        Ok(ceps.len())
+    })
+}
@@ -0,0 +1,418 @@
+// cliente.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este módulo engloba as estruturas do controller do cliente para o SQLite.
+
+use super::last_insert_rowid;
+use super::logdb;
+use crate::cifra::Cifra;
+use crate::controller::cliente::CLIENTE_PAGE_SIZE;
+use crate::controller::regras::{self, MovimentoRecusado};
+use crate::model::busca::{self, ClienteEncontrado};
+use crate::model::cliente::*;
+use crate::model::contato::Contato;
+use crate::model::duplicado::{self, GrupoDuplicados, Mescla};
+use crate::model::endereco::{ClienteDetalhado, Endereco};
+use crate::model::privacidade::{self, DadosCliente};
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::SqliteConnection;
+
+/// Realiza o cadastro de um único cliente, de acordo com os dados básicos
+/// necessários para cadastro. Requer uma conexão com o banco, e o cliente
+/// recém-cadastrado será retornado, em caso de sucesso.
+#[tracing::instrument(name = "cliente::cadastra", skip_all)]
+pub fn cadastra(conn: &SqliteConnection, dados: NovoCliente) -> Result<Cliente, Error> {
+    use crate::model::schema_sqlite::cliente::dsl::*;
+
+    conn.transaction(|| {
+        diesel::insert_into(cliente)
+            .values((
+                tipo.eq(dados.tipo),
+                nome.eq(dados.nome),
+                pj.eq(dados.pj),
+                docto.eq(dados.docto),
+                ativo.eq(dados.ativo),
+                bloqueado.eq(dados.bloqueado),
+                docto_indice.eq(dados.docto_indice),
+            ))
+            .execute(conn)?;
+
+        let novo_id = diesel::select(last_insert_rowid).get_result::<i32>(conn)?;
+        cliente.find(novo_id).first::<Cliente>(conn)
+    })
+}
+
+/// Consulta os dados de um único cliente, através do ID requisitado.
+/// Em caso de sucesso, retorna uma estrutura única contendo tais dados.
+#[tracing::instrument(name = "cliente::consulta", skip(conn))]
+pub fn consulta(conn: &SqliteConnection, req_id: i32) -> Result<Cliente, Error> {
+    use crate::model::schema_sqlite::cliente::dsl::*;
+    cliente.filter(id.eq(&req_id)).first::<Cliente>(conn)
+}
+
+/// Consulta um cliente através de seu documento, como em
+/// [`controller::cliente::consulta_docto`](crate::controller::cliente::consulta_docto).
+/// Os clientes ainda sem índice são todos comparados pela aplicação.
+#[tracing::instrument(name = "cliente::consulta_docto", skip_all)]
+pub fn consulta_docto(
+    conn: &SqliteConnection,
+    req_docto: &str,
+    cifra: &Cifra,
+) -> Result<Cliente, Error> {
+    use crate::model::schema_sqlite::cliente::dsl::*;
+
+    let chave = duplicado::chave_texto(req_docto, cifra).ok_or(Error::NotFound)?;
+    cliente
+        .filter(
+            docto_indice
+                .eq(cifra.indice(req_docto))
+                .or(docto_indice.is_null()),
+        )
+        .order(id)
+        .load::<Cliente>(conn)?
+        .into_iter()
+        .find(|c| duplicado::chave_docto(c, cifra).as_deref() == Some(&chave))
+        .ok_or(Error::NotFound)
+}
+
+/// Retorna uma lista de clientes, por ordem de ID, de acordo com a página
+/// requisitada.
+///
+/// As páginas começam a serem contadas a partir de 0. Em caso de sucesso,
+/// retorna um `Vec` contendo um número `CLIENTE_PAGE_SIZE` de clientes.
+#[tracing::instrument(name = "cliente::lista", skip(conn))]
+pub fn lista(conn: &SqliteConnection, pagina: i64) -> Result<Vec<Cliente>, Error> {
+    use crate::model::schema_sqlite::cliente::dsl::*;
+
+    cliente
+        .order(id)
+        .limit(CLIENTE_PAGE_SIZE)
+        .offset(pagina * CLIENTE_PAGE_SIZE)
+        .load::<Cliente>(conn)
+}
+
+/// Busca clientes cujos nomes sejam similares ao termo informado. Retorna no
+/// máximo `limite` clientes, da maior para a menor similaridade.
+///
+/// Como o SQLite não possui as extensões usadas no PostgreSQL, os nomes de
+/// todos os clientes são comparados com o termo pela aplicação (veja
+/// [`model::busca`](crate::model::busca)).
+#[tracing::instrument(name = "cliente::busca", skip(conn, termo))]
+pub fn busca(
+    conn: &SqliteConnection,
+    termo: &str,
+    limite: i64,
+) -> Result<Vec<ClienteEncontrado>, Error> {
+    use crate::model::schema_sqlite::cliente::dsl::*;
+
+    let clientes = cliente.order(id).load::<Cliente>(conn)?;
+    Ok(busca::busca(clientes, termo, limite))
+}
+
+/// Consulta os dados de um único cliente, junto com todos os seus endereços
+/// e contatos, por ordem de ID.
+#[tracing::instrument(name = "cliente::consulta_detalhada", skip(conn))]
+pub fn consulta_detalhada(conn: &SqliteConnection, req_id: i32) -> Result<ClienteDetalhado, Error> {
+    use crate::model::schema_sqlite::{contato, endereco};
+
+    let c = consulta(conn, req_id)?;
+    let enderecos = endereco::table
+        .filter(endereco::cliente_id.eq(c.id))
+        .order(endereco::id)
+        .load::<Endereco>(conn)?;
+    let contatos = contato::table
+        .filter(contato::cliente_id.eq(c.id))
+        .order(contato::id)
+        .load::<Contato>(conn)?;
+    Ok((c, enderecos, contatos))
+}
+
+/// Retorna uma página de clientes, como em [`lista`], incluindo opcionalmente
+/// os endereços e os contatos de cada cliente.
+///
+/// Os endereços e os contatos de todos os clientes da página são recuperados
+/// em uma consulta para cada entidade, e então agrupados por cliente.
+#[tracing::instrument(name = "cliente::lista_detalhada", skip(conn))]
+pub fn lista_detalhada(
+    conn: &SqliteConnection,
+    pagina: i64,
+    incluir_enderecos: bool,
+    incluir_contatos: bool,
+) -> Result<Vec<ClienteDetalhado>, Error> {
+    use crate::model::schema_sqlite::{contato, endereco};
+
+    let clientes = lista(conn, pagina)?;
+    let ids: Vec<i32> = clientes.iter().map(|c| c.id).collect();
+    let enderecos = if incluir_enderecos && !clientes.is_empty() {
+        endereco::table
+            .filter(endereco::cliente_id.eq_any(&ids))
+            .order(endereco::id)
+            .load::<Endereco>(conn)?
+            .grouped_by(&clientes)
+    } else {
+        vec![vec![]; clientes.len()]
+    };
+    let contatos = if incluir_contatos && !clientes.is_empty() {
+        contato::table
+            .filter(contato::cliente_id.eq_any(&ids))
+            .order(contato::id)
+            .load::<Contato>(conn)?
+            .grouped_by(&clientes)
+    } else {
+        vec![vec![]; clientes.len()]
+    };
+
+    Ok(clientes
+        .into_iter()
+        .zip(enderecos)
+        .zip(contatos)
+        .map(|((c, e), ct)| (c, e, ct))
+        .collect())
+}
+
+/// Grava os dados de crédito de um cliente, incluindo a situação de bloqueio
+/// definida pelo motor de regras.
+fn grava_credito(conn: &SqliteConnection, c: &Cliente) -> Result<Cliente, Error> {
+    use crate::model::schema_sqlite::cliente::dsl::*;
+
+    diesel::update(cliente.find(c.id))
+        .set((
+            limite_credito.eq(c.limite_credito),
+            saldo_devedor.eq(c.saldo_devedor),
+            bloqueado.eq(c.bloqueado),
+            bloqueio_automatico.eq(c.bloqueio_automatico),
+        ))
+        .execute(conn)?;
+    cliente.find(c.id).first::<Cliente>(conn)
+}
+
+/// Define o limite de crédito de um cliente, reavaliando as regras de
+/// crédito do mesmo. Um limite nulo remove o limite do cliente.
+#[tracing::instrument(name = "cliente::define_limite", skip(conn))]
+pub fn define_limite(
+    conn: &SqliteConnection,
+    req_id: i32,
+    limite: Option<i64>,
+) -> Result<Cliente, Error> {
+    conn.transaction(|| {
+        let mut c = consulta(conn, req_id)?;
+        c.limite_credito = limite;
+        regras::aplica(&mut c);
+        grava_credito(conn, &c)
+    })
+}
+
+/// Registra um movimento de crédito em nome de um cliente (veja
+/// [`regras::movimenta`]), retornando o cliente atualizado. Caso o movimento
+/// seja recusado pelas regras de crédito, nada é gravado.
+#[tracing::instrument(name = "cliente::registra_movimento", skip(conn))]
+pub fn registra_movimento(
+    conn: &SqliteConnection,
+    req_id: i32,
+    valor: i64,
+    bloqueia_excedente: bool,
+) -> Result<Result<Cliente, MovimentoRecusado>, Error> {
+    conn.transaction(|| {
+        let mut c = consulta(conn, req_id)?;
+        if let Err(recusa) = regras::movimenta(&mut c, valor, bloqueia_excedente) {
+            return Ok(Err(recusa));
+        }
+        grava_credito(conn, &c).map(Ok)
+    })
+}
+
+/// Detecta grupos de clientes possivelmente duplicados (veja
+/// [`model::duplicado`](crate::model::duplicado)). Como o SQLite não possui
+/// as extensões usadas no PostgreSQL, todos os clientes são comparados entre
+/// si pela aplicação.
+#[tracing::instrument(name = "cliente::detecta_duplicados", skip_all)]
+pub fn detecta_duplicados(
+    conn: &SqliteConnection,
+    cifra: &Cifra,
+) -> Result<Vec<GrupoDuplicados>, Error> {
+    use crate::model::schema_sqlite::cliente::dsl::*;
+
+    let clientes = cliente.order(id).load::<Cliente>(conn)?;
+    Ok(duplicado::detecta(clientes, cifra))
+}
+
+/// Mescla um cliente duplicado em outro, em uma única transação, como em
+/// [`controller::cliente::mescla`](crate::controller::cliente::mescla).
+#[tracing::instrument(name = "cliente::mescla", skip(conn))]
+pub fn mescla(conn: &SqliteConnection, dados: Mescla, req_usuario: &str) -> Result<Cliente, Error> {
+    use crate::model::schema_sqlite::{contato, endereco};
+
+    conn.transaction(|| {
+        let origem = consulta(conn, dados.origem_id)?;
+        let mut destino = consulta(conn, dados.destino_id)?;
+
+        let principais = endereco::table
+            .select(endereco::tipo)
+            .filter(endereco::cliente_id.eq(destino.id))
+            .filter(endereco::principal)
+            .load::<i16>(conn)?;
+        diesel::update(
+            endereco::table
+                .filter(endereco::cliente_id.eq(origem.id))
+                .filter(endereco::principal)
+                .filter(endereco::tipo.eq_any(principais)),
+        )
+        .set(endereco::principal.eq(false))
+        .execute(conn)?;
+        diesel::update(endereco::table.filter(endereco::cliente_id.eq(origem.id)))
+            .set(endereco::cliente_id.eq(destino.id))
+            .execute(conn)?;
+
+        let principais = contato::table
+            .select(contato::tipo)
+            .filter(contato::cliente_id.eq(destino.id))
+            .filter(contato::principal)
+            .load::<i16>(conn)?;
+        diesel::update(
+            contato::table
+                .filter(contato::cliente_id.eq(origem.id))
+                .filter(contato::principal)
+                .filter(contato::tipo.eq_any(principais)),
+        )
+        .set(contato::principal.eq(false))
+        .execute(conn)?;
+        diesel::update(contato::table.filter(contato::cliente_id.eq(origem.id)))
+            .set(contato::cliente_id.eq(destino.id))
+            .execute(conn)?;
+
+        regras::mescla(&mut destino, &origem);
+        let destino = grava_credito(conn, &destino)?;
+        remove(conn, origem.id)?;
+
+        logdb::transfere(conn, "cliente", origem.id, destino.id)?;
+        logdb::registra(conn, dados.log(&origem, &destino, req_usuario))?;
+        Ok(destino)
+    })
+}
+
+/// Retorna todos os dados armazenados a respeito de um cliente (veja
+/// [`DadosCliente`]), ou [`Error::NotFound`] caso o mesmo não exista.
+#[tracing::instrument(name = "cliente::exporta", skip(conn))]
+pub fn exporta(conn: &SqliteConnection, req_id: i32) -> Result<DadosCliente, Error> {
+    conn.transaction(|| {
+        let (cliente, enderecos, contatos) = consulta_detalhada(conn, req_id)?;
+        let log = logdb::lista_registro(conn, "cliente", req_id)?;
+        Ok(DadosCliente {
+            cliente,
+            enderecos,
+            contatos,
+            log,
+        })
+    })
+}
+
+/// Anonimiza um cliente em uma única transação, de forma irreversível (veja
+/// [`model::privacidade`](crate::model::privacidade)). A anonimização é
+/// registrada no log de operações, em nome de `req_usuario`. Retorna o
+/// cliente anonimizado, ou [`Error::NotFound`] caso o mesmo não exista.
+#[tracing::instrument(name = "cliente::anonimiza", skip(conn))]
+pub fn anonimiza(
+    conn: &SqliteConnection,
+    req_id: i32,
+    req_usuario: &str,
+) -> Result<Cliente, Error> {
+    use crate::model::schema_sqlite::{cliente, contato, endereco};
+
+    conn.transaction(|| {
+        let mut c = cliente::table.find(req_id).first::<Cliente>(conn)?;
+        privacidade::anonimiza_cliente(&mut c);
+        diesel::update(cliente::table.find(c.id))
+            .set((
+                cliente::nome.eq(&c.nome),
+                cliente::docto.eq(&c.docto),
+                cliente::docto_indice.eq(&c.docto_indice),
+                cliente::ativo.eq(c.ativo),
+            ))
+            .execute(conn)?;
+
+        let enderecos = endereco::table
+            .filter(endereco::cliente_id.eq(c.id))
+            .load::<Endereco>(conn)?;
+        for mut e in enderecos {
+            privacidade::anonimiza_endereco(&mut e);
+            diesel::update(endereco::table.find(e.id))
+                .set((
+                    endereco::logradouro.eq(&e.logradouro),
+                    endereco::numero.eq(&e.numero),
+                    endereco::complemento.eq(&e.complemento),
+                    endereco::bairro.eq(&e.bairro),
+                    endereco::cep.eq(&e.cep),
+                ))
+                .execute(conn)?;
+        }
+        diesel::delete(contato::table.filter(contato::cliente_id.eq(c.id))).execute(conn)?;
+
+        logdb::anonimiza(conn, "cliente", c.id)?;
+        logdb::registra(conn, privacidade::log_anonimizacao(c.id, req_usuario))?;
+        Ok(c)
+    })
+}
+
+/// Retorna os documentos armazenados de até `limite` clientes, a partir do
+/// cliente seguinte a `apos_id`, como em
+/// [`controller::cliente::doctos`](crate::controller::cliente::doctos).
+#[tracing::instrument(name = "cliente::doctos", skip(conn))]
+pub fn doctos(
+    conn: &SqliteConnection,
+    apos_id: i32,
+    limite: i64,
+) -> Result<Vec<(i32, String, Option<String>)>, Error> {
+    use crate::model::schema_sqlite::cliente::dsl::*;
+
+    cliente
+        .select((id, docto, docto_indice))
+        .filter(id.gt(apos_id))
+        .order(id)
+        .limit(limite)
+        .load(conn)
+}
+
+/// Grava os documentos recifrados de vários clientes, em uma única
+/// transação, como em
+/// [`controller::cliente::grava_doctos`](crate::controller::cliente::grava_doctos).
+#[tracing::instrument(name = "cliente::grava_doctos", skip_all)]
+pub fn grava_doctos(
+    conn: &SqliteConnection,
+    recifrados: Vec<DoctoRecifrado>,
+) -> Result<usize, Error> {
+    use crate::model::schema_sqlite::cliente::dsl::*;
+
+    conn.transaction(|| {
+        let mut total = 0;
+        for r in recifrados {
+            total += diesel::update(cliente.filter(id.eq(r.id)).filter(docto.eq(r.anterior)))
+                .set((docto.eq(r.docto), docto_indice.eq(r.docto_indice)))
+                .execute(conn)?;
+        }
+        Ok(total)
+    })
+}
+
+/// Remove um cliente, através do ID requisitado, caso o mesmo exista
+/// no banco de dados.
+#[tracing::instrument(name = "cliente::remove", skip(conn))]
+pub fn remove(conn: &SqliteConnection, req_id: i32) -> Result<(), Error> {
+    use crate::model::schema_sqlite::cliente::dsl::*;
+    diesel::delete(cliente.filter(id.eq(&req_id)))
+        .execute(conn)
+        .map(|_| ())
+}
@@ -0,0 +1,90 @@
+// controller/contato.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este módulo engloba as estruturas do controller de contatos para o
+//! SQLite.
+
+use super::last_insert_rowid;
+use crate::model::contato::*;
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::SqliteConnection;
+
+/// Realiza o cadastro de um único contato, retornando o contato
+/// recém-cadastrado em caso de sucesso.
+#[tracing::instrument(name = "contato::cadastra", skip_all)]
+pub fn cadastra(conn: &SqliteConnection, dados: NovoContato) -> Result<Contato, Error> {
+    use crate::model::schema_sqlite::contato::dsl::*;
+
+    conn.transaction(|| {
+        diesel::insert_into(contato)
+            .values((
+                cliente_id.eq(dados.cliente_id),
+                tipo.eq(dados.tipo),
+                valor.eq(dados.valor),
+                principal.eq(dados.principal),
+                observacao.eq(dados.observacao),
+            ))
+            .execute(conn)?;
+
+        let novo_id = diesel::select(last_insert_rowid).get_result::<i32>(conn)?;
+        contato.find(novo_id).first::<Contato>(conn)
+    })
+}
+
+/// Retorna todos os contatos de um cliente, por ordem de ID. Caso o cliente
+/// não exista, retorna [`Error::NotFound`].
+#[tracing::instrument(name = "contato::lista", skip(conn))]
+pub fn lista(conn: &SqliteConnection, req_cliente_id: i32) -> Result<Vec<Contato>, Error> {
+    use crate::model::schema_sqlite::contato::dsl::*;
+
+    let c = super::cliente::consulta(conn, req_cliente_id)?;
+    contato
+        .filter(cliente_id.eq(c.id))
+        .order(id)
+        .load::<Contato>(conn)
+}
+
+/// Atualiza os dados de um contato, retornando o contato atualizado. Caso o
+/// contato não exista, retorna [`Error::NotFound`].
+#[tracing::instrument(name = "contato::atualiza", skip_all, fields(id = dados.id))]
+pub fn atualiza(conn: &SqliteConnection, dados: AlteraContato) -> Result<Contato, Error> {
+    use crate::model::schema_sqlite::contato::dsl::*;
+
+    conn.transaction(|| {
+        let alterados = diesel::update(contato.find(dados.id))
+            .set((
+                tipo.eq(dados.tipo),
+                valor.eq(&dados.valor),
+                principal.eq(dados.principal),
+                observacao.eq(&dados.observacao),
+            ))
+            .execute(conn)?;
+        if alterados == 0 {
+            return Err(Error::NotFound);
+        }
+        contato.find(dados.id).first::<Contato>(conn)
+    })
+}
+
+/// Remove um contato, através do ID requisitado, caso o mesmo exista.
+#[tracing::instrument(name = "contato::remove", skip(conn))]
+pub fn remove(conn: &SqliteConnection, req_id: i32) -> Result<(), Error> {
+    use crate::model::schema_sqlite::contato::dsl::*;
+    diesel::delete(contato.filter(id.eq(&req_id)))
+        .execute(conn)
+        .map(|_| ())
+}
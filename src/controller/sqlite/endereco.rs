@@ -0,0 +1,78 @@
+// endereco.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este módulo engloba as estruturas do controller de endereços para o
+//! SQLite.
+
+use super::last_insert_rowid;
+use crate::model::endereco::*;
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::SqliteConnection;
+
+/// Realiza o cadastro de um único endereço, retornando o endereço
+/// recém-cadastrado em caso de sucesso.
+#[tracing::instrument(name = "endereco::cadastra", skip_all)]
+pub fn cadastra(conn: &SqliteConnection, dados: NovoEndereco) -> Result<Endereco, Error> {
+    use crate::model::schema_sqlite::endereco::dsl::*;
+
+    conn.transaction(|| {
+        diesel::insert_into(endereco)
+            .values((
+                cliente_id.eq(dados.cliente_id),
+                tipo.eq(dados.tipo),
+                logradouro.eq(dados.logradouro),
+                numero.eq(dados.numero),
+                complemento.eq(dados.complemento),
+                bairro.eq(dados.bairro),
+                uf.eq(dados.uf),
+                cidade.eq(dados.cidade),
+                cep.eq(dados.cep),
+                principal.eq(dados.principal),
+            ))
+            .execute(conn)?;
+
+        let novo_id = diesel::select(last_insert_rowid).get_result::<i32>(conn)?;
+        endereco.find(novo_id).first::<Endereco>(conn)
+    })
+}
+
+/// Remove um endereço, através do ID requisitado, caso o mesmo exista.
+#[tracing::instrument(name = "endereco::remove", skip(conn))]
+pub fn remove(conn: &SqliteConnection, req_id: i32) -> Result<(), Error> {
+    use crate::model::schema_sqlite::endereco::dsl::*;
+    diesel::delete(endereco.filter(id.eq(&req_id)))
+        .execute(conn)
+        .map(|_| ())
+}
+
+/// Retorna todos os tipos de endereço do catálogo, por ordem de código.
+#[tracing::instrument(name = "endereco::lista_tipos", skip(conn))]
+pub fn lista_tipos(conn: &SqliteConnection) -> Result<Vec<TipoEndereco>, Error> {
+    use crate::model::schema_sqlite::tipo_endereco::dsl::*;
+    tipo_endereco.order(id).load::<TipoEndereco>(conn)
+}
+
+/// Cadastra um novo tipo de endereço no catálogo.
+#[tracing::instrument(name = "endereco::cadastra_tipo", skip(conn))]
+pub fn cadastra_tipo(conn: &SqliteConnection, novo: TipoEndereco) -> Result<TipoEndereco, Error> {
+    use crate::model::schema_sqlite::tipo_endereco::dsl::*;
+
+    diesel::insert_into(tipo_endereco)
+        .values((id.eq(novo.id), descricao.eq(&novo.descricao)))
+        .execute(conn)?;
+    Ok(novo)
+}
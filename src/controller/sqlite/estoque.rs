@@ -0,0 +1,141 @@
+// controller/sqlite/estoque.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este módulo engloba as estruturas do controller de estoque de produtos
+//! para o SQLite.
+//!
+//! O SQLite armazena as quantidades e os preços como números de ponto
+//! flutuante, que são convertidos para os decimais usados pelos models.
+
+use crate::model::estoque::*;
+use bigdecimal::BigDecimal;
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::SqliteConnection;
+
+/// Converte um número armazenado no SQLite para decimal.
+fn decimal(valor: f64) -> BigDecimal {
+    valor.to_string().parse().unwrap_or_default()
+}
+
+/// Converte um decimal para o número armazenado no SQLite.
+fn real(valor: &BigDecimal) -> f64 {
+    valor.to_string().parse().unwrap_or_default()
+}
+
+/// Movimentação de estoque, como armazenada no SQLite, cuja data e hora não
+/// possui fuso horário e é sempre gravada em UTC.
+type MovEstoqueSqlite = (i32, i32, String, f64, f64, NaiveDateTime, f64);
+
+/// Converte uma movimentação de estoque armazenada no SQLite.
+fn converte(
+    (id, produto_id, docto, quantidade, preco_frete, datahora, preco_unitario): MovEstoqueSqlite,
+) -> MovEstoque {
+    MovEstoque {
+        id,
+        produto_id,
+        docto,
+        quantidade: decimal(quantidade),
+        preco_frete: decimal(preco_frete),
+        datahora: Utc.from_utc_datetime(&datahora),
+        preco_unitario: decimal(preco_unitario),
+    }
+}
+
+/// Lê o registro de estoque de um produto, caso exista.
+fn le(conn: &SqliteConnection, req_produto_id: i32) -> Result<Option<Estoque>, Error> {
+    use crate::model::schema_sqlite::estoque::dsl::*;
+
+    Ok(estoque
+        .find(req_produto_id)
+        .first::<(i32, f64, f64)>(conn)
+        .optional()?
+        .map(|(p, q, preco)| Estoque {
+            produto_id: p,
+            quantidade: decimal(q),
+            precounitario: decimal(preco),
+        }))
+}
+
+/// Retorna o estoque de um produto. Produtos sem movimentações possuem
+/// estoque zerado; caso o produto não exista, retorna [`Error::NotFound`].
+#[tracing::instrument(name = "estoque::consulta", skip(conn))]
+pub fn consulta(conn: &SqliteConnection, req_produto_id: i32) -> Result<Estoque, Error> {
+    let p = super::produto::consulta(conn, req_produto_id)?;
+    Ok(le(conn, p.id)?.unwrap_or_else(|| Estoque::vazio(p.id)))
+}
+
+/// Registra uma movimentação de estoque, retornando o estoque atualizado do
+/// produto. Caso o produto não exista, a restrição de chave estrangeira da
+/// movimentação é violada.
+#[tracing::instrument(name = "estoque::movimenta", skip_all, fields(produto_id = dados.produto_id))]
+pub fn movimenta(conn: &SqliteConnection, dados: NovoMovEstoque) -> Result<Estoque, Error> {
+    use crate::model::schema_sqlite::{estoque, mov_estoque};
+
+    conn.transaction(|| {
+        diesel::insert_into(mov_estoque::table)
+            .values((
+                mov_estoque::produto_id.eq(dados.produto_id),
+                mov_estoque::docto.eq(&dados.docto),
+                mov_estoque::quantidade.eq(real(&dados.quantidade)),
+                mov_estoque::preco_frete.eq(real(&dados.preco_frete)),
+                mov_estoque::preco_unitario.eq(real(&dados.preco_unitario)),
+            ))
+            .execute(conn)?;
+
+        let mut atual =
+            le(conn, dados.produto_id)?.unwrap_or_else(|| Estoque::vazio(dados.produto_id));
+        atual.movimenta(&dados);
+
+        diesel::replace_into(estoque::table)
+            .values((
+                estoque::produto_id.eq(atual.produto_id),
+                estoque::quantidade.eq(real(&atual.quantidade)),
+                estoque::precounitario.eq(real(&atual.precounitario)),
+            ))
+            .execute(conn)?;
+        Ok(atual)
+    })
+}
+
+/// Retorna as movimentações de estoque de um produto, por ordem de ID. Caso
+/// o produto não exista, retorna [`Error::NotFound`].
+#[tracing::instrument(name = "estoque::lista_movimentos", skip(conn))]
+pub fn lista_movimentos(
+    conn: &SqliteConnection,
+    req_produto_id: i32,
+) -> Result<Vec<MovEstoque>, Error> {
+    use crate::model::schema_sqlite::mov_estoque::dsl::*;
+
+    let p = super::produto::consulta(conn, req_produto_id)?;
+    Ok(mov_estoque
+        .select((
+            id,
+            produto_id,
+            docto,
+            quantidade,
+            preco_frete,
+            datahora,
+            preco_unitario,
+        ))
+        .filter(produto_id.eq(p.id))
+        .order(id)
+        .load::<MovEstoqueSqlite>(conn)?
+        .into_iter()
+        .map(converte)
+        .collect())
+}
@@ -0,0 +1,142 @@
+// controller/logdb.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este módulo engloba as estruturas do controller do log de operações para
+//! o SQLite.
+
+use crate::model::logdb::*;
+use chrono::{NaiveDateTime, TimeZone, Utc};
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::SqliteConnection;
+
+/// Grava um registro no log de operações. Deve ser chamado dentro da mesma
+/// transação da operação registrada.
+#[tracing::instrument(name = "logdb::registra", skip_all, fields(tabela = %dados.tabela))]
+pub fn registra(conn: &SqliteConnection, dados: NovoLog) -> Result<(), Error> {
+    use crate::model::schema_sqlite::logdb::dsl::*;
+
+    diesel::insert_into(logdb)
+        .values((
+            tabela.eq(dados.tabela),
+            registro.eq(dados.registro),
+            usuario.eq(dados.usuario),
+            operacao.eq(dados.operacao),
+            descricao.eq(dados.descricao),
+        ))
+        .execute(conn)
+        .map(|_| ())
+}
+
+/// Registro do log de operações, como armazenado no SQLite, cuja data e hora
+/// não possui fuso horário e é sempre gravada em UTC.
+type LogSqlite = (
+    i32,
+    String,
+    Option<i32>,
+    String,
+    i16,
+    NaiveDateTime,
+    Option<String>,
+);
+
+/// Converte um registro do log armazenado no SQLite.
+fn converte((id, tabela, registro, usuario, operacao, datahora, descricao): LogSqlite) -> Log {
+    Log {
+        id,
+        tabela,
+        registro,
+        usuario,
+        operacao,
+        datahora: Utc.from_utc_datetime(&datahora),
+        descricao,
+    }
+}
+
+/// Retorna os registros do log de operações sobre uma tabela, por ordem de
+/// ID.
+#[tracing::instrument(name = "logdb::lista", skip(conn))]
+pub fn lista(conn: &SqliteConnection, req_tabela: &str) -> Result<Vec<Log>, Error> {
+    use crate::model::schema_sqlite::logdb::dsl::*;
+
+    Ok(logdb
+        .select((id, tabela, registro, usuario, operacao, datahora, descricao))
+        .filter(tabela.eq(req_tabela))
+        .order(id)
+        .load::<LogSqlite>(conn)?
+        .into_iter()
+        .map(converte)
+        .collect())
+}
+
+/// Retorna os registros do log de operações sobre um único registro de uma
+/// tabela, por ordem de ID.
+#[tracing::instrument(name = "logdb::lista_registro", skip(conn))]
+pub fn lista_registro(
+    conn: &SqliteConnection,
+    req_tabela: &str,
+    req_registro: i32,
+) -> Result<Vec<Log>, Error> {
+    use crate::model::schema_sqlite::logdb::dsl::*;
+
+    Ok(logdb
+        .select((id, tabela, registro, usuario, operacao, datahora, descricao))
+        .filter(tabela.eq(req_tabela))
+        .filter(registro.eq(req_registro))
+        .order(id)
+        .load::<LogSqlite>(conn)?
+        .into_iter()
+        .map(converte)
+        .collect())
+}
+
+/// Transfere os registros do log de operações sobre um registro de uma
+/// tabela para outro registro da mesma tabela, como quando um cliente é
+/// mesclado em outro.
+#[tracing::instrument(name = "logdb::transfere", skip(conn))]
+pub fn transfere(
+    conn: &SqliteConnection,
+    req_tabela: &str,
+    de: i32,
+    para: i32,
+) -> Result<(), Error> {
+    use crate::model::schema_sqlite::logdb::dsl::*;
+
+    diesel::update(logdb.filter(tabela.eq(req_tabela)).filter(registro.eq(de)))
+        .set(registro.eq(para))
+        .execute(conn)
+        .map(|_| ())
+}
+
+/// Apaga as descrições dos registros do log de operações sobre um registro
+/// de uma tabela, que podem conter dados pessoais.
+#[tracing::instrument(name = "logdb::anonimiza", skip(conn))]
+pub fn anonimiza(
+    conn: &SqliteConnection,
+    req_tabela: &str,
+    req_registro: i32,
+) -> Result<(), Error> {
+    use crate::model::schema_sqlite::logdb::dsl::*;
+
+    diesel::update(
+        logdb
+            .filter(tabela.eq(req_tabela))
+            .filter(registro.eq(req_registro)),
+    )
+    .set(descricao.eq(None::<String>))
+    .execute(conn)
+    .map(|_| ())
+}
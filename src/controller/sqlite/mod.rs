@@ -0,0 +1,36 @@
+// mod.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este módulo engloba as implementações dos controllers para o SQLite.
+//!
+//! As regras de negócio são as mesmas dos controllers para o PostgreSQL, mas
+//! as consultas usam o schema `schema_sqlite` e contornam recursos que o
+//! SQLite não possui, como a cláusula `RETURNING`.
+
+pub mod cep;
+pub mod cliente;
+pub mod contato;
+pub mod endereco;
+pub mod estoque;
+pub mod logdb;
+pub mod produto;
+pub mod usuario;
+
+no_arg_sql_function!(
+    last_insert_rowid,
+    diesel::sql_types::Integer,
+    "Retorna o ID da última linha inserida na conexão atual."
+);
@@ -0,0 +1,63 @@
+// controller/usuario.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este módulo engloba as estruturas do controller de usuários do sistema.
+//!
+//! A unicidade do login é garantida pelas restrições do banco de dados.
+
+use crate::model::usuario::*;
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::PgConnection;
+
+/// Realiza o cadastro de um único usuário, retornando o usuário
+/// recém-cadastrado em caso de sucesso.
+#[tracing::instrument(name = "usuario::cadastra", skip_all)]
+pub fn cadastra(conn: &PgConnection, dados: NovoUsuario) -> Result<Usuario, Error> {
+    diesel::insert_into(crate::model::schema::usuario::table)
+        .values(&dados)
+        .get_result::<Usuario>(conn)
+}
+
+/// Retorna um único usuário, através do ID requisitado.
+#[tracing::instrument(name = "usuario::consulta", skip(conn))]
+pub fn consulta(conn: &PgConnection, req_id: i32) -> Result<Usuario, Error> {
+    use crate::model::schema::usuario::dsl::*;
+    usuario.find(req_id).first::<Usuario>(conn)
+}
+
+/// Retorna um único usuário, através de seu login.
+#[tracing::instrument(name = "usuario::consulta_login", skip(conn))]
+pub fn consulta_login(conn: &PgConnection, req_login: &str) -> Result<Usuario, Error> {
+    use crate::model::schema::usuario::dsl::*;
+    usuario.filter(login.eq(req_login)).first::<Usuario>(conn)
+}
+
+/// Retorna todos os usuários, por ordem de ID.
+#[tracing::instrument(name = "usuario::lista", skip(conn))]
+pub fn lista(conn: &PgConnection) -> Result<Vec<Usuario>, Error> {
+    use crate::model::schema::usuario::dsl::*;
+    usuario.order(id).load::<Usuario>(conn)
+}
+
+/// Remove um usuário, através do ID requisitado, caso o mesmo exista.
+#[tracing::instrument(name = "usuario::remove", skip(conn))]
+pub fn remove(conn: &PgConnection, req_id: i32) -> Result<(), Error> {
+    use crate::model::schema::usuario::dsl::*;
+    diesel::delete(usuario.filter(id.eq(&req_id)))
+        .execute(conn)
+        .map(|_| ())
+}
@@ -0,0 +1,232 @@
+// db.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este módulo implementa utilitários diretamente atrelados à conexão com o
+//! banco de dados, mais especificamente com a geração da pool de conexões
+//! para possibilitar conexões assíncronas ao banco.
+//!
+//! Como as consultas do Diesel são síncronas, elas nunca devem ser executadas
+//! diretamente nas threads do runtime assíncrono. Ao invés disso, utilize a
+//! função [`run`], que executa a consulta na pool de threads bloqueantes do
+//! Tokio.
+//!
+//! O banco de dados usado é definido pela variável `DATABASE_URL`. Por padrão,
+//! trata-se de um banco PostgreSQL; caso a aplicação seja compilada com a
+//! feature `sqlite`, endereços no formato `sqlite://<arquivo>` podem ser usados
+//! para um banco SQLite.
+
+use bb8::RunError;
+use bb8_diesel::DieselConnectionManager;
+use diesel::{Connection, PgConnection};
+use std::env;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "sqlite")]
+use diesel::SqliteConnection;
+
+/// Representação de uma pool de conexões com um banco de dados qualquer.
+pub type Pool<C> = bb8::Pool<DieselConnectionManager<C>>;
+
+/// Representação de um tipo de pool de conexões com o banco de dados.
+pub type ConnectionPool = Pool<PgConnection>;
+
+/// Representação de uma pool de conexões com um banco de dados SQLite.
+#[cfg(feature = "sqlite")]
+pub type SqlitePool = Pool<SqliteConnection>;
+
+/// Representação de uma conexão com o banco de dados, retirada da pool.
+pub type PooledConnection<'a, C = PgConnection> =
+    bb8::PooledConnection<'a, DieselConnectionManager<C>>;
+
+/// Representação de um erro ao retirar uma conexão da pool.
+pub type PoolError = RunError<diesel::r2d2::Error>;
+
+/// Representação de um erro ao executar uma operação no banco de dados.
+#[derive(Debug)]
+pub enum DbError {
+    /// Não foi possível retirar uma conexão da pool.
+    Pool(PoolError),
+    /// A operação foi executada, mas retornou um erro.
+    Query(diesel::result::Error),
+    /// A operação foi cancelada antes de terminar, como ocorre durante o
+    /// encerramento do runtime.
+    Cancelado,
+}
+
+/// Quantidadde máxima de conexões com o banco de dados abertas para
+/// uso na pool assíncrona de conexões com o mesmo.
+pub const MAX_DATABASE_CONNECTIONS: u32 = 15;
+
+/// Tempo máximo padrão de espera por uma conexão da pool, em milissegundos.
+pub const DEFAULT_POOL_TIMEOUT_MS: u64 = 5000;
+
+/// Prefixo de endereços de bancos de dados SQLite na variável `DATABASE_URL`.
+pub const SQLITE_URL_PREFIX: &str = "sqlite://";
+
+/// Tipo de banco de dados em uso pela aplicação.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Backend {
+    /// Banco de dados PostgreSQL, com o endereço de conexão informado.
+    Postgres(String),
+    /// Banco de dados SQLite, com o caminho do arquivo informado. O caminho
+    /// `:memory:` cria um banco em memória.
+    Sqlite(String),
+}
+
+impl Backend {
+    /// Identifica o tipo de banco de dados a partir de seu endereço.
+    pub fn from_url(database_url: &str) -> Self {
+        match database_url.strip_prefix(SQLITE_URL_PREFIX) {
+            Some(caminho) => Backend::Sqlite(caminho.to_string()),
+            None => Backend::Postgres(database_url.to_string()),
+        }
+    }
+
+    /// Identifica o tipo de banco de dados a partir da variável de ambiente
+    /// `DATABASE_URL`.
+    pub fn from_env() -> Self {
+        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL não foi definido");
+        Self::from_url(&database_url)
+    }
+}
+
+/// Lê o tempo máximo de espera por uma conexão da pool, definido em
+/// milissegundos através da variável `DB_POOL_TIMEOUT_MS`.
+fn pool_timeout() -> Duration {
+    let timeout = env::var("DB_POOL_TIMEOUT_MS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_POOL_TIMEOUT_MS);
+    Duration::from_millis(timeout)
+}
+
+/// Cria uma pool com no máximo `MAX_DATABASE_CONNECTIONS` conexões
+/// disponíveis com o banco de dados.
+///
+/// Depende da variável de ambiente `DATABASE_URL` para realizar conexão.
+/// O tempo máximo de espera por uma conexão pode ser definido em milissegundos
+/// através da variável `DB_POOL_TIMEOUT_MS`.
+pub async fn make_connection_pool() -> ConnectionPool {
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL não foi definido");
+    let manager = DieselConnectionManager::<PgConnection>::new(&database_url);
+
+    Pool::builder()
+        .max_size(MAX_DATABASE_CONNECTIONS)
+        .connection_timeout(pool_timeout())
+        .build(manager)
+        .await
+        .expect("Impossível criar pool de conexões com o banco de dados")
+}
+
+/// Configura cada nova conexão com o SQLite, habilitando chaves estrangeiras e
+/// aguardando por travas de escrita ao invés de falhar imediatamente.
+#[cfg(feature = "sqlite")]
+#[derive(Debug)]
+struct SqliteCustomizer;
+
+#[cfg(feature = "sqlite")]
+#[tonic::async_trait]
+impl bb8::CustomizeConnection<bb8_diesel::DieselConnection<SqliteConnection>, diesel::r2d2::Error>
+    for SqliteCustomizer
+{
+    async fn on_acquire(
+        &self,
+        conn: &mut bb8_diesel::DieselConnection<SqliteConnection>,
+    ) -> Result<(), diesel::r2d2::Error> {
+        use diesel::connection::SimpleConnection;
+        conn.batch_execute(&format!(
+            "PRAGMA foreign_keys = ON; PRAGMA busy_timeout = {};",
+            pool_timeout().as_millis()
+        ))
+        .map_err(diesel::r2d2::Error::QueryError)
+    }
+}
+
+/// Cria uma pool de conexões com um banco de dados SQLite, no arquivo
+/// informado.
+///
+/// Bancos em memória (`:memory:`) existem apenas enquanto sua conexão existir,
+/// e por isso usam uma pool com uma única conexão permanente.
+#[cfg(feature = "sqlite")]
+pub async fn make_sqlite_pool(caminho: &str) -> SqlitePool {
+    let manager = DieselConnectionManager::<SqliteConnection>::new(caminho);
+
+    let builder = if caminho == ":memory:" {
+        Pool::builder()
+            .max_size(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+    } else {
+        Pool::builder().max_size(MAX_DATABASE_CONNECTIONS)
+    };
+
+    builder
+        .connection_timeout(pool_timeout())
+        .connection_customizer(Box::new(SqliteCustomizer))
+        .build(manager)
+        .await
+        .expect("Impossível criar pool de conexões com o banco de dados")
+}
+
+/// Retira uma conexão da pool, aguardando até que uma esteja disponível.
+#[tracing::instrument(name = "db.checkout", skip_all)]
+pub async fn get_connection<C>(pool: &Pool<C>) -> Result<PooledConnection<'static, C>, PoolError>
+where
+    C: Connection + Send + 'static,
+{
+    let inicio = Instant::now();
+    let result = pool.get_owned().await;
+    #[cfg(feature = "server")]
+    crate::service::metrics::observa_espera_pool(inicio);
+    #[cfg(not(feature = "server"))]
+    let _ = inicio;
+    result
+}
+
+/// Executa uma operação síncrona no banco de dados, usando uma conexão
+/// retirada da pool.
+///
+/// A retirada da conexão é feita de forma assíncrona, mas a operação em si é
+/// executada na pool de threads bloqueantes do Tokio, de forma a não bloquear
+/// as threads do runtime assíncrono. O span atual é propagado para a operação.
+pub async fn run<C, F, T>(pool: &Pool<C>, f: F) -> Result<T, DbError>
+where
+    C: Connection + Send + 'static,
+    F: FnOnce(&C) -> Result<T, diesel::result::Error> + Send + 'static,
+    T: Send + 'static,
+{
+    let conn = get_connection(pool).await.map_err(DbError::Pool)?;
+    let span = tracing::Span::current();
+
+    let result = tokio::task::spawn_blocking(move || span.in_scope(|| f(&conn))).await;
+
+    match result {
+        Ok(result) => result.map_err(DbError::Query),
+        // Propaga um possível pânico da operação para a task atual
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        Err(_) => Err(DbError::Cancelado),
+    }
+}
+
+impl std::fmt::Display for DbError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DbError::Pool(e) => write!(f, "{}", e),
+            DbError::Query(e) => write!(f, "{}", e),
+            DbError::Cancelado => write!(f, "Operação cancelada"),
+        }
+    }
+}
@@ -0,0 +1,193 @@
+// service/importacao.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este módulo implementa a importação de dados a partir de arquivos CSV,
+//! usada pelos subcomandos de importação do `liteserver`.
+//!
+//! # Base de CEPs
+//!
+//! O arquivo da base de CEPs deve possuir um cabeçalho com as colunas `cep`,
+//! `logradouro`, `bairro`, `cidade` e `uf`, em qualquer ordem, e estar
+//! codificado em UTF-8. As colunas `logradouro` e `bairro` podem estar vazias
+//! ou ausentes, para CEPs que abrangem uma cidade inteira.
+//!
+//! ```text
+//! cep,logradouro,bairro,cidade,uf
+//! 39100-000,,,Diamantina,MG
+//! 01001-000,Praça da Sé,Sé,São Paulo,SP
+//! ```
+//!
+//! Linhas com dados inválidos são ignoradas, e informadas no resumo da
+//! importação. CEPs já existentes na base têm seus dados substituídos.
+
+use crate::model::cep::Cep;
+use crate::repository::{RepoError, Repository};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::Read;
+
+/// Número de CEPs gravados no banco de dados de uma só vez.
+pub const TAMANHO_LOTE: usize = 1000;
+
+/// Colunas que devem estar presentes no cabeçalho da base de CEPs.
+const COLUNAS_OBRIGATORIAS: [&str; 3] = ["cep", "cidade", "uf"];
+
+/// Linha do arquivo CSV da base de CEPs.
+#[derive(Deserialize)]
+struct RegistroCep {
+    cep: String,
+    #[serde(default)]
+    logradouro: String,
+    #[serde(default)]
+    bairro: String,
+    cidade: String,
+    uf: String,
+}
+
+/// Linha ignorada durante uma importação.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinhaIgnorada {
+    /// Número da linha no arquivo, a partir de 1 (incluindo o cabeçalho).
+    pub linha: u64,
+    /// Motivo pelo qual a linha foi ignorada.
+    pub motivo: String,
+}
+
+/// Resumo de uma importação concluída.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ResumoImportacao {
+    /// Número de registros importados.
+    pub importados: usize,
+    /// Linhas ignoradas por conterem dados inválidos.
+    pub ignoradas: Vec<LinhaIgnorada>,
+}
+
+/// Representação de um erro que interrompe uma importação.
+#[derive(Debug)]
+pub enum ErroImportacao {
+    /// Não foi possível ler o arquivo CSV.
+    Leitura(csv::Error),
+    /// O arquivo CSV não está no formato esperado.
+    Formato(String),
+    /// Não foi possível gravar os dados no repositório.
+    Repositorio(RepoError),
+}
+
+impl fmt::Display for ErroImportacao {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErroImportacao::Leitura(e) => write!(f, "Impossível ler arquivo CSV: {}", e),
+            ErroImportacao::Formato(msg) => write!(f, "Formato de arquivo inválido: {}", msg),
+            ErroImportacao::Repositorio(e) => write!(f, "Impossível gravar dados: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ErroImportacao {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ErroImportacao::Leitura(e) => Some(e),
+            ErroImportacao::Repositorio(e) => Some(e),
+            ErroImportacao::Formato(_) => None,
+        }
+    }
+}
+
+impl From<RepoError> for ErroImportacao {
+    fn from(e: RepoError) -> Self {
+        ErroImportacao::Repositorio(e)
+    }
+}
+
+/// Importa a base de CEPs a partir de um arquivo CSV, cujas colunas são
+/// separadas por `delimitador`. Os CEPs são gravados no repositório em lotes
+/// de [`TAMANHO_LOTE`].
+///
+/// A importação é interrompida caso o arquivo não possa ser lido, caso falte
+/// uma coluna obrigatória no cabeçalho, ou caso um lote não possa ser gravado;
+/// neste caso, os lotes anteriores permanecem gravados.
+pub async fn importa_ceps<R: Read>(
+    repo: &dyn Repository,
+    leitor: R,
+    delimitador: u8,
+) -> Result<ResumoImportacao, ErroImportacao> {
+    let mut leitor = csv::ReaderBuilder::new()
+        .delimiter(delimitador)
+        .trim(csv::Trim::All)
+        .from_reader(leitor);
+
+    let cabecalho = leitor.headers().map_err(ErroImportacao::Leitura)?.clone();
+    for coluna in COLUNAS_OBRIGATORIAS {
+        if !cabecalho.iter().any(|c| c == coluna) {
+            return Err(ErroImportacao::Formato(format!(
+                "Coluna obrigatória ausente: {}",
+                coluna
+            )));
+        }
+    }
+
+    let mut resumo = ResumoImportacao::default();
+    // CEPs repetidos em um mesmo lote não podem ser gravados de uma só vez;
+    // neste caso, prevalecem os dados da última linha.
+    let mut lote = BTreeMap::new();
+    let mut registro = csv::StringRecord::new();
+
+    loop {
+        match leitor.read_record(&mut registro) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(e) => match e.kind() {
+                csv::ErrorKind::UnequalLengths { pos, .. } => {
+                    resumo.ignoradas.push(LinhaIgnorada {
+                        linha: pos.as_ref().map(|p| p.line()).unwrap_or_default(),
+                        motivo: "Número de colunas incorreto".to_string(),
+                    });
+                    continue;
+                }
+                _ => return Err(ErroImportacao::Leitura(e)),
+            },
+        }
+
+        let cep = registro
+            .deserialize::<RegistroCep>(Some(&cabecalho))
+            .map_err(|e| e.to_string())
+            .and_then(|r| Cep::novo(&r.cep, &r.logradouro, &r.bairro, &r.cidade, &r.uf));
+
+        match cep {
+            Ok(cep) => {
+                lote.insert(cep.codigo.clone(), cep);
+            }
+            Err(motivo) => {
+                resumo.ignoradas.push(LinhaIgnorada {
+                    linha: registro.position().map(|p| p.line()).unwrap_or_default(),
+                    motivo,
+                });
+                continue;
+            }
+        }
+
+        if lote.len() == TAMANHO_LOTE {
+            let ceps = std::mem::take(&mut lote).into_values().collect();
+            resumo.importados += repo.importa_ceps(ceps).await?;
+        }
+    }
+
+    if !lote.is_empty() {
+        resumo.importados += repo.importa_ceps(lote.into_values().collect()).await?;
+    }
+    Ok(resumo)
+}
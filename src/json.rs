@@ -0,0 +1,79 @@
+// json.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este módulo implementa o mapeamento JSON do proto3 para os campos que não
+//! são serializados pelo serde da forma esperada: inteiros de 64 bits são
+//! representados como strings, já que não cabem em um número do JavaScript.
+//!
+//! Os módulos são usados através do atributo `#[serde(with = "...")]`, que é
+//! adicionado aos campos das mensagens geradas pelo `build.rs`. Na leitura,
+//! tanto strings quanto números são aceitos.
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serializer};
+
+/// Inteiro de 64 bits recebido em JSON, como número ou string.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Inteiro {
+    Numero(i64),
+    Texto(String),
+}
+
+impl Inteiro {
+    fn valor<E: Error>(self) -> Result<i64, E> {
+        match self {
+            Inteiro::Numero(n) => Ok(n),
+            Inteiro::Texto(s) => s
+                .parse()
+                .map_err(|_| E::custom(format!("Inteiro inválido: {}", s))),
+        }
+    }
+}
+
+/// Serialização de campos `int64`.
+pub mod int64 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(valor: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(valor)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+        Inteiro::deserialize(deserializer)?.valor()
+    }
+}
+
+/// Serialização de campos `optional int64`. Campos ausentes devem ser
+/// omitidos através de `skip_serializing_if`.
+pub mod int64_opcional {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(valor: &Option<i64>, serializer: S) -> Result<S::Ok, S::Error> {
+        match valor {
+            Some(valor) => serializer.collect_str(valor),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<i64>, D::Error> {
+        Option::<Inteiro>::deserialize(deserializer)?
+            .map(Inteiro::valor)
+            .transpose()
+    }
+}
@@ -22,13 +22,55 @@
 //! Adicionalmente, este módulo também implementa estruturas globais de acordo
 //! com o padrão gRPC. Estes dados podem ser melhor interpretados através do
 //! arquivo "minerva.proto".
+//!
+//! O código gerado a partir do protobuf está sempre disponível. Os demais
+//! módulos dependem das seguintes features:
+//!
+//! - `client`: o cliente de alto nível ([`sdk`]);
+//! - `db`: modelos, controllers, migrações, repositórios e a cifragem de
+//!   dados sensíveis, que dependem do Diesel e da biblioteca do PostgreSQL;
+//! - `server`: os serviços gRPC e a importação de dados, incluindo a feature
+//!   `db`.
+//!
+//! Por padrão, as features `client` e `server` são habilitadas. Programas que
+//! precisem apenas acessar o servidor podem usar somente a feature `client`,
+//! sem depender do banco de dados.
 
+// As macros do Diesel 1.x geram blocos `impl` dentro de constantes anônimas,
+// o que é sinalizado por versões recentes do compilador.
+#![allow(non_local_definitions)]
+
+#[cfg(feature = "db")]
 #[macro_use]
 extern crate diesel;
 
-// Módulos extras
+// Módulos de acesso ao banco de dados (feature `db`)
+#[cfg(feature = "db")]
+pub mod cifra;
+#[cfg(feature = "db")]
 pub mod controller;
+#[cfg(feature = "db")]
+pub mod db;
+#[cfg(feature = "db")]
+pub mod migrations;
+#[cfg(feature = "db")]
 pub mod model;
+#[cfg(feature = "db")]
+pub mod repository;
+
+// Mapeamento JSON das mensagens do protobuf (feature `serde`)
+#[cfg(feature = "serde")]
+pub mod json;
+
+// Cliente de alto nível (feature `client`)
+#[cfg(feature = "client")]
+pub mod sdk;
+
+// Serviços gRPC e importação de dados (feature `server`)
+#[cfg(feature = "server")]
+pub mod importacao;
+#[cfg(feature = "server")]
+pub mod service;
 
 // Inclui o arquivo minerva.proto e gera código
 // relativo ao protobuf, no módulo atual
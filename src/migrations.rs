@@ -0,0 +1,175 @@
+// migrations.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este módulo engloba as migrações do banco de dados, embutidas no próprio
+//! binário da aplicação.
+//!
+//! As migrações ficam no diretório `migrations` da raiz do projeto, no mesmo
+//! formato usado pela ferramenta de linha de comando do Diesel. As versões
+//! aplicadas são registradas na tabela `__diesel_schema_migrations`, de forma
+//! que ambas as ferramentas possam ser usadas no mesmo banco de dados.
+//!
+//! Caso a feature `sqlite` esteja habilitada, as migrações equivalentes para o
+//! SQLite ficam no diretório `migrations_sqlite`, e são embutidas em
+//! [`MIGRACOES_SQLITE`].
+
+use diesel::connection::SimpleConnection;
+use diesel::migration::{Migration, RunMigrationsError};
+use diesel_migrations::MigrationConnection;
+use std::io::Write;
+
+/// Representa uma única migração embutida, com seus scripts de aplicação e
+/// de reversão.
+pub struct Migracao {
+    /// Versão da migração, composta apenas pelos dígitos do nome do diretório.
+    pub versao: &'static str,
+    /// Nome do diretório da migração.
+    pub nome: &'static str,
+    /// Script SQL de aplicação da migração.
+    pub up: &'static str,
+    /// Script SQL de reversão da migração.
+    pub down: &'static str,
+}
+
+impl Migration for Migracao {
+    fn version(&self) -> &str {
+        self.versao
+    }
+
+    fn run(&self, conn: &dyn SimpleConnection) -> Result<(), RunMigrationsError> {
+        conn.batch_execute(self.up).map_err(Into::into)
+    }
+
+    fn revert(&self, conn: &dyn SimpleConnection) -> Result<(), RunMigrationsError> {
+        conn.batch_execute(self.down).map_err(Into::into)
+    }
+}
+
+/// Declara uma migração embutida a partir do nome de seu diretório. Caso não
+/// seja informado, o diretório base das migrações será `migrations`.
+macro_rules! migracao {
+    ($versao:literal, $nome:literal) => {
+        migracao!("migrations", $versao, $nome)
+    };
+    ($base:literal, $versao:literal, $nome:literal) => {
+        Migracao {
+            versao: $versao,
+            nome: $nome,
+            up: include_str!(concat!("../", $base, "/", $nome, "/up.sql")),
+            down: include_str!(concat!("../", $base, "/", $nome, "/down.sql")),
+        }
+    };
+}
+
+/// Lista de todas as migrações do banco de dados, em ordem de aplicação.
+pub const MIGRACOES: &[Migracao] = &[
+    migracao!("00000000000000", "00000000000000_diesel_initial_setup"),
+    migracao!("20220301000001", "2022-03-01-000001_cria_usuario"),
+    migracao!("20220301000002", "2022-03-01-000002_cria_produto"),
+    migracao!("20220301000003", "2022-03-01-000003_cria_estoque"),
+    migracao!("20220301000004", "2022-03-01-000004_cria_mov_estoque"),
+    migracao!("20220301000005", "2022-03-01-000005_cria_cliente"),
+    migracao!("20220301000006", "2022-03-01-000006_cria_endereco"),
+    migracao!("20220301000007", "2022-03-01-000007_cria_logdb"),
+    migracao!("20261018000001", "2026-10-18-000001_valida_endereco"),
+    migracao!("20261018000002", "2026-10-18-000002_cria_cep"),
+    migracao!("20261018000003", "2026-10-18-000003_cria_contato"),
+    migracao!("20261018000004", "2026-10-18-000004_limite_credito"),
+    migracao!("20261018000005", "2026-10-18-000005_busca_cliente"),
+    migracao!("20261018000006", "2026-10-18-000006_registro_logdb"),
+    migracao!("20261018000007", "2026-10-18-000007_cifra_docto"),
+    migracao!("20261018000008", "2026-10-18-000008_indice_docto_unico"),
+];
+
+/// Lista de todas as migrações do banco de dados SQLite, em ordem de
+/// aplicação. As versões são as mesmas das migrações do PostgreSQL, exceto
+/// pela configuração inicial do Diesel e pelos índices da busca de clientes,
+/// que não se aplicam ao SQLite.
+#[cfg(feature = "sqlite")]
+pub const MIGRACOES_SQLITE: &[Migracao] = &[
+    migracao!("migrations_sqlite", "20220301000001", "2022-03-01-000001_cria_usuario"),
+    migracao!("migrations_sqlite", "20220301000002", "2022-03-01-000002_cria_produto"),
+    migracao!("migrations_sqlite", "20220301000003", "2022-03-01-000003_cria_estoque"),
+    migracao!("migrations_sqlite", "20220301000004", "2022-03-01-000004_cria_mov_estoque"),
+    migracao!("migrations_sqlite", "20220301000005", "2022-03-01-000005_cria_cliente"),
+    migracao!("migrations_sqlite", "20220301000006", "2022-03-01-000006_cria_endereco"),
+    migracao!("migrations_sqlite", "20220301000007", "2022-03-01-000007_cria_logdb"),
+    migracao!("migrations_sqlite", "20261018000001", "2026-10-18-000001_valida_endereco"),
+    migracao!("migrations_sqlite", "20261018000002", "2026-10-18-000002_cria_cep"),
+    migracao!("migrations_sqlite", "20261018000003", "2026-10-18-000003_cria_contato"),
+    migracao!("migrations_sqlite", "20261018000004", "2026-10-18-000004_limite_credito"),
+    migracao!("migrations_sqlite", "20261018000006", "2026-10-18-000006_registro_logdb"),
+    migracao!("migrations_sqlite", "20261018000007", "2026-10-18-000007_cifra_docto"),
+    migracao!("migrations_sqlite", "20261018000008", "2026-10-18-000008_indice_docto_unico"),
+];
+
+/// Aplica todas as migrações pendentes, imprimindo o progresso na saída
+/// informada.
+pub fn up<C: MigrationConnection>(
+    conn: &C,
+    migracoes: &[Migracao],
+    out: &mut dyn Write,
+) -> Result<(), RunMigrationsError> {
+    diesel_migrations::run_migrations(conn, migracoes.iter().map(|m| m as &dyn Migration), out)
+}
+
+/// Reverte a última migração aplicada. Retorna a migração revertida, ou
+/// `None` caso nenhuma migração tenha sido aplicada.
+pub fn down<'a, C: MigrationConnection>(
+    conn: &C,
+    migracoes: &'a [Migracao],
+    out: &mut dyn Write,
+) -> Result<Option<&'a Migracao>, RunMigrationsError> {
+    diesel_migrations::setup_database(conn)?;
+
+    let versao = match conn.latest_run_migration_version()? {
+        Some(versao) => versao,
+        None => return Ok(None),
+    };
+
+    let migracao = migracoes
+        .iter()
+        .find(|m| m.versao == versao)
+        .ok_or_else(|| RunMigrationsError::MigrationError(
+            diesel_migrations::MigrationError::UnknownMigrationVersion(versao.clone()),
+        ))?;
+
+    conn.transaction(|| {
+        writeln!(out, "Revertendo migração {}", migracao.nome)?;
+        migracao.revert(conn)?;
+        // A versão é composta apenas por dígitos, e vem da lista de
+        // migrações embutidas, e portanto pode ser interpolada.
+        conn.batch_execute(&format!(
+            "DELETE FROM __diesel_schema_migrations WHERE version = '{}'",
+            migracao.versao
+        ))?;
+        Ok(Some(migracao))
+    })
+}
+
+/// Retorna a situação de cada migração: se já foi aplicada ou não.
+pub fn status<'a, C: MigrationConnection>(
+    conn: &C,
+    migracoes: &'a [Migracao],
+) -> Result<Vec<(&'a Migracao, bool)>, RunMigrationsError> {
+    diesel_migrations::setup_database(conn)?;
+    let aplicadas = conn.previously_run_migration_versions()?;
+
+    Ok(migracoes
+        .iter()
+        .map(|m| (m, aplicadas.contains(m.versao)))
+        .collect())
+}
@@ -0,0 +1,124 @@
+// busca.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2021-2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Utilitários da busca de clientes por nome.
+//!
+//! No PostgreSQL, a busca é feita pelas extensões `pg_trgm` e `unaccent`,
+//! criadas pelas migrações. As funções deste módulo reproduzem a mesma
+//! comparação por trigramas para os repositórios que não dispõem dessas
+//! extensões, como o SQLite e o repositório em memória.
+
+use crate::model::cliente::{Cliente, ContemClientes};
+use crate::ClienteEncontradoResponse;
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+
+/// Similaridade mínima para que um cliente seja retornado pela busca. Deve
+/// ser a mesma usada nas consultas ao PostgreSQL.
+pub const SIMILARIDADE_MINIMA: f32 = 0.3;
+
+/// Cliente retornado por uma busca, junto com a similaridade entre seu nome
+/// e o termo buscado, entre 0 e 1.
+pub type ClienteEncontrado = (Cliente, f32);
+
+impl From<ClienteEncontrado> for ClienteEncontradoResponse {
+    fn from((cliente, similaridade): ClienteEncontrado) -> ClienteEncontradoResponse {
+        ClienteEncontradoResponse {
+            cliente: Some(cliente.into()),
+            similaridade,
+        }
+    }
+}
+
+impl ContemClientes for ClienteEncontrado {
+    fn clientes_mut(&mut self) -> Vec<&mut Cliente> {
+        vec![&mut self.0]
+    }
+}
+
+/// Normaliza um texto para comparação, convertendo-o para letras minúsculas
+/// e removendo os acentos, como feito por `f_unaccent(lower(...))`.
+pub fn normaliza(texto: &str) -> String {
+    texto
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'á' | 'à' | 'â' | 'ã' | 'ä' | 'å' => 'a',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'í' | 'ì' | 'î' | 'ï' => 'i',
+            'ó' | 'ò' | 'ô' | 'õ' | 'ö' => 'o',
+            'ú' | 'ù' | 'û' | 'ü' => 'u',
+            'ç' => 'c',
+            'ñ' => 'n',
+            'ý' | 'ÿ' => 'y',
+            c => c,
+        })
+        .collect()
+}
+
+/// Extrai os trigramas de um texto já normalizado, da mesma forma que o
+/// `pg_trgm`: cada palavra é precedida por dois espaços e seguida por um.
+pub(crate) fn trigramas(texto: &str) -> BTreeSet<[char; 3]> {
+    texto
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|palavra| !palavra.is_empty())
+        .flat_map(|palavra| {
+            let chars: Vec<char> = format!("  {} ", palavra).chars().collect();
+            chars
+                .windows(3)
+                .map(|t| [t[0], t[1], t[2]])
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Calcula a similaridade entre um termo de busca e um nome, como uma
+/// aproximação de `word_similarity` do `pg_trgm`: a proporção dos trigramas
+/// do termo que também aparecem no nome, desconsiderando acentos e
+/// maiúsculas.
+pub fn similaridade(termo: &str, nome: &str) -> f32 {
+    let termo = trigramas(&normaliza(termo));
+    if termo.is_empty() {
+        return 0.0;
+    }
+    let nome = trigramas(&normaliza(nome));
+    termo.intersection(&nome).count() as f32 / termo.len() as f32
+}
+
+/// Busca clientes cujos nomes sejam similares ao termo informado, dentre os
+/// clientes fornecidos. Retorna no máximo `limite` clientes, ordenados da
+/// maior para a menor similaridade e, em caso de empate, por ID.
+pub fn busca<I>(clientes: I, termo: &str, limite: i64) -> Vec<ClienteEncontrado>
+where
+    I: IntoIterator<Item = Cliente>,
+{
+    let mut encontrados: Vec<ClienteEncontrado> = clientes
+        .into_iter()
+        .map(|c| {
+            let s = similaridade(termo, &c.nome);
+            (c, s)
+        })
+        .filter(|(_, s)| *s >= SIMILARIDADE_MINIMA)
+        .collect();
+
+    encontrados.sort_by(|(a, sa), (b, sb)| {
+        sb.partial_cmp(sa)
+            .unwrap_or(Ordering::Equal)
+            .then(a.id.cmp(&b.id))
+    });
+    encontrados.truncate(limite.max(0) as usize);
+    encontrados
+}
@@ -0,0 +1,81 @@
+// cep.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2021-2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Utilitários de modelagem da base de CEPs, usada para preencher os dados de
+//! endereços a partir de seu CEP.
+//!
+//! A base é mantida localmente, e é importada a partir de um arquivo CSV
+//! através do comando `liteserver importa-cep`.
+
+use crate::model::endereco::{normaliza_cep, normaliza_uf};
+use crate::model::schema::cep;
+use crate::CepResponse;
+
+/// Representa a estrutura de um elemento da tabela `cep` do banco de dados.
+#[derive(Queryable, Insertable, Clone, Debug, PartialEq, Eq)]
+#[table_name = "cep"]
+pub struct Cep {
+    /// CEP, no formato `00000-000`.
+    pub codigo: String,
+    /// Logradouro do CEP. Pode ser vazio para CEPs que abrangem uma cidade
+    /// inteira.
+    pub logradouro: String,
+    /// Bairro do CEP. Pode ser vazio para CEPs que abrangem uma cidade
+    /// inteira.
+    pub bairro: String,
+    /// Cidade do CEP.
+    pub cidade: String,
+    /// Sigla da unidade federativa do CEP.
+    pub uf: String,
+}
+
+impl Cep {
+    /// Valida e normaliza os dados de um CEP importado. Em caso de erro,
+    /// retorna uma mensagem descrevendo o campo inválido.
+    pub fn novo(
+        codigo: &str,
+        logradouro: &str,
+        bairro: &str,
+        cidade: &str,
+        uf: &str,
+    ) -> Result<Cep, String> {
+        let codigo = normaliza_cep(codigo).ok_or_else(|| format!("CEP inválido: {}", codigo))?;
+        let uf = normaliza_uf(uf).ok_or_else(|| format!("UF inválida: {}", uf))?;
+        if cidade.trim().is_empty() {
+            return Err("Cidade não informada".to_string());
+        }
+
+        Ok(Cep {
+            codigo,
+            logradouro: logradouro.trim().to_string(),
+            bairro: bairro.trim().to_string(),
+            cidade: cidade.trim().to_string(),
+            uf,
+        })
+    }
+}
+
+impl From<Cep> for CepResponse {
+    fn from(cep: Cep) -> CepResponse {
+        CepResponse {
+            cep: cep.codigo,
+            logradouro: cep.logradouro,
+            bairro: cep.bairro,
+            cidade: cep.cidade,
+            uf: cep.uf,
+        }
+    }
+}
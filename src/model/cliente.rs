@@ -26,13 +26,23 @@
 //! - Modificação da documentação para se adaptar ao Minerva.Lite;
 //! - Remoção da estrutura `UsuarioRecv` e de seu bloco `impl`;
 //! - Adição de traits para conversão de `Cliente` para `ClienteResponse`;
-//! - Adição de traits para conversão de `NovoClienteRequest` para `NovoCliente`.
+//! - Adição de traits para conversão de `NovoClienteRequest` para `NovoCliente`,
+//!   com validação do formato do documento.
+//! - Adição do trait `Identifiable` a `Cliente`, para associação com endereços.
+//! - Adição do limite de crédito e do saldo devedor a `Cliente`, avaliados
+//!   pelo motor de regras em [`controller::regras`](crate::controller::regras).
+//! - Adição do índice cego do documento a `Cliente`, usado quando o documento
+//!   é cifrado pela aplicação (veja [`cifra`](crate::cifra)).
+//! - Adição do mascaramento do documento, para usuários sem permissão para
+//!   ver dados sensíveis (veja [`ContemClientes`]).
 
+use crate::cifra::Cifra;
 use crate::model::schema::cliente;
 use crate::{ClienteResponse, NovoClienteRequest};
 
 /// Representa a estrutura de um elemento da tabela `cliente` do banco de dados.
-#[derive(Queryable, Clone)]
+#[derive(Queryable, Identifiable, Clone)]
+#[table_name = "cliente"]
 pub struct Cliente {
     /// Id do cliente no banco.
     pub id: i32,
@@ -51,8 +61,22 @@ pub struct Cliente {
     /// inativo se sua remoção não for conveniente.
     pub ativo: bool,
     /// Determina se o cliente está bloqueado. Um cliente bloqueado não poderá
-    /// ter operações feitas em seu nome.
+    /// ter débitos de crédito feitos em seu nome (veja
+    /// [`regras`](crate::controller::regras)).
     pub bloqueado: bool,
+    /// Limite de crédito do cliente, em centavos. Caso não seja definido, o
+    /// cliente não possui limite, e não será bloqueado automaticamente.
+    pub limite_credito: Option<i64>,
+    /// Valor devido pelo cliente, em centavos, resultante das operações a
+    /// prazo feitas em seu nome. Um valor negativo indica um saldo credor.
+    pub saldo_devedor: i64,
+    /// Determina se o bloqueio do cliente foi feito pelo motor de regras. Um
+    /// bloqueio automático é removido assim que o cliente volta a respeitar
+    /// todas as regras.
+    pub bloqueio_automatico: bool,
+    /// Índice cego do documento, gravado quando o mesmo é cifrado. Permite
+    /// encontrar clientes com documentos iguais sem decifrá-los.
+    pub docto_indice: Option<String>,
 }
 
 impl From<Cliente> for ClienteResponse {
@@ -65,10 +89,80 @@ impl From<Cliente> for ClienteResponse {
             docto: cliente.docto,
             ativo: cliente.ativo,
             bloqueado: cliente.bloqueado,
+            limite_credito: cliente.limite_credito,
+            saldo_devedor: cliente.saldo_devedor,
+        }
+    }
+}
+
+impl Cliente {
+    /// Mascara os dados sensíveis do cliente, para que sejam mostrados a
+    /// usuários sem permissão para vê-los. Apenas os dígitos centrais do CPF
+    /// são mantidos (por exemplo, `***.456.789-**`); o CNPJ de pessoas
+    /// jurídicas não é um dado pessoal, e é mantido.
+    pub fn mascara(&mut self) {
+        if !self.pj {
+            self.docto = mascara_docto(&self.docto);
         }
     }
 }
 
+/// Mascara um documento, substituindo por `*` os três primeiros e os dois
+/// últimos caracteres alfanuméricos, e mantendo a pontuação. Documentos com
+/// até cinco caracteres alfanuméricos são mascarados por completo.
+pub fn mascara_docto(docto: &str) -> String {
+    let total = docto.chars().filter(|c| c.is_alphanumeric()).count();
+    let mut posicao = 0;
+    docto
+        .chars()
+        .map(|c| {
+            if !c.is_alphanumeric() {
+                return c;
+            }
+            posicao += 1;
+            if total <= 5 || posicao <= 3 || posicao > total - 2 {
+                '*'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Estruturas que contêm clientes, retornadas pelas operações sobre os
+/// mesmos. Permite que os clientes sejam tratados da mesma forma antes de
+/// serem convertidos em respostas, independente da estrutura retornada.
+pub trait ContemClientes {
+    /// Retorna referências a todos os clientes contidos.
+    fn clientes_mut(&mut self) -> Vec<&mut Cliente>;
+
+    /// Retorna a estrutura com os dados sensíveis de todos os seus clientes
+    /// mascarados (veja [`Cliente::mascara`]).
+    fn mascarado(mut self) -> Self
+    where
+        Self: Sized,
+    {
+        for cliente in self.clientes_mut() {
+            cliente.mascara();
+        }
+        self
+    }
+}
+
+impl ContemClientes for Cliente {
+    fn clientes_mut(&mut self) -> Vec<&mut Cliente> {
+        vec![self]
+    }
+}
+
+impl<T: ContemClientes> ContemClientes for Vec<T> {
+    fn clientes_mut(&mut self) -> Vec<&mut Cliente> {
+        self.iter_mut()
+            .flat_map(|item| item.clientes_mut())
+            .collect()
+    }
+}
+
 /// Representa os dados de um cliente a serem inseridos na criação de um novo
 /// cliente no banco de dados.
 #[derive(Insertable, Default)]
@@ -86,17 +180,68 @@ pub struct NovoCliente {
     pub ativo: bool,
     /// Determina se o cliente está bloqueado. Ver [`Cliente::bloqueado`].
     pub bloqueado: bool,
+    /// Índice cego do documento. Ver [`Cliente::docto_indice`].
+    pub docto_indice: Option<String>,
 }
 
-impl From<NovoClienteRequest> for NovoCliente {
-    fn from(req: NovoClienteRequest) -> NovoCliente {
+impl NovoCliente {
+    /// Cifra o documento do novo cliente, calculando seu índice cego. Caso a
+    /// cifragem não esteja habilitada, o documento não é alterado, e o índice
+    /// é calculado apenas se sua chave estiver definida.
+    pub fn cifrado(self, cifra: &Cifra) -> Self {
         Self {
+            docto_indice: cifra.indice(&self.docto),
+            docto: cifra.cifra(&self.docto),
+            ..self
+        }
+    }
+}
+
+/// Verifica se um documento possui o formato de um CPF ou de um CNPJ: onze
+/// ou quatorze dígitos, opcionalmente separados por `.`, `-` ou `/`. Um
+/// documento vazio indica que o mesmo não foi informado, e é aceito.
+pub fn docto_valido(docto: &str) -> bool {
+    if docto.is_empty() {
+        return true;
+    }
+    let permitidos = |c: char| c.is_ascii_digit() || ".-/".contains(c);
+    let digitos = docto.chars().filter(|c| c.is_ascii_digit()).count();
+    docto.chars().all(permitidos) && (digitos == 11 || digitos == 14)
+}
+
+impl TryFrom<NovoClienteRequest> for NovoCliente {
+    type Error = String;
+
+    /// Valida os dados de um novo cliente. Em caso de erro, retorna uma
+    /// mensagem descrevendo o campo inválido.
+    fn try_from(req: NovoClienteRequest) -> Result<NovoCliente, String> {
+        let docto = req.docto.trim();
+        if !docto_valido(docto) {
+            return Err(format!("Documento inválido: {}", docto));
+        }
+
+        Ok(Self {
             tipo: 0,
             nome: req.nome,
             pj: req.pj,
-            docto: req.docto,
+            docto: docto.to_string(),
             ativo: true,
             bloqueado: false,
-        }
+            docto_indice: None,
+        })
     }
 }
+
+/// Documento de um cliente recifrado com a chave atual (veja
+/// [`Cifra::recifra`]).
+pub struct DoctoRecifrado {
+    /// Id do cliente.
+    pub id: i32,
+    /// Documento lido anteriormente. O novo documento só é gravado caso o
+    /// mesmo não tenha sido alterado desde então.
+    pub anterior: String,
+    /// Documento recifrado. Ver [`Cliente::docto`].
+    pub docto: String,
+    /// Novo índice cego do documento. Ver [`Cliente::docto_indice`].
+    pub docto_indice: Option<String>,
+}
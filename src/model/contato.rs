@@ -0,0 +1,243 @@
+// contato.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2021-2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Utilitários de modelagem de contatos de clientes para banco de dados e
+//! regras de negócio.
+//!
+//! Cada cliente pode possuir vários contatos, que são removidos junto com o
+//! cliente. Um contato pode ser um telefone ou um endereço de e-mail (veja
+//! [`TipoContato`]), e cada cliente pode ter no máximo um contato principal
+//! de cada tipo.
+//!
+//! Os dados dos contatos são validados e normalizados na conversão a partir
+//! das mensagens de requisição: telefones devem possuir DDD e oito (fixo) ou
+//! nove (celular) dígitos, e são gravados no formato `(00) 00000-0000`;
+//! e-mails são gravados em letras minúsculas.
+
+use crate::model::cliente::Cliente;
+use crate::model::schema::contato;
+use crate::{AtualizaContatoRequest, ContatoResponse, NovoContatoRequest};
+
+/// Tipo de um contato, armazenado como [`Contato::tipo`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TipoContato {
+    /// Telefone fixo ou celular, com DDD.
+    Telefone = 0,
+    /// Endereço de e-mail.
+    Email = 1,
+}
+
+impl TipoContato {
+    /// Retorna o tipo de contato correspondente a um código, caso exista.
+    pub fn from_i32(tipo: i32) -> Option<TipoContato> {
+        match tipo {
+            0 => Some(TipoContato::Telefone),
+            1 => Some(TipoContato::Email),
+            _ => None,
+        }
+    }
+
+    /// Descrição do tipo de contato, usada em mensagens de erro.
+    fn descricao(self) -> &'static str {
+        match self {
+            TipoContato::Telefone => "Telefone",
+            TipoContato::Email => "E-mail",
+        }
+    }
+}
+
+/// Normaliza um telefone brasileiro para o formato `(00) 0000-0000`, no caso
+/// de telefones fixos, ou `(00) 00000-0000`, no caso de celulares.
+///
+/// O telefone deve incluir o DDD, e pode ser informado com ou sem pontuação
+/// e com o código do país (`+55`). Retorna `None` caso o número seja
+/// inválido.
+pub fn normaliza_telefone(telefone: &str) -> Option<String> {
+    let telefone = telefone.trim();
+    let permitidos = |c: char| c.is_ascii_digit() || " ()-.+".contains(c);
+    if telefone.is_empty() || !telefone.chars().all(permitidos) {
+        return None;
+    }
+
+    let digitos: String = telefone.chars().filter(|c| c.is_ascii_digit()).collect();
+    let digitos = match digitos.strip_prefix("55") {
+        Some(resto) if telefone.starts_with('+') || digitos.len() > 11 => resto,
+        _ if telefone.starts_with('+') => return None,
+        _ => digitos.as_str(),
+    };
+
+    let (ddd, numero) = digitos.split_at(digitos.len().min(2));
+    if ddd.len() != 2 || ddd.contains('0') {
+        return None;
+    }
+
+    // Celulares possuem nove dígitos, começando por 9; telefones fixos
+    // possuem oito, começando por 2 a 5.
+    let valido = match numero.len() {
+        9 => numero.starts_with('9'),
+        8 => matches!(numero.as_bytes()[0], b'2'..=b'5'),
+        _ => false,
+    };
+    if !valido {
+        return None;
+    }
+
+    let (prefixo, sufixo) = numero.split_at(numero.len() - 4);
+    Some(format!("({}) {}-{}", ddd, prefixo, sufixo))
+}
+
+/// Normaliza um endereço de e-mail para letras minúsculas, retornando `None`
+/// caso o mesmo seja inválido.
+///
+/// A validação é propositalmente simples: o endereço deve possuir um único
+/// `@`, sem espaços, e o domínio deve possuir ao menos um ponto.
+pub fn normaliza_email(email: &str) -> Option<String> {
+    let email = email.trim().to_lowercase();
+    let (usuario, dominio) = email.split_once('@')?;
+
+    let valido = !usuario.is_empty()
+        && !dominio.contains('@')
+        && !email.chars().any(char::is_whitespace)
+        && dominio.split('.').count() > 1
+        && dominio.split('.').all(|parte| !parte.is_empty());
+    if valido {
+        Some(email)
+    } else {
+        None
+    }
+}
+
+/// Valida e normaliza o tipo e o valor de um contato. Em caso de erro,
+/// retorna uma mensagem descrevendo o campo inválido.
+fn valida(tipo: i32, valor: &str) -> Result<(i16, String), String> {
+    let tipo_contato = TipoContato::from_i32(tipo).ok_or("Tipo de contato inválido")?;
+    let valor = match tipo_contato {
+        TipoContato::Telefone => normaliza_telefone(valor),
+        TipoContato::Email => normaliza_email(valor),
+    }
+    .ok_or_else(|| format!("{} inválido: {}", tipo_contato.descricao(), valor))?;
+    Ok((tipo_contato as i16, valor))
+}
+
+/// Remove espaços das observações de um contato, descartando observações
+/// vazias.
+fn normaliza_observacao(observacao: Option<String>) -> Option<String> {
+    observacao
+        .map(|o| o.trim().to_string())
+        .filter(|o| !o.is_empty())
+}
+
+/// Representa a estrutura de um elemento da tabela `contato` do banco de
+/// dados.
+#[derive(Queryable, Identifiable, Associations, Clone, Debug, PartialEq, Eq)]
+#[belongs_to(Cliente)]
+#[table_name = "contato"]
+pub struct Contato {
+    /// Id do contato no banco.
+    pub id: i32,
+    /// Id do cliente ao qual o contato pertence.
+    pub cliente_id: i32,
+    /// Tipo do contato, correspondente a um valor de [`TipoContato`].
+    pub tipo: i16,
+    /// Telefone ou e-mail, já normalizado de acordo com o tipo do contato.
+    pub valor: String,
+    /// Determina se este é o contato principal do cliente para seu tipo.
+    pub principal: bool,
+    /// Observações sobre o contato, caso existam.
+    pub observacao: Option<String>,
+}
+
+impl From<Contato> for ContatoResponse {
+    fn from(contato: Contato) -> ContatoResponse {
+        ContatoResponse {
+            id: contato.id,
+            cliente_id: contato.cliente_id,
+            tipo: contato.tipo as i32,
+            valor: contato.valor,
+            principal: contato.principal,
+            observacao: contato.observacao,
+        }
+    }
+}
+
+/// Representa os dados de um contato a serem inseridos no banco de dados.
+#[derive(Insertable, Default, Clone)]
+#[table_name = "contato"]
+pub struct NovoContato {
+    /// Id do cliente ao qual o contato pertence. Ver [`Contato::cliente_id`].
+    pub cliente_id: i32,
+    /// Tipo do contato. Ver [`Contato::tipo`].
+    pub tipo: i16,
+    /// Telefone ou e-mail. Ver [`Contato::valor`].
+    pub valor: String,
+    /// Determina se este é o contato principal. Ver [`Contato::principal`].
+    pub principal: bool,
+    /// Observações sobre o contato. Ver [`Contato::observacao`].
+    pub observacao: Option<String>,
+}
+
+impl TryFrom<NovoContatoRequest> for NovoContato {
+    type Error = String;
+
+    /// Valida e normaliza os dados de um novo contato. Em caso de erro,
+    /// retorna uma mensagem descrevendo o campo inválido.
+    fn try_from(req: NovoContatoRequest) -> Result<NovoContato, String> {
+        let (tipo, valor) = valida(req.tipo, &req.valor)?;
+        Ok(NovoContato {
+            cliente_id: req.cliente_id,
+            tipo,
+            valor,
+            principal: req.principal,
+            observacao: normaliza_observacao(req.observacao),
+        })
+    }
+}
+
+/// Representa os novos dados de um contato existente. O cliente ao qual o
+/// contato pertence não pode ser alterado.
+#[derive(Identifiable, AsChangeset, Default, Clone)]
+#[table_name = "contato"]
+#[changeset_options(treat_none_as_null = "true")]
+pub struct AlteraContato {
+    /// Id do contato a ser alterado. Ver [`Contato::id`].
+    pub id: i32,
+    /// Tipo do contato. Ver [`Contato::tipo`].
+    pub tipo: i16,
+    /// Telefone ou e-mail. Ver [`Contato::valor`].
+    pub valor: String,
+    /// Determina se este é o contato principal. Ver [`Contato::principal`].
+    pub principal: bool,
+    /// Observações sobre o contato. Caso ausentes, as observações existentes
+    /// são removidas. Ver [`Contato::observacao`].
+    pub observacao: Option<String>,
+}
+
+impl TryFrom<AtualizaContatoRequest> for AlteraContato {
+    type Error = String;
+
+    /// Valida e normaliza os novos dados de um contato, da mesma forma que em
+    /// [`NovoContato`].
+    fn try_from(req: AtualizaContatoRequest) -> Result<AlteraContato, String> {
+        let (tipo, valor) = valida(req.tipo, &req.valor)?;
+        Ok(AlteraContato {
+            id: req.id,
+            tipo,
+            valor,
+            principal: req.principal,
+            observacao: normaliza_observacao(req.observacao),
+        })
+    }
+}
@@ -0,0 +1,250 @@
+// duplicado.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2021-2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Utilitários de detecção de clientes duplicados.
+//!
+//! Dois clientes são considerados possíveis duplicados quando possuem o mesmo
+//! documento, desconsiderando a pontuação, ou nomes muito similares (veja
+//! [`SIMILARIDADE_DUPLICADOS`]). Os candidatos são reportados em grupos, que
+//! podem ser revisados e resolvidos através da mescla de clientes. Quando a
+//! chave do índice cego está definida, os documentos são comparados através
+//! de seus índices (veja [`chave_docto`]).
+//!
+//! No PostgreSQL, os pares de nomes similares são encontrados pelo `pg_trgm`;
+//! nos demais repositórios, todos os nomes são comparados pela aplicação,
+//! através de [`detecta`].
+
+use crate::cifra::Cifra;
+use crate::model::busca::{normaliza, trigramas};
+use crate::model::cliente::{Cliente, ContemClientes};
+use crate::model::logdb::{NovoLog, OperacaoLog};
+use crate::{GrupoDuplicadosResponse, MesclaRequest};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Similaridade mínima entre os nomes de dois clientes para que os mesmos
+/// sejam considerados duplicados. Deve ser a mesma usada nas consultas ao
+/// PostgreSQL.
+pub const SIMILARIDADE_DUPLICADOS: f32 = 0.8;
+
+/// Motivo pelo qual um grupo de clientes foi considerado duplicado.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MotivoDuplicidade {
+    /// Os clientes possuem o mesmo documento.
+    Documento = 0,
+    /// Os clientes possuem nomes similares.
+    Nome = 1,
+}
+
+/// Grupo de clientes possivelmente duplicados.
+#[derive(Clone)]
+pub struct GrupoDuplicados {
+    /// Motivo pelo qual os clientes foram agrupados.
+    pub motivo: MotivoDuplicidade,
+    /// Clientes do grupo, por ordem de ID.
+    pub clientes: Vec<Cliente>,
+}
+
+impl From<GrupoDuplicados> for GrupoDuplicadosResponse {
+    fn from(grupo: GrupoDuplicados) -> GrupoDuplicadosResponse {
+        GrupoDuplicadosResponse {
+            motivo: grupo.motivo as i32,
+            clientes: grupo.clientes.into_iter().map(|c| c.into()).collect(),
+        }
+    }
+}
+
+impl ContemClientes for GrupoDuplicados {
+    fn clientes_mut(&mut self) -> Vec<&mut Cliente> {
+        self.clientes.iter_mut().collect()
+    }
+}
+
+/// Mescla de um cliente em outro, validada a partir de uma [`MesclaRequest`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Mescla {
+    /// ID do cliente que será incorporado e removido.
+    pub origem_id: i32,
+    /// ID do cliente que receberá os dados da origem.
+    pub destino_id: i32,
+}
+
+impl TryFrom<MesclaRequest> for Mescla {
+    type Error = &'static str;
+
+    fn try_from(req: MesclaRequest) -> Result<Self, Self::Error> {
+        if req.origem_id == req.destino_id {
+            return Err("Um cliente não pode ser mesclado consigo mesmo");
+        }
+        Ok(Self {
+            origem_id: req.origem_id,
+            destino_id: req.destino_id,
+        })
+    }
+}
+
+impl Mescla {
+    /// Cria o registro do log de operações da mescla, feita por `usuario`. O
+    /// registro se refere ao cliente de destino, que é mantido. Os documentos
+    /// dos clientes não são registrados, já que podem ser cifrados.
+    pub fn log(&self, origem: &Cliente, destino: &Cliente, usuario: &str) -> NovoLog {
+        NovoLog::new(
+            "cliente",
+            destino.id,
+            usuario,
+            OperacaoLog::Mescla,
+            format!(
+                "Cliente {} ({}) mesclado em {} ({})",
+                origem.id, origem.nome, destino.id, destino.nome
+            ),
+        )
+    }
+}
+
+/// Normaliza um documento para comparação, mantendo apenas seus dígitos e
+/// letras (CNPJs alfanuméricos são permitidos).
+pub fn normaliza_docto(docto: &str) -> String {
+    docto
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+/// Retorna a chave de comparação de um documento em texto puro: seu índice
+/// cego, caso a chave do índice esteja definida, ou o documento normalizado.
+/// Retorna `None` para documentos vazios.
+pub fn chave_texto(docto: &str, cifra: &Cifra) -> Option<String> {
+    if cifra.indexa() {
+        // O prefixo impede que um índice seja igual a um documento normalizado.
+        cifra.indice(docto).map(|indice| format!("#{}", indice))
+    } else {
+        Some(normaliza_docto(docto)).filter(|docto| !docto.is_empty())
+    }
+}
+
+/// Retorna a chave de comparação do documento de um cliente, como em
+/// [`chave_texto`]. O índice gravado é usado quando existe; caso contrário,
+/// como ocorre com clientes gravados antes da definição da chave do índice,
+/// o mesmo é calculado a partir do documento, decifrado caso necessário.
+/// Retorna `None` para documentos vazios ou que não possam ser decifrados.
+pub fn chave_docto(cliente: &Cliente, cifra: &Cifra) -> Option<String> {
+    match &cliente.docto_indice {
+        Some(indice) => Some(format!("#{}", indice)),
+        None => chave_texto(&cifra.decifra(&cliente.docto).ok()?, cifra),
+    }
+}
+
+/// Calcula a similaridade entre os trigramas de dois nomes, como
+/// `similarity` do `pg_trgm`: a proporção de trigramas em comum entre ambos.
+fn similaridade(a: &BTreeSet<[char; 3]>, b: &BTreeSet<[char; 3]>) -> f32 {
+    let uniao = a.union(b).count();
+    if uniao == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f32 / uniao as f32
+}
+
+/// Agrupa clientes possivelmente duplicados, a partir de uma lista de
+/// clientes candidatos e dos pares de IDs de clientes com nomes similares.
+///
+/// Clientes com o mesmo documento formam um grupo; clientes com nomes
+/// similares, direta ou indiretamente, formam outro. Grupos de nomes cujos
+/// clientes já estejam todos em um mesmo grupo de documento são omitidos.
+pub fn agrupa(clientes: Vec<Cliente>, pares: &[(i32, i32)], cifra: &Cifra) -> Vec<GrupoDuplicados> {
+    let clientes: BTreeMap<i32, Cliente> = clientes.into_iter().map(|c| (c.id, c)).collect();
+
+    let mut por_docto: BTreeMap<String, Vec<i32>> = BTreeMap::new();
+    for c in clientes.values() {
+        if let Some(docto) = chave_docto(c, cifra) {
+            por_docto.entry(docto).or_default().push(c.id);
+        }
+    }
+    let grupos_docto: Vec<Vec<i32>> = por_docto
+        .into_values()
+        .filter(|ids| ids.len() > 1)
+        .collect();
+
+    // Os pares de nomes similares são unidos em componentes conexos.
+    let mut raiz: BTreeMap<i32, i32> = BTreeMap::new();
+    fn encontra(raiz: &mut BTreeMap<i32, i32>, id: i32) -> i32 {
+        let pai = *raiz.entry(id).or_insert(id);
+        if pai == id {
+            return id;
+        }
+        let r = encontra(raiz, pai);
+        raiz.insert(id, r);
+        r
+    }
+    for &(a, b) in pares {
+        let (ra, rb) = (encontra(&mut raiz, a), encontra(&mut raiz, b));
+        if ra != rb {
+            raiz.insert(ra.max(rb), ra.min(rb));
+        }
+    }
+    let mut por_nome: BTreeMap<i32, Vec<i32>> = BTreeMap::new();
+    for id in raiz.keys().copied().collect::<Vec<_>>() {
+        let r = encontra(&mut raiz, id);
+        por_nome.entry(r).or_default().push(id);
+    }
+    let grupos_nome: Vec<Vec<i32>> = por_nome
+        .into_values()
+        .filter(|ids| {
+            ids.len() > 1
+                && !grupos_docto
+                    .iter()
+                    .any(|g| ids.iter().all(|id| g.contains(id)))
+        })
+        .collect();
+
+    let monta = |motivo, ids: Vec<i32>| GrupoDuplicados {
+        motivo,
+        clientes: ids
+            .iter()
+            .filter_map(|id| clientes.get(id).cloned())
+            .collect(),
+    };
+
+    let mut grupos: Vec<GrupoDuplicados> = grupos_docto
+        .into_iter()
+        .map(|ids| monta(MotivoDuplicidade::Documento, ids))
+        .chain(
+            grupos_nome
+                .into_iter()
+                .map(|ids| monta(MotivoDuplicidade::Nome, ids)),
+        )
+        .collect();
+    grupos.sort_by_key(|g| (g.clientes[0].id, g.motivo as i32));
+    grupos
+}
+
+/// Detecta clientes possivelmente duplicados, comparando todos os clientes
+/// fornecidos entre si.
+pub fn detecta(clientes: Vec<Cliente>, cifra: &Cifra) -> Vec<GrupoDuplicados> {
+    let nomes: Vec<(i32, BTreeSet<[char; 3]>)> = clientes
+        .iter()
+        .map(|c| (c.id, trigramas(&normaliza(&c.nome))))
+        .collect();
+
+    let mut pares = vec![];
+    for (i, (a, ta)) in nomes.iter().enumerate() {
+        for (b, tb) in &nomes[i + 1..] {
+            if similaridade(ta, tb) >= SIMILARIDADE_DUPLICADOS {
+                pares.push((*a, *b));
+            }
+        }
+    }
+    agrupa(clientes, &pares, cifra)
+}
@@ -0,0 +1,250 @@
+// endereco.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2021-2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Utilitários de modelagem de endereços de clientes para banco de dados e
+//! regras de negócio.
+//!
+//! Cada cliente pode possuir vários endereços, que são removidos junto com o
+//! cliente. Os endereços são classificados de acordo com um catálogo de tipos
+//! (cobrança, entrega, etc.), e cada cliente pode ter no máximo um endereço
+//! principal de cada tipo.
+//!
+//! Os dados de novos endereços são validados na conversão a partir de
+//! [`NovoEnderecoRequest`]: a UF deve ser uma das 27 unidades federativas do
+//! Brasil, e o CEP deve possuir oito dígitos.
+
+use crate::model::cliente::{Cliente, ContemClientes};
+use crate::model::contato::Contato;
+use crate::model::schema::{endereco, tipo_endereco};
+use crate::{ClienteDetalhadoResponse, EnderecoResponse, NovoEnderecoRequest, TipoEnderecoMessage};
+
+/// Siglas das unidades federativas do Brasil.
+pub const UFS: [&str; 27] = [
+    "AC", "AL", "AM", "AP", "BA", "CE", "DF", "ES", "GO", "MA", "MG", "MS", "MT", "PA", "PB", "PE",
+    "PI", "PR", "RJ", "RN", "RO", "RR", "RS", "SC", "SE", "SP", "TO",
+];
+
+/// Tipos de endereço cadastrados por padrão no catálogo. Devem ser os mesmos
+/// inseridos pelas migrações do banco de dados.
+pub const TIPOS_ENDERECO_PADRAO: [(i16, &str); 4] = [
+    (0, "Residencial"),
+    (1, "Comercial"),
+    (2, "Cobrança"),
+    (3, "Entrega"),
+];
+
+/// Normaliza a sigla de uma unidade federativa para letras maiúsculas,
+/// retornando `None` caso a mesma não exista.
+pub fn normaliza_uf(uf: &str) -> Option<String> {
+    let uf = uf.trim().to_uppercase();
+    if UFS.contains(&uf.as_str()) {
+        Some(uf)
+    } else {
+        None
+    }
+}
+
+/// Normaliza um CEP para o formato `00000-000`. O CEP pode ser informado com
+/// ou sem hífen; caso não possua oito dígitos, retorna `None`.
+pub fn normaliza_cep(cep: &str) -> Option<String> {
+    let cep = cep.trim();
+    let digitos: String = match cep.len() {
+        8 => cep.to_string(),
+        9 if cep.as_bytes()[5] == b'-' => cep.replacen('-', "", 1),
+        _ => return None,
+    };
+
+    if digitos.len() != 8 || !digitos.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some(format!("{}-{}", &digitos[..5], &digitos[5..]))
+}
+
+/// Representa a estrutura de um elemento da tabela `endereco` do banco de
+/// dados.
+#[derive(Queryable, Identifiable, Associations, Clone, Debug, PartialEq, Eq)]
+#[belongs_to(Cliente)]
+#[table_name = "endereco"]
+pub struct Endereco {
+    /// Id do endereço no banco.
+    pub id: i32,
+    /// Id do cliente ao qual o endereço pertence.
+    pub cliente_id: i32,
+    /// Tipo do endereço. Definido como 0 por padrão.
+    pub tipo: i16,
+    /// Logradouro (rua, avenida, etc.) do endereço.
+    pub logradouro: String,
+    /// Número do endereço. Pode conter letras, como em `12-A`, ou `S/N`.
+    pub numero: String,
+    /// Complemento do endereço, caso exista.
+    pub complemento: Option<String>,
+    /// Bairro do endereço.
+    pub bairro: String,
+    /// Sigla da unidade federativa do endereço.
+    pub uf: String,
+    /// Cidade do endereço.
+    pub cidade: String,
+    /// CEP do endereço, no formato `00000-000`. Endereços cadastrados antes da
+    /// validação de CEP podem não possuí-lo.
+    pub cep: Option<String>,
+    /// Determina se este é o endereço principal do cliente para seu tipo.
+    pub principal: bool,
+}
+
+impl From<Endereco> for EnderecoResponse {
+    fn from(endereco: Endereco) -> EnderecoResponse {
+        EnderecoResponse {
+            id: endereco.id,
+            cliente_id: endereco.cliente_id,
+            tipo: endereco.tipo as i32,
+            logradouro: endereco.logradouro,
+            numero: endereco.numero,
+            complemento: endereco.complemento,
+            bairro: endereco.bairro,
+            uf: endereco.uf,
+            cidade: endereco.cidade,
+            cep: endereco.cep,
+            principal: endereco.principal,
+        }
+    }
+}
+
+/// Representa os dados de um endereço a serem inseridos no banco de dados.
+#[derive(Insertable, Default, Clone)]
+#[table_name = "endereco"]
+pub struct NovoEndereco {
+    /// Id do cliente ao qual o endereço pertence. Ver [`Endereco::cliente_id`].
+    pub cliente_id: i32,
+    /// Tipo do endereço. Ver [`Endereco::tipo`].
+    pub tipo: i16,
+    /// Logradouro do endereço. Ver [`Endereco::logradouro`].
+    pub logradouro: String,
+    /// Número do endereço. Ver [`Endereco::numero`].
+    pub numero: String,
+    /// Complemento do endereço. Ver [`Endereco::complemento`].
+    pub complemento: Option<String>,
+    /// Bairro do endereço. Ver [`Endereco::bairro`].
+    pub bairro: String,
+    /// Unidade federativa do endereço. Ver [`Endereco::uf`].
+    pub uf: String,
+    /// Cidade do endereço. Ver [`Endereco::cidade`].
+    pub cidade: String,
+    /// CEP do endereço. Ver [`Endereco::cep`].
+    pub cep: String,
+    /// Determina se este é o endereço principal. Ver [`Endereco::principal`].
+    pub principal: bool,
+}
+
+impl TryFrom<NovoEnderecoRequest> for NovoEndereco {
+    type Error = String;
+
+    /// Valida e normaliza os dados de um novo endereço. Em caso de erro,
+    /// retorna uma mensagem descrevendo o campo inválido.
+    fn try_from(req: NovoEnderecoRequest) -> Result<NovoEndereco, String> {
+        let tipo = i16::try_from(req.tipo).map_err(|_| "Tipo de endereço inválido")?;
+        let uf = normaliza_uf(&req.uf).ok_or_else(|| format!("UF inválida: {}", req.uf))?;
+        let cep = normaliza_cep(&req.cep).ok_or_else(|| format!("CEP inválido: {}", req.cep))?;
+
+        let obrigatorios = [
+            ("logradouro", &req.logradouro),
+            ("numero", &req.numero),
+            ("bairro", &req.bairro),
+            ("cidade", &req.cidade),
+        ];
+        for (campo, valor) in obrigatorios {
+            if valor.trim().is_empty() {
+                return Err(format!("Campo obrigatório não informado: {}", campo));
+            }
+        }
+
+        Ok(NovoEndereco {
+            cliente_id: req.cliente_id,
+            tipo,
+            logradouro: req.logradouro.trim().to_string(),
+            numero: req.numero.trim().to_string(),
+            complemento: req
+                .complemento
+                .map(|c| c.trim().to_string())
+                .filter(|c| !c.is_empty()),
+            bairro: req.bairro.trim().to_string(),
+            uf,
+            cidade: req.cidade.trim().to_string(),
+            cep,
+            principal: req.principal,
+        })
+    }
+}
+
+/// Representa a estrutura de um elemento da tabela `tipo_endereco`, o
+/// catálogo de tipos de endereço.
+#[derive(Queryable, Insertable, Clone, Debug, PartialEq, Eq)]
+#[table_name = "tipo_endereco"]
+pub struct TipoEndereco {
+    /// Código do tipo de endereço, referenciado por [`Endereco::tipo`].
+    pub id: i16,
+    /// Descrição do tipo de endereço.
+    pub descricao: String,
+}
+
+impl From<TipoEndereco> for TipoEnderecoMessage {
+    fn from(tipo: TipoEndereco) -> TipoEnderecoMessage {
+        TipoEnderecoMessage {
+            id: tipo.id as i32,
+            descricao: tipo.descricao,
+        }
+    }
+}
+
+impl TryFrom<TipoEnderecoMessage> for TipoEndereco {
+    type Error = String;
+
+    /// Valida os dados de um novo tipo de endereço. O código não pode ser
+    /// negativo, e a descrição não pode ser vazia.
+    fn try_from(msg: TipoEnderecoMessage) -> Result<TipoEndereco, String> {
+        let id = i16::try_from(msg.id)
+            .ok()
+            .filter(|id| *id >= 0)
+            .ok_or("Código de tipo de endereço inválido")?;
+        let descricao = msg.descricao.trim();
+        if descricao.is_empty() {
+            return Err("Descrição do tipo de endereço não informada".to_string());
+        }
+
+        Ok(TipoEndereco {
+            id,
+            descricao: descricao.to_string(),
+        })
+    }
+}
+
+/// Um cliente, junto com todos os seus endereços e contatos.
+pub type ClienteDetalhado = (Cliente, Vec<Endereco>, Vec<Contato>);
+
+impl From<(Cliente, Vec<Endereco>, Vec<Contato>)> for ClienteDetalhadoResponse {
+    fn from((cliente, enderecos, contatos): ClienteDetalhado) -> ClienteDetalhadoResponse {
+        ClienteDetalhadoResponse {
+            cliente: Some(cliente.into()),
+            enderecos: enderecos.into_iter().map(|e| e.into()).collect(),
+            contatos: contatos.into_iter().map(|c| c.into()).collect(),
+        }
+    }
+}
+
+impl ContemClientes for ClienteDetalhado {
+    fn clientes_mut(&mut self) -> Vec<&mut Cliente> {
+        vec![&mut self.0]
+    }
+}
@@ -0,0 +1,107 @@
+// estoque.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2021-2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Utilitários de modelagem do estoque de produtos e de suas movimentações.
+//!
+//! Cada produto possui no máximo um registro de estoque, com a quantidade
+//! disponível e o custo médio unitário. O estoque é alterado apenas através
+//! de movimentações: entradas (quantidades positivas) recalculam o custo
+//! médio, ponderado pelas quantidades e incluindo o frete, enquanto saídas
+//! (quantidades negativas) apenas reduzem a quantidade disponível. Produtos
+//! sem movimentações possuem estoque zerado.
+
+use crate::model::schema::{estoque, mov_estoque};
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+
+/// Representa a estrutura de um elemento da tabela `estoque` do banco de
+/// dados.
+#[derive(Queryable, Insertable, AsChangeset, Clone, Debug, PartialEq, Eq)]
+#[table_name = "estoque"]
+pub struct Estoque {
+    /// Id do produto ao qual o estoque pertence.
+    pub produto_id: i32,
+    /// Quantidade disponível do produto.
+    pub quantidade: BigDecimal,
+    /// Custo médio unitário do produto.
+    pub precounitario: BigDecimal,
+}
+
+impl Estoque {
+    /// Retorna o estoque zerado de um produto sem movimentações.
+    pub fn vazio(produto_id: i32) -> Self {
+        Self {
+            produto_id,
+            quantidade: BigDecimal::default(),
+            precounitario: BigDecimal::default(),
+        }
+    }
+
+    /// Aplica uma movimentação ao estoque. Entradas recalculam o custo médio
+    /// unitário; caso a quantidade resultante não seja positiva, o custo da
+    /// própria entrada é usado.
+    pub fn movimenta(&mut self, mov: &NovoMovEstoque) {
+        let zero = BigDecimal::default();
+        let quantidade = &self.quantidade + &mov.quantidade;
+
+        if mov.quantidade > zero {
+            let custo_entrada = &mov.quantidade * &mov.preco_unitario + &mov.preco_frete;
+            self.precounitario = if self.quantidade > zero {
+                (&self.quantidade * &self.precounitario + custo_entrada) / &quantidade
+            } else {
+                custo_entrada / &mov.quantidade
+            };
+        }
+        self.quantidade = quantidade;
+    }
+}
+
+/// Representa a estrutura de um elemento da tabela `mov_estoque` do banco de
+/// dados.
+#[derive(Queryable, Clone, Debug, PartialEq, Eq)]
+pub struct MovEstoque {
+    /// Id da movimentação no banco.
+    pub id: i32,
+    /// Id do produto movimentado.
+    pub produto_id: i32,
+    /// Documento que originou a movimentação (por exemplo, o número da nota
+    /// fiscal).
+    pub docto: String,
+    /// Quantidade movimentada: positiva para entradas, negativa para saídas.
+    pub quantidade: BigDecimal,
+    /// Valor total do frete da movimentação.
+    pub preco_frete: BigDecimal,
+    /// Data e hora da movimentação, definida pelo próprio banco.
+    pub datahora: DateTime<Utc>,
+    /// Preço unitário do produto na movimentação.
+    pub preco_unitario: BigDecimal,
+}
+
+/// Representa os dados de uma nova movimentação de estoque.
+#[derive(Insertable, Clone, Debug)]
+#[table_name = "mov_estoque"]
+pub struct NovoMovEstoque {
+    /// Id do produto movimentado. Ver [`MovEstoque::produto_id`].
+    pub produto_id: i32,
+    /// Documento da movimentação. Ver [`MovEstoque::docto`].
+    pub docto: String,
+    /// Quantidade movimentada. Ver [`MovEstoque::quantidade`].
+    pub quantidade: BigDecimal,
+    /// Valor total do frete. Ver [`MovEstoque::preco_frete`].
+    pub preco_frete: BigDecimal,
+    /// Preço unitário do produto. Ver [`MovEstoque::preco_unitario`].
+    pub preco_unitario: BigDecimal,
+}
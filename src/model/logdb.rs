@@ -0,0 +1,115 @@
+// logdb.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2021-2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Utilitários de modelagem do log de operações do banco de dados.
+//!
+//! A tabela `logdb` registra operações relevantes feitas sobre as demais
+//! tabelas, como a mescla de clientes duplicados, junto com o usuário que as
+//! executou. Os registros são gravados na mesma transação da operação, e
+//! identificam o registro afetado, de forma que o histórico de um único
+//! cliente possa ser recuperado ou anonimizado.
+
+use crate::model::schema::logdb;
+use crate::RegistroLogResponse;
+use chrono::{DateTime, SecondsFormat, Utc};
+
+/// Usuário registrado no log para operações feitas pelo próprio servidor.
+pub const USUARIO_SISTEMA: &str = "sistema";
+
+/// Operação registrada no log, armazenada como [`Log::operacao`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OperacaoLog {
+    /// Inserção de um registro.
+    Insercao = 0,
+    /// Alteração de um registro.
+    Alteracao = 1,
+    /// Remoção de um registro.
+    Remocao = 2,
+    /// Mescla de um registro duplicado em outro.
+    Mescla = 3,
+    /// Anonimização dos dados pessoais de um registro.
+    Anonimizacao = 4,
+}
+
+/// Representa a estrutura de um elemento da tabela `logdb` do banco de dados.
+#[derive(Queryable, Clone, Debug, PartialEq, Eq)]
+pub struct Log {
+    /// Id do registro no banco.
+    pub id: i32,
+    /// Tabela sobre a qual a operação foi feita.
+    pub tabela: String,
+    /// ID do registro afetado pela operação, caso exista.
+    pub registro: Option<i32>,
+    /// Usuário que executou a operação.
+    pub usuario: String,
+    /// Operação executada. Ver [`OperacaoLog`].
+    pub operacao: i16,
+    /// Data e hora do registro, definida pelo próprio banco.
+    pub datahora: DateTime<Utc>,
+    /// Descrição da operação, incluindo os registros envolvidos. É removida
+    /// caso o registro afetado seja anonimizado.
+    pub descricao: Option<String>,
+}
+
+/// Representa os dados de um novo registro do log.
+#[derive(Insertable, Clone, Debug)]
+#[table_name = "logdb"]
+pub struct NovoLog {
+    /// Tabela sobre a qual a operação foi feita. Ver [`Log::tabela`].
+    pub tabela: String,
+    /// ID do registro afetado. Ver [`Log::registro`].
+    pub registro: Option<i32>,
+    /// Usuário que executou a operação. Ver [`Log::usuario`].
+    pub usuario: String,
+    /// Operação executada. Ver [`Log::operacao`].
+    pub operacao: i16,
+    /// Descrição da operação. Ver [`Log::descricao`].
+    pub descricao: Option<String>,
+}
+
+impl NovoLog {
+    /// Cria um registro do log para uma operação sobre o registro `registro`
+    /// de uma tabela.
+    pub fn new(
+        tabela: &str,
+        registro: i32,
+        usuario: &str,
+        operacao: OperacaoLog,
+        descricao: String,
+    ) -> Self {
+        Self {
+            tabela: tabela.to_string(),
+            registro: Some(registro),
+            usuario: usuario.to_string(),
+            operacao: operacao as i16,
+            descricao: Some(descricao),
+        }
+    }
+}
+
+impl From<Log> for RegistroLogResponse {
+    fn from(log: Log) -> Self {
+        Self {
+            id: log.id,
+            tabela: log.tabela,
+            registro: log.registro,
+            usuario: log.usuario,
+            operacao: log.operacao as i32,
+            datahora: log.datahora.to_rfc3339_opts(SecondsFormat::Secs, true),
+            descricao: log.descricao,
+        }
+    }
+}
@@ -18,9 +18,25 @@
 //! dados para tais estruturas.
 //!
 //! O schema é gerado automaticamente pela biblioteca Diesel, sendo diretamente
-//! copiado, para maior facilidade, do programa Minerva.rs. Assim, este arquivo
-//! será melhor aproveitado caso o banco seja criado, primeiramente, a partir do
-//! projeto Minerva.rs.
+//! copiado, para maior facilidade, do programa Minerva.rs. As tabelas
+//! correspondentes são criadas pelas migrações embutidas no módulo
+//! `migrations`.
+//!
+//! Caso a feature `sqlite` esteja habilitada, o módulo `schema_sqlite` define
+//! as mesmas tabelas com tipos compatíveis com o SQLite.
 
+pub mod busca;
+pub mod cep;
 pub mod cliente;
+pub mod contato;
+pub mod duplicado;
+pub mod endereco;
+pub mod estoque;
+pub mod logdb;
+pub mod privacidade;
+pub mod produto;
 pub mod schema;
+pub mod usuario;
+
+#[cfg(feature = "sqlite")]
+pub mod schema_sqlite;
@@ -0,0 +1,94 @@
+// privacidade.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2021-2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Utilitários para o atendimento aos direitos dos titulares de dados
+//! pessoais, previstos na LGPD (Lei nº 13.709/2018).
+//!
+//! Um cliente pode solicitar todos os dados armazenados a seu respeito, que
+//! são exportados através de [`DadosCliente`], ou a anonimização dos mesmos.
+//! A anonimização é irreversível: o nome, o documento, os endereços e o
+//! histórico do cliente no log de operações são apagados, e seus contatos são
+//! removidos. O cliente em si é mantido, assim como seus dados de crédito,
+//! de forma que as referências ao mesmo e o histórico financeiro continuem
+//! íntegros.
+
+use crate::model::cliente::{Cliente, ContemClientes};
+use crate::model::contato::Contato;
+use crate::model::endereco::Endereco;
+use crate::model::logdb::{Log, NovoLog, OperacaoLog};
+use crate::DadosClienteMessage;
+
+/// Todos os dados armazenados a respeito de um cliente: o próprio cliente,
+/// seus endereços e contatos, e os registros do log de operações que se
+/// referem ao mesmo.
+#[derive(Clone)]
+pub struct DadosCliente {
+    /// Dados do cliente.
+    pub cliente: Cliente,
+    /// Endereços do cliente, por ordem de ID.
+    pub enderecos: Vec<Endereco>,
+    /// Contatos do cliente, por ordem de ID.
+    pub contatos: Vec<Contato>,
+    /// Registros do log de operações sobre o cliente, por ordem de ID.
+    pub log: Vec<Log>,
+}
+
+impl From<DadosCliente> for DadosClienteMessage {
+    fn from(dados: DadosCliente) -> Self {
+        Self {
+            cliente: Some(dados.cliente.into()),
+            enderecos: dados.enderecos.into_iter().map(|e| e.into()).collect(),
+            contatos: dados.contatos.into_iter().map(|c| c.into()).collect(),
+            log: dados.log.into_iter().map(|l| l.into()).collect(),
+        }
+    }
+}
+
+impl ContemClientes for DadosCliente {
+    fn clientes_mut(&mut self) -> Vec<&mut Cliente> {
+        vec![&mut self.cliente]
+    }
+}
+
+/// Apaga os dados pessoais de um cliente. O cliente também é inativado.
+pub fn anonimiza_cliente(cliente: &mut Cliente) {
+    cliente.nome.clear();
+    cliente.docto.clear();
+    cliente.docto_indice = None;
+    cliente.ativo = false;
+}
+
+/// Apaga os dados de um endereço que permitem localizar o cliente. A cidade
+/// e a unidade federativa são mantidas, por não identificarem o cliente.
+pub fn anonimiza_endereco(endereco: &mut Endereco) {
+    endereco.logradouro.clear();
+    endereco.numero.clear();
+    endereco.complemento = None;
+    endereco.bairro.clear();
+    endereco.cep = None;
+}
+
+/// Cria o registro do log de operações da anonimização de um cliente, feita
+/// por `usuario`. A descrição não inclui dados pessoais.
+pub fn log_anonimizacao(cliente_id: i32, usuario: &str) -> NovoLog {
+    NovoLog::new(
+        "cliente",
+        cliente_id,
+        usuario,
+        OperacaoLog::Anonimizacao,
+        format!("Cliente {} anonimizado", cliente_id),
+    )
+}
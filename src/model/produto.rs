@@ -0,0 +1,42 @@
+// produto.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2021-2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Utilitários de modelagem dos produtos, cujo estoque é controlado pelo
+//! módulo [`estoque`](crate::model::estoque).
+
+use crate::model::schema::produto;
+
+/// Representa a estrutura de um elemento da tabela `produto` do banco de
+/// dados.
+#[derive(Queryable, Clone, Debug, PartialEq, Eq)]
+pub struct Produto {
+    /// Id do produto no banco.
+    pub id: i32,
+    /// Descrição do produto.
+    pub descricao: String,
+    /// Unidade de saída do produto (por exemplo, `UN` ou `KG`).
+    pub unidsaida: String,
+}
+
+/// Representa os dados de um novo produto a ser cadastrado.
+#[derive(Insertable, Clone, Debug)]
+#[table_name = "produto"]
+pub struct NovoProduto {
+    /// Descrição do produto. Ver [`Produto::descricao`].
+    pub descricao: String,
+    /// Unidade de saída do produto. Ver [`Produto::unidsaida`].
+    pub unidsaida: String,
+}
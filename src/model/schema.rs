@@ -1,3 +1,13 @@
+table! {
+    cep (codigo) {
+        codigo -> Varchar,
+        logradouro -> Varchar,
+        bairro -> Varchar,
+        cidade -> Varchar,
+        uf -> Varchar,
+    }
+}
+
 table! {
     cliente (id) {
         id -> Int4,
@@ -7,6 +17,21 @@ table! {
         docto -> Varchar,
         ativo -> Bool,
         bloqueado -> Bool,
+        limite_credito -> Nullable<Int8>,
+        saldo_devedor -> Int8,
+        bloqueio_automatico -> Bool,
+        docto_indice -> Nullable<Varchar>,
+    }
+}
+
+table! {
+    contato (id) {
+        id -> Int4,
+        cliente_id -> Int4,
+        tipo -> Int2,
+        valor -> Varchar,
+        principal -> Bool,
+        observacao -> Nullable<Varchar>,
     }
 }
 
@@ -21,6 +46,8 @@ table! {
         bairro -> Varchar,
         uf -> Varchar,
         cidade -> Varchar,
+        cep -> Nullable<Varchar>,
+        principal -> Bool,
     }
 }
 
@@ -40,6 +67,7 @@ table! {
         operacao -> Int2,
         datahora -> Timestamptz,
         descricao -> Nullable<Varchar>,
+        registro -> Nullable<Int4>,
     }
 }
 
@@ -63,6 +91,13 @@ table! {
     }
 }
 
+table! {
+    tipo_endereco (id) {
+        id -> Int2,
+        descricao -> Varchar,
+    }
+}
+
 table! {
     usuario (id) {
         id -> Int4,
@@ -73,14 +108,19 @@ table! {
     }
 }
 
+joinable!(contato -> cliente (cliente_id));
 joinable!(endereco -> cliente (cliente_id));
+joinable!(endereco -> tipo_endereco (tipo));
 
 allow_tables_to_appear_in_same_query!(
+    cep,
     cliente,
+    contato,
     endereco,
     estoque,
     logdb,
     mov_estoque,
     produto,
+    tipo_endereco,
     usuario,
 );
@@ -0,0 +1,152 @@
+// schema_sqlite.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Schema do banco de dados para o SQLite.
+//!
+//! As tabelas são equivalentes às do módulo `schema`, mas os tipos
+//! exclusivos do PostgreSQL foram substituídos por tipos suportados pelo
+//! SQLite:
+//!
+//! - `Numeric` passa a ser `Double`, armazenado como `REAL`;
+//! - `Timestamptz` passa a ser `Timestamp`, armazenado como texto em UTC;
+//! - `Bytea` passa a ser `Binary`, armazenado como `BLOB`.
+
+table! {
+    cep (codigo) {
+        codigo -> Text,
+        logradouro -> Text,
+        bairro -> Text,
+        cidade -> Text,
+        uf -> Text,
+    }
+}
+
+table! {
+    cliente (id) {
+        id -> Integer,
+        tipo -> SmallInt,
+        nome -> Text,
+        pj -> Bool,
+        docto -> Text,
+        ativo -> Bool,
+        bloqueado -> Bool,
+        limite_credito -> Nullable<BigInt>,
+        saldo_devedor -> BigInt,
+        bloqueio_automatico -> Bool,
+        docto_indice -> Nullable<Text>,
+    }
+}
+
+table! {
+    contato (id) {
+        id -> Integer,
+        cliente_id -> Integer,
+        tipo -> SmallInt,
+        valor -> Text,
+        principal -> Bool,
+        observacao -> Nullable<Text>,
+    }
+}
+
+table! {
+    endereco (id) {
+        id -> Integer,
+        cliente_id -> Integer,
+        tipo -> SmallInt,
+        logradouro -> Text,
+        numero -> Text,
+        complemento -> Nullable<Text>,
+        bairro -> Text,
+        uf -> Text,
+        cidade -> Text,
+        cep -> Nullable<Text>,
+        principal -> Bool,
+    }
+}
+
+table! {
+    estoque (produto_id) {
+        produto_id -> Integer,
+        quantidade -> Double,
+        precounitario -> Double,
+    }
+}
+
+table! {
+    logdb (id) {
+        id -> Integer,
+        tabela -> Text,
+        usuario -> Text,
+        operacao -> SmallInt,
+        datahora -> Timestamp,
+        descricao -> Nullable<Text>,
+        registro -> Nullable<Integer>,
+    }
+}
+
+table! {
+    mov_estoque (id) {
+        id -> Integer,
+        produto_id -> Integer,
+        docto -> Text,
+        quantidade -> Double,
+        preco_frete -> Double,
+        datahora -> Timestamp,
+        preco_unitario -> Double,
+    }
+}
+
+table! {
+    produto (id) {
+        id -> Integer,
+        descricao -> Text,
+        unidsaida -> Text,
+    }
+}
+
+table! {
+    tipo_endereco (id) {
+        id -> SmallInt,
+        descricao -> Text,
+    }
+}
+
+table! {
+    usuario (id) {
+        id -> Integer,
+        login -> Text,
+        nome -> Text,
+        email -> Nullable<Text>,
+        senha_hash -> Binary,
+    }
+}
+
+joinable!(contato -> cliente (cliente_id));
+joinable!(endereco -> cliente (cliente_id));
+joinable!(endereco -> tipo_endereco (tipo));
+
+allow_tables_to_appear_in_same_query!(
+    cep,
+    cliente,
+    contato,
+    endereco,
+    estoque,
+    logdb,
+    mov_estoque,
+    produto,
+    tipo_endereco,
+    usuario,
+);
@@ -0,0 +1,53 @@
+// usuario.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2021-2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Utilitários de modelagem dos usuários do sistema, que são as entidades que
+//! efetuam operações no sistema.
+//!
+//! O login de cada usuário é único. A senha nunca é armazenada, apenas seu
+//! hash, calculado por quem cadastra o usuário.
+
+use crate::model::schema::usuario;
+
+/// Representa a estrutura de um elemento da tabela `usuario` do banco de
+/// dados.
+#[derive(Queryable, Clone, Debug, PartialEq, Eq)]
+pub struct Usuario {
+    /// Id do usuário no banco.
+    pub id: i32,
+    /// Login do usuário, único no sistema.
+    pub login: String,
+    /// Nome do usuário.
+    pub nome: String,
+    /// E-mail do usuário, caso informado.
+    pub email: Option<String>,
+    /// Hash da senha do usuário.
+    pub senha_hash: Vec<u8>,
+}
+
+/// Representa os dados de um novo usuário a ser cadastrado.
+#[derive(Insertable, Clone, Debug)]
+#[table_name = "usuario"]
+pub struct NovoUsuario {
+    /// Login do usuário. Ver [`Usuario::login`].
+    pub login: String,
+    /// Nome do usuário. Ver [`Usuario::nome`].
+    pub nome: String,
+    /// E-mail do usuário. Ver [`Usuario::email`].
+    pub email: Option<String>,
+    /// Hash da senha do usuário. Ver [`Usuario::senha_hash`].
+    pub senha_hash: Vec<u8>,
+}
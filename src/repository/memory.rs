@@ -0,0 +1,740 @@
+// memory.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Implementação dos repositórios em memória.
+//!
+//! Os dados armazenados aqui são perdidos ao final da execução. Este
+//! repositório é útil para testes e para executar o servidor em modo de
+//! demonstração, sem um banco de dados.
+
+use super::{
+    CepRepository, ClienteRepository, ContatoRepository, EnderecoRepository, EstoqueRepository,
+    LogRepository, ProdutoRepository, RepoError, Repository, UsuarioRepository,
+};
+use crate::cifra::Cifra;
+use crate::controller::cliente::CLIENTE_PAGE_SIZE;
+use crate::controller::regras;
+use crate::model::busca::{self, ClienteEncontrado};
+use crate::model::cep::Cep;
+use crate::model::cliente::{Cliente, NovoCliente};
+use crate::model::contato::{AlteraContato, Contato, NovoContato};
+use crate::model::duplicado::{self, GrupoDuplicados, Mescla};
+use crate::model::endereco::{
+    ClienteDetalhado, Endereco, NovoEndereco, TipoEndereco, TIPOS_ENDERECO_PADRAO,
+};
+use crate::model::estoque::{Estoque, MovEstoque, NovoMovEstoque};
+use crate::model::logdb::{Log, NovoLog};
+use crate::model::privacidade::{self, DadosCliente};
+use crate::model::produto::{NovoProduto, Produto};
+use crate::model::usuario::{NovoUsuario, Usuario};
+use chrono::Utc;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// Dados armazenados no repositório em memória.
+struct Dados {
+    ultimo_id: i32,
+    clientes: BTreeMap<i32, Cliente>,
+    ultimo_endereco_id: i32,
+    enderecos: BTreeMap<i32, Endereco>,
+    tipos_endereco: BTreeMap<i16, TipoEndereco>,
+    ultimo_contato_id: i32,
+    contatos: BTreeMap<i32, Contato>,
+    ceps: BTreeMap<String, Cep>,
+    logs: Vec<Log>,
+    ultimo_usuario_id: i32,
+    usuarios: BTreeMap<i32, Usuario>,
+    ultimo_produto_id: i32,
+    produtos: BTreeMap<i32, Produto>,
+    estoques: BTreeMap<i32, Estoque>,
+    ultimo_movimento_id: i32,
+    movimentos: BTreeMap<i32, MovEstoque>,
+}
+
+impl Default for Dados {
+    /// Cria o repositório vazio, exceto pelos tipos de endereço padrão, que
+    /// são cadastrados da mesma forma que nas migrações do banco de dados.
+    fn default() -> Self {
+        let tipos_endereco = TIPOS_ENDERECO_PADRAO
+            .iter()
+            .map(|&(id, descricao)| {
+                let tipo = TipoEndereco {
+                    id,
+                    descricao: descricao.to_string(),
+                };
+                (id, tipo)
+            })
+            .collect();
+
+        Self {
+            ultimo_id: 0,
+            clientes: BTreeMap::new(),
+            ultimo_endereco_id: 0,
+            enderecos: BTreeMap::new(),
+            tipos_endereco,
+            ultimo_contato_id: 0,
+            contatos: BTreeMap::new(),
+            ceps: BTreeMap::new(),
+            logs: vec![],
+            ultimo_usuario_id: 0,
+            usuarios: BTreeMap::new(),
+            ultimo_produto_id: 0,
+            produtos: BTreeMap::new(),
+            estoques: BTreeMap::new(),
+            ultimo_movimento_id: 0,
+            movimentos: BTreeMap::new(),
+        }
+    }
+}
+
+impl Dados {
+    /// Retorna os endereços de um cliente, por ordem de ID.
+    fn enderecos_de(&self, cliente_id: i32) -> Vec<Endereco> {
+        self.enderecos
+            .values()
+            .filter(|e| e.cliente_id == cliente_id)
+            .cloned()
+            .collect()
+    }
+
+    /// Retorna os contatos de um cliente, por ordem de ID.
+    fn contatos_de(&self, cliente_id: i32) -> Vec<Contato> {
+        self.contatos
+            .values()
+            .filter(|c| c.cliente_id == cliente_id)
+            .cloned()
+            .collect()
+    }
+
+    /// Verifica se um cliente já possui um contato principal do tipo
+    /// informado, desconsiderando o contato `ignorado`.
+    fn possui_contato_principal(&self, cliente_id: i32, tipo: i16, ignorado: i32) -> bool {
+        self.contatos.values().any(|c| {
+            c.cliente_id == cliente_id && c.tipo == tipo && c.principal && c.id != ignorado
+        })
+    }
+
+    /// Grava um registro no log de operações, com a data e hora atuais.
+    fn registra_log(&mut self, log: NovoLog) {
+        let id = self.logs.len() as i32 + 1;
+        self.logs.push(Log {
+            id,
+            tabela: log.tabela,
+            registro: log.registro,
+            usuario: log.usuario,
+            operacao: log.operacao,
+            datahora: Utc::now(),
+            descricao: log.descricao,
+        });
+    }
+
+    /// Retorna os registros do log de operações sobre um registro de uma
+    /// tabela, por ordem de ID.
+    fn logs_de(&mut self, tabela: &'static str, registro: i32) -> impl Iterator<Item = &mut Log> {
+        self.logs
+            .iter_mut()
+            .filter(move |l| l.tabela == tabela && l.registro == Some(registro))
+    }
+}
+
+/// Repositório de entidades armazenadas em memória.
+#[derive(Default)]
+pub struct MemRepository {
+    dados: Mutex<Dados>,
+}
+
+impl MemRepository {
+    /// Cria um repositório em memória vazio.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[tonic::async_trait]
+impl ClienteRepository for MemRepository {
+    async fn cadastra(&self, dados: NovoCliente) -> Result<Cliente, RepoError> {
+        let mut banco = self.dados.lock().unwrap();
+        banco.ultimo_id += 1;
+
+        let cliente = Cliente {
+            id: banco.ultimo_id,
+            tipo: dados.tipo,
+            nome: dados.nome,
+            pj: dados.pj,
+            docto: dados.docto,
+            ativo: dados.ativo,
+            bloqueado: dados.bloqueado,
+            limite_credito: None,
+            saldo_devedor: 0,
+            bloqueio_automatico: false,
+            docto_indice: dados.docto_indice,
+        };
+
+        banco.clientes.insert(cliente.id, cliente.clone());
+        Ok(cliente)
+    }
+
+    async fn consulta(&self, id: i32) -> Result<Cliente, RepoError> {
+        let banco = self.dados.lock().unwrap();
+        banco
+            .clientes
+            .get(&id)
+            .cloned()
+            .ok_or(RepoError::NaoEncontrado)
+    }
+
+    async fn consulta_docto(&self, docto: String) -> Result<Cliente, RepoError> {
+        // Os dados em memória nunca são cifrados ou indexados.
+        let cifra = Cifra::default();
+        let chave = duplicado::chave_texto(&docto, &cifra).ok_or(RepoError::NaoEncontrado)?;
+        let banco = self.dados.lock().unwrap();
+        banco
+            .clientes
+            .values()
+            .find(|c| duplicado::chave_docto(c, &cifra).as_deref() == Some(&chave))
+            .cloned()
+            .ok_or(RepoError::NaoEncontrado)
+    }
+
+    async fn lista(&self, pagina: i64) -> Result<Vec<Cliente>, RepoError> {
+        let banco = self.dados.lock().unwrap();
+        let offset = (pagina * CLIENTE_PAGE_SIZE).max(0) as usize;
+        Ok(banco
+            .clientes
+            .values()
+            .skip(offset)
+            .take(CLIENTE_PAGE_SIZE as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn busca(&self, termo: String, limite: i64) -> Result<Vec<ClienteEncontrado>, RepoError> {
+        let banco = self.dados.lock().unwrap();
+        Ok(busca::busca(
+            banco.clientes.values().cloned(),
+            &termo,
+            limite,
+        ))
+    }
+
+    async fn consulta_detalhada(&self, id: i32) -> Result<ClienteDetalhado, RepoError> {
+        let banco = self.dados.lock().unwrap();
+        let cliente = banco
+            .clientes
+            .get(&id)
+            .cloned()
+            .ok_or(RepoError::NaoEncontrado)?;
+        Ok((cliente, banco.enderecos_de(id), banco.contatos_de(id)))
+    }
+
+    async fn lista_detalhada(
+        &self,
+        pagina: i64,
+        incluir_enderecos: bool,
+        incluir_contatos: bool,
+    ) -> Result<Vec<ClienteDetalhado>, RepoError> {
+        let clientes = self.lista(pagina).await?;
+        let banco = self.dados.lock().unwrap();
+        Ok(clientes
+            .into_iter()
+            .map(|c| {
+                let enderecos = if incluir_enderecos {
+                    banco.enderecos_de(c.id)
+                } else {
+                    vec![]
+                };
+                let contatos = if incluir_contatos {
+                    banco.contatos_de(c.id)
+                } else {
+                    vec![]
+                };
+                (c, enderecos, contatos)
+            })
+            .collect())
+    }
+
+    async fn remove(&self, id: i32) -> Result<(), RepoError> {
+        let mut banco = self.dados.lock().unwrap();
+        banco.clientes.remove(&id);
+        banco.enderecos.retain(|_, e| e.cliente_id != id);
+        banco.contatos.retain(|_, c| c.cliente_id != id);
+        Ok(())
+    }
+
+    async fn define_limite_credito(
+        &self,
+        id: i32,
+        limite: Option<i64>,
+    ) -> Result<Cliente, RepoError> {
+        let mut banco = self.dados.lock().unwrap();
+        let cliente = banco
+            .clientes
+            .get_mut(&id)
+            .ok_or(RepoError::NaoEncontrado)?;
+        cliente.limite_credito = limite;
+        regras::aplica(cliente);
+        Ok(cliente.clone())
+    }
+
+    async fn registra_movimento_credito(
+        &self,
+        id: i32,
+        valor: i64,
+        bloqueia_excedente: bool,
+    ) -> Result<Cliente, RepoError> {
+        let mut banco = self.dados.lock().unwrap();
+        let cliente = banco
+            .clientes
+            .get_mut(&id)
+            .ok_or(RepoError::NaoEncontrado)?;
+        regras::movimenta(cliente, valor, bloqueia_excedente)?;
+        Ok(cliente.clone())
+    }
+
+    async fn detecta_duplicados(&self) -> Result<Vec<GrupoDuplicados>, RepoError> {
+        let banco = self.dados.lock().unwrap();
+        Ok(duplicado::detecta(
+            banco.clientes.values().cloned().collect(),
+            &Cifra::default(),
+        ))
+    }
+
+    async fn mescla(&self, dados: Mescla, usuario: String) -> Result<Cliente, RepoError> {
+        let mut banco = self.dados.lock().unwrap();
+        let origem = banco
+            .clientes
+            .get(&dados.origem_id)
+            .cloned()
+            .ok_or(RepoError::NaoEncontrado)?;
+        let mut destino = banco
+            .clientes
+            .get(&dados.destino_id)
+            .cloned()
+            .ok_or(RepoError::NaoEncontrado)?;
+
+        let enderecos_principais: Vec<i16> = banco
+            .enderecos
+            .values()
+            .filter(|e| e.cliente_id == destino.id && e.principal)
+            .map(|e| e.tipo)
+            .collect();
+        for e in banco.enderecos.values_mut() {
+            if e.cliente_id == origem.id {
+                e.cliente_id = destino.id;
+                e.principal &= !enderecos_principais.contains(&e.tipo);
+            }
+        }
+
+        let contatos_principais: Vec<i16> = banco
+            .contatos
+            .values()
+            .filter(|c| c.cliente_id == destino.id && c.principal)
+            .map(|c| c.tipo)
+            .collect();
+        for c in banco.contatos.values_mut() {
+            if c.cliente_id == origem.id {
+                c.cliente_id = destino.id;
+                c.principal &= !contatos_principais.contains(&c.tipo);
+            }
+        }
+
+        regras::mescla(&mut destino, &origem);
+        banco.clientes.insert(destino.id, destino.clone());
+        banco.clientes.remove(&origem.id);
+
+        for l in banco.logs_de("cliente", origem.id) {
+            l.registro = Some(destino.id);
+        }
+        banco.registra_log(dados.log(&origem, &destino, &usuario));
+        Ok(destino)
+    }
+
+    async fn exporta(&self, id: i32) -> Result<DadosCliente, RepoError> {
+        let banco = self.dados.lock().unwrap();
+        let cliente = banco
+            .clientes
+            .get(&id)
+            .cloned()
+            .ok_or(RepoError::NaoEncontrado)?;
+        Ok(DadosCliente {
+            cliente,
+            enderecos: banco.enderecos_de(id),
+            contatos: banco.contatos_de(id),
+            log: banco
+                .logs
+                .iter()
+                .filter(|l| l.tabela == "cliente" && l.registro == Some(id))
+                .cloned()
+                .collect(),
+        })
+    }
+
+    async fn anonimiza(&self, id: i32, usuario: String) -> Result<Cliente, RepoError> {
+        let mut banco = self.dados.lock().unwrap();
+        let cliente = banco
+            .clientes
+            .get_mut(&id)
+            .ok_or(RepoError::NaoEncontrado)?;
+        privacidade::anonimiza_cliente(cliente);
+        let cliente = cliente.clone();
+
+        for e in banco.enderecos.values_mut() {
+            if e.cliente_id == id {
+                privacidade::anonimiza_endereco(e);
+            }
+        }
+        banco.contatos.retain(|_, c| c.cliente_id != id);
+
+        for l in banco.logs_de("cliente", id) {
+            l.descricao = None;
+        }
+        banco.registra_log(privacidade::log_anonimizacao(id, &usuario));
+        Ok(cliente)
+    }
+
+    async fn recifra_doctos(&self) -> Result<usize, RepoError> {
+        // Os dados em memória nunca são cifrados.
+        Ok(0)
+    }
+}
+
+#[tonic::async_trait]
+impl EnderecoRepository for MemRepository {
+    async fn cadastra_endereco(&self, dados: NovoEndereco) -> Result<Endereco, RepoError> {
+        let mut banco = self.dados.lock().unwrap();
+        if !banco.clientes.contains_key(&dados.cliente_id) {
+            return Err(RepoError::Invalido("Cliente inexistente".to_string()));
+        }
+        if !banco.tipos_endereco.contains_key(&dados.tipo) {
+            return Err(RepoError::Invalido(
+                "Tipo de endereço inexistente".to_string(),
+            ));
+        }
+        if dados.principal
+            && banco
+                .enderecos
+                .values()
+                .any(|e| e.cliente_id == dados.cliente_id && e.tipo == dados.tipo && e.principal)
+        {
+            return Err(RepoError::Conflito(
+                "Cliente já possui endereço principal deste tipo".to_string(),
+            ));
+        }
+        banco.ultimo_endereco_id += 1;
+
+        let endereco = Endereco {
+            id: banco.ultimo_endereco_id,
+            cliente_id: dados.cliente_id,
+            tipo: dados.tipo,
+            logradouro: dados.logradouro,
+            numero: dados.numero,
+            complemento: dados.complemento,
+            bairro: dados.bairro,
+            uf: dados.uf,
+            cidade: dados.cidade,
+            cep: Some(dados.cep),
+            principal: dados.principal,
+        };
+
+        banco.enderecos.insert(endereco.id, endereco.clone());
+        Ok(endereco)
+    }
+
+    async fn remove_endereco(&self, id: i32) -> Result<(), RepoError> {
+        let mut banco = self.dados.lock().unwrap();
+        banco.enderecos.remove(&id);
+        Ok(())
+    }
+
+    async fn lista_tipos_endereco(&self) -> Result<Vec<TipoEndereco>, RepoError> {
+        let banco = self.dados.lock().unwrap();
+        Ok(banco.tipos_endereco.values().cloned().collect())
+    }
+
+    async fn cadastra_tipo_endereco(&self, tipo: TipoEndereco) -> Result<TipoEndereco, RepoError> {
+        let mut banco = self.dados.lock().unwrap();
+        let existe = banco
+            .tipos_endereco
+            .values()
+            .any(|t| t.id == tipo.id || t.descricao == tipo.descricao);
+        if existe {
+            return Err(RepoError::Conflito(
+                "Tipo de endereço já cadastrado".to_string(),
+            ));
+        }
+
+        banco.tipos_endereco.insert(tipo.id, tipo.clone());
+        Ok(tipo)
+    }
+}
+
+#[tonic::async_trait]
+impl ContatoRepository for MemRepository {
+    async fn cadastra_contato(&self, dados: NovoContato) -> Result<Contato, RepoError> {
+        let mut banco = self.dados.lock().unwrap();
+        if !banco.clientes.contains_key(&dados.cliente_id) {
+            return Err(RepoError::Invalido("Cliente inexistente".to_string()));
+        }
+        if dados.principal && banco.possui_contato_principal(dados.cliente_id, dados.tipo, 0) {
+            return Err(RepoError::Conflito(
+                "Cliente já possui contato principal deste tipo".to_string(),
+            ));
+        }
+        banco.ultimo_contato_id += 1;
+
+        let contato = Contato {
+            id: banco.ultimo_contato_id,
+            cliente_id: dados.cliente_id,
+            tipo: dados.tipo,
+            valor: dados.valor,
+            principal: dados.principal,
+            observacao: dados.observacao,
+        };
+
+        banco.contatos.insert(contato.id, contato.clone());
+        Ok(contato)
+    }
+
+    async fn lista_contatos(&self, cliente_id: i32) -> Result<Vec<Contato>, RepoError> {
+        let banco = self.dados.lock().unwrap();
+        if !banco.clientes.contains_key(&cliente_id) {
+            return Err(RepoError::NaoEncontrado);
+        }
+        Ok(banco.contatos_de(cliente_id))
+    }
+
+    async fn atualiza_contato(&self, dados: AlteraContato) -> Result<Contato, RepoError> {
+        let mut banco = self.dados.lock().unwrap();
+        let cliente_id = banco
+            .contatos
+            .get(&dados.id)
+            .map(|c| c.cliente_id)
+            .ok_or(RepoError::NaoEncontrado)?;
+        if dados.principal && banco.possui_contato_principal(cliente_id, dados.tipo, dados.id) {
+            return Err(RepoError::Conflito(
+                "Cliente já possui contato principal deste tipo".to_string(),
+            ));
+        }
+
+        let contato = Contato {
+            id: dados.id,
+            cliente_id,
+            tipo: dados.tipo,
+            valor: dados.valor,
+            principal: dados.principal,
+            observacao: dados.observacao,
+        };
+
+        banco.contatos.insert(contato.id, contato.clone());
+        Ok(contato)
+    }
+
+    async fn remove_contato(&self, id: i32) -> Result<(), RepoError> {
+        let mut banco = self.dados.lock().unwrap();
+        banco.contatos.remove(&id);
+        Ok(())
+    }
+}
+
+#[tonic::async_trait]
+impl CepRepository for MemRepository {
+    async fn consulta_cep(&self, codigo: String) -> Result<Cep, RepoError> {
+        let banco = self.dados.lock().unwrap();
+        banco
+            .ceps
+            .get(&codigo)
+            .cloned()
+            .ok_or(RepoError::NaoEncontrado)
+    }
+
+    async fn importa_ceps(&self, ceps: Vec<Cep>) -> Result<usize, RepoError> {
+        let mut banco = self.dados.lock().unwrap();
+        let quantidade = ceps.len();
+        for cep in ceps {
+            banco.ceps.insert(cep.codigo.clone(), cep);
+        }
+        Ok(quantidade)
+    }
+}
+
+#[tonic::async_trait]
+impl LogRepository for MemRepository {
+    async fn lista_log(&self, tabela: String) -> Result<Vec<Log>, RepoError> {
+        let banco = self.dados.lock().unwrap();
+        Ok(banco
+            .logs
+            .iter()
+            .filter(|l| l.tabela == tabela)
+            .cloned()
+            .collect())
+    }
+}
+
+#[tonic::async_trait]
+impl UsuarioRepository for MemRepository {
+    async fn cadastra_usuario(&self, dados: NovoUsuario) -> Result<Usuario, RepoError> {
+        let mut banco = self.dados.lock().unwrap();
+        if banco.usuarios.values().any(|u| u.login == dados.login) {
+            return Err(RepoError::Conflito("Login já cadastrado".to_string()));
+        }
+        banco.ultimo_usuario_id += 1;
+
+        let usuario = Usuario {
+            id: banco.ultimo_usuario_id,
+            login: dados.login,
+            nome: dados.nome,
+            email: dados.email,
+            senha_hash: dados.senha_hash,
+        };
+
+        banco.usuarios.insert(usuario.id, usuario.clone());
+        Ok(usuario)
+    }
+
+    async fn consulta_usuario(&self, id: i32) -> Result<Usuario, RepoError> {
+        let banco = self.dados.lock().unwrap();
+        banco
+            .usuarios
+            .get(&id)
+            .cloned()
+            .ok_or(RepoError::NaoEncontrado)
+    }
+
+    async fn consulta_usuario_login(&self, login: String) -> Result<Usuario, RepoError> {
+        let banco = self.dados.lock().unwrap();
+        banco
+            .usuarios
+            .values()
+            .find(|u| u.login == login)
+            .cloned()
+            .ok_or(RepoError::NaoEncontrado)
+    }
+
+    async fn lista_usuarios(&self) -> Result<Vec<Usuario>, RepoError> {
+        let banco = self.dados.lock().unwrap();
+        Ok(banco.usuarios.values().cloned().collect())
+    }
+
+    async fn remove_usuario(&self, id: i32) -> Result<(), RepoError> {
+        let mut banco = self.dados.lock().unwrap();
+        banco.usuarios.remove(&id);
+        Ok(())
+    }
+}
+
+#[tonic::async_trait]
+impl ProdutoRepository for MemRepository {
+    async fn cadastra_produto(&self, dados: NovoProduto) -> Result<Produto, RepoError> {
+        let mut banco = self.dados.lock().unwrap();
+        banco.ultimo_produto_id += 1;
+
+        let produto = Produto {
+            id: banco.ultimo_produto_id,
+            descricao: dados.descricao,
+            unidsaida: dados.unidsaida,
+        };
+
+        banco.produtos.insert(produto.id, produto.clone());
+        Ok(produto)
+    }
+
+    async fn consulta_produto(&self, id: i32) -> Result<Produto, RepoError> {
+        let banco = self.dados.lock().unwrap();
+        banco
+            .produtos
+            .get(&id)
+            .cloned()
+            .ok_or(RepoError::NaoEncontrado)
+    }
+
+    async fn lista_produtos(&self) -> Result<Vec<Produto>, RepoError> {
+        let banco = self.dados.lock().unwrap();
+        Ok(banco.produtos.values().cloned().collect())
+    }
+
+    async fn remove_produto(&self, id: i32) -> Result<(), RepoError> {
+        let mut banco = self.dados.lock().unwrap();
+        banco.produtos.remove(&id);
+        banco.estoques.remove(&id);
+        banco.movimentos.retain(|_, m| m.produto_id != id);
+        Ok(())
+    }
+}
+
+#[tonic::async_trait]
+impl EstoqueRepository for MemRepository {
+    async fn consulta_estoque(&self, produto_id: i32) -> Result<Estoque, RepoError> {
+        let banco = self.dados.lock().unwrap();
+        if !banco.produtos.contains_key(&produto_id) {
+            return Err(RepoError::NaoEncontrado);
+        }
+        Ok(banco
+            .estoques
+            .get(&produto_id)
+            .cloned()
+            .unwrap_or_else(|| Estoque::vazio(produto_id)))
+    }
+
+    async fn registra_movimento_estoque(
+        &self,
+        dados: NovoMovEstoque,
+    ) -> Result<Estoque, RepoError> {
+        let mut banco = self.dados.lock().unwrap();
+        if !banco.produtos.contains_key(&dados.produto_id) {
+            return Err(RepoError::Invalido("Produto inexistente".to_string()));
+        }
+
+        let mut estoque = banco
+            .estoques
+            .get(&dados.produto_id)
+            .cloned()
+            .unwrap_or_else(|| Estoque::vazio(dados.produto_id));
+        estoque.movimenta(&dados);
+
+        banco.ultimo_movimento_id += 1;
+        let movimento = MovEstoque {
+            id: banco.ultimo_movimento_id,
+            produto_id: dados.produto_id,
+            docto: dados.docto,
+            quantidade: dados.quantidade,
+            preco_frete: dados.preco_frete,
+            datahora: Utc::now(),
+            preco_unitario: dados.preco_unitario,
+        };
+
+        banco.movimentos.insert(movimento.id, movimento);
+        banco.estoques.insert(estoque.produto_id, estoque.clone());
+        Ok(estoque)
+    }
+
+    async fn lista_movimentos_estoque(
+        &self,
+        produto_id: i32,
+    ) -> Result<Vec<MovEstoque>, RepoError> {
+        let banco = self.dados.lock().unwrap();
+        if !banco.produtos.contains_key(&produto_id) {
+            return Err(RepoError::NaoEncontrado);
+        }
+        Ok(banco
+            .movimentos
+            .values()
+            .filter(|m| m.produto_id == produto_id)
+            .cloned()
+            .collect())
+    }
+}
+
+impl Repository for MemRepository {}
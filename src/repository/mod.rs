@@ -0,0 +1,448 @@
+// mod.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este módulo define os repositórios de entidades, que abstraem o meio de
+//! armazenamento utilizado pelos serviços.
+//!
+//! Cada entidade possui um trait próprio com as operações suportadas, e cada
+//! submódulo implementa todos estes traits para um meio de armazenamento
+//! específico:
+//!
+//! - [`postgres`]: armazenamento no PostgreSQL, através dos controllers;
+//! - [`memory`]: armazenamento volátil em memória, útil para testes e para
+//!   demonstrações sem um banco de dados;
+//! - `sqlite`: armazenamento no SQLite, disponível apenas com a feature
+//!   `sqlite`.
+
+pub mod memory;
+pub mod postgres;
+
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+use crate::cifra::{Cifra, ErroCifra};
+use crate::controller::regras::MovimentoRecusado;
+use crate::db::DbError;
+use crate::model::busca::ClienteEncontrado;
+use crate::model::cep::Cep;
+use crate::model::cliente::{Cliente, ContemClientes, DoctoRecifrado, NovoCliente};
+use crate::model::contato::{AlteraContato, Contato, NovoContato};
+use crate::model::duplicado::{GrupoDuplicados, Mescla};
+use crate::model::endereco::{ClienteDetalhado, Endereco, NovoEndereco, TipoEndereco};
+use crate::model::estoque::{Estoque, MovEstoque, NovoMovEstoque};
+use crate::model::logdb::Log;
+use crate::model::privacidade::DadosCliente;
+use crate::model::produto::{NovoProduto, Produto};
+use crate::model::usuario::{NovoUsuario, Usuario};
+use bb8::RunError;
+use diesel::result::{DatabaseErrorInformation, DatabaseErrorKind, Error};
+use std::fmt;
+use tonic::Status;
+
+/// Número de clientes lidos e regravados de cada vez ao recifrar os
+/// documentos (veja [`ClienteRepository::recifra_doctos`]).
+pub(crate) const LOTE_RECIFRA: i64 = 500;
+
+/// Representação de um erro ocorrido em uma operação de um repositório.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RepoError {
+    /// A entidade requisitada não existe.
+    NaoEncontrado,
+    /// Os dados informados para a operação são inválidos.
+    Invalido(String),
+    /// Os dados informados conflitam com uma entidade já existente.
+    Conflito(String),
+    /// A operação foi recusada pelas regras de negócio, como ocorre com
+    /// operações em nome de clientes bloqueados.
+    Recusado(String),
+    /// O meio de armazenamento está sobrecarregado, e a operação não pôde
+    /// ser executada a tempo.
+    Esgotado,
+    /// Não foi possível acessar o meio de armazenamento.
+    Indisponivel(String),
+    /// Ocorreu um erro inesperado no meio de armazenamento.
+    Interno(String),
+}
+
+impl fmt::Display for RepoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RepoError::NaoEncontrado => write!(f, "Registro não encontrado"),
+            RepoError::Invalido(msg) => write!(f, "Dados inválidos: {}", msg),
+            RepoError::Conflito(msg) => write!(f, "Conflito: {}", msg),
+            RepoError::Recusado(msg) => write!(f, "Operação recusada: {}", msg),
+            RepoError::Esgotado => write!(f, "Pool de conexões esgotada"),
+            RepoError::Indisponivel(msg) => write!(f, "Armazenamento indisponível: {}", msg),
+            RepoError::Interno(msg) => write!(f, "Erro interno: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for RepoError {}
+
+impl RepoError {
+    /// Indica se o erro diz respeito ao meio de armazenamento em si, e não
+    /// aos dados envolvidos na operação.
+    pub fn armazenamento(&self) -> bool {
+        matches!(self, RepoError::Esgotado | RepoError::Indisponivel(_))
+    }
+}
+
+impl From<DbError> for RepoError {
+    fn from(e: DbError) -> RepoError {
+        match e {
+            DbError::Pool(RunError::TimedOut) => RepoError::Esgotado,
+            DbError::Pool(RunError::User(e)) => RepoError::Indisponivel(e.to_string()),
+            DbError::Query(Error::NotFound) => RepoError::NaoEncontrado,
+            DbError::Query(Error::DatabaseError(kind, info)) => match kind {
+                DatabaseErrorKind::UniqueViolation if viola_indice_docto(info.as_ref()) => {
+                    RepoError::Conflito("Documento já cadastrado para outro cliente".to_string())
+                }
+                DatabaseErrorKind::UniqueViolation => {
+                    RepoError::Conflito(info.message().to_string())
+                }
+                DatabaseErrorKind::ForeignKeyViolation => {
+                    RepoError::Invalido(info.message().to_string())
+                }
+                _ => RepoError::Interno(info.message().to_string()),
+            },
+            DbError::Query(e) => RepoError::Interno(e.to_string()),
+            DbError::Cancelado => RepoError::Indisponivel(e.to_string()),
+        }
+    }
+}
+
+/// Indica se uma violação de unicidade diz respeito ao índice cego dos
+/// documentos dos clientes. O PostgreSQL informa o nome do índice violado, e
+/// o SQLite apenas a coluna, na mensagem de erro.
+fn viola_indice_docto(info: &(dyn DatabaseErrorInformation + Send + Sync)) -> bool {
+    info.constraint_name() == Some("cliente_docto_indice_idx")
+        || info.message().contains("cliente.docto_indice")
+}
+
+impl From<MovimentoRecusado> for RepoError {
+    fn from(e: MovimentoRecusado) -> RepoError {
+        RepoError::Recusado(e.to_string())
+    }
+}
+
+impl From<ErroCifra> for RepoError {
+    fn from(e: ErroCifra) -> RepoError {
+        RepoError::Interno(e.to_string())
+    }
+}
+
+/// Decifragem dos documentos dos clientes lidos do meio de armazenamento
+/// (veja [`cifra`](crate::cifra)).
+pub(crate) trait Decifravel: ContemClientes + Sized {
+    /// Retorna a estrutura com os documentos de seus clientes decifrados.
+    fn decifrado(mut self, cifra: &Cifra) -> Result<Self, RepoError> {
+        for cliente in self.clientes_mut() {
+            cliente.docto = cifra.decifra(&cliente.docto)?;
+        }
+        Ok(self)
+    }
+}
+
+impl<T: ContemClientes> Decifravel for T {}
+
+/// Recifra um lote de documentos, no formato retornado pelos controllers
+/// (ID, documento e índice cego), com a chave atual. Apenas os documentos que
+/// precisam ser regravados são retornados.
+pub(crate) fn recifra_lote(
+    cifra: &Cifra,
+    lote: Vec<(i32, String, Option<String>)>,
+) -> Result<Vec<DoctoRecifrado>, ErroCifra> {
+    let mut recifrados = Vec::new();
+    for (id, anterior, indice) in lote {
+        if let Some((docto, docto_indice)) = cifra.recifra(&anterior, indice.as_deref())? {
+            recifrados.push(DoctoRecifrado {
+                id,
+                anterior,
+                docto,
+                docto_indice,
+            });
+        }
+    }
+    Ok(recifrados)
+}
+
+impl From<RepoError> for Status {
+    fn from(e: RepoError) -> Status {
+        match e {
+            RepoError::NaoEncontrado => Status::not_found("Registro não encontrado"),
+            RepoError::Invalido(msg) => Status::invalid_argument(msg),
+            RepoError::Conflito(msg) => Status::already_exists(msg),
+            RepoError::Recusado(msg) => Status::failed_precondition(msg),
+            RepoError::Esgotado => Status::resource_exhausted("Pool de conexões esgotada"),
+            RepoError::Indisponivel(_) => Status::internal("Impossível conectar ao banco de dados"),
+            RepoError::Interno(_) => Status::internal("Erro interno no banco de dados"),
+        }
+    }
+}
+
+/// Operações de armazenamento de clientes.
+#[tonic::async_trait]
+pub trait ClienteRepository: Send + Sync {
+    /// Cadastra um novo cliente, retornando o cliente recém-cadastrado.
+    async fn cadastra(&self, dados: NovoCliente) -> Result<Cliente, RepoError>;
+
+    /// Consulta os dados de um único cliente, através de seu ID.
+    async fn consulta(&self, id: i32) -> Result<Cliente, RepoError>;
+
+    /// Consulta os dados de um único cliente, através de seu documento,
+    /// desconsiderando a pontuação. Caso mais de um cliente possua o mesmo
+    /// documento, retorna o de menor ID.
+    async fn consulta_docto(&self, docto: String) -> Result<Cliente, RepoError>;
+
+    /// Retorna uma página de clientes, por ordem de ID. As páginas começam a
+    /// ser contadas a partir de 0, e possuem no máximo
+    /// [`CLIENTE_PAGE_SIZE`](crate::controller::cliente::CLIENTE_PAGE_SIZE)
+    /// clientes.
+    async fn lista(&self, pagina: i64) -> Result<Vec<Cliente>, RepoError>;
+
+    /// Busca clientes cujos nomes sejam similares ao termo informado,
+    /// desconsiderando acentos e maiúsculas (veja
+    /// [`busca`](crate::model::busca)). Retorna no máximo `limite` clientes,
+    /// da maior para a menor similaridade.
+    async fn busca(&self, termo: String, limite: i64)
+        -> Result<Vec<ClienteEncontrado>, RepoError>;
+
+    /// Consulta os dados de um único cliente, junto com todos os seus
+    /// endereços e contatos.
+    async fn consulta_detalhada(&self, id: i32) -> Result<ClienteDetalhado, RepoError>;
+
+    /// Retorna uma página de clientes, como em [`lista`](Self::lista). Caso
+    /// `incluir_enderecos` ou `incluir_contatos` sejam verdadeiros, os
+    /// endereços ou os contatos de cada cliente também são retornados; caso
+    /// contrário, as listas correspondentes estarão vazias.
+    async fn lista_detalhada(
+        &self,
+        pagina: i64,
+        incluir_enderecos: bool,
+        incluir_contatos: bool,
+    ) -> Result<Vec<ClienteDetalhado>, RepoError>;
+
+    /// Remove um cliente através de seu ID, caso o mesmo exista. Os endereços
+    /// e os contatos do cliente também são removidos.
+    async fn remove(&self, id: i32) -> Result<(), RepoError>;
+
+    /// Define o limite de crédito de um cliente, em centavos, reavaliando as
+    /// regras de crédito (veja [`regras`](crate::controller::regras)).
+    /// Retorna o cliente atualizado.
+    async fn define_limite_credito(
+        &self,
+        id: i32,
+        limite: Option<i64>,
+    ) -> Result<Cliente, RepoError>;
+
+    /// Registra um movimento de crédito em nome de um cliente, em centavos,
+    /// reavaliando as regras de crédito (veja
+    /// [`regras::movimenta`](crate::controller::regras::movimenta)). Retorna
+    /// o cliente atualizado, ou [`RepoError::Recusado`] caso o movimento seja
+    /// um débito e o cliente esteja bloqueado, ou o débito viole as regras
+    /// sem que `bloqueia_excedente` seja verdadeiro.
+    async fn registra_movimento_credito(
+        &self,
+        id: i32,
+        valor: i64,
+        bloqueia_excedente: bool,
+    ) -> Result<Cliente, RepoError>;
+
+    /// Detecta grupos de clientes possivelmente duplicados, com o mesmo
+    /// documento ou com nomes similares (veja
+    /// [`duplicado`](crate::model::duplicado)).
+    async fn detecta_duplicados(&self) -> Result<Vec<GrupoDuplicados>, RepoError>;
+
+    /// Mescla um cliente duplicado em outro, em uma única operação: os
+    /// endereços e os contatos da origem passam a pertencer ao destino, os
+    /// dados de crédito são combinados, a origem é removida e a mescla é
+    /// registrada no log de operações em nome de `usuario`. Retorna o cliente
+    /// de destino atualizado, ou [`RepoError::NaoEncontrado`] caso algum dos
+    /// clientes não exista.
+    async fn mescla(&self, dados: Mescla, usuario: String) -> Result<Cliente, RepoError>;
+
+    /// Retorna todos os dados armazenados a respeito de um cliente, incluindo
+    /// os registros do log de operações sobre o mesmo, ou
+    /// [`RepoError::NaoEncontrado`] caso o cliente não exista.
+    async fn exporta(&self, id: i32) -> Result<DadosCliente, RepoError>;
+
+    /// Anonimiza um cliente de forma irreversível, em uma única operação (veja
+    /// [`privacidade`](crate::model::privacidade)), registrando a
+    /// anonimização no log de operações em nome de `usuario`. Retorna o
+    /// cliente anonimizado, ou [`RepoError::NaoEncontrado`] caso o mesmo não
+    /// exista.
+    async fn anonimiza(&self, id: i32, usuario: String) -> Result<Cliente, RepoError>;
+
+    /// Recifra os documentos de todos os clientes com a chave atual do
+    /// repositório (veja [`Cifra::recifra`]), em lotes. Documentos cifrados
+    /// com chaves antigas passam a usar a chave atual, e documentos em texto
+    /// puro são cifrados; caso nenhuma chave atual seja configurada, os
+    /// documentos são decifrados. Retorna o número de clientes atualizados.
+    async fn recifra_doctos(&self) -> Result<usize, RepoError>;
+}
+
+/// Operações de armazenamento de endereços de clientes e do catálogo de tipos
+/// de endereço.
+///
+/// Os dados recebidos já devem estar validados (veja
+/// [`model::endereco`](crate::model::endereco)); cabe ao repositório garantir
+/// que o cliente e o tipo de endereço existam, e que cada cliente tenha no
+/// máximo um endereço principal de cada tipo.
+#[tonic::async_trait]
+pub trait EnderecoRepository: Send + Sync {
+    /// Cadastra um endereço para um cliente, retornando o endereço
+    /// recém-cadastrado. Retorna [`RepoError::Invalido`] caso o cliente ou o
+    /// tipo de endereço não existam, e [`RepoError::Conflito`] caso o
+    /// endereço seja principal e o cliente já possua um endereço principal do
+    /// mesmo tipo.
+    async fn cadastra_endereco(&self, dados: NovoEndereco) -> Result<Endereco, RepoError>;
+
+    /// Remove um endereço através de seu ID, caso o mesmo exista.
+    async fn remove_endereco(&self, id: i32) -> Result<(), RepoError>;
+
+    /// Retorna todos os tipos de endereço do catálogo, por ordem de código.
+    async fn lista_tipos_endereco(&self) -> Result<Vec<TipoEndereco>, RepoError>;
+
+    /// Cadastra um novo tipo de endereço no catálogo. Retorna
+    /// [`RepoError::Conflito`] caso o código ou a descrição já existam.
+    async fn cadastra_tipo_endereco(&self, tipo: TipoEndereco)
+        -> Result<TipoEndereco, RepoError>;
+}
+
+/// Operações de armazenamento de contatos de clientes.
+///
+/// Os dados recebidos já devem estar validados (veja
+/// [`model::contato`](crate::model::contato)); cabe ao repositório garantir
+/// que o cliente exista, e que cada cliente tenha no máximo um contato
+/// principal de cada tipo.
+#[tonic::async_trait]
+pub trait ContatoRepository: Send + Sync {
+    /// Cadastra um contato para um cliente, retornando o contato
+    /// recém-cadastrado. Retorna [`RepoError::Invalido`] caso o cliente não
+    /// exista, e [`RepoError::Conflito`] caso o contato seja principal e o
+    /// cliente já possua um contato principal do mesmo tipo.
+    async fn cadastra_contato(&self, dados: NovoContato) -> Result<Contato, RepoError>;
+
+    /// Retorna todos os contatos de um cliente, por ordem de ID. Retorna
+    /// [`RepoError::NaoEncontrado`] caso o cliente não exista.
+    async fn lista_contatos(&self, cliente_id: i32) -> Result<Vec<Contato>, RepoError>;
+
+    /// Atualiza os dados de um contato, retornando o contato atualizado.
+    /// Retorna [`RepoError::NaoEncontrado`] caso o contato não exista, e
+    /// [`RepoError::Conflito`] nas mesmas condições de
+    /// [`cadastra_contato`](Self::cadastra_contato).
+    async fn atualiza_contato(&self, dados: AlteraContato) -> Result<Contato, RepoError>;
+
+    /// Remove um contato através de seu ID, caso o mesmo exista.
+    async fn remove_contato(&self, id: i32) -> Result<(), RepoError>;
+}
+
+/// Operações sobre a base de CEPs, usada para preencher endereços.
+#[tonic::async_trait]
+pub trait CepRepository: Send + Sync {
+    /// Consulta os dados de um CEP, no formato `00000-000`.
+    async fn consulta_cep(&self, codigo: String) -> Result<Cep, RepoError>;
+
+    /// Importa um lote de CEPs já validados para a base, substituindo os
+    /// dados de CEPs já existentes. Retorna o número de CEPs importados.
+    async fn importa_ceps(&self, ceps: Vec<Cep>) -> Result<usize, RepoError>;
+}
+
+/// Operações sobre o log de operações do banco de dados. Os registros são
+/// gravados pelas próprias operações registradas.
+#[tonic::async_trait]
+pub trait LogRepository: Send + Sync {
+    /// Retorna os registros do log de operações sobre uma tabela, por ordem
+    /// de ID.
+    async fn lista_log(&self, tabela: String) -> Result<Vec<Log>, RepoError>;
+}
+
+/// Operações de armazenamento de usuários do sistema.
+#[tonic::async_trait]
+pub trait UsuarioRepository: Send + Sync {
+    /// Cadastra um novo usuário, retornando o usuário recém-cadastrado.
+    /// Retorna [`RepoError::Conflito`] caso o login já exista.
+    async fn cadastra_usuario(&self, dados: NovoUsuario) -> Result<Usuario, RepoError>;
+
+    /// Consulta os dados de um único usuário, através de seu ID.
+    async fn consulta_usuario(&self, id: i32) -> Result<Usuario, RepoError>;
+
+    /// Consulta os dados de um único usuário, através de seu login.
+    async fn consulta_usuario_login(&self, login: String) -> Result<Usuario, RepoError>;
+
+    /// Retorna todos os usuários, por ordem de ID.
+    async fn lista_usuarios(&self) -> Result<Vec<Usuario>, RepoError>;
+
+    /// Remove um usuário através de seu ID, caso o mesmo exista.
+    async fn remove_usuario(&self, id: i32) -> Result<(), RepoError>;
+}
+
+/// Operações de armazenamento de produtos.
+#[tonic::async_trait]
+pub trait ProdutoRepository: Send + Sync {
+    /// Cadastra um novo produto, retornando o produto recém-cadastrado.
+    async fn cadastra_produto(&self, dados: NovoProduto) -> Result<Produto, RepoError>;
+
+    /// Consulta os dados de um único produto, através de seu ID.
+    async fn consulta_produto(&self, id: i32) -> Result<Produto, RepoError>;
+
+    /// Retorna todos os produtos, por ordem de ID.
+    async fn lista_produtos(&self) -> Result<Vec<Produto>, RepoError>;
+
+    /// Remove um produto através de seu ID, caso o mesmo exista, junto com
+    /// seu estoque e suas movimentações.
+    async fn remove_produto(&self, id: i32) -> Result<(), RepoError>;
+}
+
+/// Operações sobre o estoque de produtos (veja
+/// [`model::estoque`](crate::model::estoque)).
+#[tonic::async_trait]
+pub trait EstoqueRepository: Send + Sync {
+    /// Consulta o estoque de um produto. Produtos sem movimentações possuem
+    /// estoque zerado; retorna [`RepoError::NaoEncontrado`] caso o produto
+    /// não exista.
+    async fn consulta_estoque(&self, produto_id: i32) -> Result<Estoque, RepoError>;
+
+    /// Registra uma movimentação de estoque, retornando o estoque atualizado
+    /// do produto. Retorna [`RepoError::Invalido`] caso o produto não exista.
+    async fn registra_movimento_estoque(&self, dados: NovoMovEstoque)
+        -> Result<Estoque, RepoError>;
+
+    /// Retorna as movimentações de estoque de um produto, por ordem de ID.
+    /// Retorna [`RepoError::NaoEncontrado`] caso o produto não exista.
+    async fn lista_movimentos_estoque(&self, produto_id: i32)
+        -> Result<Vec<MovEstoque>, RepoError>;
+}
+
+/// Repositório completo da aplicação, englobando os repositórios de todas as
+/// entidades. Novas entidades devem ter seus traits adicionados como
+/// supertraits deste.
+pub trait Repository:
+    ClienteRepository
+    + EnderecoRepository
+    + ContatoRepository
+    + CepRepository
+    + LogRepository
+    + UsuarioRepository
+    + ProdutoRepository
+    + EstoqueRepository
+{
+    /// Retorna o estado da pool de conexões do repositório, caso exista.
+    fn estado_pool(&self) -> Option<bb8::State> {
+        None
+    }
+}
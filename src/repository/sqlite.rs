@@ -0,0 +1,348 @@
+// sqlite.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Implementação dos repositórios sobre o SQLite.
+//!
+//! As operações são delegadas aos controllers, e executadas na pool de threads
+//! bloqueantes através de [`db::run`].
+
+use super::{
+    recifra_lote, CepRepository, ClienteRepository, ContatoRepository, Decifravel,
+    EnderecoRepository, EstoqueRepository, LogRepository, ProdutoRepository, RepoError, Repository,
+    UsuarioRepository, LOTE_RECIFRA,
+};
+use crate::cifra::Cifra;
+use crate::controller::sqlite::cep;
+use crate::controller::sqlite::cliente as controller;
+use crate::controller::sqlite::contato;
+use crate::controller::sqlite::endereco;
+use crate::controller::sqlite::estoque;
+use crate::controller::sqlite::logdb;
+use crate::controller::sqlite::produto;
+use crate::controller::sqlite::usuario;
+use crate::db::{self, SqlitePool};
+use crate::model::busca::ClienteEncontrado;
+use crate::model::cep::Cep;
+use crate::model::cliente::{Cliente, NovoCliente};
+use crate::model::contato::{AlteraContato, Contato, NovoContato};
+use crate::model::duplicado::{GrupoDuplicados, Mescla};
+use crate::model::endereco::{ClienteDetalhado, Endereco, NovoEndereco, TipoEndereco};
+use crate::model::estoque::{Estoque, MovEstoque, NovoMovEstoque};
+use crate::model::logdb::Log;
+use crate::model::privacidade::DadosCliente;
+use crate::model::produto::{NovoProduto, Produto};
+use crate::model::usuario::{NovoUsuario, Usuario};
+
+/// Repositório de entidades armazenadas no SQLite.
+#[derive(Clone)]
+pub struct SqliteRepository {
+    pool: SqlitePool,
+    cifra: Cifra,
+}
+
+impl SqliteRepository {
+    /// Cria um repositório a partir de uma pool de conexões, sem cifragem
+    /// de documentos.
+    pub fn new(pool: SqlitePool) -> Self {
+        Self {
+            pool,
+            cifra: Cifra::default(),
+        }
+    }
+
+    /// Define as chaves usadas para cifrar e decifrar os documentos dos
+    /// clientes (veja [`cifra`](crate::cifra)).
+    pub fn com_cifra(self, cifra: Cifra) -> Self {
+        Self { cifra, ..self }
+    }
+
+    /// Retorna a pool de conexões usada pelo repositório.
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+}
+
+#[tonic::async_trait]
+impl ClienteRepository for SqliteRepository {
+    async fn cadastra(&self, dados: NovoCliente) -> Result<Cliente, RepoError> {
+        let dados = dados.cifrado(&self.cifra);
+        db::run(&self.pool, move |conn| controller::cadastra(conn, dados))
+            .await?
+            .decifrado(&self.cifra)
+    }
+
+    async fn consulta(&self, id: i32) -> Result<Cliente, RepoError> {
+        db::run(&self.pool, move |conn| controller::consulta(conn, id))
+            .await?
+            .decifrado(&self.cifra)
+    }
+
+    async fn consulta_docto(&self, docto: String) -> Result<Cliente, RepoError> {
+        let cifra = self.cifra.clone();
+        db::run(&self.pool, move |conn| {
+            controller::consulta_docto(conn, &docto, &cifra)
+        })
+        .await?
+        .decifrado(&self.cifra)
+    }
+
+    async fn lista(&self, pagina: i64) -> Result<Vec<Cliente>, RepoError> {
+        db::run(&self.pool, move |conn| controller::lista(conn, pagina))
+            .await?
+            .decifrado(&self.cifra)
+    }
+
+    async fn busca(&self, termo: String, limite: i64) -> Result<Vec<ClienteEncontrado>, RepoError> {
+        db::run(&self.pool, move |conn| {
+            controller::busca(conn, &termo, limite)
+        })
+        .await?
+        .decifrado(&self.cifra)
+    }
+
+    async fn consulta_detalhada(&self, id: i32) -> Result<ClienteDetalhado, RepoError> {
+        db::run(&self.pool, move |conn| {
+            controller::consulta_detalhada(conn, id)
+        })
+        .await?
+        .decifrado(&self.cifra)
+    }
+
+    async fn lista_detalhada(
+        &self,
+        pagina: i64,
+        incluir_enderecos: bool,
+        incluir_contatos: bool,
+    ) -> Result<Vec<ClienteDetalhado>, RepoError> {
+        db::run(&self.pool, move |conn| {
+            controller::lista_detalhada(conn, pagina, incluir_enderecos, incluir_contatos)
+        })
+        .await?
+        .decifrado(&self.cifra)
+    }
+
+    async fn remove(&self, id: i32) -> Result<(), RepoError> {
+        Ok(db::run(&self.pool, move |conn| controller::remove(conn, id)).await?)
+    }
+
+    async fn define_limite_credito(
+        &self,
+        id: i32,
+        limite: Option<i64>,
+    ) -> Result<Cliente, RepoError> {
+        db::run(&self.pool, move |conn| {
+            controller::define_limite(conn, id, limite)
+        })
+        .await?
+        .decifrado(&self.cifra)
+    }
+
+    async fn registra_movimento_credito(
+        &self,
+        id: i32,
+        valor: i64,
+        bloqueia_excedente: bool,
+    ) -> Result<Cliente, RepoError> {
+        db::run(&self.pool, move |conn| {
+            controller::registra_movimento(conn, id, valor, bloqueia_excedente)
+        })
+        .await??
+        .decifrado(&self.cifra)
+    }
+
+    async fn detecta_duplicados(&self) -> Result<Vec<GrupoDuplicados>, RepoError> {
+        let cifra = self.cifra.clone();
+        db::run(&self.pool, move |conn| {
+            controller::detecta_duplicados(conn, &cifra)
+        })
+        .await?
+        .decifrado(&self.cifra)
+    }
+
+    async fn mescla(&self, dados: Mescla, usuario: String) -> Result<Cliente, RepoError> {
+        db::run(&self.pool, move |conn| {
+            controller::mescla(conn, dados, &usuario)
+        })
+        .await?
+        .decifrado(&self.cifra)
+    }
+
+    async fn exporta(&self, id: i32) -> Result<DadosCliente, RepoError> {
+        db::run(&self.pool, move |conn| controller::exporta(conn, id))
+            .await?
+            .decifrado(&self.cifra)
+    }
+
+    async fn anonimiza(&self, id: i32, usuario: String) -> Result<Cliente, RepoError> {
+        db::run(&self.pool, move |conn| {
+            controller::anonimiza(conn, id, &usuario)
+        })
+        .await?
+        .decifrado(&self.cifra)
+    }
+
+    async fn recifra_doctos(&self) -> Result<usize, RepoError> {
+        let mut apos_id = 0;
+        let mut total = 0;
+        loop {
+            let lote = db::run(&self.pool, move |conn| {
+                controller::doctos(conn, apos_id, LOTE_RECIFRA)
+            })
+            .await?;
+            let ultimo = match lote.last() {
+                Some(&(id, _, _)) => id,
+                None => return Ok(total),
+            };
+            let recifrados = recifra_lote(&self.cifra, lote)?;
+            total += db::run(&self.pool, move |conn| {
+                controller::grava_doctos(conn, recifrados)
+            })
+            .await?;
+            apos_id = ultimo;
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl EnderecoRepository for SqliteRepository {
+    async fn cadastra_endereco(&self, dados: NovoEndereco) -> Result<Endereco, RepoError> {
+        Ok(db::run(&self.pool, move |conn| endereco::cadastra(conn, dados)).await?)
+    }
+
+    async fn remove_endereco(&self, id: i32) -> Result<(), RepoError> {
+        Ok(db::run(&self.pool, move |conn| endereco::remove(conn, id)).await?)
+    }
+
+    async fn lista_tipos_endereco(&self) -> Result<Vec<TipoEndereco>, RepoError> {
+        Ok(db::run(&self.pool, endereco::lista_tipos).await?)
+    }
+
+    async fn cadastra_tipo_endereco(&self, tipo: TipoEndereco) -> Result<TipoEndereco, RepoError> {
+        Ok(db::run(&self.pool, move |conn| endereco::cadastra_tipo(conn, tipo)).await?)
+    }
+}
+
+#[tonic::async_trait]
+impl ContatoRepository for SqliteRepository {
+    async fn cadastra_contato(&self, dados: NovoContato) -> Result<Contato, RepoError> {
+        Ok(db::run(&self.pool, move |conn| contato::cadastra(conn, dados)).await?)
+    }
+
+    async fn lista_contatos(&self, cliente_id: i32) -> Result<Vec<Contato>, RepoError> {
+        Ok(db::run(&self.pool, move |conn| contato::lista(conn, cliente_id)).await?)
+    }
+
+    async fn atualiza_contato(&self, dados: AlteraContato) -> Result<Contato, RepoError> {
+        Ok(db::run(&self.pool, move |conn| contato::atualiza(conn, dados)).await?)
+    }
+
+    async fn remove_contato(&self, id: i32) -> Result<(), RepoError> {
+        Ok(db::run(&self.pool, move |conn| contato::remove(conn, id)).await?)
+    }
+}
+
+#[tonic::async_trait]
+impl CepRepository for SqliteRepository {
+    async fn consulta_cep(&self, codigo: String) -> Result<Cep, RepoError> {
+        Ok(db::run(&self.pool, move |conn| cep::consulta(conn, &codigo)).await?)
+    }
+
+    async fn importa_ceps(&self, ceps: Vec<Cep>) -> Result<usize, RepoError> {
+        Ok(db::run(&self.pool, move |conn| cep::importa(conn, &ceps)).await?)
+    }
+}
+
+#[tonic::async_trait]
+impl LogRepository for SqliteRepository {
+    async fn lista_log(&self, tabela: String) -> Result<Vec<Log>, RepoError> {
+        Ok(db::run(&self.pool, move |conn| logdb::lista(conn, &tabela)).await?)
+    }
+}
+
+#[tonic::async_trait]
+impl UsuarioRepository for SqliteRepository {
+    async fn cadastra_usuario(&self, dados: NovoUsuario) -> Result<Usuario, RepoError> {
+        Ok(db::run(&self.pool, move |conn| usuario::cadastra(conn, dados)).await?)
+    }
+
+    async fn consulta_usuario(&self, id: i32) -> Result<Usuario, RepoError> {
+        Ok(db::run(&self.pool, move |conn| usuario::consulta(conn, id)).await?)
+    }
+
+    async fn consulta_usuario_login(&self, login: String) -> Result<Usuario, RepoError> {
+        Ok(db::run(&self.pool, move |conn| {
+            usuario::consulta_login(conn, &login)
+        })
+        .await?)
+    }
+
+    async fn lista_usuarios(&self) -> Result<Vec<Usuario>, RepoError> {
+        Ok(db::run(&self.pool, usuario::lista).await?)
+    }
+
+    async fn remove_usuario(&self, id: i32) -> Result<(), RepoError> {
+        Ok(db::run(&self.pool, move |conn| usuario::remove(conn, id)).await?)
+    }
+}
+
+#[tonic::async_trait]
+impl ProdutoRepository for SqliteRepository {
+    async fn cadastra_produto(&self, dados: NovoProduto) -> Result<Produto, RepoError> {
+        Ok(db::run(&self.pool, move |conn| produto::cadastra(conn, dados)).await?)
+    }
+
+    async fn consulta_produto(&self, id: i32) -> Result<Produto, RepoError> {
+        Ok(db::run(&self.pool, move |conn| produto::consulta(conn, id)).await?)
+    }
+
+    async fn lista_produtos(&self) -> Result<Vec<Produto>, RepoError> {
+        Ok(db::run(&self.pool, produto::lista).await?)
+    }
+
+    async fn remove_produto(&self, id: i32) -> Result<(), RepoError> {
+        Ok(db::run(&self.pool, move |conn| produto::remove(conn, id)).await?)
+    }
+}
+
+#[tonic::async_trait]
+impl EstoqueRepository for SqliteRepository {
+    async fn consulta_estoque(&self, produto_id: i32) -> Result<Estoque, RepoError> {
+        Ok(db::run(&self.pool, move |conn| estoque::consulta(conn, produto_id)).await?)
+    }
+
+    async fn registra_movimento_estoque(
+        &self,
+        dados: NovoMovEstoque,
+    ) -> Result<Estoque, RepoError> {
+        Ok(db::run(&self.pool, move |conn| estoque::movimenta(conn, dados)).await?)
+    }
+
+    async fn lista_movimentos_estoque(
+        &self,
+        produto_id: i32,
+    ) -> Result<Vec<MovEstoque>, RepoError> {
+        Ok(db::run(&self.pool, move |conn| {
+            estoque::lista_movimentos(conn, produto_id)
+        })
+        .await?)
+    }
+}
+
+impl Repository for SqliteRepository {
+    fn estado_pool(&self) -> Option<bb8::State> {
+        Some(self.pool.state())
+    }
+}
@@ -0,0 +1,694 @@
+// sdk.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este módulo implementa um cliente de alto nível para o Minerva.Lite,
+//! que esconde os detalhes do gRPC de quem o utiliza.
+//!
+//! O cliente [`MinervaLite`] cuida da conexão com o servidor, do envio do
+//! token de acesso, e de novas tentativas em caso de falhas transitórias.
+//! Os erros retornados pelo servidor são convertidos para o tipo [`Erro`].
+//!
+//! ```no_run
+//! use futures::TryStreamExt;
+//! use minerva_lite::sdk::{MinervaLite, NovoCliente};
+//!
+//! # async fn exemplo() -> Result<(), minerva_lite::sdk::Erro> {
+//! let minerva = MinervaLite::builder("http://127.0.0.1:50051")
+//!     .token("segredo")
+//!     .conecta()
+//!     .await?;
+//!
+//! let cliente = minerva
+//!     .cadastra(NovoCliente {
+//!         nome: "Fulano".into(),
+//!         pj: false,
+//!         docto: "000.000.000-00".into(),
+//!     })
+//!     .await?;
+//!
+//! let mut clientes = minerva.lista().await?;
+//! while let Some(cliente) = clientes.try_next().await? {
+//!     println!("{}: {}", cliente.id, cliente.nome);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::minerva_client::MinervaClient;
+use crate::minerva_clientes_client::MinervaClientesClient;
+use crate::{
+    BuscaRequest, CepRequest, DoctoClienteRequest, IdClienteRequest, IdContatoRequest,
+    IdEnderecoRequest, LimiteCreditoRequest, ListaDetalhadaRequest, MesclaRequest,
+    MovimentoCreditoRequest,
+};
+use futures::{stream, Stream, TryStreamExt};
+use rand::Rng;
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+use tonic::codegen::InterceptedService;
+use tonic::metadata::{Ascii, MetadataValue};
+use tonic::service::Interceptor;
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Code, Request, Response, Status};
+
+pub use crate::AtualizaContatoRequest as AtualizaContato;
+pub use crate::CepResponse as Cep;
+pub use crate::ClienteDetalhadoResponse as ClienteDetalhado;
+pub use crate::ClienteEncontradoResponse as ClienteEncontrado;
+pub use crate::ClienteResponse as Cliente;
+pub use crate::ContatoResponse as Contato;
+pub use crate::EnderecoResponse as Endereco;
+pub use crate::GrupoDuplicadosResponse as GrupoDuplicados;
+pub use crate::NovoClienteRequest as NovoCliente;
+pub use crate::NovoContatoRequest as NovoContato;
+pub use crate::NovoEnderecoRequest as NovoEndereco;
+pub use crate::TipoEnderecoMessage as TipoEndereco;
+
+type Canal = InterceptedService<Channel, Autenticacao>;
+
+/// Representação de um erro ocorrido em uma operação do cliente.
+#[derive(Debug)]
+pub enum Erro {
+    /// O endereço do servidor é inválido.
+    Endereco(String),
+    /// O token de acesso contém caracteres inválidos.
+    Token,
+    /// Não foi possível conectar ao servidor.
+    Conexao(tonic::transport::Error),
+    /// O registro requisitado não existe.
+    NaoEncontrado(String),
+    /// Os dados enviados ao servidor são inválidos.
+    Invalido(String),
+    /// Os dados enviados ao servidor conflitam com um registro existente.
+    Conflito(String),
+    /// A operação foi recusada pelas regras de negócio (por exemplo, por ser
+    /// feita em nome de um cliente bloqueado).
+    Recusado(String),
+    /// O token de acesso não foi informado, ou não é válido.
+    NaoAutenticado(String),
+    /// O token de acesso não permite a operação requisitada.
+    SemPermissao(String),
+    /// O servidor está sobrecarregado, ou o limite de requisições foi
+    /// excedido.
+    Esgotado(String),
+    /// O prazo da requisição foi excedido.
+    PrazoExcedido(String),
+    /// O servidor não está disponível.
+    Indisponivel(String),
+    /// Qualquer outro erro retornado pelo servidor.
+    Servidor(Box<Status>),
+}
+
+impl fmt::Display for Erro {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Erro::Endereco(addr) => write!(f, "Endereço inválido: {}", addr),
+            Erro::Token => write!(f, "Token de acesso inválido"),
+            Erro::Conexao(e) => write!(f, "Impossível conectar ao servidor: {}", e),
+            Erro::NaoEncontrado(msg)
+            | Erro::Invalido(msg)
+            | Erro::Conflito(msg)
+            | Erro::Recusado(msg)
+            | Erro::NaoAutenticado(msg)
+            | Erro::SemPermissao(msg)
+            | Erro::Esgotado(msg)
+            | Erro::PrazoExcedido(msg)
+            | Erro::Indisponivel(msg) => write!(f, "{}", msg),
+            Erro::Servidor(status) => write!(f, "{} ({:?})", status.message(), status.code()),
+        }
+    }
+}
+
+impl std::error::Error for Erro {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Erro::Conexao(e) => Some(e),
+            Erro::Servidor(status) => Some(status.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<Status> for Erro {
+    fn from(status: Status) -> Erro {
+        let msg = status.message().to_string();
+        match status.code() {
+            Code::NotFound => Erro::NaoEncontrado(msg),
+            Code::InvalidArgument => Erro::Invalido(msg),
+            Code::AlreadyExists => Erro::Conflito(msg),
+            Code::FailedPrecondition => Erro::Recusado(msg),
+            Code::Unauthenticated => Erro::NaoAutenticado(msg),
+            Code::PermissionDenied => Erro::SemPermissao(msg),
+            Code::ResourceExhausted => Erro::Esgotado(msg),
+            Code::DeadlineExceeded => Erro::PrazoExcedido(msg),
+            Code::Unavailable => Erro::Indisponivel(msg),
+            _ => Erro::Servidor(Box::new(status)),
+        }
+    }
+}
+
+impl Erro {
+    /// Retorna o código de status gRPC do erro, caso tenha sido retornado
+    /// pelo servidor.
+    pub fn codigo(&self) -> Option<Code> {
+        match self {
+            Erro::Endereco(_) | Erro::Token | Erro::Conexao(_) => None,
+            Erro::NaoEncontrado(_) => Some(Code::NotFound),
+            Erro::Invalido(_) => Some(Code::InvalidArgument),
+            Erro::Conflito(_) => Some(Code::AlreadyExists),
+            Erro::Recusado(_) => Some(Code::FailedPrecondition),
+            Erro::NaoAutenticado(_) => Some(Code::Unauthenticated),
+            Erro::SemPermissao(_) => Some(Code::PermissionDenied),
+            Erro::Esgotado(_) => Some(Code::ResourceExhausted),
+            Erro::PrazoExcedido(_) => Some(Code::DeadlineExceeded),
+            Erro::Indisponivel(_) => Some(Code::Unavailable),
+            Erro::Servidor(status) => Some(status.code()),
+        }
+    }
+
+    /// Indica se a requisição pode ser repetida. Quando `idempotente` é
+    /// falso, apenas erros que garantem que a requisição não foi executada
+    /// são considerados.
+    fn transitorio(&self, idempotente: bool) -> bool {
+        match self {
+            Erro::Esgotado(_) => true,
+            Erro::Indisponivel(_) => idempotente,
+            _ => false,
+        }
+    }
+}
+
+/// Interceptador que adiciona o token de acesso ao metadado `authorization`
+/// das requisições, caso exista.
+#[derive(Clone, Default)]
+pub struct Autenticacao(Option<MetadataValue<Ascii>>);
+
+impl Autenticacao {
+    /// Cria um interceptador para o token informado, que será enviado no
+    /// formato `Bearer <token>`.
+    pub fn new(token: Option<&str>) -> Result<Self, Erro> {
+        let token = token
+            .map(|token| format!("Bearer {}", token).parse())
+            .transpose()
+            .map_err(|_| Erro::Token)?;
+        Ok(Self(token))
+    }
+}
+
+impl Interceptor for Autenticacao {
+    fn call(&mut self, mut req: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(token) = &self.0 {
+            req.metadata_mut().insert("authorization", token.clone());
+        }
+        Ok(req)
+    }
+}
+
+/// Política de novas tentativas para falhas transitórias. A espera entre as
+/// tentativas dobra a cada falha, até o limite de `espera_maxima`, e é
+/// sorteada entre a metade e o total deste valor.
+#[derive(Debug, Clone, Copy)]
+pub struct Retentativas {
+    /// Número máximo de novas tentativas. Zero desabilita as novas
+    /// tentativas.
+    pub maximo: u32,
+    /// Espera antes da primeira nova tentativa.
+    pub espera_inicial: Duration,
+    /// Espera máxima entre duas tentativas.
+    pub espera_maxima: Duration,
+}
+
+impl Default for Retentativas {
+    fn default() -> Self {
+        Self {
+            maximo: 3,
+            espera_inicial: Duration::from_millis(100),
+            espera_maxima: Duration::from_secs(2),
+        }
+    }
+}
+
+impl Retentativas {
+    /// Política que não realiza novas tentativas.
+    pub fn nenhuma() -> Self {
+        Self {
+            maximo: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Calcula a espera antes da nova tentativa de número `tentativa`,
+    /// começando em 0.
+    fn espera(&self, tentativa: u32) -> Duration {
+        let espera = self
+            .espera_inicial
+            .saturating_mul(2u32.saturating_pow(tentativa))
+            .min(self.espera_maxima);
+        rand::thread_rng().gen_range(espera / 2..=espera)
+    }
+}
+
+/// Construtor de um cliente [`MinervaLite`].
+pub struct MinervaLiteBuilder {
+    addr: String,
+    token: Option<String>,
+    timeout: Option<Duration>,
+    retentativas: Retentativas,
+}
+
+impl MinervaLiteBuilder {
+    /// Define o token de acesso enviado em todas as requisições.
+    pub fn token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Define o prazo de cada requisição. O prazo é informado ao servidor
+    /// através do metadado `grpc-timeout`.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Define a política de novas tentativas para falhas transitórias.
+    pub fn retentativas(mut self, retentativas: Retentativas) -> Self {
+        self.retentativas = retentativas;
+        self
+    }
+
+    /// Conecta ao servidor, retornando o cliente.
+    pub async fn conecta(self) -> Result<MinervaLite, Erro> {
+        let mut endpoint =
+            Endpoint::from_shared(self.addr.clone()).map_err(|_| Erro::Endereco(self.addr))?;
+        if let Some(timeout) = self.timeout {
+            endpoint = endpoint.timeout(timeout);
+        }
+
+        let autenticacao = Autenticacao::new(self.token.as_deref())?;
+        let channel = endpoint.connect().await.map_err(Erro::Conexao)?;
+
+        Ok(MinervaLite {
+            base: MinervaClient::with_interceptor(channel.clone(), autenticacao.clone()),
+            clientes: MinervaClientesClient::with_interceptor(channel, autenticacao),
+            retentativas: self.retentativas,
+        })
+    }
+}
+
+/// Cliente de alto nível para o Minerva.Lite.
+///
+/// O cliente pode ser clonado livremente; todas as cópias compartilham a
+/// mesma conexão com o servidor.
+#[derive(Clone)]
+pub struct MinervaLite {
+    base: MinervaClient<Canal>,
+    clientes: MinervaClientesClient<Canal>,
+    retentativas: Retentativas,
+}
+
+impl MinervaLite {
+    /// Cria um construtor para um cliente conectado ao endereço informado
+    /// (por exemplo, `http://127.0.0.1:50051`).
+    pub fn builder(addr: impl Into<String>) -> MinervaLiteBuilder {
+        MinervaLiteBuilder {
+            addr: addr.into(),
+            token: None,
+            timeout: None,
+            retentativas: Retentativas::default(),
+        }
+    }
+
+    /// Conecta ao endereço informado, com as configurações padrão.
+    pub async fn conecta(addr: impl Into<String>) -> Result<Self, Erro> {
+        Self::builder(addr).conecta().await
+    }
+
+    /// Executa uma requisição, repetindo-a em caso de falhas transitórias
+    /// de acordo com a política de novas tentativas.
+    async fn executa<T, F, Fut>(&self, idempotente: bool, mut requisicao: F) -> Result<T, Erro>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<Response<T>, Status>>,
+    {
+        let mut tentativa = 0;
+        loop {
+            match requisicao().await {
+                Ok(resposta) => return Ok(resposta.into_inner()),
+                Err(status) => {
+                    let erro = Erro::from(status);
+                    if tentativa >= self.retentativas.maximo || !erro.transitorio(idempotente) {
+                        return Err(erro);
+                    }
+                    tokio::time::sleep(self.retentativas.espera(tentativa)).await;
+                    tentativa += 1;
+                }
+            }
+        }
+    }
+
+    /// Verifica se o servidor está respondendo.
+    pub async fn ping(&self) -> Result<(), Erro> {
+        self.executa(true, || {
+            let mut client = self.base.clone();
+            async move { client.ping(()).await }
+        })
+        .await
+    }
+
+    /// Cadastra um novo cliente, retornando o cliente cadastrado.
+    ///
+    /// Como o cadastro não é idempotente, novas tentativas só são feitas
+    /// quando o servidor recusa a requisição por excesso de carga.
+    pub async fn cadastra(&self, dados: NovoCliente) -> Result<Cliente, Erro> {
+        self.executa(false, || {
+            let mut client = self.clientes.clone();
+            let dados = dados.clone();
+            async move { client.cadastra(dados).await }
+        })
+        .await
+    }
+
+    /// Consulta um único cliente através de seu ID.
+    pub async fn consulta(&self, id: i32) -> Result<Cliente, Erro> {
+        self.executa(true, || {
+            let mut client = self.clientes.clone();
+            async move { client.consulta(IdClienteRequest { id }).await }
+        })
+        .await
+    }
+
+    /// Consulta um único cliente através de seu documento, com ou sem
+    /// pontuação.
+    pub async fn consulta_docto(&self, docto: &str) -> Result<Cliente, Erro> {
+        self.executa(true, || {
+            let mut client = self.clientes.clone();
+            let docto = docto.to_string();
+            async move { client.consulta_docto(DoctoClienteRequest { docto }).await }
+        })
+        .await
+    }
+
+    /// Consulta os dados de um único cliente, junto com seus endereços e
+    /// contatos.
+    pub async fn consulta_detalhada(&self, id: i32) -> Result<ClienteDetalhado, Erro> {
+        self.executa(true, || {
+            let mut client = self.clientes.clone();
+            async move { client.consulta_detalhada(IdClienteRequest { id }).await }
+        })
+        .await
+    }
+
+    /// Retorna todos os clientes cadastrados, por ordem de ID.
+    ///
+    /// As páginas enviadas pelo servidor são recebidas sob demanda, à medida
+    /// que o stream é consumido. Apenas o início da listagem é repetido em
+    /// caso de falhas transitórias; erros durante o recebimento das páginas
+    /// são entregues pelo próprio stream.
+    pub async fn lista(
+        &self,
+    ) -> Result<impl Stream<Item = Result<Cliente, Erro>> + Send + 'static, Erro> {
+        let paginas = self
+            .executa(true, || {
+                let mut client = self.clientes.clone();
+                async move { client.lista(()).await }
+            })
+            .await?;
+
+        Ok(paginas
+            .map_err(Erro::from)
+            .map_ok(|pagina| stream::iter(pagina.clientes.into_iter().map(Ok)))
+            .try_flatten())
+    }
+
+    /// Retorna todos os clientes cadastrados em um vetor, por ordem de ID.
+    pub async fn lista_todos(&self) -> Result<Vec<Cliente>, Erro> {
+        self.lista().await?.try_collect().await
+    }
+
+    /// Retorna todos os clientes cadastrados, por ordem de ID, como em
+    /// [`lista`](Self::lista). Caso `incluir_enderecos` ou `incluir_contatos`
+    /// sejam verdadeiros, os endereços ou os contatos de cada cliente também
+    /// são retornados.
+    pub async fn lista_detalhada(
+        &self,
+        incluir_enderecos: bool,
+        incluir_contatos: bool,
+    ) -> Result<impl Stream<Item = Result<ClienteDetalhado, Erro>> + Send + 'static, Erro> {
+        let paginas = self
+            .executa(true, || {
+                let mut client = self.clientes.clone();
+                async move {
+                    client
+                        .lista_detalhada(ListaDetalhadaRequest {
+                            incluir_enderecos,
+                            incluir_contatos,
+                        })
+                        .await
+                }
+            })
+            .await?;
+
+        Ok(paginas
+            .map_err(Erro::from)
+            .map_ok(|pagina| stream::iter(pagina.clientes.into_iter().map(Ok)))
+            .try_flatten())
+    }
+
+    /// Busca clientes pelo nome, tolerando erros de digitação, acentos e
+    /// maiúsculas. Retorna no máximo `limite` clientes (ou o padrão do
+    /// servidor, caso seja 0), da maior para a menor similaridade com o
+    /// termo buscado.
+    pub async fn busca(&self, termo: &str, limite: i32) -> Result<Vec<ClienteEncontrado>, Erro> {
+        self.executa(true, || {
+            let mut client = self.clientes.clone();
+            let termo = termo.to_string();
+            async move { client.busca(BuscaRequest { termo, limite }).await }
+        })
+        .await
+        .map(|resposta| resposta.clientes)
+    }
+
+    /// Retorna os grupos de clientes possivelmente duplicados, com o mesmo
+    /// documento ou com nomes similares.
+    pub async fn detecta_duplicados(&self) -> Result<Vec<GrupoDuplicados>, Erro> {
+        self.executa(true, || {
+            let mut client = self.clientes.clone();
+            async move { client.detecta_duplicados(()).await }
+        })
+        .await
+        .map(|resposta| resposta.grupos)
+    }
+
+    /// Mescla o cliente `origem_id` no cliente `destino_id`, que recebe seus
+    /// endereços, contatos e saldo devedor. A origem é removida, e o destino
+    /// atualizado é retornado.
+    ///
+    /// Como a origem deixa de existir após a mescla, a requisição não é
+    /// repetida em caso de falhas transitórias.
+    pub async fn mescla(&self, origem_id: i32, destino_id: i32) -> Result<Cliente, Erro> {
+        self.executa(false, || {
+            let mut client = self.clientes.clone();
+            async move {
+                client
+                    .mescla(MesclaRequest {
+                        origem_id,
+                        destino_id,
+                    })
+                    .await
+            }
+        })
+        .await
+    }
+
+    /// Exporta todos os dados armazenados a respeito de um cliente, para
+    /// atender a uma solicitação do titular dos dados (LGPD). Retorna um
+    /// documento JSON com a estrutura de
+    /// [`DadosClienteMessage`](crate::DadosClienteMessage).
+    pub async fn exporta_dados(&self, id: i32) -> Result<String, Erro> {
+        self.executa(true, || {
+            let mut client = self.clientes.clone();
+            async move { client.exporta_dados(IdClienteRequest { id }).await }
+        })
+        .await
+        .map(|resposta| resposta.documento)
+    }
+
+    /// Anonimiza um cliente de forma irreversível, para atender a uma
+    /// solicitação do titular dos dados (LGPD). Retorna o cliente
+    /// anonimizado.
+    ///
+    /// Como o resultado de uma nova anonimização é o mesmo, a requisição é
+    /// repetida em caso de falhas transitórias.
+    pub async fn anonimiza(&self, id: i32) -> Result<Cliente, Erro> {
+        self.executa(true, || {
+            let mut client = self.clientes.clone();
+            async move { client.anonimiza(IdClienteRequest { id }).await }
+        })
+        .await
+    }
+
+    /// Remove um cliente através de seu ID.
+    pub async fn deleta(&self, id: i32) -> Result<(), Erro> {
+        self.executa(true, || {
+            let mut client = self.clientes.clone();
+            async move { client.deleta(IdClienteRequest { id }).await }
+        })
+        .await
+    }
+
+    /// Cadastra um endereço para um cliente existente. Os dados são validados
+    /// pelo servidor; UFs e CEPs inválidos resultam em [`Erro::Invalido`], e
+    /// um segundo endereço principal do mesmo tipo resulta em
+    /// [`Erro::Conflito`].
+    pub async fn cadastra_endereco(&self, dados: NovoEndereco) -> Result<Endereco, Erro> {
+        self.executa(false, || {
+            let mut client = self.clientes.clone();
+            let dados = dados.clone();
+            async move { client.cadastra_endereco(dados).await }
+        })
+        .await
+    }
+
+    /// Remove um endereço através de seu ID.
+    pub async fn deleta_endereco(&self, id: i32) -> Result<(), Erro> {
+        self.executa(true, || {
+            let mut client = self.clientes.clone();
+            async move { client.deleta_endereco(IdEnderecoRequest { id }).await }
+        })
+        .await
+    }
+
+    /// Retorna o catálogo de tipos de endereço, por ordem de código.
+    pub async fn lista_tipos_endereco(&self) -> Result<Vec<TipoEndereco>, Erro> {
+        let tipos = self
+            .executa(true, || {
+                let mut client = self.clientes.clone();
+                async move { client.lista_tipos_endereco(()).await }
+            })
+            .await?;
+        Ok(tipos.tipos)
+    }
+
+    /// Cadastra um novo tipo de endereço no catálogo.
+    pub async fn cadastra_tipo_endereco(&self, tipo: TipoEndereco) -> Result<TipoEndereco, Erro> {
+        self.executa(false, || {
+            let mut client = self.clientes.clone();
+            let tipo = tipo.clone();
+            async move { client.cadastra_tipo_endereco(tipo).await }
+        })
+        .await
+    }
+
+    /// Define o limite de crédito de um cliente, em centavos, retornando o
+    /// cliente atualizado. Caso `limite` seja `None`, o limite é removido.
+    pub async fn define_limite_credito(
+        &self,
+        cliente_id: i32,
+        limite: Option<i64>,
+    ) -> Result<Cliente, Erro> {
+        self.executa(true, || {
+            let mut client = self.clientes.clone();
+            let req = LimiteCreditoRequest { cliente_id, limite };
+            async move { client.define_limite_credito(req).await }
+        })
+        .await
+    }
+
+    /// Registra um movimento de crédito em nome de um cliente, em centavos:
+    /// valores positivos são débitos, e negativos são pagamentos. Débitos em
+    /// nome de clientes bloqueados são recusados com [`Erro::Recusado`], assim
+    /// como débitos que excedam o limite de crédito, exceto caso
+    /// `bloqueia_excedente` seja verdadeiro: neste caso, o débito é registrado
+    /// e o cliente é bloqueado.
+    ///
+    /// Como o movimento não é idempotente, a requisição não é repetida em caso
+    /// de falhas que não garantam que o mesmo não foi registrado.
+    pub async fn registra_movimento_credito(
+        &self,
+        cliente_id: i32,
+        valor: i64,
+        bloqueia_excedente: bool,
+    ) -> Result<Cliente, Erro> {
+        self.executa(false, || {
+            let mut client = self.clientes.clone();
+            let req = MovimentoCreditoRequest {
+                cliente_id,
+                valor,
+                bloqueia_excedente,
+            };
+            async move { client.registra_movimento_credito(req).await }
+        })
+        .await
+    }
+
+    /// Cadastra um contato (telefone ou e-mail) para um cliente existente,
+    /// retornando o contato cadastrado com o valor normalizado.
+    pub async fn cadastra_contato(&self, dados: NovoContato) -> Result<Contato, Erro> {
+        self.executa(false, || {
+            let mut client = self.clientes.clone();
+            let dados = dados.clone();
+            async move { client.cadastra_contato(dados).await }
+        })
+        .await
+    }
+
+    /// Retorna todos os contatos de um cliente, por ordem de ID.
+    pub async fn lista_contatos(&self, cliente_id: i32) -> Result<Vec<Contato>, Erro> {
+        let contatos = self
+            .executa(true, || {
+                let mut client = self.clientes.clone();
+                async move {
+                    client
+                        .lista_contatos(IdClienteRequest { id: cliente_id })
+                        .await
+                }
+            })
+            .await?;
+        Ok(contatos.contatos)
+    }
+
+    /// Substitui os dados de um contato, retornando o contato atualizado.
+    pub async fn atualiza_contato(&self, dados: AtualizaContato) -> Result<Contato, Erro> {
+        self.executa(true, || {
+            let mut client = self.clientes.clone();
+            let dados = dados.clone();
+            async move { client.atualiza_contato(dados).await }
+        })
+        .await
+    }
+
+    /// Remove um contato através de seu ID.
+    pub async fn deleta_contato(&self, id: i32) -> Result<(), Erro> {
+        self.executa(true, || {
+            let mut client = self.clientes.clone();
+            async move { client.deleta_contato(IdContatoRequest { id }).await }
+        })
+        .await
+    }
+
+    /// Consulta um CEP na base de CEPs do servidor, retornando os dados que
+    /// podem ser usados para preencher um endereço. O CEP pode ser informado
+    /// com ou sem hífen.
+    pub async fn consulta_cep(&self, cep: &str) -> Result<Cep, Erro> {
+        self.executa(true, || {
+            let mut client = self.clientes.clone();
+            let cep = cep.to_string();
+            async move { client.consulta_cep(CepRequest { cep }).await }
+        })
+        .await
+    }
+}
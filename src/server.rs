@@ -14,31 +14,322 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-mod service;
-
+use clap::{Parser, Subcommand};
+use diesel::{Connection, PgConnection};
+use diesel_migrations::MigrationConnection;
 use dotenv::dotenv;
+use minerva_lite::cifra::Cifra;
+use minerva_lite::migrations::{self, Migracao, MIGRACOES};
+use minerva_lite::repository::memory::MemRepository;
+use minerva_lite::repository::postgres::PgRepository;
+use minerva_lite::repository::Repository;
+use minerva_lite::{db, importacao, service};
 use std::env;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 use tonic::transport::Server;
+use tower::Layer;
+
+#[cfg(feature = "sqlite")]
+use diesel::SqliteConnection;
+#[cfg(feature = "sqlite")]
+use minerva_lite::migrations::MIGRACOES_SQLITE;
+#[cfg(feature = "sqlite")]
+use minerva_lite::repository::sqlite::SqliteRepository;
 
 type ErrorImpl = Box<dyn std::error::Error>;
 
+/// Mensagem de erro para bancos SQLite quando o suporte não foi compilado.
+#[cfg(not(feature = "sqlite"))]
+const SQLITE_DESABILITADO: &str =
+    "Suporte a SQLite não habilitado; compile o servidor com a feature `sqlite`";
+
+/// Servidor gRPC do Minerva.Lite.
+#[derive(Parser)]
+#[clap(name = "liteserver", version)]
+struct Cli {
+    /// Aplica as migrações pendentes do banco de dados antes de iniciar o
+    /// servidor.
+    #[clap(long, env = "AUTO_MIGRATE", action)]
+    auto_migrate: bool,
+
+    /// Executa o servidor em modo de demonstração, armazenando os dados
+    /// apenas em memória, sem um banco de dados.
+    #[clap(long, env = "DEMO_MODE", action)]
+    demo: bool,
+
+    /// Habilita o gRPC-Web, permitindo que navegadores chamem o servidor
+    /// diretamente. As origens permitidas são definidas pela variável
+    /// `CORS_ALLOWED_ORIGINS`.
+    #[clap(long, env = "GRPC_WEB", action)]
+    grpc_web: bool,
+
+    #[clap(subcommand)]
+    comando: Option<Comando>,
+}
+
+/// Subcomandos do servidor. Caso nenhum seja informado, o servidor será
+/// iniciado.
+#[derive(Subcommand)]
+enum Comando {
+    /// Gerencia as migrações do banco de dados.
+    Migrate {
+        #[clap(subcommand)]
+        acao: AcaoMigracao,
+    },
+    /// Importa a base de CEPs a partir de um arquivo CSV, com as colunas
+    /// `cep`, `logradouro`, `bairro`, `cidade` e `uf`.
+    ImportaCep {
+        /// Caminho do arquivo CSV.
+        arquivo: PathBuf,
+        /// Caractere que separa as colunas do arquivo.
+        #[clap(long, default_value = ",")]
+        delimitador: char,
+    },
+    /// Recifra os documentos de todos os clientes com a chave definida em
+    /// `DOCTO_ENCRYPTION_KEY`, lendo os documentos cifrados com as chaves
+    /// definidas em `DOCTO_PREVIOUS_KEYS`, e recalcula seus índices cegos com
+    /// a chave definida em `DOCTO_INDEX_KEY`. Caso `DOCTO_ENCRYPTION_KEY` não
+    /// seja definida, os documentos são decifrados.
+    RecifraDoctos,
+}
+
+/// Ações possíveis sobre as migrações do banco de dados.
+#[derive(Subcommand)]
+enum AcaoMigracao {
+    /// Aplica todas as migrações pendentes.
+    Up,
+    /// Reverte a última migração aplicada.
+    Down,
+    /// Mostra quais migrações já foram aplicadas.
+    Status,
+}
+
 #[tokio::main]
 async fn main() -> Result<(), ErrorImpl> {
-    println!("Minerva.Lite gRPC v0.1.0 -- Server");
-
     dotenv().ok();
+    let cli = Cli::parse();
+
+    match cli.comando {
+        Some(Comando::Migrate { acao }) => return executa_migracao(acao),
+        Some(Comando::ImportaCep {
+            arquivo,
+            delimitador,
+        }) => return importa_cep(&arquivo, delimitador, cli.auto_migrate).await,
+        Some(Comando::RecifraDoctos) => return recifra_doctos(cli.auto_migrate).await,
+        None => {}
+    }
+
+    service::logging::init();
+
+    tracing::info!("Minerva.Lite gRPC v0.1.0 -- Server");
+
     let port = env::var("GRPC_PORT").expect("Impossível ler porta gRPC");
     let addr = format!("0.0.0.0:{}", port).parse()?;
 
-    let server = Server::builder()
-        .add_service(service::base::make_service().await)
-        .add_service(service::clientes::make_service().await)
-        .serve(addr);
+    let repo: Arc<dyn Repository> = if cli.demo {
+        tracing::warn!("Modo de demonstração: os dados serão mantidos apenas em memória");
+        Arc::new(MemRepository::new())
+    } else {
+        cria_repositorio(cli.auto_migrate).await?
+    };
+
+    let acesso = service::acesso::AcessoConfig::from_env();
+    if acesso.habilitado() {
+        tracing::info!(
+            tokens = acesso.tokens.len(),
+            "Controle de acesso habilitado"
+        );
+    } else {
+        tracing::warn!("Controle de acesso desabilitado; defina ACCESS_TOKENS");
+    }
+
+    // O servidor de métricas só é iniciado caso sua porta seja definida.
+    if let Ok(metrics_port) = env::var("METRICS_PORT") {
+        let metrics_addr = format!("0.0.0.0:{}", metrics_port).parse()?;
+        let repo = repo.clone();
+        tokio::spawn(async move {
+            if let Err(e) = service::metrics::serve(metrics_addr, repo).await {
+                tracing::error!(erro = %e, "Falha no servidor de métricas");
+            }
+        });
+        tracing::info!("Métricas disponíveis em http://{}/metrics.", metrics_addr);
+    }
+
+    // O gateway REST só é iniciado caso sua porta seja definida.
+    if let Ok(rest_port) = env::var("REST_PORT") {
+        let rest_addr = format!("0.0.0.0:{}", rest_port).parse()?;
+        let repo = repo.clone();
+        let acesso = acesso.clone();
+        tokio::spawn(async move {
+            if let Err(e) = service::rest::serve(rest_addr, repo, acesso).await {
+                tracing::error!(erro = %e, "Falha no gateway REST");
+            }
+        });
+        tracing::info!("Gateway REST disponível em http://{}/clientes.", rest_addr);
+    }
+
+    // Prazo máximo padrão para qualquer requisição, caso definido.
+    let timeout = env::var("REQUEST_TIMEOUT_MS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .map(Duration::from_millis);
 
-    println!("Escutando em {}.", addr);
-    println!("Use Ctrl+C para sair.");
+    let mut builder = Server::builder()
+        .accept_http1(cli.grpc_web)
+        .layer(service::logging::RequestTraceLayer)
+        .layer(service::metrics::MetricsLayer)
+        .layer(service::limits::LimitsLayer::new(
+            service::limits::LimitsConfig::from_env(),
+        ))
+        .layer(service::deadline::DeadlineLayer::new(timeout));
+
+    // O controle de acesso é aplicado a cada serviço, de forma que o gRPC-Web
+    // responda às requisições preflight do CORS sem exigir autenticação.
+    let acesso = service::acesso::AcessoLayer::new(acesso);
+    let base = acesso.layer(service::base::make_service().await);
+    let clientes = acesso.layer(service::clientes::make_service(repo).await);
+
+    let router = if cli.grpc_web {
+        let web = service::web::WebConfig::from_env();
+        if web.origens.is_empty() {
+            tracing::warn!("gRPC-Web habilitado para qualquer origem; defina CORS_ALLOWED_ORIGINS");
+        } else {
+            tracing::info!(origens = ?web.origens, "gRPC-Web habilitado");
+        }
+        let web = web.config();
+        builder
+            .add_service(web.enable(base))
+            .add_service(web.enable(clientes))
+    } else {
+        builder.add_service(base).add_service(clientes)
+    };
+
+    let server = router.serve(addr);
+
+    tracing::info!("Escutando em {}.", addr);
+    tracing::info!("Use Ctrl+C para sair.");
 
     server.await?;
 
+    service::logging::shutdown();
+    Ok(())
+}
+
+/// Cria o repositório do banco de dados definido na variável de ambiente
+/// `DATABASE_URL`, aplicando as migrações pendentes caso requisitado. Os
+/// documentos dos clientes são cifrados com as chaves definidas nas variáveis
+/// de ambiente (veja [`Cifra::from_env`]).
+///
+/// As migrações são aplicadas em uma conexão da própria pool, de forma que
+/// bancos SQLite em memória também sejam preparados.
+async fn cria_repositorio(auto_migrate: bool) -> Result<Arc<dyn Repository>, ErrorImpl> {
+    let cifra = Cifra::from_env()?;
+    if cifra.atual().is_some() {
+        tracing::info!("Cifragem de documentos habilitada");
+    }
+
+    match db::Backend::from_env() {
+        db::Backend::Postgres(_) => {
+            let pool = db::make_connection_pool().await;
+            if auto_migrate {
+                tracing::info!("Aplicando migrações pendentes...");
+                let conn = db::get_connection(&pool).await?;
+                migrations::up(&*conn, MIGRACOES, &mut std::io::stdout())?;
+            }
+            Ok(Arc::new(PgRepository::new(pool).com_cifra(cifra)))
+        }
+        #[cfg(feature = "sqlite")]
+        db::Backend::Sqlite(caminho) => {
+            tracing::info!(caminho = %caminho, "Usando banco de dados SQLite");
+            let pool = db::make_sqlite_pool(&caminho).await;
+            if auto_migrate {
+                tracing::info!("Aplicando migrações pendentes...");
+                let conn = db::get_connection(&pool).await?;
+                migrations::up(&*conn, MIGRACOES_SQLITE, &mut std::io::stdout())?;
+            }
+            Ok(Arc::new(SqliteRepository::new(pool).com_cifra(cifra)))
+        }
+        #[cfg(not(feature = "sqlite"))]
+        db::Backend::Sqlite(_) => Err(SQLITE_DESABILITADO.into()),
+    }
+}
+
+/// Importa a base de CEPs de um arquivo CSV para o banco de dados definido na
+/// variável de ambiente `DATABASE_URL`, informando as linhas ignoradas.
+async fn importa_cep(
+    arquivo: &Path,
+    delimitador: char,
+    auto_migrate: bool,
+) -> Result<(), ErrorImpl> {
+    let delimitador =
+        u8::try_from(delimitador).map_err(|_| "O delimitador deve ser um caractere ASCII")?;
+    let leitor = File::open(arquivo)
+        .map_err(|e| format!("Impossível abrir {}: {}", arquivo.display(), e))?;
+    let repo = cria_repositorio(auto_migrate).await?;
+
+    let resumo = importacao::importa_ceps(repo.as_ref(), leitor, delimitador).await?;
+    for ignorada in &resumo.ignoradas {
+        eprintln!("Linha {} ignorada: {}", ignorada.linha, ignorada.motivo);
+    }
+    println!(
+        "{} CEPs importados; {} linhas ignoradas.",
+        resumo.importados,
+        resumo.ignoradas.len()
+    );
+    Ok(())
+}
+
+/// Recifra os documentos dos clientes do banco de dados definido na variável
+/// de ambiente `DATABASE_URL` com a chave atual, informando quantos clientes
+/// foram atualizados.
+async fn recifra_doctos(auto_migrate: bool) -> Result<(), ErrorImpl> {
+    let repo = cria_repositorio(auto_migrate).await?;
+    let total = repo.recifra_doctos().await?;
+    println!("{} documentos recifrados.", total);
+    Ok(())
+}
+
+/// Executa uma ação sobre as migrações do banco de dados, através de uma
+/// conexão com o banco definido na variável de ambiente `DATABASE_URL`.
+fn executa_migracao(acao: AcaoMigracao) -> Result<(), ErrorImpl> {
+    match db::Backend::from_env() {
+        db::Backend::Postgres(url) => migra(&PgConnection::establish(&url)?, MIGRACOES, acao),
+        #[cfg(feature = "sqlite")]
+        db::Backend::Sqlite(caminho) => {
+            migra(&SqliteConnection::establish(&caminho)?, MIGRACOES_SQLITE, acao)
+        }
+        #[cfg(not(feature = "sqlite"))]
+        db::Backend::Sqlite(_) => Err(SQLITE_DESABILITADO.into()),
+    }
+}
+
+/// Executa uma ação sobre as migrações informadas, em uma conexão qualquer.
+fn migra<C: MigrationConnection>(
+    conn: &C,
+    migracoes: &[Migracao],
+    acao: AcaoMigracao,
+) -> Result<(), ErrorImpl> {
+    let mut out = std::io::stdout();
+
+    match acao {
+        AcaoMigracao::Up => {
+            migrations::up(conn, migracoes, &mut out)?;
+            println!("Todas as migrações foram aplicadas.");
+        }
+        AcaoMigracao::Down => match migrations::down(conn, migracoes, &mut out)? {
+            Some(migracao) => println!("Migração {} revertida.", migracao.nome),
+            None => println!("Nenhuma migração a ser revertida."),
+        },
+        AcaoMigracao::Status => {
+            for (migracao, aplicada) in migrations::status(conn, migracoes)? {
+                let marca = if aplicada { "X" } else { " " };
+                println!("[{}] {}", marca, migracao.nome);
+            }
+        }
+    }
+
     Ok(())
 }
@@ -0,0 +1,266 @@
+// acesso.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este módulo implementa o controle de acesso ao servidor, através de tokens
+//! de acesso enviados no metadado `authorization`, no formato `Bearer <token>`
+//! (como feito pelo [`sdk`](crate::sdk)).
+//!
+//! Cada token identifica um usuário, em nome de quem as operações são
+//! registradas no log de operações, e que pode possuir permissões adicionais
+//! -- como a de ver os dados sensíveis dos clientes sem máscara. Requisições
+//! sem um token válido são recusadas com o status `unauthenticated`.
+//!
+//! Caso nenhum token seja configurado, o controle de acesso é desabilitado, e
+//! todas as requisições são atendidas com todas as permissões, em nome do
+//! usuário do sistema.
+
+use crate::model::cliente::ContemClientes;
+use crate::model::logdb::USUARIO_SISTEMA;
+use futures::future::BoxFuture;
+use hyper::{HeaderMap, Request, Response};
+use std::env;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tonic::body::BoxBody;
+use tonic::transport::NamedService;
+use tonic::Status;
+use tower::{Layer, Service};
+
+/// Nome do metadado que carrega o token de acesso.
+pub const AUTHORIZATION_HEADER: &str = "authorization";
+
+/// Permissões que podem ser concedidas aos usuários.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Permissao {
+    /// Permite ver os dados sensíveis dos clientes, como o CPF, sem máscara.
+    DadosSensiveis,
+}
+
+/// Usuário autenticado de uma requisição, e suas permissões. Esta estrutura
+/// é inserida nas extensões de cada requisição autenticada, de forma que os
+/// handlers possam consultá-la.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Acesso {
+    /// Nome do usuário.
+    pub usuario: String,
+    /// Permissões concedidas ao usuário.
+    pub permissoes: Vec<Permissao>,
+}
+
+impl Acesso {
+    /// Acesso das requisições atendidas sem o controle de acesso: o usuário
+    /// do sistema, com todas as permissões.
+    pub fn total() -> Self {
+        Self {
+            usuario: USUARIO_SISTEMA.to_string(),
+            permissoes: vec![Permissao::DadosSensiveis],
+        }
+    }
+
+    /// Recupera o acesso de uma requisição gRPC. Requisições sem um acesso
+    /// definido possuem acesso total (veja [`Acesso::total`]).
+    pub fn da_requisicao<T>(req: &tonic::Request<T>) -> Self {
+        req.extensions()
+            .get::<Acesso>()
+            .cloned()
+            .unwrap_or_else(Acesso::total)
+    }
+
+    /// Determina se o usuário possui uma permissão.
+    pub fn permite(&self, permissao: Permissao) -> bool {
+        self.permissoes.contains(&permissao)
+    }
+
+    /// Mascara os dados sensíveis dos clientes contidos em `dados`, caso o
+    /// usuário não possua permissão para vê-los.
+    pub fn protege<T: ContemClientes>(&self, dados: T) -> T {
+        if self.permite(Permissao::DadosSensiveis) {
+            dados
+        } else {
+            dados.mascarado()
+        }
+    }
+}
+
+/// Compara dois valores em tempo constante com relação ao seu conteúdo, de
+/// forma que o tempo da comparação não revele partes de um token.
+fn iguais(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Configuração do controle de acesso.
+#[derive(Clone, Default)]
+pub struct AcessoConfig {
+    /// Tokens de acesso aceitos, junto com os usuários identificados pelos
+    /// mesmos.
+    pub tokens: Vec<(String, Acesso)>,
+}
+
+impl AcessoConfig {
+    /// Lê a configuração do controle de acesso a partir das variáveis de
+    /// ambiente:
+    ///
+    /// - `ACCESS_TOKENS`: usuários e seus tokens de acesso, no formato
+    ///   `usuario=token,...`;
+    /// - `SENSITIVE_DATA_USERS`: usuários com permissão para ver os dados
+    ///   sensíveis dos clientes, separados por vírgulas.
+    ///
+    /// Entradas sem usuário ou sem token são ignoradas.
+    pub fn from_env() -> Self {
+        let sensiveis: Vec<String> = env::var("SENSITIVE_DATA_USERS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|usuario| usuario.trim().to_string())
+            .filter(|usuario| !usuario.is_empty())
+            .collect();
+
+        let tokens = env::var("ACCESS_TOKENS")
+            .unwrap_or_default()
+            .split(',')
+            .filter(|par| !par.trim().is_empty())
+            .filter_map(|par| {
+                let entrada = par
+                    .split_once('=')
+                    .map(|(usuario, token)| (usuario.trim(), token.trim()))
+                    .filter(|(usuario, token)| !usuario.is_empty() && !token.is_empty());
+                if entrada.is_none() {
+                    tracing::warn!("Token de acesso inválido ignorado");
+                }
+                entrada
+            })
+            .map(|(usuario, token)| {
+                let permissoes = if sensiveis.iter().any(|u| u == usuario) {
+                    vec![Permissao::DadosSensiveis]
+                } else {
+                    vec![]
+                };
+                let acesso = Acesso {
+                    usuario: usuario.to_string(),
+                    permissoes,
+                };
+                (token.to_string(), acesso)
+            })
+            .collect();
+
+        Self { tokens }
+    }
+
+    /// Determina se o controle de acesso está habilitado, isto é, se algum
+    /// token foi configurado.
+    pub fn habilitado(&self) -> bool {
+        !self.tokens.is_empty()
+    }
+
+    /// Autentica uma requisição através do token de acesso informado em seus
+    /// cabeçalhos, retornando o acesso do usuário identificado pelo mesmo.
+    /// Caso o controle de acesso esteja desabilitado, retorna o acesso total.
+    pub fn autentica(&self, headers: &HeaderMap) -> Result<Acesso, Box<Status>> {
+        if !self.habilitado() {
+            return Ok(Acesso::total());
+        }
+
+        let token = headers
+            .get(AUTHORIZATION_HEADER)
+            .and_then(|valor| valor.to_str().ok())
+            .and_then(|valor| valor.strip_prefix("Bearer "))
+            .ok_or_else(|| Box::new(Status::unauthenticated("Token de acesso não informado")))?;
+
+        // Todos os tokens são comparados, para que o tempo da autenticação
+        // não dependa da posição do token na configuração.
+        let mut encontrado = None;
+        for (aceito, acesso) in &self.tokens {
+            if iguais(aceito.as_bytes(), token.as_bytes()) {
+                encontrado = Some(acesso);
+            }
+        }
+        encontrado
+            .cloned()
+            .ok_or_else(|| Box::new(Status::unauthenticated("Token de acesso inválido")))
+    }
+}
+
+/// Camada (tower layer) que autentica as requisições.
+///
+/// A camada deve ser aplicada a cada serviço, antes de habilitar o gRPC-Web
+/// (veja [`web`](super::web)): assim, as requisições /preflight/ do CORS, que
+/// não carregam o token de acesso, são respondidas antes da autenticação, e
+/// as respostas de erro recebem os cabeçalhos do CORS.
+#[derive(Clone, Default)]
+pub struct AcessoLayer {
+    config: Arc<AcessoConfig>,
+}
+
+impl AcessoLayer {
+    /// Cria a camada de controle de acesso a partir de sua configuração.
+    pub fn new(config: AcessoConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+        }
+    }
+}
+
+impl<S> Layer<S> for AcessoLayer {
+    type Service = AcessoService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AcessoService {
+            inner,
+            config: self.config.clone(),
+        }
+    }
+}
+
+/// Serviço criado por [`AcessoLayer`].
+#[derive(Clone)]
+pub struct AcessoService<S> {
+    inner: S,
+    config: Arc<AcessoConfig>,
+}
+
+impl<S: NamedService> NamedService for AcessoService<S> {
+    const NAME: &'static str = S::NAME;
+}
+
+impl<S, ReqBody> Service<Request<ReqBody>> for AcessoService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        match self.config.autentica(req.headers()) {
+            Ok(acesso) => {
+                req.extensions_mut().insert(acesso);
+                Box::pin(inner.call(req))
+            }
+            Err(status) => {
+                tracing::warn!("Requisição não autenticada");
+                Box::pin(async move { Ok(status.to_http()) })
+            }
+        }
+    }
+}
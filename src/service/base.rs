@@ -18,8 +18,7 @@
 //! apenas a informações muito básicas e que não devem atrapalhar as regras
 //! de negócios.
 
-use super::utils;
-use minerva_lite::minerva_server::{Minerva, MinervaServer};
+use crate::minerva_server::{Minerva, MinervaServer};
 use tonic::{Request, Response, Status};
 
 /// Estrutura básica do serviço MinervaLite.
@@ -29,8 +28,8 @@ pub struct MinervaLiteService;
 #[tonic::async_trait]
 impl Minerva for MinervaLiteService {
     /// Resposta à requisição de ping.
-    async fn ping(&self, req: Request<()>) -> Result<Response<()>, Status> {
-        utils::log(utils::get_address(&req), "Ping(Empty) -> (Empty)");
+    async fn ping(&self, _req: Request<()>) -> Result<Response<()>, Status> {
+        tracing::debug!("Ping(Empty) -> (Empty)");
         Ok(Response::new(()))
     }
 }
@@ -38,5 +37,5 @@ impl Minerva for MinervaLiteService {
 /// Cria um serviço base do Minerva.Lite. Este serviço deverá ser atrelado
 /// ao servidor gRPC no ponto de entrada da aplicação.
 pub async fn make_service() -> MinervaServer<MinervaLiteService> {
-    MinervaServer::new(MinervaLiteService::default())
+    MinervaServer::new(MinervaLiteService)
 }
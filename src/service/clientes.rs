@@ -17,24 +17,124 @@
 //! Este módulo implementa o serviço gRPC do CRUD de Clientes do Minerva.Lite.
 //! Este CRUD envolve protocolos para criação, remoção, consulta, listagem e
 //! atualização de usuários.
+//!
+//! Os dados sensíveis dos clientes retornados são mascarados para usuários
+//! sem permissão para vê-los (veja [`acesso`](super::acesso)).
 
-use super::{db, utils};
-use futures::Stream;
-use minerva_lite::minerva_clientes_server::{MinervaClientes, MinervaClientesServer};
-use minerva_lite::*;
+use super::acesso::Acesso;
+use super::deadline::Deadline;
+use super::metrics;
+use crate::controller::cliente::{BUSCA_LIMITE_MAXIMO, BUSCA_LIMITE_PADRAO};
+use crate::minerva_clientes_server::{MinervaClientes, MinervaClientesServer};
+use crate::model::cliente::NovoCliente;
+use crate::model::contato::{AlteraContato, NovoContato};
+use crate::model::duplicado::Mescla;
+use crate::model::endereco::{normaliza_cep, NovoEndereco, TipoEndereco};
+use crate::repository::{RepoError, Repository};
+use crate::*;
+use futures::{Future, Stream};
 use std::pin::Pin;
+use std::sync::Arc;
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
 use tonic::{Request, Response, Status};
-
-use minerva_lite::controller::cliente as controller;
-
-const DB_ERR_MSG: &str = "Impossível conectar ao banco de dados";
+use tracing::Instrument;
 
 /// Estrutura do serviço de clientes do MinervaLite.
-/// A estrutura possui apenas um pool de conexões ao PostgreSQL.
+/// A estrutura possui apenas o repositório onde os clientes são armazenados.
 pub struct MinervaLiteClientesService {
-    pool: db::ConnectionPool,
+    repo: Arc<dyn Repository>,
+}
+
+impl MinervaLiteClientesService {
+    /// Cria o serviço de clientes a partir de um repositório qualquer.
+    pub fn new(repo: Arc<dyn Repository>) -> Self {
+        Self { repo }
+    }
+}
+
+/// Converte um erro ao recuperar uma página de clientes em um status gRPC.
+pub(crate) fn erro_lista(e: RepoError) -> Status {
+    tracing::error!(erro = %e, "Impossível recuperar página de clientes");
+    if e.armazenamento() {
+        e.into()
+    } else {
+        Status::internal("Impossível recuperar página de clientes")
+    }
+}
+
+/// Cria um stream por onde serão enviadas, em ordem, todas as páginas
+/// retornadas por `busca`, até que uma página vazia seja encontrada. Cada
+/// página é convertida em uma mensagem através de `monta`.
+///
+/// Caso a requisição possua um prazo, o stream será encerrado com o status
+/// `deadline_exceeded` se o mesmo for excedido durante o envio.
+fn envia_paginas<I, T, F, Fut>(
+    deadline: Option<Deadline>,
+    busca: F,
+    monta: fn(Vec<I>) -> T,
+) -> ReceiverStream<Result<T, Status>>
+where
+    I: Send + 'static,
+    T: Send + 'static,
+    F: Fn(i64) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<Vec<I>, RepoError>> + Send,
+{
+    let (tx, rx) = mpsc::channel(128);
+    // A task produtora herda o span da requisição, de forma que cada
+    // página enviada seja registrada como um span filho do mesmo.
+    tokio::spawn(
+        async move {
+            let _ativo = metrics::StreamAtivo::new();
+            let mut page_number = 0;
+            loop {
+                let span = tracing::info_span!("pagina", numero = page_number);
+                let enviada = async {
+                    if deadline.map(|d| d.expirou()).unwrap_or(false) {
+                        tracing::warn!("Prazo da listagem excedido");
+                        let _ = tx
+                            .send(Err(Status::deadline_exceeded("Prazo da listagem excedido")))
+                            .await;
+                        return false;
+                    }
+
+                    let page = match busca(page_number).await {
+                        Ok(page) => page,
+                        Err(e) => {
+                            let _ = tx.send(Err(erro_lista(e))).await;
+                            return false;
+                        }
+                    };
+
+                    if page.is_empty() {
+                        // Nada a ser enviado
+                        tracing::debug!("Fim da listagem");
+                        return false;
+                    }
+
+                    tracing::debug!(clientes = page.len(), "Enviando página");
+                    if tx.send(Ok(monta(page))).await.is_err() {
+                        tracing::debug!("Stream de saída foi encerrado");
+                        return false;
+                    }
+                    metrics::pagina_enviada();
+                    true
+                }
+                .instrument(span)
+                .await;
+
+                if !enviada {
+                    break;
+                }
+
+                // Página enfileirada; ir para a próxima
+                page_number += 1;
+            }
+        }
+        .in_current_span(),
+    );
+
+    ReceiverStream::new(rx)
 }
 
 #[tonic::async_trait]
@@ -47,22 +147,28 @@ impl MinervaClientes for MinervaLiteClientesService {
     /// um status próprio para erros do gRPC.
     type ListaStream = Pin<Box<dyn Stream<Item = Result<ClientePageResponse, Status>> + Send>>;
 
+    /// Tipo para o stream das páginas de clientes detalhados. Ver
+    /// [`ListaStream`](Self::ListaStream).
+    type ListaDetalhadaStream =
+        Pin<Box<dyn Stream<Item = Result<ClienteDetalhadoPageResponse, Status>> + Send>>;
+
     /// Resposta à requisição de cadastro do cliente.
     async fn cadastra(
         &self,
         req: Request<NovoClienteRequest>,
     ) -> Result<Response<ClienteResponse>, Status> {
-        utils::log(utils::get_address(&req), "Clientes::Cadastra");
+        let acesso = Acesso::da_requisicao(&req);
+        let dados = NovoCliente::try_from(req.into_inner()).map_err(Status::invalid_argument)?;
 
-        let conn = self
-            .pool
-            .get()
+        self.repo
+            .cadastra(dados)
             .await
-            .map_err(|_| Status::internal(DB_ERR_MSG))?;
-
-        controller::cadastra(&conn, req.into_inner().into())
-            .map_err(|_| Status::invalid_argument("Usuário não cadastrado"))
-            .map(|result| Response::new(result.into()))
+            .map_err(|e| match e {
+                RepoError::Conflito(_) => Status::already_exists("Documento já cadastrado"),
+                e if e.armazenamento() => e.into(),
+                _ => Status::invalid_argument("Usuário não cadastrado"),
+            })
+            .map(|result| Response::new(acesso.protege(result).into()))
     }
 
     /// Resposta à requisição de consulta de um único cliente.
@@ -71,99 +177,591 @@ impl MinervaClientes for MinervaLiteClientesService {
         req: Request<IdClienteRequest>,
     ) -> Result<Response<ClienteResponse>, Status> {
         let id = req.get_ref().id;
-        utils::log(
-            utils::get_address(&req),
-            &format!("Clientes::Consulta (ID = {})", id),
-        );
+        tracing::debug!(id, "Clientes::Consulta");
+        let acesso = Acesso::da_requisicao(&req);
 
-        let conn = self
-            .pool
-            .get()
+        self.repo
+            .consulta(id)
             .await
-            .map_err(|_| Status::internal(DB_ERR_MSG))?;
+            .map_err(|e| {
+                if e.armazenamento() {
+                    e.into()
+                } else {
+                    Status::not_found("Usuário não encontrado")
+                }
+            })
+            .map(|result| Response::new(acesso.protege(result).into()))
+    }
 
-        controller::consulta(&conn, id)
-            .map_err(|_| Status::not_found("Usuário não encontrado"))
-            .map(|result| Response::new(result.into()))
+    /// Resposta à requisição de consulta de um único cliente através de seu
+    /// documento. O documento não é registrado nos logs.
+    async fn consulta_docto(
+        &self,
+        req: Request<DoctoClienteRequest>,
+    ) -> Result<Response<ClienteResponse>, Status> {
+        tracing::debug!("Clientes::ConsultaDocto");
+        let acesso = Acesso::da_requisicao(&req);
+        let docto = req.into_inner().docto;
+        if docto.trim().is_empty() {
+            return Err(Status::invalid_argument("Documento não informado"));
+        }
+
+        self.repo
+            .consulta_docto(docto)
+            .await
+            .map_err(|e| {
+                if e.armazenamento() {
+                    e.into()
+                } else {
+                    Status::not_found("Usuário não encontrado")
+                }
+            })
+            .map(|result| Response::new(acesso.protege(result).into()))
     }
 
     /// Retorna um stream por onde será enviada a lista de todos os
     /// clientes cadastrados.
+    ///
+    /// Caso a requisição possua um prazo, o stream será encerrado com o
+    /// status `deadline_exceeded` se o mesmo for excedido durante o envio.
     async fn lista(&self, req: Request<()>) -> Result<Response<Self::ListaStream>, Status> {
-        let destino = utils::get_address(&req);
-        utils::log(destino, "Clientes::Lista (Stream)");
+        tracing::debug!("Clientes::Lista (Stream)");
+        let deadline = req.extensions().get::<Deadline>().copied();
+        let acesso = Acesso::da_requisicao(&req);
 
-        let pool = self.pool.clone();
+        let repo = self.repo.clone();
+        let output_stream = envia_paginas(
+            deadline,
+            move |pagina| {
+                let repo = repo.clone();
+                let acesso = acesso.clone();
+                async move { repo.lista(pagina).await.map(|page| acesso.protege(page)) }
+            },
+            |page| ClientePageResponse {
+                clientes: page.into_iter().map(|c| c.into()).collect(),
+            },
+        );
 
-        let (tx, rx) = mpsc::channel(128);
-        tokio::spawn(async move {
-            let mut page_number = 0;
-            loop {
-                let conn = match pool.get().await {
-                    Ok(c) => c,
-                    Err(_) => {
-                        // Impossível recuperar conexão com o BD
-                        break;
-                    }
-                };
+        // Retorna o stream em si
+        Ok(Response::new(Box::pin(output_stream) as Self::ListaStream))
+    }
 
-                let page: Vec<ClienteResponse> = match controller::lista(&conn, page_number) {
-                    Ok(page) => page.iter().map(|c| c.clone().into()).collect(),
-                    Err(_) => {
-                        // Impossível recuperar página de usuários
-                        break;
-                    }
-                };
+    /// Resposta à requisição de consulta de um único cliente, junto com seus
+    /// endereços e contatos.
+    async fn consulta_detalhada(
+        &self,
+        req: Request<IdClienteRequest>,
+    ) -> Result<Response<ClienteDetalhadoResponse>, Status> {
+        let id = req.get_ref().id;
+        tracing::debug!(id, "Clientes::ConsultaDetalhada");
+        let acesso = Acesso::da_requisicao(&req);
 
-                if page.is_empty() {
-                    // Nada a ser enviado
-                    break;
+        self.repo
+            .consulta_detalhada(id)
+            .await
+            .map_err(|e| {
+                if e.armazenamento() {
+                    e.into()
+                } else {
+                    Status::not_found("Usuário não encontrado")
                 }
-                utils::log(destino, &format!("Clientes::Lista (Pág {})", page_number));
-                let response = ClientePageResponse { clientes: page };
-                match tx.send(Result::<_, Status>::Ok(response)).await {
-                    Ok(_) => {
-                        // Página enfileirada; ir para a próxima
-                        page_number += 1;
-                    }
-                    Err(_) => {
-                        // Stream de saída foi encerrado
-                        break;
-                    }
+            })
+            .map(|result| Response::new(acesso.protege(result).into()))
+    }
+
+    /// Retorna um stream por onde será enviada a lista de todos os clientes
+    /// cadastrados, como em [`lista`](Self::lista), incluindo opcionalmente
+    /// os endereços e os contatos de cada cliente.
+    async fn lista_detalhada(
+        &self,
+        req: Request<ListaDetalhadaRequest>,
+    ) -> Result<Response<Self::ListaDetalhadaStream>, Status> {
+        let ListaDetalhadaRequest {
+            incluir_enderecos,
+            incluir_contatos,
+        } = *req.get_ref();
+        tracing::debug!(
+            incluir_enderecos,
+            incluir_contatos,
+            "Clientes::ListaDetalhada (Stream)"
+        );
+        let deadline = req.extensions().get::<Deadline>().copied();
+        let acesso = Acesso::da_requisicao(&req);
+
+        let repo = self.repo.clone();
+        let output_stream = envia_paginas(
+            deadline,
+            move |pagina| {
+                let repo = repo.clone();
+                let acesso = acesso.clone();
+                async move {
+                    repo.lista_detalhada(pagina, incluir_enderecos, incluir_contatos)
+                        .await
+                        .map(|page| acesso.protege(page))
+                }
+            },
+            |page| ClienteDetalhadoPageResponse {
+                clientes: page.into_iter().map(|c| c.into()).collect(),
+            },
+        );
+
+        Ok(Response::new(
+            Box::pin(output_stream) as Self::ListaDetalhadaStream
+        ))
+    }
+
+    /// Resposta à requisição de cadastro de um endereço. Os dados são
+    /// validados antes do cadastro; UFs e CEPs inválidos são rejeitados com o
+    /// status `invalid_argument`.
+    async fn cadastra_endereco(
+        &self,
+        req: Request<NovoEnderecoRequest>,
+    ) -> Result<Response<EnderecoResponse>, Status> {
+        let dados = NovoEndereco::try_from(req.into_inner()).map_err(Status::invalid_argument)?;
+        tracing::debug!(cliente_id = dados.cliente_id, "Clientes::CadastraEndereco");
+
+        self.repo
+            .cadastra_endereco(dados)
+            .await
+            .map_err(|e| match e {
+                RepoError::Invalido(_) => {
+                    Status::invalid_argument("Cliente ou tipo de endereço inexistente")
+                }
+                RepoError::Conflito(_) => {
+                    Status::already_exists("Cliente já possui endereço principal deste tipo")
+                }
+                e if e.armazenamento() => e.into(),
+                e => {
+                    tracing::error!(erro = %e, "Impossível cadastrar endereço");
+                    Status::internal("Endereço não cadastrado")
+                }
+            })
+            .map(|result| Response::new(result.into()))
+    }
+
+    /// Resposta à requisição de remoção de um endereço.
+    async fn deleta_endereco(
+        &self,
+        req: Request<IdEnderecoRequest>,
+    ) -> Result<Response<()>, Status> {
+        let id = req.get_ref().id;
+        tracing::debug!(id, "Clientes::DeletaEndereco");
+
+        self.repo
+            .remove_endereco(id)
+            .await
+            .map_err(|e| {
+                if e.armazenamento() {
+                    e.into()
+                } else {
+                    Status::not_found("Endereço não encontrado")
+                }
+            })
+            .map(|_| Response::new(()))
+    }
+
+    /// Resposta à requisição do catálogo de tipos de endereço.
+    async fn lista_tipos_endereco(
+        &self,
+        _req: Request<()>,
+    ) -> Result<Response<TiposEnderecoResponse>, Status> {
+        tracing::debug!("Clientes::ListaTiposEndereco");
+
+        self.repo
+            .lista_tipos_endereco()
+            .await
+            .map_err(|e| {
+                if e.armazenamento() {
+                    e.into()
+                } else {
+                    tracing::error!(erro = %e, "Impossível recuperar tipos de endereço");
+                    Status::internal("Impossível recuperar tipos de endereço")
+                }
+            })
+            .map(|tipos| {
+                Response::new(TiposEnderecoResponse {
+                    tipos: tipos.into_iter().map(|t| t.into()).collect(),
+                })
+            })
+    }
+
+    /// Resposta à requisição de cadastro de um tipo de endereço no catálogo.
+    async fn cadastra_tipo_endereco(
+        &self,
+        req: Request<TipoEnderecoMessage>,
+    ) -> Result<Response<TipoEnderecoMessage>, Status> {
+        let tipo = TipoEndereco::try_from(req.into_inner()).map_err(Status::invalid_argument)?;
+        tracing::debug!(id = tipo.id, "Clientes::CadastraTipoEndereco");
+
+        self.repo
+            .cadastra_tipo_endereco(tipo)
+            .await
+            .map_err(|e| match e {
+                RepoError::Conflito(_) => Status::already_exists("Tipo de endereço já cadastrado"),
+                e if e.armazenamento() => e.into(),
+                _ => Status::invalid_argument("Tipo de endereço não cadastrado"),
+            })
+            .map(|result| Response::new(result.into()))
+    }
+
+    /// Resposta à requisição de consulta a um CEP na base de CEPs.
+    async fn consulta_cep(
+        &self,
+        req: Request<CepRequest>,
+    ) -> Result<Response<CepResponse>, Status> {
+        let cep = normaliza_cep(&req.get_ref().cep)
+            .ok_or_else(|| Status::invalid_argument("CEP inválido"))?;
+        tracing::debug!(cep = %cep, "Clientes::ConsultaCep");
+
+        self.repo
+            .consulta_cep(cep)
+            .await
+            .map_err(|e| {
+                if e.armazenamento() {
+                    e.into()
+                } else {
+                    Status::not_found("CEP não encontrado")
+                }
+            })
+            .map(|result| Response::new(result.into()))
+    }
+
+    /// Resposta à requisição de cadastro de um contato. Os dados são validados
+    /// antes do cadastro; telefones e e-mails inválidos são rejeitados com o
+    /// status `invalid_argument`.
+    async fn cadastra_contato(
+        &self,
+        req: Request<NovoContatoRequest>,
+    ) -> Result<Response<ContatoResponse>, Status> {
+        let dados = NovoContato::try_from(req.into_inner()).map_err(Status::invalid_argument)?;
+        tracing::debug!(cliente_id = dados.cliente_id, "Clientes::CadastraContato");
+
+        self.repo
+            .cadastra_contato(dados)
+            .await
+            .map_err(|e| match e {
+                RepoError::Invalido(_) => Status::invalid_argument("Cliente inexistente"),
+                RepoError::Conflito(_) => {
+                    Status::already_exists("Cliente já possui contato principal deste tipo")
+                }
+                e if e.armazenamento() => e.into(),
+                e => {
+                    tracing::error!(erro = %e, "Impossível cadastrar contato");
+                    Status::internal("Contato não cadastrado")
+                }
+            })
+            .map(|result| Response::new(result.into()))
+    }
+
+    /// Resposta à requisição dos contatos de um cliente.
+    async fn lista_contatos(
+        &self,
+        req: Request<IdClienteRequest>,
+    ) -> Result<Response<ContatosResponse>, Status> {
+        let id = req.get_ref().id;
+        tracing::debug!(id, "Clientes::ListaContatos");
+
+        self.repo
+            .lista_contatos(id)
+            .await
+            .map_err(|e| {
+                if e.armazenamento() {
+                    e.into()
+                } else {
+                    Status::not_found("Usuário não encontrado")
+                }
+            })
+            .map(|contatos| {
+                Response::new(ContatosResponse {
+                    contatos: contatos.into_iter().map(|c| c.into()).collect(),
+                })
+            })
+    }
+
+    /// Resposta à requisição de atualização de um contato. Os dados são
+    /// validados da mesma forma que no cadastro.
+    async fn atualiza_contato(
+        &self,
+        req: Request<AtualizaContatoRequest>,
+    ) -> Result<Response<ContatoResponse>, Status> {
+        let dados = AlteraContato::try_from(req.into_inner()).map_err(Status::invalid_argument)?;
+        tracing::debug!(id = dados.id, "Clientes::AtualizaContato");
+
+        self.repo
+            .atualiza_contato(dados)
+            .await
+            .map_err(|e| match e {
+                RepoError::NaoEncontrado => Status::not_found("Contato não encontrado"),
+                RepoError::Conflito(_) => {
+                    Status::already_exists("Cliente já possui contato principal deste tipo")
+                }
+                e if e.armazenamento() => e.into(),
+                e => {
+                    tracing::error!(erro = %e, "Impossível atualizar contato");
+                    Status::internal("Contato não atualizado")
+                }
+            })
+            .map(|result| Response::new(result.into()))
+    }
+
+    /// Resposta à requisição de remoção de um contato.
+    async fn deleta_contato(&self, req: Request<IdContatoRequest>) -> Result<Response<()>, Status> {
+        let id = req.get_ref().id;
+        tracing::debug!(id, "Clientes::DeletaContato");
+
+        self.repo
+            .remove_contato(id)
+            .await
+            .map_err(|e| {
+                if e.armazenamento() {
+                    e.into()
+                } else {
+                    Status::not_found("Contato não encontrado")
                 }
+            })
+            .map(|_| Response::new(()))
+    }
+
+    /// Resposta à requisição de definição do limite de crédito de um cliente.
+    async fn define_limite_credito(
+        &self,
+        req: Request<LimiteCreditoRequest>,
+    ) -> Result<Response<ClienteResponse>, Status> {
+        let acesso = Acesso::da_requisicao(&req);
+        let LimiteCreditoRequest { cliente_id, limite } = req.into_inner();
+        tracing::debug!(cliente_id, ?limite, "Clientes::DefineLimiteCredito");
+        if matches!(limite, Some(limite) if limite < 0) {
+            return Err(Status::invalid_argument(
+                "O limite de crédito não pode ser negativo",
+            ));
+        }
+
+        self.repo
+            .define_limite_credito(cliente_id, limite)
+            .await
+            .map_err(|e| match e {
+                RepoError::NaoEncontrado => Status::not_found("Usuário não encontrado"),
+                e if e.armazenamento() => e.into(),
+                e => {
+                    tracing::error!(erro = %e, "Impossível definir limite de crédito");
+                    Status::internal("Limite de crédito não definido")
+                }
+            })
+            .map(|result| Response::new(acesso.protege(result).into()))
+    }
+
+    /// Resposta à requisição de registro de um movimento de crédito. Débitos
+    /// em nome de clientes bloqueados, ou que excedam o limite de crédito sem
+    /// que o bloqueio do cliente seja requisitado, são recusados com o status
+    /// `failed_precondition`.
+    async fn registra_movimento_credito(
+        &self,
+        req: Request<MovimentoCreditoRequest>,
+    ) -> Result<Response<ClienteResponse>, Status> {
+        let acesso = Acesso::da_requisicao(&req);
+        let MovimentoCreditoRequest {
+            cliente_id,
+            valor,
+            bloqueia_excedente,
+        } = req.into_inner();
+        tracing::debug!(cliente_id, valor, "Clientes::RegistraMovimentoCredito");
+        if valor == 0 {
+            return Err(Status::invalid_argument("Valor do movimento não informado"));
+        }
+
+        self.repo
+            .registra_movimento_credito(cliente_id, valor, bloqueia_excedente)
+            .await
+            .map_err(|e| match e {
+                RepoError::NaoEncontrado => Status::not_found("Usuário não encontrado"),
+                RepoError::Recusado(_) => e.into(),
+                e if e.armazenamento() => e.into(),
+                e => {
+                    tracing::error!(erro = %e, "Impossível registrar movimento de crédito");
+                    Status::internal("Movimento de crédito não registrado")
+                }
+            })
+            .map(|result| Response::new(acesso.protege(result).into()))
+    }
+
+    /// Resposta à requisição de busca de clientes pelo nome. Caso o limite
+    /// não seja informado, serão retornados até
+    /// [`BUSCA_LIMITE_PADRAO`] clientes; limites acima de
+    /// [`BUSCA_LIMITE_MAXIMO`] são reduzidos a este valor.
+    async fn busca(&self, req: Request<BuscaRequest>) -> Result<Response<BuscaResponse>, Status> {
+        let acesso = Acesso::da_requisicao(&req);
+        let BuscaRequest { termo, limite } = req.into_inner();
+        tracing::debug!(limite, "Clientes::Busca");
+
+        let termo = termo.trim();
+        if termo.is_empty() {
+            return Err(Status::invalid_argument("Termo de busca não informado"));
+        }
+        let limite = match limite {
+            0 => BUSCA_LIMITE_PADRAO,
+            limite if limite < 0 => {
+                return Err(Status::invalid_argument("Limite de busca inválido"))
             }
-        });
+            limite => (limite as i64).min(BUSCA_LIMITE_MAXIMO),
+        };
 
-        // Retorna o stream em si
-        let output_stream = ReceiverStream::new(rx);
-        Ok(Response::new(Box::pin(output_stream) as Self::ListaStream))
+        self.repo
+            .busca(termo.to_string(), limite)
+            .await
+            .map_err(|e| {
+                tracing::error!(erro = %e, "Impossível buscar clientes");
+                if e.armazenamento() {
+                    e.into()
+                } else {
+                    Status::internal("Impossível buscar clientes")
+                }
+            })
+            .map(|clientes| {
+                Response::new(BuscaResponse {
+                    clientes: acesso
+                        .protege(clientes)
+                        .into_iter()
+                        .map(|c| c.into())
+                        .collect(),
+                })
+            })
+    }
+
+    /// Resposta à requisição de detecção de clientes duplicados.
+    async fn detecta_duplicados(
+        &self,
+        req: Request<()>,
+    ) -> Result<Response<DuplicadosResponse>, Status> {
+        tracing::debug!("Clientes::DetectaDuplicados");
+        let acesso = Acesso::da_requisicao(&req);
+
+        self.repo
+            .detecta_duplicados()
+            .await
+            .map_err(|e| {
+                tracing::error!(erro = %e, "Impossível detectar clientes duplicados");
+                if e.armazenamento() {
+                    e.into()
+                } else {
+                    Status::internal("Impossível detectar clientes duplicados")
+                }
+            })
+            .map(|grupos| {
+                Response::new(DuplicadosResponse {
+                    grupos: acesso
+                        .protege(grupos)
+                        .into_iter()
+                        .map(|g| g.into())
+                        .collect(),
+                })
+            })
+    }
+
+    /// Resposta à requisição de mescla de um cliente em outro. A mescla é
+    /// registrada no log de operações, em nome do usuário autenticado.
+    async fn mescla(
+        &self,
+        req: Request<MesclaRequest>,
+    ) -> Result<Response<ClienteResponse>, Status> {
+        let acesso = Acesso::da_requisicao(&req);
+        let dados = Mescla::try_from(req.into_inner()).map_err(Status::invalid_argument)?;
+        tracing::debug!(
+            origem_id = dados.origem_id,
+            destino_id = dados.destino_id,
+            "Clientes::Mescla"
+        );
+
+        self.repo
+            .mescla(dados, acesso.usuario.clone())
+            .await
+            .map_err(|e| match e {
+                RepoError::NaoEncontrado => Status::not_found("Usuário não encontrado"),
+                e if e.armazenamento() => e.into(),
+                e => {
+                    tracing::error!(erro = %e, "Impossível mesclar clientes");
+                    Status::internal("Clientes não mesclados")
+                }
+            })
+            .map(|result| Response::new(acesso.protege(result).into()))
+    }
+
+    /// Resposta à requisição de exportação dos dados de um cliente. O
+    /// documento é serializado no mesmo formato JSON do gateway REST.
+    async fn exporta_dados(
+        &self,
+        req: Request<IdClienteRequest>,
+    ) -> Result<Response<ExportacaoResponse>, Status> {
+        let id = req.get_ref().id;
+        tracing::debug!(id, "Clientes::ExportaDados");
+        let acesso = Acesso::da_requisicao(&req);
+
+        let dados = self.repo.exporta(id).await.map_err(|e| match e {
+            RepoError::NaoEncontrado => Status::not_found("Usuário não encontrado"),
+            e if e.armazenamento() => e.into(),
+            e => {
+                tracing::error!(erro = %e, "Impossível exportar dados do cliente");
+                Status::internal("Dados do cliente não exportados")
+            }
+        })?;
+
+        let dados = DadosClienteMessage::from(acesso.protege(dados));
+        let documento = serde_json::to_string_pretty(&dados).map_err(|e| {
+            tracing::error!(erro = %e, "Impossível serializar dados do cliente");
+            Status::internal("Dados do cliente não exportados")
+        })?;
+        Ok(Response::new(ExportacaoResponse { documento }))
+    }
+
+    /// Resposta à requisição de anonimização de um cliente. A anonimização é
+    /// registrada no log de operações, em nome do usuário autenticado.
+    async fn anonimiza(
+        &self,
+        req: Request<IdClienteRequest>,
+    ) -> Result<Response<ClienteResponse>, Status> {
+        let id = req.get_ref().id;
+        tracing::debug!(id, "Clientes::Anonimiza");
+        let acesso = Acesso::da_requisicao(&req);
+
+        self.repo
+            .anonimiza(id, acesso.usuario.clone())
+            .await
+            .map_err(|e| match e {
+                RepoError::NaoEncontrado => Status::not_found("Usuário não encontrado"),
+                e if e.armazenamento() => e.into(),
+                e => {
+                    tracing::error!(erro = %e, "Impossível anonimizar cliente");
+                    Status::internal("Cliente não anonimizado")
+                }
+            })
+            .map(|result| Response::new(acesso.protege(result).into()))
     }
 
     /// Resposta à requisição de remoção de um cliente.
     async fn deleta(&self, req: Request<IdClienteRequest>) -> Result<Response<()>, Status> {
         let id = req.get_ref().id;
-        utils::log(
-            utils::get_address(&req),
-            &format!("Clientes::Deleta (ID = {})", id),
-        );
+        tracing::debug!(id, "Clientes::Deleta");
 
-        let conn = self
-            .pool
-            .get()
+        self.repo
+            .remove(id)
             .await
-            .map_err(|_| Status::internal(DB_ERR_MSG))?;
-
-        controller::remove(&conn, id)
-            .map_err(|_| Status::not_found("Usuário não encontrado"))
+            .map_err(|e| {
+                if e.armazenamento() {
+                    e.into()
+                } else {
+                    Status::not_found("Usuário não encontrado")
+                }
+            })
             .map(|_| Response::new(()))
     }
 }
 
-/// Cria um serviço de clientes Minerva.Lite.
+/// Cria um serviço de clientes Minerva.Lite, a partir de um repositório
+/// qualquer (por exemplo, [`PgRepository`](crate::repository::postgres::PgRepository)
+/// ou [`MemRepository`](crate::repository::memory::MemRepository)).
 /// Este serviço deverá ser atrelado ao servidor gRPC no ponto de entrada
 /// da aplicação.
-pub async fn make_service() -> MinervaClientesServer<MinervaLiteClientesService> {
-    MinervaClientesServer::new(MinervaLiteClientesService {
-        pool: db::make_connection_pool().await,
-    })
+pub async fn make_service(
+    repo: Arc<dyn Repository>,
+) -> MinervaClientesServer<MinervaLiteClientesService> {
+    MinervaClientesServer::new(MinervaLiteClientesService::new(repo))
 }
@@ -0,0 +1,145 @@
+// deadline.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este módulo implementa o respeito a prazos (deadlines) de requisições.
+//!
+//! O prazo de uma requisição é definido pelo metadado `grpc-timeout` enviado
+//! pelo cliente, ou por um tempo máximo padrão definido no servidor, o que for
+//! menor. Requisições que não forem respondidas dentro do prazo serão
+//! encerradas com o status `deadline_exceeded`.
+
+use futures::future::BoxFuture;
+use hyper::{HeaderMap, Request, Response};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::time::Instant;
+use tonic::body::BoxBody;
+use tonic::Status;
+use tower::{Layer, Service};
+
+/// Nome do metadado que carrega o prazo definido pelo cliente.
+const GRPC_TIMEOUT_HEADER: &str = "grpc-timeout";
+
+/// Prazo máximo de uma requisição. Esta estrutura é inserida nas extensões
+/// de cada requisição que possua um prazo, de forma que os handlers possam
+/// consultá-lo -- por exemplo, durante o envio de streams.
+#[derive(Clone, Copy, Debug)]
+pub struct Deadline(pub Instant);
+
+impl Deadline {
+    /// Determina se o prazo já foi excedido.
+    pub fn expirou(&self) -> bool {
+        Instant::now() >= self.0
+    }
+}
+
+/// Interpreta o valor do metadado `grpc-timeout`, de acordo com a
+/// especificação do protocolo gRPC: até oito dígitos, seguidos de uma unidade
+/// (`H`, `M`, `S`, `m`, `u` ou `n`).
+fn parse_grpc_timeout(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(GRPC_TIMEOUT_HEADER)?.to_str().ok()?;
+    if value.len() < 2 || value.len() > 9 {
+        return None;
+    }
+
+    let (digits, unit) = value.split_at(value.len() - 1);
+    let amount: u64 = digits.parse().ok()?;
+
+    match unit {
+        "H" => Some(Duration::from_secs(amount * 60 * 60)),
+        "M" => Some(Duration::from_secs(amount * 60)),
+        "S" => Some(Duration::from_secs(amount)),
+        "m" => Some(Duration::from_millis(amount)),
+        "u" => Some(Duration::from_micros(amount)),
+        "n" => Some(Duration::from_nanos(amount)),
+        _ => None,
+    }
+}
+
+/// Camada (tower layer) que aplica prazos às requisições.
+#[derive(Clone, Default)]
+pub struct DeadlineLayer {
+    padrao: Option<Duration>,
+}
+
+impl DeadlineLayer {
+    /// Cria a camada de prazos. Caso `padrao` seja informado, este será o
+    /// prazo máximo de qualquer requisição, mesmo que o cliente não tenha
+    /// informado um prazo.
+    pub fn new(padrao: Option<Duration>) -> Self {
+        Self { padrao }
+    }
+}
+
+impl<S> Layer<S> for DeadlineLayer {
+    type Service = DeadlineService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        DeadlineService {
+            inner,
+            padrao: self.padrao,
+        }
+    }
+}
+
+/// Serviço criado por [`DeadlineLayer`].
+#[derive(Clone)]
+pub struct DeadlineService<S> {
+    inner: S,
+    padrao: Option<Duration>,
+}
+
+impl<S, ReqBody> Service<Request<ReqBody>> for DeadlineService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        let prazo = match (parse_grpc_timeout(req.headers()), self.padrao) {
+            (Some(cliente), Some(servidor)) => Some(cliente.min(servidor)),
+            (cliente, servidor) => cliente.or(servidor),
+        };
+
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        let prazo = match prazo {
+            Some(prazo) => Instant::now() + prazo,
+            None => return Box::pin(inner.call(req)),
+        };
+
+        req.extensions_mut().insert(Deadline(prazo));
+
+        Box::pin(async move {
+            match tokio::time::timeout_at(prazo, inner.call(req)).await {
+                Ok(result) => result,
+                Err(_) => {
+                    tracing::warn!("Prazo da requisição excedido");
+                    Ok(Status::deadline_exceeded("Prazo da requisição excedido").to_http())
+                }
+            }
+        })
+    }
+}
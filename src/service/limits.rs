@@ -0,0 +1,324 @@
+// limits.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este módulo implementa limites de uso do servidor: limites de requisições
+//! simultâneas (em todo o servidor e por método gRPC) e limites de taxa de
+//! requisições por cliente.
+//!
+//! Requisições que excedam algum destes limites são imediatamente recusadas
+//! com o status `resource_exhausted`, ao invés de aguardarem em fila.
+
+use super::METODOS;
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use http_body::Body;
+use hyper::{HeaderMap, Request, Response};
+use std::collections::HashMap;
+use std::env;
+use std::net::IpAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tonic::body::BoxBody;
+use tonic::transport::server::TcpConnectInfo;
+use tonic::Status;
+use tower::{Layer, Service};
+
+/// Número máximo de clientes distintos mantidos na tabela de limite de taxa,
+/// antes que clientes inativos sejam descartados.
+const MAX_CLIENTES_RASTREADOS: usize = 10_000;
+
+/// Configuração dos limites de uso do servidor.
+#[derive(Clone, Debug, Default)]
+pub struct LimitsConfig {
+    /// Número máximo de requisições simultâneas em todo o servidor.
+    pub max_requisicoes: Option<usize>,
+    /// Número máximo de requisições simultâneas para cada método que não
+    /// possua um limite próprio.
+    pub max_por_metodo: Option<usize>,
+    /// Limites de requisições simultâneas específicos, por nome de método
+    /// (por exemplo, `Lista`).
+    pub limites_metodos: HashMap<String, usize>,
+    /// Número de requisições por segundo permitidas para cada cliente.
+    pub taxa_por_cliente: Option<f64>,
+    /// Número de requisições que um cliente pode fazer em rajada, acima da
+    /// taxa permitida.
+    pub rajada_por_cliente: f64,
+}
+
+impl LimitsConfig {
+    /// Lê a configuração de limites a partir das variáveis de ambiente:
+    ///
+    /// - `MAX_CONCURRENT_REQUESTS`: requisições simultâneas no servidor;
+    /// - `MAX_CONCURRENT_PER_METHOD`: requisições simultâneas por método;
+    /// - `METHOD_CONCURRENCY_LIMITS`: limites específicos por método, no
+    ///   formato `Lista=4,Cadastra=20`;
+    /// - `RATE_LIMIT_PER_SECOND`: requisições por segundo por cliente;
+    /// - `RATE_LIMIT_BURST`: rajada máxima por cliente (padrão: a própria taxa).
+    ///
+    /// Limites não definidos não serão aplicados.
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(nome: &str) -> Option<T> {
+            env::var(nome).ok().and_then(|v| v.trim().parse().ok())
+        }
+
+        let limites_metodos = env::var("METHOD_CONCURRENCY_LIMITS")
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|par| {
+                let (metodo, limite) = par.split_once('=')?;
+                Some((metodo.trim().to_string(), limite.trim().parse().ok()?))
+            })
+            .collect();
+
+        let taxa_por_cliente: Option<f64> = var("RATE_LIMIT_PER_SECOND");
+
+        Self {
+            max_requisicoes: var("MAX_CONCURRENT_REQUESTS"),
+            max_por_metodo: var("MAX_CONCURRENT_PER_METHOD"),
+            limites_metodos,
+            taxa_por_cliente,
+            rajada_por_cliente: var("RATE_LIMIT_BURST")
+                .or(taxa_por_cliente)
+                .unwrap_or(1.0),
+        }
+    }
+}
+
+/// Balde de fichas (token bucket) usado para limitar a taxa de requisições
+/// de um único cliente.
+struct Balde {
+    fichas: f64,
+    atualizado: Instant,
+}
+
+/// Estado compartilhado entre todas as cópias do serviço de limites.
+struct Estado {
+    config: LimitsConfig,
+    global: Option<Arc<Semaphore>>,
+    /// Semáforos de cada método gRPC, criados na inicialização. Caminhos que
+    /// não correspondem a nenhum método não possuem semáforo próprio.
+    metodos: HashMap<&'static str, Option<Arc<Semaphore>>>,
+    baldes: Mutex<HashMap<IpAddr, Balde>>,
+}
+
+impl Estado {
+    /// Cria os semáforos de cada método gRPC, de acordo com a configuração.
+    fn semaforos_metodos(config: &LimitsConfig) -> HashMap<&'static str, Option<Arc<Semaphore>>> {
+        METODOS
+            .iter()
+            .map(|&caminho| {
+                let nome = caminho.rsplit('/').next().unwrap_or(caminho);
+                let semaforo = config
+                    .limites_metodos
+                    .get(nome)
+                    .copied()
+                    .or(config.max_por_metodo)
+                    .map(|limite| Arc::new(Semaphore::new(limite)));
+                (caminho, semaforo)
+            })
+            .collect()
+    }
+
+    /// Recupera o semáforo de um método, caso o mesmo possua um limite.
+    fn semaforo_metodo(&self, caminho: &str) -> Option<Arc<Semaphore>> {
+        self.metodos.get(caminho).cloned().flatten()
+    }
+
+    /// Consome uma ficha do balde de um cliente. Retorna falso caso o
+    /// cliente tenha excedido sua taxa de requisições.
+    fn consome_ficha(&self, cliente: IpAddr) -> bool {
+        let taxa = match self.config.taxa_por_cliente {
+            Some(taxa) => taxa,
+            None => return true,
+        };
+        let rajada = self.config.rajada_por_cliente;
+        let agora = Instant::now();
+
+        let mut baldes = self.baldes.lock().unwrap();
+        if baldes.len() > MAX_CLIENTES_RASTREADOS {
+            // Descarta clientes cujos baldes já estariam cheios
+            baldes.retain(|_, b| {
+                b.fichas + agora.duration_since(b.atualizado).as_secs_f64() * taxa < rajada
+            });
+        }
+
+        let balde = baldes.entry(cliente).or_insert(Balde {
+            fichas: rajada,
+            atualizado: agora,
+        });
+
+        let decorrido = agora.duration_since(balde.atualizado).as_secs_f64();
+        balde.fichas = (balde.fichas + decorrido * taxa).min(rajada);
+        balde.atualizado = agora;
+
+        if balde.fichas >= 1.0 {
+            balde.fichas -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Tenta reservar vagas para uma requisição, respeitando o limite de taxa
+    /// do cliente e os limites de concorrência. Em caso de falha, retorna o
+    /// motivo da recusa.
+    fn reserva(
+        &self,
+        caminho: &str,
+        cliente: Option<IpAddr>,
+    ) -> Result<Vec<OwnedSemaphorePermit>, &'static str> {
+        if let Some(cliente) = cliente {
+            if !self.consome_ficha(cliente) {
+                return Err("Limite de requisições por segundo excedido");
+            }
+        }
+
+        let mut permissoes = vec![];
+        for semaforo in [self.global.clone(), self.semaforo_metodo(caminho)]
+            .into_iter()
+            .flatten()
+        {
+            match semaforo.try_acquire_owned() {
+                Ok(permissao) => permissoes.push(permissao),
+                Err(_) => return Err("Limite de requisições simultâneas excedido"),
+            }
+        }
+        Ok(permissoes)
+    }
+}
+
+/// Camada (tower layer) que aplica os limites de uso do servidor.
+#[derive(Clone)]
+pub struct LimitsLayer {
+    estado: Arc<Estado>,
+}
+
+impl LimitsLayer {
+    /// Cria a camada de limites a partir de uma configuração.
+    pub fn new(config: LimitsConfig) -> Self {
+        Self {
+            estado: Arc::new(Estado {
+                global: config.max_requisicoes.map(|n| Arc::new(Semaphore::new(n))),
+                metodos: Estado::semaforos_metodos(&config),
+                config,
+                baldes: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+}
+
+impl<S> Layer<S> for LimitsLayer {
+    type Service = LimitsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        LimitsService {
+            inner,
+            estado: self.estado.clone(),
+        }
+    }
+}
+
+/// Serviço criado por [`LimitsLayer`].
+#[derive(Clone)]
+pub struct LimitsService<S> {
+    inner: S,
+    estado: Arc<Estado>,
+}
+
+impl<S, ReqBody> Service<Request<ReqBody>> for LimitsService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        let cliente = req
+            .extensions()
+            .get::<TcpConnectInfo>()
+            .and_then(|info| info.remote_addr())
+            .map(|addr| addr.ip());
+
+        let permissoes = match self.estado.reserva(req.uri().path(), cliente) {
+            Ok(permissoes) => permissoes,
+            Err(motivo) => {
+                tracing::warn!(motivo, "Requisição recusada");
+                let response = Status::resource_exhausted(motivo).to_http();
+                return Box::pin(async move { Ok(response) });
+            }
+        };
+
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(async move {
+            // As vagas só são liberadas quando o corpo da resposta termina de
+            // ser enviado, de forma que streams também sejam contabilizados.
+            inner.call(req).await.map(|response| {
+                response.map(|body| {
+                    PermitBody {
+                        inner: body,
+                        _permissoes: permissoes,
+                    }
+                    .boxed_unsync()
+                })
+            })
+        })
+    }
+}
+
+/// Corpo de resposta que mantém as vagas de uma requisição enquanto existir.
+struct PermitBody {
+    inner: BoxBody,
+    _permissoes: Vec<OwnedSemaphorePermit>,
+}
+
+impl Body for PermitBody {
+    type Data = Bytes;
+    type Error = Status;
+
+    fn poll_data(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        Pin::new(&mut self.inner).poll_data(cx)
+    }
+
+    fn poll_trailers(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<HeaderMap>, Self::Error>> {
+        Pin::new(&mut self.inner).poll_trailers(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
@@ -0,0 +1,319 @@
+// logging.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este módulo implementa o log estruturado do servidor, através da biblioteca
+//! `tracing`.
+//!
+//! Cada requisição recebida pelo servidor é envolvida em um span próprio, que
+//! contém o método gRPC chamado, o endereço remoto, um ID de requisição, a
+//! duração do atendimento e o código de status gRPC retornado. O status e a
+//! duração só são registrados quando a resposta termina de ser enviada, de
+//! forma que streams e erros informados nos trailers também sejam
+//! registrados corretamente.
+
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use futures::ready;
+use http_body::Body;
+use hyper::{HeaderMap, Request, Response};
+use std::env;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tonic::body::BoxBody;
+use tonic::transport::server::TcpConnectInfo;
+use tonic::{Code, Status};
+use tower::{Layer, Service};
+use tracing::field::Empty;
+use tracing::Instrument;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, EnvFilter};
+
+/// Nome do cabeçalho (metadado gRPC) que carrega o ID da requisição.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Inicializa o subscriber global de logs.
+///
+/// O formato da saída é definido pela variável de ambiente `LOG_FORMAT`,
+/// que pode assumir os valores `json` ou `pretty` (padrão). Os níveis de log
+/// são filtrados através da variável `RUST_LOG`, que assume `info` caso não
+/// tenha sido definida.
+///
+/// Caso a feature `otel` esteja habilitada, os spans também poderão ser
+/// exportados via OTLP. Veja o módulo `telemetria`.
+pub fn init() {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+
+    let output = match env::var("LOG_FORMAT").as_deref() {
+        Ok("json") => fmt::layer().json().with_current_span(true).boxed(),
+        _ => fmt::layer().pretty().boxed(),
+    };
+
+    let registry = tracing_subscriber::registry().with(output).with(filter);
+
+    #[cfg(feature = "otel")]
+    let (registry, erro_otel) = match super::telemetria::layer() {
+        Ok(camada) => (registry.with(camada), None),
+        Err(e) => (registry.with(None), Some(e)),
+    };
+
+    registry.init();
+
+    // O erro só pode ser registrado após a instalação do subscriber.
+    #[cfg(feature = "otel")]
+    if let Some(e) = erro_otel {
+        tracing::warn!(erro = %e, "Impossível iniciar exportação OTLP; traces não serão exportados");
+    }
+}
+
+/// Encerra o log estruturado, garantindo que spans pendentes sejam
+/// exportados.
+pub fn shutdown() {
+    #[cfg(feature = "otel")]
+    super::telemetria::shutdown();
+}
+
+/// Gera um novo ID de requisição aleatório, em formato hexadecimal.
+pub(crate) fn novo_request_id() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
+
+/// Recupera o código de status gRPC de um conjunto de cabeçalhos ou
+/// trailers, caso presente.
+pub(crate) fn grpc_status(headers: &HeaderMap) -> Option<i32> {
+    headers
+        .get("grpc-status")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<i32>().ok())
+}
+
+/// Observa o término de uma resposta gRPC, chamando `ao_terminar` com seu
+/// código de status assim que o mesmo for conhecido.
+///
+/// O status é lido dos cabeçalhos da resposta, no caso de erros sem corpo, ou
+/// dos trailers. Respostas interrompidas antes dos trailers são consideradas
+/// canceladas; respostas do gRPC-Web, cujo status é codificado no próprio
+/// corpo, são consideradas bem-sucedidas caso terminem sem erro.
+pub(crate) fn ao_terminar<F>(response: Response<BoxBody>, ao_terminar: F) -> Response<BoxBody>
+where
+    F: FnOnce(i32) + Send + 'static,
+{
+    if let Some(status) = grpc_status(response.headers()) {
+        ao_terminar(status);
+        return response;
+    }
+
+    response.map(|inner| {
+        CorpoObservado {
+            inner,
+            ao_terminar: Some(Box::new(ao_terminar)),
+            fim_dados: false,
+        }
+        .boxed_unsync()
+    })
+}
+
+/// Corpo de resposta que informa seu código de status ao terminar (veja
+/// [`ao_terminar`]).
+struct CorpoObservado {
+    inner: BoxBody,
+    /// Função chamada com o código de status, caso ainda não tenha sido.
+    ao_terminar: Option<Box<dyn FnOnce(i32) + Send>>,
+    /// Indica se todos os dados do corpo já foram enviados.
+    fim_dados: bool,
+}
+
+impl CorpoObservado {
+    /// Informa o código de status da resposta, caso ainda não tenha sido
+    /// informado.
+    fn termina(&mut self, status: i32) {
+        if let Some(ao_terminar) = self.ao_terminar.take() {
+            ao_terminar(status);
+        }
+    }
+}
+
+impl Body for CorpoObservado {
+    type Data = Bytes;
+    type Error = Status;
+
+    fn poll_data(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        let result = ready!(Pin::new(&mut self.inner).poll_data(cx));
+        match &result {
+            Some(Err(status)) => self.termina(status.code() as i32),
+            None => self.fim_dados = true,
+            Some(Ok(_)) => {}
+        }
+        Poll::Ready(result)
+    }
+
+    fn poll_trailers(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<HeaderMap>, Self::Error>> {
+        let result = ready!(Pin::new(&mut self.inner).poll_trailers(cx));
+        match &result {
+            Ok(trailers) => {
+                let status = trailers.as_ref().and_then(grpc_status);
+                self.termina(status.unwrap_or(0));
+            }
+            Err(status) => self.termina(status.code() as i32),
+        }
+        Poll::Ready(result)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+impl Drop for CorpoObservado {
+    fn drop(&mut self) {
+        let status = if self.fim_dados || self.inner.is_end_stream() {
+            0
+        } else {
+            Code::Cancelled as i32
+        };
+        self.termina(status);
+    }
+}
+
+/// Registra o código de status e a duração de uma requisição em seu span,
+/// emitindo também um evento de log com os mesmos.
+fn registra_fim(span: &tracing::Span, inicio: Instant, status: i32) {
+    let duracao = inicio.elapsed().as_secs_f64() * 1000.0;
+    span.record("duracao_ms", duracao);
+    span.record("status", status);
+
+    span.in_scope(|| {
+        if status == 0 {
+            tracing::info!(status, duracao_ms = duracao, "Requisição atendida");
+        } else {
+            tracing::warn!(status, duracao_ms = duracao, "Requisição com erro");
+        }
+    });
+}
+
+/// Camada (tower layer) que cria um span para cada requisição gRPC recebida.
+#[derive(Clone, Default)]
+pub struct RequestTraceLayer;
+
+impl<S> Layer<S> for RequestTraceLayer {
+    type Service = RequestTrace<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestTrace { inner }
+    }
+}
+
+/// Serviço criado por [`RequestTraceLayer`].
+///
+/// Caso a requisição não possua um ID no metadado `x-request-id`, um novo ID
+/// será gerado e inserido na mesma, de forma que os handlers também tenham
+/// acesso a ele. O ID também é devolvido no cabeçalho da resposta.
+#[derive(Clone)]
+pub struct RequestTrace<S> {
+    inner: S,
+}
+
+impl<S, ReqBody> Service<Request<ReqBody>> for RequestTrace<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        let request_id = req
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from)
+            .unwrap_or_else(novo_request_id);
+
+        if let Ok(value) = request_id.parse() {
+            req.headers_mut().insert(REQUEST_ID_HEADER, value);
+        }
+
+        let remoto = req
+            .extensions()
+            .get::<TcpConnectInfo>()
+            .and_then(|info| info.remote_addr());
+
+        let span = tracing::info_span!(
+            "rpc",
+            metodo = %req.uri().path(),
+            request_id = %request_id,
+            remoto = ?remoto,
+            status = Empty,
+            duracao_ms = Empty,
+        );
+
+        #[cfg(feature = "otel")]
+        {
+            use tracing_opentelemetry::OpenTelemetrySpanExt;
+            span.set_parent(super::telemetria::extrai_contexto(req.headers()));
+        }
+
+        // O serviço clonado pode não estar pronto; assim, trocamos pelo
+        // serviço que foi efetivamente preparado em `poll_ready`.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(
+            async move {
+                let inicio = Instant::now();
+                let result = inner.call(req).await;
+
+                match result {
+                    Ok(mut response) => {
+                        if let Ok(value) = request_id.parse() {
+                            response.headers_mut().insert(REQUEST_ID_HEADER, value);
+                        }
+
+                        // O span é mantido pelo corpo da resposta, e só é
+                        // encerrado quando a mesma termina de ser enviada.
+                        let span = tracing::Span::current();
+                        Ok(ao_terminar(response, move |status| {
+                            registra_fim(&span, inicio, status)
+                        }))
+                    }
+                    Err(e) => {
+                        let duracao = inicio.elapsed().as_secs_f64() * 1000.0;
+                        tracing::Span::current().record("duracao_ms", duracao);
+                        tracing::error!(duracao_ms = duracao, "Falha no transporte da requisição");
+                        Err(e)
+                    }
+                }
+            }
+            .instrument(span),
+        )
+    }
+}
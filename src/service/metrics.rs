@@ -0,0 +1,248 @@
+// metrics.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este módulo implementa as métricas do servidor no formato do Prometheus,
+//! assim como o endpoint HTTP `/metrics` por onde as mesmas são expostas.
+
+use super::{logging, METODOS};
+use crate::repository::Repository;
+use futures::future::BoxFuture;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use lazy_static::lazy_static;
+use prometheus::{
+    register_histogram, register_histogram_vec, register_int_counter, register_int_counter_vec,
+    register_int_gauge, Encoder, Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+    TextEncoder,
+};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tonic::body::BoxBody;
+use tower::{Layer, Service};
+
+/// Rótulo das requisições a caminhos que não correspondem a nenhum método
+/// gRPC, de forma que caminhos arbitrários não criem novas séries.
+pub const METODO_DESCONHECIDO: &str = "desconhecido";
+
+lazy_static! {
+    /// Número de requisições gRPC atendidas, por método e código de status.
+    static ref RPC_REQUISICOES: IntCounterVec = register_int_counter_vec!(
+        "minerva_rpc_requisicoes_total",
+        "Número de requisições gRPC atendidas",
+        &["metodo", "status"]
+    )
+    .unwrap();
+
+    /// Tempo de atendimento de requisições gRPC, por método.
+    static ref RPC_DURACAO: HistogramVec = register_histogram_vec!(
+        "minerva_rpc_duracao_segundos",
+        "Tempo de atendimento de requisições gRPC",
+        &["metodo"]
+    )
+    .unwrap();
+
+    /// Número de streams de listagem de clientes em andamento.
+    static ref LISTA_STREAMS_ATIVOS: IntGauge = register_int_gauge!(
+        "minerva_lista_streams_ativos",
+        "Número de streams de listagem em andamento"
+    )
+    .unwrap();
+
+    /// Número de páginas enviadas através de streams de listagem.
+    static ref LISTA_PAGINAS_ENVIADAS: IntCounter = register_int_counter!(
+        "minerva_lista_paginas_enviadas_total",
+        "Número de páginas enviadas em streams de listagem"
+    )
+    .unwrap();
+
+    /// Número de conexões abertas na pool do banco de dados.
+    static ref DB_POOL_CONEXOES: IntGauge = register_int_gauge!(
+        "minerva_db_pool_conexoes",
+        "Número de conexões abertas na pool do banco de dados"
+    )
+    .unwrap();
+
+    /// Número de conexões ociosas na pool do banco de dados.
+    static ref DB_POOL_OCIOSAS: IntGauge = register_int_gauge!(
+        "minerva_db_pool_conexoes_ociosas",
+        "Número de conexões ociosas na pool do banco de dados"
+    )
+    .unwrap();
+
+    /// Tempo de espera para retirada de uma conexão da pool.
+    static ref DB_POOL_ESPERA: Histogram = register_histogram!(
+        "minerva_db_pool_espera_segundos",
+        "Tempo de espera para retirar uma conexão da pool do banco de dados"
+    )
+    .unwrap();
+}
+
+/// Registra o tempo de espera por uma conexão da pool.
+pub fn observa_espera_pool(inicio: Instant) {
+    DB_POOL_ESPERA.observe(inicio.elapsed().as_secs_f64());
+}
+
+/// Registra o envio de uma página em um stream de listagem.
+pub fn pagina_enviada() {
+    LISTA_PAGINAS_ENVIADAS.inc();
+}
+
+/// Guarda que contabiliza um stream de listagem como ativo enquanto existir.
+pub struct StreamAtivo;
+
+impl StreamAtivo {
+    /// Contabiliza um novo stream de listagem ativo.
+    pub fn new() -> Self {
+        LISTA_STREAMS_ATIVOS.inc();
+        Self
+    }
+}
+
+impl Default for StreamAtivo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for StreamAtivo {
+    fn drop(&mut self) {
+        LISTA_STREAMS_ATIVOS.dec();
+    }
+}
+
+/// Retorna o rótulo do método gRPC de um caminho requisitado.
+fn rotulo_metodo(caminho: &str) -> &'static str {
+    METODOS
+        .iter()
+        .find(|metodo| **metodo == caminho)
+        .copied()
+        .unwrap_or(METODO_DESCONHECIDO)
+}
+
+/// Camada (tower layer) que contabiliza requisições gRPC e seus tempos de
+/// atendimento.
+///
+/// O código de status de cada requisição é lido dos cabeçalhos da resposta,
+/// no caso de erros sem corpo, ou dos trailers, e a requisição só é
+/// contabilizada quando os mesmos são enviados. Respostas interrompidas
+/// antes dos trailers são contabilizadas como canceladas; respostas do
+/// gRPC-Web, cujo status é codificado no próprio corpo, são contabilizadas
+/// como bem-sucedidas caso terminem sem erro.
+#[derive(Clone, Default)]
+pub struct MetricsLayer;
+
+impl<S> Layer<S> for MetricsLayer {
+    type Service = MetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MetricsService { inner }
+    }
+}
+
+/// Serviço criado por [`MetricsLayer`].
+#[derive(Clone)]
+pub struct MetricsService<S> {
+    inner: S,
+}
+
+impl<S, ReqBody> Service<Request<ReqBody>> for MetricsService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        let metodo = rotulo_metodo(req.uri().path());
+
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(async move {
+            let inicio = Instant::now();
+            let result = inner.call(req).await;
+
+            RPC_DURACAO
+                .with_label_values(&[metodo])
+                .observe(inicio.elapsed().as_secs_f64());
+
+            result.map(|response| {
+                logging::ao_terminar(response, move |status| {
+                    RPC_REQUISICOES
+                        .with_label_values(&[metodo, &status.to_string()])
+                        .inc();
+                })
+            })
+        })
+    }
+}
+
+/// Gera o texto com todas as métricas registradas, atualizando antes o
+/// estado da pool de conexões do repositório, caso exista.
+fn coleta(repo: &dyn Repository) -> Vec<u8> {
+    if let Some(estado) = repo.estado_pool() {
+        DB_POOL_CONEXOES.set(estado.connections as i64);
+        DB_POOL_OCIOSAS.set(estado.idle_connections as i64);
+    }
+
+    let mut buffer = vec![];
+    TextEncoder::new()
+        .encode(&prometheus::gather(), &mut buffer)
+        .expect("Impossível codificar métricas");
+    buffer
+}
+
+/// Responde a uma requisição HTTP ao servidor de métricas.
+async fn responde(
+    repo: Arc<dyn Repository>,
+    req: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let response = match (req.method(), req.uri().path()) {
+        (&Method::GET, "/metrics") => Response::builder()
+            .header(
+                hyper::header::CONTENT_TYPE,
+                TextEncoder::new().format_type(),
+            )
+            .body(Body::from(coleta(repo.as_ref()))),
+        _ => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::empty()),
+    };
+
+    Ok(response.expect("Impossível gerar resposta HTTP"))
+}
+
+/// Executa o servidor HTTP de métricas no endereço informado. As métricas
+/// poderão ser obtidas através do endpoint `/metrics`. As métricas da pool
+/// de conexões só serão atualizadas caso o repositório possua uma pool.
+pub async fn serve(addr: SocketAddr, repo: Arc<dyn Repository>) -> Result<(), hyper::Error> {
+    let make_service = make_service_fn(move |_| {
+        let repo = repo.clone();
+        async move { Ok::<_, Infallible>(service_fn(move |req| responde(repo.clone(), req))) }
+    });
+
+    hyper::Server::bind(&addr).serve(make_service).await
+}
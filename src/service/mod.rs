@@ -16,7 +16,18 @@
 
 //! Este módulo engloba os submódulos do serviço gRPC do Minerva.Lite.
 
+pub mod acesso;
 pub mod base;
 pub mod clientes;
-pub mod db;
-pub mod utils;
+pub mod deadline;
+pub mod limits;
+pub mod logging;
+pub mod metrics;
+pub mod rest;
+pub mod web;
+
+#[cfg(feature = "otel")]
+pub mod telemetria;
+
+/// Caminhos de todos os métodos gRPC do protobuf, gerados pelo `build.rs`.
+pub(crate) const METODOS: &[&str] = include!(concat!(env!("OUT_DIR"), "/metodos.rs"));
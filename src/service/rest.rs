@@ -0,0 +1,304 @@
+// rest.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este módulo implementa o gateway REST/JSON do Minerva.Lite, para clientes
+//! que não utilizam gRPC.
+//!
+//! As rotas são atendidas pelo mesmo serviço de clientes usado pelo gRPC, de
+//! forma que as regras e os erros sejam os mesmos nos dois protocolos. As
+//! mensagens seguem o mapeamento JSON do proto3, e os erros são retornados no
+//! formato de `google.rpc.Status`, com o status HTTP correspondente ao código
+//! gRPC:
+//!
+//! - `POST /clientes`: cadastra um cliente;
+//! - `GET /clientes?pagina=N`: retorna uma página de clientes;
+//! - `GET /clientes/{id}`: consulta um cliente;
+//! - `DELETE /clientes/{id}`: remove um cliente;
+//! - `GET /openapi.json`: documento OpenAPI das rotas acima, gerado a partir
+//!   do protobuf durante a compilação.
+//!
+//! O token de acesso é enviado no cabeçalho `authorization`, da mesma forma
+//! que no gRPC (veja [`acesso`](super::acesso)).
+
+use super::acesso::{Acesso, AcessoConfig};
+use super::clientes::{erro_lista, MinervaLiteClientesService};
+use super::logging::{novo_request_id, REQUEST_ID_HEADER};
+use crate::minerva_clientes_server::MinervaClientes;
+use crate::repository::Repository;
+use crate::{ClienteResponse, ClientePageResponse, IdClienteRequest, NovoClienteRequest};
+use axum::body::Body;
+use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
+use axum::extract::{Extension, Path, Query};
+use axum::http::{header, HeaderValue, Request, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+use tonic::{Code, Status};
+use tracing::field::Empty;
+use tracing::Instrument;
+
+/// Documento OpenAPI do gateway, gerado a partir do protobuf pelo script de
+/// compilação.
+pub const OPENAPI: &str = include_str!(concat!(env!("OUT_DIR"), "/openapi.json"));
+
+/// Erro de uma rota REST, representado pelo status gRPC equivalente.
+pub struct ErroRest(Status);
+
+impl From<Status> for ErroRest {
+    fn from(status: Status) -> Self {
+        Self(status)
+    }
+}
+
+impl From<JsonRejection> for ErroRest {
+    fn from(e: JsonRejection) -> Self {
+        Self(Status::invalid_argument(e.to_string()))
+    }
+}
+
+impl From<PathRejection> for ErroRest {
+    fn from(e: PathRejection) -> Self {
+        Self(Status::invalid_argument(e.to_string()))
+    }
+}
+
+impl From<QueryRejection> for ErroRest {
+    fn from(e: QueryRejection) -> Self {
+        Self(Status::invalid_argument(e.to_string()))
+    }
+}
+
+impl IntoResponse for ErroRest {
+    fn into_response(self) -> Response {
+        let corpo = serde_json::json!({
+            "code": self.0.code() as i32,
+            "message": self.0.message(),
+        });
+        (status_http(self.0.code()), Json(corpo)).into_response()
+    }
+}
+
+/// Retorna o status HTTP equivalente a um código de status gRPC.
+pub fn status_http(code: Code) -> StatusCode {
+    match code {
+        Code::Ok => StatusCode::OK,
+        Code::InvalidArgument | Code::FailedPrecondition | Code::OutOfRange => {
+            StatusCode::BAD_REQUEST
+        }
+        Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+        Code::PermissionDenied => StatusCode::FORBIDDEN,
+        Code::NotFound => StatusCode::NOT_FOUND,
+        Code::AlreadyExists | Code::Aborted => StatusCode::CONFLICT,
+        Code::ResourceExhausted => StatusCode::TOO_MANY_REQUESTS,
+        Code::Cancelled => StatusCode::from_u16(499).unwrap(),
+        Code::Unimplemented => StatusCode::NOT_IMPLEMENTED,
+        Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        Code::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+        Code::Unknown | Code::Internal | Code::DataLoss => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Estado compartilhado pelas rotas.
+#[derive(Clone)]
+struct Estado {
+    servico: Arc<MinervaLiteClientesService>,
+    repo: Arc<dyn Repository>,
+}
+
+/// Parâmetros de paginação da listagem de clientes.
+#[derive(Deserialize)]
+struct Paginacao {
+    #[serde(default)]
+    pagina: i64,
+}
+
+/// Cria as rotas do gateway REST, atendidas a partir de um repositório
+/// qualquer, com o controle de acesso informado. O documento OpenAPI não
+/// exige autenticação.
+pub fn router(repo: Arc<dyn Repository>, acesso: AcessoConfig) -> Router {
+    let estado = Estado {
+        servico: Arc::new(MinervaLiteClientesService::new(repo.clone())),
+        repo,
+    };
+    let acesso = Arc::new(acesso);
+
+    let clientes = Router::new()
+        .route("/clientes", get(lista).post(cadastra))
+        .route("/clientes/:id", get(consulta).delete(deleta))
+        .layer(middleware::from_fn(move |req, next| {
+            autentica(acesso.clone(), req, next)
+        }));
+
+    Router::new()
+        .merge(clientes)
+        .route("/openapi.json", get(openapi))
+        .layer(Extension(estado))
+        .layer(middleware::from_fn(registra))
+}
+
+/// Inicia o gateway REST no endereço informado.
+pub async fn serve(
+    addr: SocketAddr,
+    repo: Arc<dyn Repository>,
+    acesso: AcessoConfig,
+) -> Result<(), hyper::Error> {
+    axum::Server::bind(&addr)
+        .serve(router(repo, acesso).into_make_service())
+        .await
+}
+
+/// Autentica uma requisição REST, inserindo o acesso do usuário em suas
+/// extensões (veja [`AcessoConfig::autentica`]).
+async fn autentica(
+    config: Arc<AcessoConfig>,
+    mut req: Request<Body>,
+    next: Next<Body>,
+) -> Result<Response, ErroRest> {
+    let acesso = config.autentica(req.headers()).map_err(|status| *status)?;
+    req.extensions_mut().insert(acesso);
+    Ok(next.run(req).await)
+}
+
+/// Cria uma requisição ao serviço de clientes em nome do usuário
+/// autenticado.
+fn requisicao<T>(mensagem: T, acesso: Acesso) -> tonic::Request<T> {
+    let mut req = tonic::Request::new(mensagem);
+    req.extensions_mut().insert(acesso);
+    req
+}
+
+/// Rota de cadastro de cliente. Retorna o cliente cadastrado, e o endereço
+/// do mesmo no cabeçalho `Location`.
+async fn cadastra(
+    Extension(estado): Extension<Estado>,
+    Extension(acesso): Extension<Acesso>,
+    dados: Result<Json<NovoClienteRequest>, JsonRejection>,
+) -> Result<impl IntoResponse, ErroRest> {
+    let Json(dados) = dados?;
+    let cliente = estado
+        .servico
+        .cadastra(requisicao(dados, acesso))
+        .await?
+        .into_inner();
+
+    let local = [(header::LOCATION, format!("/clientes/{}", cliente.id))];
+    Ok((StatusCode::CREATED, local, Json(cliente)))
+}
+
+/// Rota de consulta a um único cliente.
+async fn consulta(
+    Extension(estado): Extension<Estado>,
+    Extension(acesso): Extension<Acesso>,
+    id: Result<Path<i32>, PathRejection>,
+) -> Result<Json<ClienteResponse>, ErroRest> {
+    let Path(id) = id?;
+    let cliente = estado
+        .servico
+        .consulta(requisicao(IdClienteRequest { id }, acesso))
+        .await?
+        .into_inner();
+    Ok(Json(cliente))
+}
+
+/// Rota de listagem de clientes. Ao contrário do gRPC, que envia todas as
+/// páginas via streaming, cada requisição retorna uma única página.
+async fn lista(
+    Extension(estado): Extension<Estado>,
+    Extension(acesso): Extension<Acesso>,
+    paginacao: Result<Query<Paginacao>, QueryRejection>,
+) -> Result<Json<ClientePageResponse>, ErroRest> {
+    let Query(Paginacao { pagina }) = paginacao?;
+    if pagina < 0 {
+        return Err(Status::invalid_argument("Número de página inválido").into());
+    }
+
+    let clientes = estado.repo.lista(pagina).await.map_err(erro_lista)?;
+    Ok(Json(ClientePageResponse {
+        clientes: acesso
+            .protege(clientes)
+            .into_iter()
+            .map(|c| c.into())
+            .collect(),
+    }))
+}
+
+/// Rota de remoção de um cliente.
+async fn deleta(
+    Extension(estado): Extension<Estado>,
+    Extension(acesso): Extension<Acesso>,
+    id: Result<Path<i32>, PathRejection>,
+) -> Result<StatusCode, ErroRest> {
+    let Path(id) = id?;
+    estado
+        .servico
+        .deleta(requisicao(IdClienteRequest { id }, acesso))
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Rota do documento OpenAPI.
+async fn openapi() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "application/json")], OPENAPI)
+}
+
+/// Cria um span para cada requisição REST, da mesma forma que é feito para
+/// as requisições gRPC (veja [`RequestTraceLayer`](super::logging::RequestTraceLayer)).
+async fn registra(req: Request<Body>, next: Next<Body>) -> Response {
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from)
+        .unwrap_or_else(novo_request_id);
+
+    let span = tracing::info_span!(
+        "rest",
+        metodo = %req.method(),
+        caminho = %req.uri().path(),
+        request_id = %request_id,
+        status = Empty,
+        duracao_ms = Empty,
+    );
+
+    async move {
+        let inicio = Instant::now();
+        let mut response = next.run(req).await;
+        let duracao = inicio.elapsed().as_secs_f64() * 1000.0;
+
+        let status = response.status().as_u16();
+        let span = tracing::Span::current();
+        span.record("status", status);
+        span.record("duracao_ms", duracao);
+
+        if let Ok(value) = HeaderValue::from_str(&request_id) {
+            response.headers_mut().insert(REQUEST_ID_HEADER, value);
+        }
+
+        if response.status().is_success() {
+            tracing::info!(status, duracao_ms = duracao, "Requisição atendida");
+        } else {
+            tracing::warn!(status, duracao_ms = duracao, "Requisição com erro");
+        }
+        response
+    }
+    .instrument(span)
+    .await
+}
@@ -0,0 +1,92 @@
+// telemetria.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este módulo implementa a exportação de traces via OpenTelemetry (OTLP).
+//!
+//! A exportação só é compilada com a feature `otel`, e só é habilitada caso a
+//! variável de ambiente `OTEL_EXPORTER_OTLP_ENDPOINT` esteja definida (por
+//! exemplo, `http://127.0.0.1:4317`). O contexto de trace de requisições
+//! recebidas é propagado segundo o padrão W3C Trace Context, através do
+//! metadado `traceparent`.
+
+use hyper::HeaderMap;
+use opentelemetry::propagation::Extractor;
+use opentelemetry::sdk::propagation::TraceContextPropagator;
+use opentelemetry::sdk::{trace, Resource};
+use opentelemetry::trace::TraceError;
+use opentelemetry::{global, Context, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use std::env;
+use tracing::Subscriber;
+use tracing_opentelemetry::OpenTelemetryLayer;
+use tracing_subscriber::registry::LookupSpan;
+
+/// Cria a camada de exportação de spans para o subscriber de logs, caso a
+/// exportação esteja habilitada através do ambiente.
+///
+/// Retorna um erro caso não seja possível instalar o exportador; nesse caso,
+/// a aplicação pode seguir sem exportar traces.
+pub fn layer<S>() -> Result<Option<OpenTelemetryLayer<S, trace::Tracer>>, TraceError>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let endpoint = match env::var("OTEL_EXPORTER_OTLP_ENDPOINT") {
+        Ok(endpoint) => endpoint,
+        Err(_) => return Ok(None),
+    };
+
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(endpoint),
+        )
+        .with_trace_config(
+            trace::config().with_resource(Resource::new(vec![KeyValue::new(
+                "service.name",
+                "liteserver",
+            )])),
+        )
+        .install_batch(opentelemetry::runtime::Tokio)?;
+
+    Ok(Some(tracing_opentelemetry::layer().with_tracer(tracer)))
+}
+
+/// Envia os spans pendentes e encerra o exportador de traces.
+pub fn shutdown() {
+    global::shutdown_tracer_provider();
+}
+
+/// Extrai o contexto de trace W3C dos cabeçalhos de uma requisição.
+pub fn extrai_contexto(headers: &HeaderMap) -> Context {
+    global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)))
+}
+
+/// Adaptador para leitura de cabeçalhos HTTP pelo propagador.
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl<'a> Extractor for HeaderExtractor<'a> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
@@ -0,0 +1,97 @@
+// web.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Este módulo implementa o suporte a gRPC-Web, que permite que navegadores
+//! chamem os serviços gRPC diretamente, sem um proxy como o Envoy.
+//!
+//! Os serviços habilitados para gRPC-Web continuam atendendo requisições
+//! gRPC comuns; apenas as requisições gRPC-Web (inclusive as requisições de
+//! preflight do CORS) são traduzidas. Como navegadores usam HTTP/1.1 sem TLS,
+//! o servidor também deve aceitar conexões HTTP/1.1.
+
+use super::logging::REQUEST_ID_HEADER;
+use hyper::header::HeaderValue;
+use std::env;
+use std::time::Duration;
+
+/// Configuração do CORS para requisições gRPC-Web.
+#[derive(Clone, Debug, Default)]
+pub struct WebConfig {
+    /// Origens que podem chamar o servidor (por exemplo,
+    /// `https://app.exemplo.com`). Caso vazio, qualquer origem é permitida.
+    pub origens: Vec<String>,
+    /// Tempo durante o qual os navegadores podem reaproveitar a resposta de
+    /// uma requisição de preflight. Caso não seja definido, será usado o
+    /// padrão de 24 horas.
+    pub max_age: Option<Duration>,
+}
+
+impl WebConfig {
+    /// Lê a configuração do CORS a partir das variáveis de ambiente:
+    ///
+    /// - `CORS_ALLOWED_ORIGINS`: origens permitidas, separadas por vírgulas;
+    /// - `CORS_MAX_AGE_SECS`: validade das respostas de preflight, em
+    ///   segundos.
+    ///
+    /// Origens inválidas são ignoradas.
+    pub fn from_env() -> Self {
+        let origens = env::var("CORS_ALLOWED_ORIGINS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|origem| !origem.is_empty())
+            .filter(|origem| {
+                let valida = HeaderValue::from_str(origem).is_ok();
+                if !valida {
+                    tracing::warn!(origem, "Origem do CORS inválida ignorada");
+                }
+                valida
+            })
+            .map(String::from)
+            .collect();
+
+        let max_age = env::var("CORS_MAX_AGE_SECS")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .map(Duration::from_secs);
+
+        Self { origens, max_age }
+    }
+
+    /// Cria a configuração do gRPC-Web, usada para habilitar cada serviço
+    /// através de [`tonic_web::Config::enable`].
+    ///
+    /// O token de acesso é enviado pelo cabeçalho `authorization`, e não por
+    /// cookies; assim, credenciais não são permitidas pelo CORS. O cabeçalho
+    /// `x-request-id` é exposto aos navegadores, junto com os cabeçalhos de
+    /// status do gRPC.
+    pub fn config(&self) -> tonic_web::Config {
+        let config = tonic_web::config()
+            .allow_credentials(false)
+            .expose_headers([REQUEST_ID_HEADER]);
+
+        let config = match self.max_age {
+            Some(max_age) => config.max_age(max_age),
+            None => config,
+        };
+
+        if self.origens.is_empty() {
+            config.allow_all_origins()
+        } else {
+            config.allow_origins(self.origens.iter().map(String::as_str))
+        }
+    }
+}
@@ -0,0 +1,200 @@
+// acesso.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Testes de integração do controle de acesso. Como o controle de acesso não
+//! depende do meio de armazenamento, apenas o repositório em memória é
+//! usado.
+
+mod common;
+
+use common::Backend;
+use minerva_lite::model::cliente::mascara_docto;
+use minerva_lite::model::logdb::USUARIO_SISTEMA;
+use minerva_lite::sdk::{Erro, MinervaLite, NovoCliente};
+use minerva_lite::service::acesso::{Acesso, AcessoConfig, Permissao};
+
+const CPF: &str = "123.456.789-00";
+const CPF_MASCARADO: &str = "***.456.789-**";
+const CNPJ: &str = "12.345.678/0001-90";
+
+/// Inicia o servidor com os usuários `admin`, que pode ver os dados
+/// sensíveis dos clientes, e `atendente`, que não pode.
+async fn inicia() -> common::Servidor {
+    let acesso = |usuario: &str, permissoes| Acesso {
+        usuario: usuario.to_string(),
+        permissoes,
+    };
+    let config = AcessoConfig {
+        tokens: vec![
+            (
+                "token-admin".to_string(),
+                acesso("admin", vec![Permissao::DadosSensiveis]),
+            ),
+            ("token-atendente".to_string(), acesso("atendente", vec![])),
+        ],
+    };
+    common::inicia_com_acesso(Backend::Memoria, config)
+        .await
+        .unwrap()
+}
+
+/// Conecta ao servidor com um token de acesso opcional.
+async fn conecta(servidor: &common::Servidor, token: Option<&str>) -> MinervaLite {
+    let mut builder = MinervaLite::builder(servidor.addr.clone());
+    if let Some(token) = token {
+        builder = builder.token(token);
+    }
+    builder
+        .conecta()
+        .await
+        .expect("Impossível conectar ao servidor")
+}
+
+fn novo_cliente(nome: &str, pj: bool, docto: &str) -> NovoCliente {
+    NovoCliente {
+        nome: nome.to_string(),
+        pj,
+        docto: docto.to_string(),
+    }
+}
+
+#[test]
+fn mascara_documentos() {
+    assert_eq!(mascara_docto(CPF), CPF_MASCARADO);
+    assert_eq!(mascara_docto("12345678900"), "***456789**");
+    assert_eq!(mascara_docto("123-45"), "***-**");
+    assert_eq!(mascara_docto(""), "");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn recusa_requisicao_sem_token_valido() {
+    let servidor = inicia().await;
+
+    let erro = conecta(&servidor, None)
+        .await
+        .consulta(1)
+        .await
+        .unwrap_err();
+    assert!(matches!(erro, Erro::NaoAutenticado(_)), "{:?}", erro);
+
+    let erro = conecta(&servidor, Some("token-errado"))
+        .await
+        .consulta(1)
+        .await
+        .unwrap_err();
+    assert!(matches!(erro, Erro::NaoAutenticado(_)), "{:?}", erro);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn mascara_documento_sem_permissao() {
+    let servidor = inicia().await;
+    let admin = conecta(&servidor, Some("token-admin")).await;
+    let atendente = conecta(&servidor, Some("token-atendente")).await;
+
+    let cadastrado = atendente
+        .cadastra(novo_cliente("Jose da Silva", false, CPF))
+        .await
+        .unwrap();
+    assert_eq!(cadastrado.docto, CPF_MASCARADO);
+    admin
+        .cadastra(novo_cliente("José da Silva", false, CPF))
+        .await
+        .unwrap();
+
+    let cliente = atendente.consulta(cadastrado.id).await.unwrap();
+    assert_eq!(cliente.docto, CPF_MASCARADO);
+
+    let clientes = atendente.lista_todos().await.unwrap();
+    assert!(clientes.iter().all(|c| c.docto == CPF_MASCARADO));
+
+    let detalhado = atendente.consulta_detalhada(cadastrado.id).await.unwrap();
+    assert_eq!(detalhado.cliente.unwrap().docto, CPF_MASCARADO);
+
+    let encontrados = atendente.busca("jose", 10).await.unwrap();
+    assert!(!encontrados.is_empty());
+    assert!(encontrados
+        .iter()
+        .all(|e| e.cliente.as_ref().unwrap().docto == CPF_MASCARADO));
+
+    let grupos = atendente.detecta_duplicados().await.unwrap();
+    assert!(!grupos.is_empty());
+    assert!(grupos
+        .iter()
+        .flat_map(|g| &g.clientes)
+        .all(|c| c.docto == CPF_MASCARADO));
+
+    let exportado = atendente.exporta_dados(cadastrado.id).await.unwrap();
+    assert!(exportado.contains(CPF_MASCARADO));
+    assert!(!exportado.contains(CPF));
+
+    // Usuários com permissão veem o documento completo.
+    let cliente = admin.consulta(cadastrado.id).await.unwrap();
+    assert_eq!(cliente.docto, CPF);
+    let exportado = admin.exporta_dados(cadastrado.id).await.unwrap();
+    assert!(exportado.contains(CPF));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn mantem_documento_de_pessoa_juridica() {
+    let servidor = inicia().await;
+    let atendente = conecta(&servidor, Some("token-atendente")).await;
+
+    let cadastrado = atendente
+        .cadastra(novo_cliente("Empresa Ltda", true, CNPJ))
+        .await
+        .unwrap();
+    assert_eq!(cadastrado.docto, CNPJ);
+    assert_eq!(atendente.consulta(cadastrado.id).await.unwrap().docto, CNPJ);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn registra_operacoes_em_nome_do_usuario() {
+    let servidor = inicia().await;
+    let atendente = conecta(&servidor, Some("token-atendente")).await;
+
+    let origem = atendente
+        .cadastra(novo_cliente("Jose da Silva", false, CPF))
+        .await
+        .unwrap();
+    let destino = atendente
+        .cadastra(novo_cliente("José da Silva", false, CPF))
+        .await
+        .unwrap();
+
+    let mesclado = atendente.mescla(origem.id, destino.id).await.unwrap();
+    assert_eq!(mesclado.docto, CPF_MASCARADO);
+
+    let logs = servidor
+        .repo
+        .lista_log("cliente".to_string())
+        .await
+        .unwrap();
+    let mescla = logs.iter().find(|l| l.usuario != USUARIO_SISTEMA).unwrap();
+    assert_eq!(mescla.usuario, "atendente");
+    assert_eq!(mescla.registro, Some(destino.id));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn acesso_total_sem_tokens_configurados() {
+    let servidor = common::inicia(Backend::Memoria).await.unwrap();
+    let minerva = conecta(&servidor, None).await;
+
+    let cadastrado = minerva
+        .cadastra(novo_cliente("Fulano", false, CPF))
+        .await
+        .unwrap();
+    assert_eq!(minerva.consulta(cadastrado.id).await.unwrap().docto, CPF);
+}
@@ -0,0 +1,479 @@
+// cifra.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Testes da cifragem de documentos. As operações de cifragem são testadas
+//! diretamente, e os cenários com repositórios são executados apenas para os
+//! bancos de dados, já que os dados em memória nunca são cifrados.
+
+mod common;
+
+use common::{Backend, Servidor};
+use minerva_lite::cifra::{Chave, ChaveIndice, Cifra, ErroCifra, PREFIXO};
+use minerva_lite::model::cliente::NovoCliente;
+use minerva_lite::model::duplicado::{Mescla, MotivoDuplicidade};
+use minerva_lite::repository::RepoError;
+use minerva_lite::{DoctoClienteRequest, IdClienteRequest, NovoClienteRequest};
+use tonic::Code;
+
+/// Inicia o servidor de um cenário, encerrando o teste caso o banco de dados
+/// não esteja disponível.
+macro_rules! inicia {
+    ($backend:expr, $cifra:expr) => {
+        match common::inicia_com_cifra($backend, $cifra).await {
+            Some(servidor) => servidor,
+            None => return,
+        }
+    };
+}
+
+/// Gera um teste para cada banco de dados, a partir das funções de cenário
+/// informadas.
+macro_rules! cenarios {
+    ($($cenario:ident),* $(,)?) => {
+        mod postgres {
+            $(
+                #[tokio::test(flavor = "multi_thread")]
+                async fn $cenario() {
+                    super::$cenario(super::Backend::Postgres).await
+                }
+            )*
+        }
+
+        #[cfg(feature = "sqlite")]
+        mod sqlite {
+            $(
+                #[tokio::test(flavor = "multi_thread")]
+                async fn $cenario() {
+                    super::$cenario(super::Backend::Sqlite).await
+                }
+            )*
+        }
+    };
+}
+
+cenarios!(
+    documentos_cifrados_no_banco,
+    detecta_duplicados_sem_indice,
+    consulta_docto_pelo_indice_cego,
+    recifra_documentos,
+);
+
+fn chave(byte: u8) -> Chave {
+    Chave::new(&[byte; 32])
+}
+
+fn indice(byte: u8) -> ChaveIndice {
+    ChaveIndice::new(&[byte; 32])
+}
+
+/// Cria uma cifra com as chaves informadas, sempre com a mesma chave do
+/// índice cego.
+fn cifra(atual: Option<u8>, antigas: &[u8]) -> Cifra {
+    Cifra::new(
+        atual.map(chave),
+        antigas.iter().copied().map(chave).collect(),
+    )
+    .com_indice(indice(9))
+}
+
+#[test]
+fn sem_chave_nao_cifra() {
+    let cifra = Cifra::default();
+    assert_eq!(cifra.cifra("111.111.111-11"), "111.111.111-11");
+    assert_eq!(cifra.decifra("111.111.111-11").unwrap(), "111.111.111-11");
+    assert_eq!(cifra.indice("111.111.111-11"), None);
+    assert!(!cifra.indexa());
+}
+
+#[test]
+fn cifra_e_decifra_documentos() {
+    let cifra = cifra(Some(1), &[]);
+    let cifrado = cifra.cifra("111.111.111-11");
+    assert!(cifrado.starts_with(&format!("{}{}:", PREFIXO, chave(1).id())));
+    assert!(!cifrado.contains("111"));
+    // Cada valor é cifrado com um nonce diferente.
+    assert_ne!(cifra.cifra("111.111.111-11"), cifrado);
+    assert_eq!(cifra.decifra(&cifrado).unwrap(), "111.111.111-11");
+
+    // Valores vazios ou sem cifragem são mantidos.
+    assert_eq!(cifra.cifra(""), "");
+    assert_eq!(cifra.decifra("111.111.111-11").unwrap(), "111.111.111-11");
+}
+
+#[test]
+fn indice_cego_desconsidera_pontuacao() {
+    let cifra1 = cifra(Some(1), &[]);
+    let indice = cifra1.indice("111.111.111-11").unwrap();
+    assert_eq!(cifra1.indice("11111111111"), Some(indice.clone()));
+    assert_ne!(cifra1.indice("222.222.222-22"), Some(indice.clone()));
+    assert_eq!(cifra1.indice("..."), None);
+
+    // O índice depende apenas de sua própria chave, e não muda com a rotação
+    // da chave de cifragem.
+    assert_eq!(
+        cifra(Some(2), &[1]).indice("111.111.111-11"),
+        Some(indice.clone())
+    );
+    assert_eq!(
+        cifra(None, &[]).indice("111.111.111-11"),
+        Some(indice.clone())
+    );
+    let outro_indice = Cifra::new(Some(chave(1)), vec![]).com_indice(self::indice(8));
+    assert_ne!(outro_indice.indice("111.111.111-11"), Some(indice));
+}
+
+#[test]
+fn decifra_rejeita_chaves_e_valores_invalidos() {
+    let cifrado = cifra(Some(1), &[]).cifra("111.111.111-11");
+    assert_eq!(
+        cifra(Some(2), &[]).decifra(&cifrado),
+        Err(ErroCifra::ChaveDesconhecida(chave(1).id().to_string()))
+    );
+    assert_eq!(
+        Cifra::default().decifra(&cifrado),
+        Err(ErroCifra::ChaveDesconhecida(chave(1).id().to_string()))
+    );
+    assert_eq!(
+        cifra(Some(2), &[1]).decifra(&cifrado).unwrap(),
+        "111.111.111-11"
+    );
+
+    let mut adulterado = cifrado.into_bytes();
+    let ultimo = adulterado.len() - 2;
+    adulterado[ultimo] = if adulterado[ultimo] == b'A' {
+        b'B'
+    } else {
+        b'A'
+    };
+    let adulterado = String::from_utf8(adulterado).unwrap();
+    assert_eq!(
+        cifra(Some(1), &[]).decifra(&adulterado),
+        Err(ErroCifra::ValorInvalido)
+    );
+
+    let sem_chave = format!("{}sem-separador", PREFIXO);
+    assert_eq!(
+        cifra(Some(1), &[]).decifra(&sem_chave),
+        Err(ErroCifra::ValorInvalido)
+    );
+}
+
+#[test]
+fn le_chaves_em_base64() {
+    let chave: Chave = base64::encode([1u8; 32]).parse().unwrap();
+    assert_eq!(chave.id(), self::chave(1).id());
+    assert_eq!(
+        base64::encode([1u8; 16]).parse::<Chave>().err(),
+        Some(ErroCifra::ChaveInvalida)
+    );
+    assert_eq!(
+        "não é base64".parse::<Chave>().err(),
+        Some(ErroCifra::ChaveInvalida)
+    );
+    // A chave em si nunca é mostrada.
+    assert_eq!(
+        format!("{:?}", chave),
+        format!("Chave {{ id: {:?} }}", chave.id())
+    );
+
+    let indice: ChaveIndice = base64::encode([9u8; 32]).parse().unwrap();
+    assert_eq!(format!("{:?}", indice), "ChaveIndice");
+    assert_eq!(
+        base64::encode([9u8; 16]).parse::<ChaveIndice>().err(),
+        Some(ErroCifra::ChaveInvalida)
+    );
+}
+
+#[test]
+fn le_chaves_do_ambiente() {
+    std::env::remove_var("DOCTO_PREVIOUS_KEYS");
+    std::env::remove_var("DOCTO_INDEX_KEY");
+    std::env::set_var("DOCTO_ENCRYPTION_KEY", base64::encode([1u8; 32]));
+    // A cifragem requer a chave do índice cego.
+    assert_eq!(Cifra::from_env().err(), Some(ErroCifra::IndiceAusente));
+
+    std::env::set_var("DOCTO_INDEX_KEY", base64::encode([9u8; 32]));
+    let lida = Cifra::from_env().unwrap();
+    assert!(lida.indexa());
+    assert_eq!(
+        lida.indice("111.111.111-11"),
+        cifra(None, &[]).indice("111.111.111-11")
+    );
+
+    // O índice pode ser usado sem a cifragem.
+    std::env::remove_var("DOCTO_ENCRYPTION_KEY");
+    let lida = Cifra::from_env().unwrap();
+    assert!(lida.indexa());
+    assert_eq!(lida.cifra("111.111.111-11"), "111.111.111-11");
+    std::env::remove_var("DOCTO_INDEX_KEY");
+}
+
+#[test]
+fn recifra_apenas_documentos_desatualizados() {
+    let antiga = cifra(Some(1), &[]);
+    let cifrado = antiga.cifra("111.111.111-11");
+    let indice = antiga.indice("111.111.111-11");
+    assert_eq!(antiga.recifra(&cifrado, indice.as_deref()).unwrap(), None);
+    assert_eq!(antiga.recifra("", None).unwrap(), None);
+
+    // Documentos sem índice são atualizados.
+    let (docto, novo_indice) = antiga.recifra(&cifrado, None).unwrap().unwrap();
+    assert_eq!(antiga.decifra(&docto).unwrap(), "111.111.111-11");
+    assert_eq!(novo_indice, indice);
+
+    // Documentos cifrados com chaves antigas, ou sem cifragem, são cifrados
+    // com a chave atual.
+    let nova = cifra(Some(2), &[1]);
+    for anterior in [cifrado.as_str(), "111.111.111-11"] {
+        let (docto, novo_indice) = nova.recifra(anterior, None).unwrap().unwrap();
+        assert!(docto.starts_with(&format!("{}{}:", PREFIXO, chave(2).id())));
+        assert_eq!(nova.decifra(&docto).unwrap(), "111.111.111-11");
+        assert_eq!(novo_indice, nova.indice("111.111.111-11"));
+    }
+
+    // A rotação não altera o índice, e documentos sem índice são indexados.
+    let recifrado = nova.cifra("111.111.111-11");
+    assert_eq!(nova.recifra(&recifrado, indice.as_deref()).unwrap(), None);
+
+    // Sem uma chave atual, os documentos são decifrados.
+    let decifra = cifra(None, &[1]);
+    assert_eq!(
+        decifra.recifra(&cifrado, indice.as_deref()).unwrap(),
+        Some(("111.111.111-11".to_string(), indice.clone()))
+    );
+    assert_eq!(
+        decifra
+            .recifra("111.111.111-11", indice.as_deref())
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        decifra.recifra("111.111.111-11", None).unwrap(),
+        Some(("111.111.111-11".to_string(), indice.clone()))
+    );
+    assert_eq!(
+        Cifra::default().recifra("111.111.111-11", None).unwrap(),
+        None
+    );
+    assert_eq!(
+        cifra(Some(2), &[]).recifra(&cifrado, None),
+        Err(ErroCifra::ChaveDesconhecida(chave(1).id().to_string()))
+    );
+}
+
+async fn cadastra_com_docto(servidor: &mut Servidor, nome: &str, docto: &str) -> i32 {
+    servidor
+        .clientes
+        .cadastra(NovoClienteRequest {
+            nome: nome.to_string(),
+            pj: false,
+            docto: docto.to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .id
+}
+
+/// Consulta o documento de um cliente em um repositório com as chaves
+/// informadas, sobre o banco de dados do servidor.
+async fn docto(servidor: &Servidor, cifra: Cifra, id: i32) -> Result<String, RepoError> {
+    servidor
+        .repositorio(cifra)
+        .consulta(id)
+        .await
+        .map(|c| c.docto)
+}
+
+async fn documentos_cifrados_no_banco(backend: Backend) {
+    let mut servidor = inicia!(backend, cifra(Some(1), &[]));
+    let id = cadastra_com_docto(&mut servidor, "José da Silva", "111.111.111-11").await;
+    let vazio = cadastra_com_docto(&mut servidor, "Maria Souza", "").await;
+
+    let cliente = servidor
+        .clientes
+        .consulta(IdClienteRequest { id })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(cliente.docto, "111.111.111-11");
+    assert_eq!(
+        docto(&servidor, cifra(Some(1), &[]), id).await.unwrap(),
+        "111.111.111-11"
+    );
+
+    // Sem a chave, o documento não pode ser lido.
+    assert!(matches!(
+        docto(&servidor, Cifra::default(), id).await,
+        Err(RepoError::Interno(_))
+    ));
+    assert!(matches!(
+        docto(&servidor, cifra(Some(2), &[]), id).await,
+        Err(RepoError::Interno(_))
+    ));
+    assert_eq!(docto(&servidor, Cifra::default(), vazio).await.unwrap(), "");
+}
+
+fn novo_cliente(nome: &str, docto: &str) -> NovoCliente {
+    NovoCliente {
+        nome: nome.to_string(),
+        docto: docto.to_string(),
+        ativo: true,
+        ..Default::default()
+    }
+}
+
+async fn detecta_duplicados_sem_indice(backend: Backend) {
+    // Clientes cadastrados antes da definição das chaves não possuem índice.
+    let mut servidor = inicia!(backend, Cifra::default());
+    let maria = cadastra_com_docto(&mut servidor, "Maria Souza", "111.111.111-11").await;
+    cadastra_com_docto(&mut servidor, "Fulano", "222.222.222-22").await;
+
+    let repo = servidor.repositorio(cifra(Some(1), &[]));
+    let pedro = repo
+        .cadastra(novo_cliente("Pedro Santos", "11111111111"))
+        .await
+        .unwrap()
+        .id;
+
+    // Os clientes sem índice são comparados com os clientes indexados.
+    let grupos = repo.detecta_duplicados().await.unwrap();
+    assert_eq!(grupos.len(), 1);
+    assert_eq!(grupos[0].motivo, MotivoDuplicidade::Documento);
+    let clientes: Vec<(i32, &str)> = grupos[0]
+        .clientes
+        .iter()
+        .map(|c| (c.id, c.docto.as_str()))
+        .collect();
+    assert_eq!(
+        clientes,
+        [(maria, "111.111.111-11"), (pedro, "11111111111")]
+    );
+    assert_eq!(
+        repo.consulta_docto("111.111.111-11".to_string())
+            .await
+            .unwrap()
+            .id,
+        maria
+    );
+
+    // O índice é único: os duplicados devem ser mesclados antes da
+    // indexação dos clientes restantes.
+    assert!(matches!(
+        repo.recifra_doctos().await,
+        Err(RepoError::Conflito(_))
+    ));
+    repo.mescla(
+        Mescla {
+            origem_id: pedro,
+            destino_id: maria,
+        },
+        "teste".to_string(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(repo.recifra_doctos().await.unwrap(), 2);
+    assert!(repo.detecta_duplicados().await.unwrap().is_empty());
+    assert_eq!(
+        repo.cadastra(novo_cliente("Pedro Santos", "11111111111"))
+            .await
+            .err(),
+        Some(RepoError::Conflito(
+            "Documento já cadastrado para outro cliente".to_string()
+        ))
+    );
+}
+
+async fn consulta_docto_pelo_indice_cego(backend: Backend) {
+    let mut servidor = inicia!(backend, cifra(Some(1), &[]));
+    let maria = cadastra_com_docto(&mut servidor, "Maria Souza", "111.111.111-11").await;
+    cadastra_com_docto(&mut servidor, "Fulano", "222.222.222-22").await;
+
+    let status = servidor
+        .clientes
+        .cadastra(NovoClienteRequest {
+            nome: "Pedro Santos".to_string(),
+            pj: false,
+            docto: "11111111111".to_string(),
+        })
+        .await
+        .expect_err("Documento duplicado foi aceito");
+    assert_eq!(status.code(), Code::AlreadyExists);
+
+    let cliente = servidor
+        .clientes
+        .consulta_docto(DoctoClienteRequest {
+            docto: "11111111111".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(cliente.id, maria);
+    assert_eq!(cliente.docto, "111.111.111-11");
+
+    // A rotação da chave de cifragem não altera os índices.
+    let repo = servidor.repositorio(cifra(Some(2), &[1]));
+    assert_eq!(repo.recifra_doctos().await.unwrap(), 2);
+    let repo = servidor.repositorio(cifra(Some(2), &[]));
+    assert_eq!(
+        repo.consulta_docto("111.111.111-11".to_string())
+            .await
+            .unwrap()
+            .id,
+        maria
+    );
+    assert_eq!(
+        repo.consulta_docto("333.333.333-33".to_string())
+            .await
+            .err(),
+        Some(RepoError::NaoEncontrado)
+    );
+}
+
+async fn recifra_documentos(backend: Backend) {
+    let mut servidor = inicia!(backend, Cifra::default());
+    let a = cadastra_com_docto(&mut servidor, "José da Silva", "111.111.111-11").await;
+    let b = cadastra_com_docto(&mut servidor, "Maria Souza", "222.222.222-22").await;
+    cadastra_com_docto(&mut servidor, "Fulano", "").await;
+
+    // Documentos sem cifragem são cifrados; documentos vazios são mantidos.
+    let repo = servidor.repositorio(cifra(Some(1), &[]));
+    assert_eq!(repo.recifra_doctos().await.unwrap(), 2);
+    assert_eq!(repo.recifra_doctos().await.unwrap(), 0);
+    assert!(docto(&servidor, Cifra::default(), a).await.is_err());
+
+    // A rotação para uma nova chave requer a chave anterior.
+    let repo = servidor.repositorio(cifra(Some(2), &[]));
+    assert!(matches!(
+        repo.recifra_doctos().await,
+        Err(RepoError::Interno(_))
+    ));
+    let repo = servidor.repositorio(cifra(Some(2), &[1]));
+    assert_eq!(repo.recifra_doctos().await.unwrap(), 2);
+    assert_eq!(
+        docto(&servidor, cifra(Some(2), &[]), a).await.unwrap(),
+        "111.111.111-11"
+    );
+    assert!(docto(&servidor, cifra(Some(1), &[]), b).await.is_err());
+
+    // Sem uma chave atual, os documentos são decifrados.
+    let repo = servidor.repositorio(cifra(None, &[2]));
+    assert_eq!(repo.recifra_doctos().await.unwrap(), 2);
+    assert_eq!(
+        docto(&servidor, Cifra::default(), b).await.unwrap(),
+        "222.222.222-22"
+    );
+}
@@ -0,0 +1,1516 @@
+// clientes.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Testes de integração do serviço de clientes. Cada cenário é executado para
+//! todos os repositórios disponíveis.
+
+mod common;
+
+use common::{Backend, Servidor};
+use minerva_lite::controller::cliente::CLIENTE_PAGE_SIZE;
+use minerva_lite::model::duplicado::MotivoDuplicidade;
+use minerva_lite::model::logdb::{OperacaoLog, USUARIO_SISTEMA};
+use minerva_lite::{
+    AtualizaContatoRequest, BuscaRequest, CepRequest, ClienteResponse, DadosClienteMessage,
+    DoctoClienteRequest, IdClienteRequest, IdContatoRequest, IdEnderecoRequest,
+    LimiteCreditoRequest, ListaDetalhadaRequest, MesclaRequest, MovimentoCreditoRequest,
+    NovoClienteRequest, NovoContatoRequest, NovoEnderecoRequest, TipoEnderecoMessage,
+};
+use tokio_stream::StreamExt;
+use tonic::Code;
+
+/// Inicia o servidor de um cenário, encerrando o teste caso o banco de dados
+/// não esteja disponível.
+macro_rules! inicia {
+    ($backend:expr) => {
+        match common::inicia($backend).await {
+            Some(servidor) => servidor,
+            None => return,
+        }
+    };
+}
+
+/// Gera um teste para cada repositório, a partir das funções de cenário
+/// informadas. Os testes usam o runtime multi-thread, exigido pela pool de
+/// conexões com o banco de dados.
+macro_rules! cenarios {
+    ($($cenario:ident),* $(,)?) => {
+        mod memoria {
+            $(
+                #[tokio::test(flavor = "multi_thread")]
+                async fn $cenario() {
+                    super::$cenario(super::Backend::Memoria).await
+                }
+            )*
+        }
+
+        mod postgres {
+            $(
+                #[tokio::test(flavor = "multi_thread")]
+                async fn $cenario() {
+                    super::$cenario(super::Backend::Postgres).await
+                }
+            )*
+        }
+
+        #[cfg(feature = "sqlite")]
+        mod sqlite {
+            $(
+                #[tokio::test(flavor = "multi_thread")]
+                async fn $cenario() {
+                    super::$cenario(super::Backend::Sqlite).await
+                }
+            )*
+        }
+    };
+}
+
+cenarios!(
+    cadastra_retorna_cliente_completo,
+    consulta_cliente_cadastrado,
+    consulta_cliente_inexistente,
+    consulta_por_documento,
+    cadastra_rejeita_docto_invalido,
+    lista_tabela_vazia,
+    lista_inclui_primeiro_cliente,
+    lista_exatamente_uma_pagina,
+    lista_varias_paginas,
+    deleta_cliente,
+    deleta_cliente_inexistente,
+    consulta_detalhada_inclui_enderecos,
+    consulta_detalhada_cliente_inexistente,
+    lista_detalhada_agrupa_enderecos,
+    deleta_cliente_remove_enderecos,
+    cadastra_endereco_normaliza_dados,
+    cadastra_endereco_rejeita_dados_invalidos,
+    endereco_principal_unico_por_tipo,
+    catalogo_tipos_endereco,
+    consulta_cep_importado,
+    importa_ceps_ignora_linhas_invalidas,
+    cadastra_contato_normaliza_dados,
+    cadastra_contato_rejeita_dados_invalidos,
+    atualiza_contato,
+    deleta_contato,
+    detalhes_do_cliente_incluem_contatos,
+    limite_credito_bloqueia_cliente,
+    credito_rejeita_dados_invalidos,
+    busca_tolera_acentos_e_erros,
+    busca_respeita_limite,
+    busca_rejeita_dados_invalidos,
+    detecta_duplicados_por_documento_e_nome,
+    mescla_transfere_dados_e_registra_log,
+    mescla_rejeita_dados_invalidos,
+    exporta_dados_do_cliente,
+    anonimiza_apaga_dados_pessoais,
+);
+
+async fn cadastra_retorna_cliente_completo(backend: Backend) {
+    let mut servidor = inicia!(backend);
+
+    let cliente = servidor.cadastra("Fulano").await;
+    assert!(cliente.id > 0);
+    assert_eq!(cliente.tipo, 0);
+    assert_eq!(cliente.nome, "Fulano");
+    assert!(!cliente.pj);
+    assert_eq!(cliente.docto, "000.000.000-00");
+    assert!(cliente.ativo);
+    assert!(!cliente.bloqueado);
+
+    let outro = servidor.cadastra("Ciclano").await;
+    assert!(outro.id > cliente.id);
+}
+
+async fn consulta_cliente_cadastrado(backend: Backend) {
+    let mut servidor = inicia!(backend);
+    let cadastrado = servidor.cadastra("Fulano").await;
+
+    let consultado = servidor
+        .clientes
+        .consulta(IdClienteRequest { id: cadastrado.id })
+        .await
+        .expect("Cliente não encontrado")
+        .into_inner();
+
+    assert_eq!(consultado, cadastrado);
+}
+
+async fn consulta_cliente_inexistente(backend: Backend) {
+    let mut servidor = inicia!(backend);
+
+    let status = servidor
+        .clientes
+        .consulta(IdClienteRequest { id: 42 })
+        .await
+        .expect_err("Cliente inexistente foi encontrado");
+
+    assert_eq!(status.code(), Code::NotFound);
+}
+
+async fn consulta_por_documento(backend: Backend) {
+    let mut servidor = inicia!(backend);
+    cadastra_com_docto(&mut servidor, "Fulano", "222.222.222-22").await;
+    let jose = cadastra_com_docto(&mut servidor, "José da Silva", "111.111.111-11").await;
+    cadastra_com_docto(&mut servidor, "Jose da Silva", "11111111111").await;
+
+    // A pontuação é desconsiderada, e o cliente de menor ID é retornado.
+    let cliente = servidor
+        .clientes
+        .consulta_docto(DoctoClienteRequest {
+            docto: "111111111-11".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(cliente.id, jose);
+    assert_eq!(cliente.docto, "111.111.111-11");
+
+    let status = servidor
+        .clientes
+        .consulta_docto(DoctoClienteRequest {
+            docto: "333.333.333-33".to_string(),
+        })
+        .await
+        .expect_err("Documento inexistente foi encontrado");
+    assert_eq!(status.code(), Code::NotFound);
+
+    let status = servidor
+        .clientes
+        .consulta_docto(DoctoClienteRequest {
+            docto: " ".to_string(),
+        })
+        .await
+        .expect_err("Documento vazio foi aceito");
+    assert_eq!(status.code(), Code::InvalidArgument);
+}
+
+async fn cadastra_rejeita_docto_invalido(backend: Backend) {
+    let mut servidor = inicia!(backend);
+
+    // Documentos com o prefixo de valores cifrados não podem ser gravados
+    // como texto, já que não poderiam ser decifrados depois.
+    let invalidos = ["cifra:v1:dead:AAAA", "123.456", "abc.def.ghi-jk", "1234"];
+    for docto in invalidos {
+        let status = servidor
+            .clientes
+            .cadastra(NovoClienteRequest {
+                nome: "Fulano".to_string(),
+                pj: false,
+                docto: docto.to_string(),
+            })
+            .await
+            .expect_err("Documento inválido foi aceito");
+        assert_eq!(status.code(), Code::InvalidArgument, "{}", docto);
+    }
+
+    // Nenhum cliente foi gravado, e a listagem continua funcionando.
+    assert!(servidor.lista().await.is_empty());
+
+    let id = cadastra_com_docto(&mut servidor, "Fulano", " 00.000.000/0001-00 ").await;
+    let cliente = servidor
+        .clientes
+        .consulta(IdClienteRequest { id })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(cliente.docto, "00.000.000/0001-00");
+}
+
+async fn lista_tabela_vazia(backend: Backend) {
+    let mut servidor = inicia!(backend);
+    assert!(servidor.lista().await.is_empty());
+}
+
+async fn lista_inclui_primeiro_cliente(backend: Backend) {
+    let mut servidor = inicia!(backend);
+    let ids = servidor.cadastra_varios(3).await;
+
+    let paginas = servidor.lista().await;
+    assert_eq!(paginas, vec![ids]);
+}
+
+async fn lista_exatamente_uma_pagina(backend: Backend) {
+    let mut servidor = inicia!(backend);
+    let ids = servidor.cadastra_varios(CLIENTE_PAGE_SIZE as usize).await;
+
+    let paginas = servidor.lista().await;
+    assert_eq!(paginas, vec![ids]);
+}
+
+async fn lista_varias_paginas(backend: Backend) {
+    let mut servidor = inicia!(backend);
+    let ids = servidor.cadastra_varios(CLIENTE_PAGE_SIZE as usize + 1).await;
+
+    let paginas = servidor.lista().await;
+    assert_eq!(paginas.len(), 2);
+    assert_eq!(paginas[0].len(), CLIENTE_PAGE_SIZE as usize);
+    assert_eq!(paginas[1].len(), 1);
+    assert_eq!(paginas.concat(), ids);
+}
+
+async fn deleta_cliente(backend: Backend) {
+    let mut servidor = inicia!(backend);
+    let ids = servidor.cadastra_varios(2).await;
+
+    servidor
+        .clientes
+        .deleta(IdClienteRequest { id: ids[0] })
+        .await
+        .expect("Impossível remover cliente");
+
+    let status = servidor
+        .clientes
+        .consulta(IdClienteRequest { id: ids[0] })
+        .await
+        .expect_err("Cliente removido foi encontrado");
+    assert_eq!(status.code(), Code::NotFound);
+
+    assert_eq!(servidor.lista().await, vec![vec![ids[1]]]);
+}
+
+async fn deleta_cliente_inexistente(backend: Backend) {
+    let mut servidor = inicia!(backend);
+
+    servidor
+        .clientes
+        .deleta(IdClienteRequest { id: 42 })
+        .await
+        .expect("Remoção de cliente inexistente deveria ser ignorada");
+}
+
+/// Recebe todas as páginas da listagem detalhada de clientes, retornando,
+/// para cada cliente, seu ID e os logradouros de seus endereços.
+async fn lista_detalhada(
+    servidor: &mut Servidor,
+    incluir_enderecos: bool,
+) -> Vec<Vec<(i32, Vec<String>)>> {
+    let mut stream = servidor
+        .clientes
+        .lista_detalhada(ListaDetalhadaRequest {
+            incluir_enderecos,
+            ..Default::default()
+        })
+        .await
+        .expect("Impossível listar clientes")
+        .into_inner();
+
+    let mut paginas = vec![];
+    while let Some(pagina) = stream.next().await {
+        let pagina = pagina.expect("Erro ao receber página de clientes");
+        paginas.push(
+            pagina
+                .clientes
+                .into_iter()
+                .map(|c| {
+                    let logradouros = c.enderecos.into_iter().map(|e| e.logradouro).collect();
+                    (c.cliente.unwrap().id, logradouros)
+                })
+                .collect(),
+        );
+    }
+    paginas
+}
+
+async fn consulta_detalhada_inclui_enderecos(backend: Backend) {
+    let mut servidor = inicia!(backend);
+    let ids = servidor.cadastra_varios(2).await;
+    servidor.cadastra_endereco(ids[0], "Rua A").await;
+    servidor.cadastra_endereco(ids[1], "Rua B").await;
+    servidor.cadastra_endereco(ids[0], "Rua C").await;
+
+    let detalhado = servidor
+        .clientes
+        .consulta_detalhada(IdClienteRequest { id: ids[0] })
+        .await
+        .expect("Cliente não encontrado")
+        .into_inner();
+
+    assert_eq!(detalhado.cliente.unwrap().id, ids[0]);
+    let logradouros: Vec<_> = detalhado.enderecos.iter().map(|e| &e.logradouro).collect();
+    assert_eq!(logradouros, ["Rua A", "Rua C"]);
+    assert!(detalhado.enderecos.iter().all(|e| e.cliente_id == ids[0]));
+    assert_eq!(detalhado.enderecos[0].complemento, None);
+
+    let sem_enderecos = servidor.cadastra("Beltrano").await;
+    let detalhado = servidor
+        .clientes
+        .consulta_detalhada(IdClienteRequest {
+            id: sem_enderecos.id,
+        })
+        .await
+        .expect("Cliente não encontrado")
+        .into_inner();
+    assert_eq!(detalhado.cliente, Some(sem_enderecos));
+    assert!(detalhado.enderecos.is_empty());
+}
+
+async fn consulta_detalhada_cliente_inexistente(backend: Backend) {
+    let mut servidor = inicia!(backend);
+
+    let status = servidor
+        .clientes
+        .consulta_detalhada(IdClienteRequest { id: 42 })
+        .await
+        .expect_err("Cliente inexistente foi encontrado");
+
+    assert_eq!(status.code(), Code::NotFound);
+}
+
+async fn lista_detalhada_agrupa_enderecos(backend: Backend) {
+    let mut servidor = inicia!(backend);
+    let ids = servidor.cadastra_varios(CLIENTE_PAGE_SIZE as usize + 1).await;
+    let ultimo = *ids.last().unwrap();
+    servidor.cadastra_endereco(ids[1], "Rua A").await;
+    servidor.cadastra_endereco(ultimo, "Rua B").await;
+    servidor.cadastra_endereco(ids[1], "Rua C").await;
+
+    let paginas = lista_detalhada(&mut servidor, true).await;
+    assert_eq!(paginas.len(), 2);
+    assert_eq!(paginas[0].len(), CLIENTE_PAGE_SIZE as usize);
+    assert_eq!(paginas[0][0], (ids[0], vec![]));
+    assert_eq!(
+        paginas[0][1],
+        (ids[1], vec!["Rua A".into(), "Rua C".into()])
+    );
+    assert_eq!(paginas[1], vec![(ultimo, vec!["Rua B".into()])]);
+
+    let paginas = lista_detalhada(&mut servidor, false).await;
+    let esperado: Vec<_> = ids.iter().map(|&id| (id, vec![])).collect();
+    assert_eq!(paginas.concat(), esperado);
+}
+
+async fn deleta_cliente_remove_enderecos(backend: Backend) {
+    let mut servidor = inicia!(backend);
+    let ids = servidor.cadastra_varios(2).await;
+    servidor.cadastra_endereco(ids[0], "Rua A").await;
+    servidor.cadastra_endereco(ids[1], "Rua B").await;
+
+    servidor
+        .clientes
+        .deleta(IdClienteRequest { id: ids[0] })
+        .await
+        .expect("Impossível remover cliente");
+
+    let paginas = lista_detalhada(&mut servidor, true).await;
+    assert_eq!(paginas, vec![vec![(ids[1], vec!["Rua B".to_string()])]]);
+}
+
+async fn cadastra_endereco_normaliza_dados(backend: Backend) {
+    let mut servidor = inicia!(backend);
+    let cliente = servidor.cadastra("Fulano").await;
+
+    let endereco = servidor
+        .clientes
+        .cadastra_endereco(NovoEnderecoRequest {
+            uf: " mg ".to_string(),
+            cep: "39100000".to_string(),
+            complemento: Some("  ".to_string()),
+            principal: true,
+            ..Servidor::novo_endereco(cliente.id, " Rua A ")
+        })
+        .await
+        .expect("Impossível cadastrar endereço")
+        .into_inner();
+
+    assert!(endereco.id > 0);
+    assert_eq!(endereco.cliente_id, cliente.id);
+    assert_eq!(endereco.logradouro, "Rua A");
+    assert_eq!(endereco.uf, "MG");
+    assert_eq!(endereco.cep.as_deref(), Some("39100-000"));
+    assert_eq!(endereco.complemento, None);
+    assert!(endereco.principal);
+
+    let detalhado = servidor
+        .clientes
+        .consulta_detalhada(IdClienteRequest { id: cliente.id })
+        .await
+        .expect("Cliente não encontrado")
+        .into_inner();
+    assert_eq!(detalhado.enderecos, vec![endereco]);
+}
+
+async fn cadastra_endereco_rejeita_dados_invalidos(backend: Backend) {
+    let mut servidor = inicia!(backend);
+    let cliente = servidor.cadastra("Fulano").await;
+    let valido = || Servidor::novo_endereco(cliente.id, "Rua A");
+
+    let invalidos = [
+        NovoEnderecoRequest {
+            uf: "XX".to_string(),
+            ..valido()
+        },
+        NovoEnderecoRequest {
+            cep: "3910-000".to_string(),
+            ..valido()
+        },
+        NovoEnderecoRequest {
+            cep: "39100-00A".to_string(),
+            ..valido()
+        },
+        NovoEnderecoRequest {
+            cidade: " ".to_string(),
+            ..valido()
+        },
+        NovoEnderecoRequest {
+            tipo: 99,
+            ..valido()
+        },
+        NovoEnderecoRequest {
+            cliente_id: 42,
+            ..valido()
+        },
+    ];
+
+    for dados in invalidos {
+        let status = servidor
+            .clientes
+            .cadastra_endereco(dados.clone())
+            .await
+            .expect_err(&format!("Endereço inválido foi cadastrado: {:?}", dados));
+        assert_eq!(status.code(), Code::InvalidArgument, "{:?}", dados);
+    }
+
+    let paginas = lista_detalhada(&mut servidor, true).await;
+    assert_eq!(paginas, vec![vec![(cliente.id, vec![])]]);
+}
+
+async fn endereco_principal_unico_por_tipo(backend: Backend) {
+    let mut servidor = inicia!(backend);
+    let ids = servidor.cadastra_varios(2).await;
+    let principal = |cliente_id, tipo| NovoEnderecoRequest {
+        tipo,
+        principal: true,
+        ..Servidor::novo_endereco(cliente_id, "Rua A")
+    };
+
+    let cobranca = servidor
+        .clientes
+        .cadastra_endereco(principal(ids[0], 2))
+        .await
+        .expect("Impossível cadastrar endereço principal")
+        .into_inner();
+
+    let status = servidor
+        .clientes
+        .cadastra_endereco(principal(ids[0], 2))
+        .await
+        .expect_err("Segundo endereço principal foi cadastrado");
+    assert_eq!(status.code(), Code::AlreadyExists);
+
+    // Outros tipos, outros clientes e endereços não principais são aceitos.
+    for dados in [
+        principal(ids[0], 3),
+        principal(ids[1], 2),
+        NovoEnderecoRequest {
+            principal: false,
+            ..principal(ids[0], 2)
+        },
+    ] {
+        servidor
+            .clientes
+            .cadastra_endereco(dados)
+            .await
+            .expect("Impossível cadastrar endereço");
+    }
+
+    servidor
+        .clientes
+        .deleta_endereco(IdEnderecoRequest { id: cobranca.id })
+        .await
+        .expect("Impossível remover endereço");
+    servidor
+        .clientes
+        .cadastra_endereco(principal(ids[0], 2))
+        .await
+        .expect("Impossível substituir endereço principal");
+}
+
+async fn catalogo_tipos_endereco(backend: Backend) {
+    let mut servidor = inicia!(backend);
+
+    let tipos = servidor
+        .clientes
+        .lista_tipos_endereco(())
+        .await
+        .expect("Impossível listar tipos de endereço")
+        .into_inner()
+        .tipos;
+    let descricoes: Vec<_> = tipos.iter().map(|t| t.descricao.as_str()).collect();
+    assert_eq!(
+        descricoes,
+        ["Residencial", "Comercial", "Cobrança", "Entrega"]
+    );
+
+    let novo = TipoEnderecoMessage {
+        id: 10,
+        descricao: "Correspondência".to_string(),
+    };
+    let cadastrado = servidor
+        .clientes
+        .cadastra_tipo_endereco(novo.clone())
+        .await
+        .expect("Impossível cadastrar tipo de endereço")
+        .into_inner();
+    assert_eq!(cadastrado, novo);
+
+    let status = servidor
+        .clientes
+        .cadastra_tipo_endereco(novo)
+        .await
+        .expect_err("Tipo de endereço duplicado foi cadastrado");
+    assert_eq!(status.code(), Code::AlreadyExists);
+
+    let status = servidor
+        .clientes
+        .cadastra_tipo_endereco(TipoEnderecoMessage {
+            id: 11,
+            descricao: " ".to_string(),
+        })
+        .await
+        .expect_err("Tipo de endereço sem descrição foi cadastrado");
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    let cliente = servidor.cadastra("Fulano").await;
+    let endereco = servidor
+        .clientes
+        .cadastra_endereco(NovoEnderecoRequest {
+            tipo: 10,
+            ..Servidor::novo_endereco(cliente.id, "Rua A")
+        })
+        .await
+        .expect("Impossível cadastrar endereço com novo tipo")
+        .into_inner();
+    assert_eq!(endereco.tipo, 10);
+}
+
+async fn consulta_cep(
+    servidor: &mut Servidor,
+    cep: &str,
+) -> Result<minerva_lite::CepResponse, Code> {
+    servidor
+        .clientes
+        .consulta_cep(CepRequest {
+            cep: cep.to_string(),
+        })
+        .await
+        .map(|r| r.into_inner())
+        .map_err(|s| s.code())
+}
+
+async fn consulta_cep_importado(backend: Backend) {
+    let mut servidor = inicia!(backend);
+
+    let resumo = servidor
+        .importa_ceps(
+            "cep,logradouro,bairro,cidade,uf\n\
+             39100-000,,,Diamantina,mg\n\
+             01001000,Praça da Sé,Sé,São Paulo,SP\n",
+        )
+        .await;
+    assert_eq!(resumo.importados, 2);
+    assert!(resumo.ignoradas.is_empty());
+
+    let cep = consulta_cep(&mut servidor, "01001-000")
+        .await
+        .expect("Impossível consultar CEP");
+    assert_eq!(cep.cep, "01001-000");
+    assert_eq!(cep.logradouro, "Praça da Sé");
+    assert_eq!(cep.bairro, "Sé");
+    assert_eq!(cep.cidade, "São Paulo");
+    assert_eq!(cep.uf, "SP");
+
+    let cep = consulta_cep(&mut servidor, " 39100000 ")
+        .await
+        .expect("Impossível consultar CEP sem hífen");
+    assert_eq!(cep.cep, "39100-000");
+    assert_eq!(cep.logradouro, "");
+    assert_eq!(cep.uf, "MG");
+
+    assert_eq!(
+        consulta_cep(&mut servidor, "99999-999").await,
+        Err(Code::NotFound)
+    );
+    assert_eq!(
+        consulta_cep(&mut servidor, "1234").await,
+        Err(Code::InvalidArgument)
+    );
+}
+
+async fn importa_ceps_ignora_linhas_invalidas(backend: Backend) {
+    let mut servidor = inicia!(backend);
+
+    let resumo = servidor
+        .importa_ceps(
+            "uf,cidade,cep\n\
+             MG,Diamantina,39100-000\n\
+             XX,Lugar Nenhum,00000-001\n\
+             MG,,39100-001\n\
+             MG,Diamantina\n\
+             MG,Datas,39100-000\n",
+        )
+        .await;
+    let linhas: Vec<_> = resumo.ignoradas.iter().map(|l| l.linha).collect();
+    assert_eq!(linhas, [3, 4, 5]);
+    assert_eq!(resumo.importados, 1);
+
+    // CEPs repetidos, no mesmo arquivo ou em importações seguintes, são
+    // substituídos pelos dados mais recentes.
+    let cep = consulta_cep(&mut servidor, "39100-000").await.unwrap();
+    assert_eq!(cep.cidade, "Datas");
+
+    servidor
+        .importa_ceps("cep,cidade,uf\n39100-000,Diamantina,MG\n")
+        .await;
+    let cep = consulta_cep(&mut servidor, "39100-000").await.unwrap();
+    assert_eq!(cep.cidade, "Diamantina");
+
+    let erro = minerva_lite::importacao::importa_ceps(
+        &*servidor.repo,
+        "cep,cidade\n39100-000,Diamantina\n".as_bytes(),
+        b',',
+    )
+    .await
+    .expect_err("Arquivo sem coluna obrigatória foi importado");
+    assert!(matches!(
+        erro,
+        minerva_lite::importacao::ErroImportacao::Formato(_)
+    ));
+}
+
+/// Tipos de contato, como definidos no protobuf.
+const TELEFONE: i32 = 0;
+const EMAIL: i32 = 1;
+
+fn novo_contato(cliente_id: i32, tipo: i32, valor: &str) -> NovoContatoRequest {
+    NovoContatoRequest {
+        cliente_id,
+        tipo,
+        valor: valor.to_string(),
+        principal: false,
+        observacao: None,
+    }
+}
+
+async fn cadastra_contato_normaliza_dados(backend: Backend) {
+    let mut servidor = inicia!(backend);
+    let cliente = servidor.cadastra("Fulano").await;
+
+    let celular = servidor
+        .clientes
+        .cadastra_contato(NovoContatoRequest {
+            principal: true,
+            observacao: Some(" Falar com Beltrano ".to_string()),
+            ..novo_contato(cliente.id, TELEFONE, "+55 (38) 99999.0000")
+        })
+        .await
+        .expect("Impossível cadastrar celular")
+        .into_inner();
+    assert_eq!(celular.cliente_id, cliente.id);
+    assert_eq!(celular.tipo, TELEFONE);
+    assert_eq!(celular.valor, "(38) 99999-0000");
+    assert!(celular.principal);
+    assert_eq!(celular.observacao.as_deref(), Some("Falar com Beltrano"));
+
+    let fixo = servidor
+        .clientes
+        .cadastra_contato(novo_contato(cliente.id, TELEFONE, "3835310000"))
+        .await
+        .expect("Impossível cadastrar telefone fixo")
+        .into_inner();
+    assert_eq!(fixo.valor, "(38) 3531-0000");
+
+    let email = servidor
+        .clientes
+        .cadastra_contato(NovoContatoRequest {
+            observacao: Some(" ".to_string()),
+            ..novo_contato(cliente.id, EMAIL, " Fulano.Silva@Exemplo.COM.br ")
+        })
+        .await
+        .expect("Impossível cadastrar e-mail")
+        .into_inner();
+    assert_eq!(email.valor, "fulano.silva@exemplo.com.br");
+    assert_eq!(email.observacao, None);
+
+    let contatos = servidor
+        .clientes
+        .lista_contatos(IdClienteRequest { id: cliente.id })
+        .await
+        .expect("Impossível listar contatos")
+        .into_inner()
+        .contatos;
+    assert_eq!(contatos, [celular, fixo, email]);
+}
+
+async fn cadastra_contato_rejeita_dados_invalidos(backend: Backend) {
+    let mut servidor = inicia!(backend);
+    let cliente = servidor.cadastra("Fulano").await;
+
+    let invalidos = [
+        (TELEFONE, "99999-0000"),
+        (TELEFONE, "(01) 99999-0000"),
+        (TELEFONE, "(38) 89999-0000"),
+        (TELEFONE, "(38) 1531-0000"),
+        (TELEFONE, "+1 (38) 99999-0000"),
+        (TELEFONE, "fulano@exemplo.com"),
+        (EMAIL, "fulano"),
+        (EMAIL, "fulano@exemplo"),
+        (EMAIL, "@exemplo.com"),
+        (EMAIL, "fulano silva@exemplo.com"),
+        (EMAIL, "(38) 99999-0000"),
+        (7, "fulano@exemplo.com"),
+    ];
+    for (tipo, valor) in invalidos {
+        let status = servidor
+            .clientes
+            .cadastra_contato(novo_contato(cliente.id, tipo, valor))
+            .await
+            .expect_err(&format!("Contato inválido foi cadastrado: {}", valor));
+        assert_eq!(status.code(), Code::InvalidArgument, "{}", valor);
+    }
+
+    let status = servidor
+        .clientes
+        .cadastra_contato(novo_contato(42, EMAIL, "fulano@exemplo.com"))
+        .await
+        .expect_err("Contato de cliente inexistente foi cadastrado");
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    let status = servidor
+        .clientes
+        .lista_contatos(IdClienteRequest { id: 42 })
+        .await
+        .expect_err("Contatos de cliente inexistente foram listados");
+    assert_eq!(status.code(), Code::NotFound);
+}
+
+async fn atualiza_contato(backend: Backend) {
+    let mut servidor = inicia!(backend);
+    let cliente = servidor.cadastra("Fulano").await;
+
+    let principal = NovoContatoRequest {
+        principal: true,
+        ..novo_contato(cliente.id, EMAIL, "fulano@exemplo.com")
+    };
+    let primeiro = servidor
+        .clientes
+        .cadastra_contato(principal.clone())
+        .await
+        .expect("Impossível cadastrar e-mail principal")
+        .into_inner();
+    let status = servidor
+        .clientes
+        .cadastra_contato(principal)
+        .await
+        .expect_err("Segundo e-mail principal foi cadastrado");
+    assert_eq!(status.code(), Code::AlreadyExists);
+
+    let segundo = servidor
+        .clientes
+        .cadastra_contato(novo_contato(cliente.id, EMAIL, "outro@exemplo.com"))
+        .await
+        .expect("Impossível cadastrar e-mail")
+        .into_inner();
+
+    let atualizacao = |id: i32, valor: &str, principal: bool| AtualizaContatoRequest {
+        id,
+        tipo: EMAIL,
+        valor: valor.to_string(),
+        principal,
+        observacao: Some("Financeiro".to_string()),
+    };
+
+    let status = servidor
+        .clientes
+        .atualiza_contato(atualizacao(segundo.id, "outro@exemplo.com", true))
+        .await
+        .expect_err("Segundo e-mail principal foi definido");
+    assert_eq!(status.code(), Code::AlreadyExists);
+
+    // O próprio contato principal pode ser atualizado sem conflito.
+    let atualizado = servidor
+        .clientes
+        .atualiza_contato(atualizacao(primeiro.id, "Fulano@Empresa.com", true))
+        .await
+        .expect("Impossível atualizar contato")
+        .into_inner();
+    assert_eq!(atualizado.id, primeiro.id);
+    assert_eq!(atualizado.cliente_id, cliente.id);
+    assert_eq!(atualizado.valor, "fulano@empresa.com");
+    assert!(atualizado.principal);
+    assert_eq!(atualizado.observacao.as_deref(), Some("Financeiro"));
+
+    let status = servidor
+        .clientes
+        .atualiza_contato(atualizacao(primeiro.id, "fulano", true))
+        .await
+        .expect_err("Contato foi atualizado com e-mail inválido");
+    assert_eq!(status.code(), Code::InvalidArgument);
+
+    let status = servidor
+        .clientes
+        .atualiza_contato(atualizacao(42, "fulano@exemplo.com", false))
+        .await
+        .expect_err("Contato inexistente foi atualizado");
+    assert_eq!(status.code(), Code::NotFound);
+
+    let contatos = servidor
+        .clientes
+        .lista_contatos(IdClienteRequest { id: cliente.id })
+        .await
+        .expect("Impossível listar contatos")
+        .into_inner()
+        .contatos;
+    assert_eq!(contatos, [atualizado, segundo]);
+}
+
+async fn deleta_contato(backend: Backend) {
+    let mut servidor = inicia!(backend);
+    let cliente = servidor.cadastra("Fulano").await;
+
+    let contato = servidor
+        .clientes
+        .cadastra_contato(novo_contato(cliente.id, TELEFONE, "(38) 99999-0000"))
+        .await
+        .expect("Impossível cadastrar contato")
+        .into_inner();
+
+    servidor
+        .clientes
+        .deleta_contato(IdContatoRequest { id: contato.id })
+        .await
+        .expect("Impossível remover contato");
+    servidor
+        .clientes
+        .deleta_contato(IdContatoRequest { id: contato.id })
+        .await
+        .expect("Remoção de contato inexistente deveria ser ignorada");
+
+    let contatos = servidor
+        .clientes
+        .lista_contatos(IdClienteRequest { id: cliente.id })
+        .await
+        .expect("Impossível listar contatos")
+        .into_inner()
+        .contatos;
+    assert!(contatos.is_empty());
+}
+
+async fn detalhes_do_cliente_incluem_contatos(backend: Backend) {
+    let mut servidor = inicia!(backend);
+    let ids = servidor.cadastra_varios(3).await;
+    for (id, valor) in [(ids[0], "a@exemplo.com"), (ids[1], "b@exemplo.com")] {
+        servidor
+            .clientes
+            .cadastra_contato(novo_contato(id, EMAIL, valor))
+            .await
+            .expect("Impossível cadastrar contato");
+    }
+    servidor.cadastra_endereco(ids[1], "Rua A").await;
+
+    let detalhado = servidor
+        .clientes
+        .consulta_detalhada(IdClienteRequest { id: ids[1] })
+        .await
+        .expect("Cliente não encontrado")
+        .into_inner();
+    assert_eq!(detalhado.enderecos.len(), 1);
+    let valores: Vec<_> = detalhado.contatos.iter().map(|c| &c.valor).collect();
+    assert_eq!(valores, ["b@exemplo.com"]);
+
+    // Os contatos de um cliente removido também são removidos.
+    servidor
+        .clientes
+        .deleta(IdClienteRequest { id: ids[0] })
+        .await
+        .expect("Impossível remover cliente");
+
+    let lista = |incluir_enderecos, incluir_contatos| ListaDetalhadaRequest {
+        incluir_enderecos,
+        incluir_contatos,
+    };
+    for (req, enderecos, contatos) in [
+        (lista(false, true), 0, 1),
+        (lista(true, false), 1, 0),
+        (lista(true, true), 1, 1),
+    ] {
+        let paginas: Vec<_> = servidor
+            .clientes
+            .lista_detalhada(req)
+            .await
+            .expect("Impossível listar clientes")
+            .into_inner()
+            .map(|pagina| pagina.expect("Erro ao receber página de clientes"))
+            .collect()
+            .await;
+        let resumo: Vec<_> = paginas
+            .iter()
+            .flat_map(|p| &p.clientes)
+            .map(|c| {
+                (
+                    c.cliente.as_ref().unwrap().id,
+                    c.enderecos.len(),
+                    c.contatos.len(),
+                )
+            })
+            .collect();
+        assert_eq!(resumo, [(ids[1], enderecos, contatos), (ids[2], 0, 0)]);
+    }
+}
+
+async fn define_limite(
+    servidor: &mut Servidor,
+    cliente_id: i32,
+    limite: Option<i64>,
+) -> Result<ClienteResponse, Code> {
+    servidor
+        .clientes
+        .define_limite_credito(LimiteCreditoRequest { cliente_id, limite })
+        .await
+        .map(|r| r.into_inner())
+        .map_err(|s| s.code())
+}
+
+async fn movimenta(
+    servidor: &mut Servidor,
+    cliente_id: i32,
+    valor: i64,
+) -> Result<ClienteResponse, Code> {
+    movimenta_com(servidor, cliente_id, valor, false).await
+}
+
+async fn movimenta_com(
+    servidor: &mut Servidor,
+    cliente_id: i32,
+    valor: i64,
+    bloqueia_excedente: bool,
+) -> Result<ClienteResponse, Code> {
+    servidor
+        .clientes
+        .registra_movimento_credito(MovimentoCreditoRequest {
+            cliente_id,
+            valor,
+            bloqueia_excedente,
+        })
+        .await
+        .map(|r| r.into_inner())
+        .map_err(|s| s.code())
+}
+
+async fn limite_credito_bloqueia_cliente(backend: Backend) {
+    let mut servidor = inicia!(backend);
+    let id = servidor.cadastra("Fulano").await.id;
+
+    // Sem limite definido, o cliente nunca é bloqueado.
+    let cliente = movimenta(&mut servidor, id, 5_000).await.unwrap();
+    assert_eq!(cliente.limite_credito, None);
+    assert_eq!(cliente.saldo_devedor, 5_000);
+    assert!(!cliente.bloqueado);
+
+    let cliente = define_limite(&mut servidor, id, Some(10_000))
+        .await
+        .unwrap();
+    assert_eq!(cliente.limite_credito, Some(10_000));
+    assert!(!cliente.bloqueado);
+
+    // O débito que excede o limite, ainda que por um centavo, é recusado
+    // sem ser registrado.
+    let status = servidor
+        .clientes
+        .registra_movimento_credito(MovimentoCreditoRequest {
+            cliente_id: id,
+            valor: 5_001,
+            bloqueia_excedente: false,
+        })
+        .await
+        .expect_err("Débito acima do limite foi registrado");
+    assert_eq!(status.code(), Code::FailedPrecondition);
+    assert!(status.message().contains("Limite de crédito excedido"));
+    let consultado = servidor
+        .clientes
+        .consulta(IdClienteRequest { id })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(consultado.saldo_devedor, 5_000);
+    assert!(!consultado.bloqueado);
+
+    // O débito que atinge exatamente o limite é aceito.
+    let cliente = movimenta(&mut servidor, id, 5_000).await.unwrap();
+    assert_eq!(cliente.saldo_devedor, 10_000);
+    assert!(!cliente.bloqueado);
+
+    // Caso requisitado, o débito que excede o limite é registrado, e bloqueia
+    // o cliente.
+    let cliente = movimenta_com(&mut servidor, id, 1_000, true).await.unwrap();
+    assert_eq!(cliente.saldo_devedor, 11_000);
+    assert!(cliente.bloqueado);
+
+    let status = servidor
+        .clientes
+        .registra_movimento_credito(MovimentoCreditoRequest {
+            cliente_id: id,
+            valor: 1,
+            bloqueia_excedente: true,
+        })
+        .await
+        .expect_err("Débito de cliente bloqueado foi registrado");
+    assert_eq!(status.code(), Code::FailedPrecondition);
+    assert!(status.message().contains("Limite de crédito excedido"));
+
+    let consultado = servidor
+        .clientes
+        .consulta(IdClienteRequest { id })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(consultado.saldo_devedor, 11_000);
+    assert!(consultado.bloqueado);
+
+    // Pagamentos são aceitos, e desbloqueiam o cliente ao respeitar o limite.
+    let cliente = movimenta(&mut servidor, id, -2_000).await.unwrap();
+    assert_eq!(cliente.saldo_devedor, 9_000);
+    assert!(!cliente.bloqueado);
+
+    let cliente = movimenta(&mut servidor, id, 1_000).await.unwrap();
+    assert_eq!(cliente.saldo_devedor, 10_000);
+    assert!(!cliente.bloqueado);
+
+    // Alterações no limite também reavaliam as regras.
+    let cliente = define_limite(&mut servidor, id, Some(5_000)).await.unwrap();
+    assert!(cliente.bloqueado);
+    assert_eq!(
+        movimenta(&mut servidor, id, 1).await,
+        Err(Code::FailedPrecondition)
+    );
+
+    let cliente = define_limite(&mut servidor, id, None).await.unwrap();
+    assert_eq!(cliente.limite_credito, None);
+    assert!(!cliente.bloqueado);
+    assert!(movimenta(&mut servidor, id, 1).await.is_ok());
+}
+
+async fn credito_rejeita_dados_invalidos(backend: Backend) {
+    let mut servidor = inicia!(backend);
+    let id = servidor.cadastra("Fulano").await.id;
+
+    assert_eq!(
+        define_limite(&mut servidor, id, Some(-1)).await,
+        Err(Code::InvalidArgument)
+    );
+    assert_eq!(
+        movimenta(&mut servidor, id, 0).await,
+        Err(Code::InvalidArgument)
+    );
+    assert_eq!(
+        define_limite(&mut servidor, 42, Some(1_000)).await,
+        Err(Code::NotFound)
+    );
+    assert_eq!(
+        movimenta(&mut servidor, 42, 1_000).await,
+        Err(Code::NotFound)
+    );
+}
+
+async fn busca(servidor: &mut Servidor, termo: &str, limite: i32) -> Result<Vec<(i32, f32)>, Code> {
+    servidor
+        .clientes
+        .busca(BuscaRequest {
+            termo: termo.to_string(),
+            limite,
+        })
+        .await
+        .map(|r| {
+            r.into_inner()
+                .clientes
+                .into_iter()
+                .map(|c| (c.cliente.unwrap().id, c.similaridade))
+                .collect()
+        })
+        .map_err(|s| s.code())
+}
+
+async fn busca_tolera_acentos_e_erros(backend: Backend) {
+    let mut servidor = inicia!(backend);
+    let jose = servidor.cadastra("José da Silva").await.id;
+    let josefina = servidor.cadastra("Josefina Pereira").await.id;
+    let maria = servidor.cadastra("Maria Souza").await.id;
+
+    let encontrados = busca(&mut servidor, "Jose", 0).await.unwrap();
+    let ids: Vec<i32> = encontrados.iter().map(|(id, _)| *id).collect();
+    assert_eq!(ids[..2], [jose, josefina]);
+    assert!(!ids.contains(&maria));
+    assert!(encontrados[0].1 > encontrados[1].1);
+    assert!(encontrados.iter().all(|(_, s)| *s > 0.0 && *s <= 1.0));
+
+    // Erros de digitação, acentos e maiúsculas são tolerados.
+    let encontrados = busca(&mut servidor, "JOSÉ DA SLVA", 0).await.unwrap();
+    assert_eq!(encontrados[0].0, jose);
+    let encontrados = busca(&mut servidor, "maria sousa", 0).await.unwrap();
+    assert_eq!(encontrados[0].0, maria);
+
+    assert!(busca(&mut servidor, "Xpto", 0).await.unwrap().is_empty());
+}
+
+async fn busca_respeita_limite(backend: Backend) {
+    let mut servidor = inicia!(backend);
+    servidor.cadastra_varios(15).await;
+
+    assert_eq!(busca(&mut servidor, "cliente", 0).await.unwrap().len(), 10);
+    assert_eq!(busca(&mut servidor, "cliente", 3).await.unwrap().len(), 3);
+    assert_eq!(
+        busca(&mut servidor, "cliente", 1000).await.unwrap().len(),
+        15
+    );
+}
+
+async fn busca_rejeita_dados_invalidos(backend: Backend) {
+    let mut servidor = inicia!(backend);
+    servidor.cadastra("Fulano").await;
+
+    assert_eq!(
+        busca(&mut servidor, "  ", 0).await,
+        Err(Code::InvalidArgument)
+    );
+    assert_eq!(
+        busca(&mut servidor, "Fulano", -1).await,
+        Err(Code::InvalidArgument)
+    );
+}
+
+async fn cadastra_com_docto(servidor: &mut Servidor, nome: &str, docto: &str) -> i32 {
+    servidor
+        .clientes
+        .cadastra(NovoClienteRequest {
+            nome: nome.to_string(),
+            pj: false,
+            docto: docto.to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .id
+}
+
+async fn mescla(
+    servidor: &mut Servidor,
+    origem_id: i32,
+    destino_id: i32,
+) -> Result<ClienteResponse, Code> {
+    servidor
+        .clientes
+        .mescla(MesclaRequest {
+            origem_id,
+            destino_id,
+        })
+        .await
+        .map(|r| r.into_inner())
+        .map_err(|s| s.code())
+}
+
+async fn detecta_duplicados_por_documento_e_nome(backend: Backend) {
+    let mut servidor = inicia!(backend);
+    let alfa = cadastra_com_docto(&mut servidor, "Empresa Alfa Ltda", "99.999.999/9999-99").await;
+    let beta = cadastra_com_docto(&mut servidor, "Comércio Beta", "99999999999999").await;
+    let jose = cadastra_com_docto(&mut servidor, "José da Silva", "111.111.111-11").await;
+    let outro_jose = cadastra_com_docto(&mut servidor, "Jose da Silva", "222.222.222-22").await;
+    cadastra_com_docto(&mut servidor, "Maria Souza", "333.333.333-33").await;
+    // Nomes similares com o mesmo documento são reportados apenas uma vez.
+    let outra_alfa =
+        cadastra_com_docto(&mut servidor, "Empresa Alfa LTDA", "99.999.999/9999-99").await;
+
+    let grupos: Vec<(i32, Vec<i32>)> = servidor
+        .clientes
+        .detecta_duplicados(())
+        .await
+        .unwrap()
+        .into_inner()
+        .grupos
+        .into_iter()
+        .map(|g| (g.motivo, g.clientes.into_iter().map(|c| c.id).collect()))
+        .collect();
+    assert_eq!(
+        grupos,
+        [
+            (
+                MotivoDuplicidade::Documento as i32,
+                vec![alfa, beta, outra_alfa]
+            ),
+            (MotivoDuplicidade::Nome as i32, vec![jose, outro_jose]),
+        ]
+    );
+}
+
+async fn mescla_transfere_dados_e_registra_log(backend: Backend) {
+    let mut servidor = inicia!(backend);
+    let destino = servidor.cadastra("José da Silva").await.id;
+    let origem = servidor.cadastra("Jose da Silva").await.id;
+
+    for (cliente_id, logradouro, tipo) in [
+        (destino, "Rua A", 0),
+        (origem, "Rua B", 0),
+        (origem, "Rua C", 1),
+    ] {
+        servidor
+            .clientes
+            .cadastra_endereco(NovoEnderecoRequest {
+                tipo,
+                principal: true,
+                ..Servidor::novo_endereco(cliente_id, logradouro)
+            })
+            .await
+            .unwrap();
+    }
+    for (cliente_id, tipo, valor) in [
+        (destino, TELEFONE, "(38) 99999-0000"),
+        (origem, EMAIL, "jose@exemplo.com"),
+    ] {
+        servidor
+            .clientes
+            .cadastra_contato(NovoContatoRequest {
+                principal: true,
+                ..novo_contato(cliente_id, tipo, valor)
+            })
+            .await
+            .unwrap();
+    }
+
+    // O limite da origem é mantido, já que o destino não possui limite.
+    define_limite(&mut servidor, origem, Some(10_000))
+        .await
+        .unwrap();
+    movimenta(&mut servidor, origem, 6_000).await.unwrap();
+    movimenta(&mut servidor, destino, 5_000).await.unwrap();
+
+    let cliente = mescla(&mut servidor, origem, destino).await.unwrap();
+    assert_eq!(cliente.id, destino);
+    assert_eq!(cliente.nome, "José da Silva");
+    assert_eq!(cliente.limite_credito, Some(10_000));
+    assert_eq!(cliente.saldo_devedor, 11_000);
+    assert!(cliente.bloqueado);
+
+    let detalhado = servidor
+        .clientes
+        .consulta_detalhada(IdClienteRequest { id: destino })
+        .await
+        .unwrap()
+        .into_inner();
+    let enderecos: Vec<(&str, i32, bool)> = detalhado
+        .enderecos
+        .iter()
+        .map(|e| (e.logradouro.as_str(), e.tipo, e.principal))
+        .collect();
+    assert_eq!(
+        enderecos,
+        [("Rua A", 0, true), ("Rua B", 0, false), ("Rua C", 1, true)]
+    );
+    assert_eq!(detalhado.contatos.len(), 2);
+    assert!(detalhado.contatos.iter().all(|c| c.principal));
+
+    let status = servidor
+        .clientes
+        .consulta(IdClienteRequest { id: origem })
+        .await
+        .expect_err("Cliente mesclado não foi removido");
+    assert_eq!(status.code(), Code::NotFound);
+
+    let log = servidor
+        .repo
+        .lista_log("cliente".to_string())
+        .await
+        .unwrap();
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].registro, Some(destino));
+    assert_eq!(log[0].usuario, USUARIO_SISTEMA);
+    assert_eq!(log[0].operacao, OperacaoLog::Mescla as i16);
+    let descricao = log[0].descricao.as_deref().unwrap();
+    assert!(
+        descricao.starts_with(&format!("Cliente {} (Jose da Silva", origem)),
+        "{}",
+        descricao
+    );
+}
+
+async fn mescla_rejeita_dados_invalidos(backend: Backend) {
+    let mut servidor = inicia!(backend);
+    let id = servidor.cadastra("Fulano").await.id;
+
+    assert_eq!(
+        mescla(&mut servidor, id, id).await,
+        Err(Code::InvalidArgument)
+    );
+    assert_eq!(mescla(&mut servidor, 42, id).await, Err(Code::NotFound));
+    assert_eq!(mescla(&mut servidor, id, 42).await, Err(Code::NotFound));
+
+    // Nada é alterado quando a mescla falha.
+    assert!(servidor
+        .clientes
+        .consulta(IdClienteRequest { id })
+        .await
+        .is_ok());
+    assert!(servidor
+        .repo
+        .lista_log("cliente".to_string())
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+async fn exporta_dados(servidor: &mut Servidor, id: i32) -> Result<DadosClienteMessage, Code> {
+    let documento = servidor
+        .clientes
+        .exporta_dados(IdClienteRequest { id })
+        .await
+        .map_err(|s| s.code())?
+        .into_inner()
+        .documento;
+    Ok(serde_json::from_str(&documento).expect("Documento exportado inválido"))
+}
+
+async fn anonimiza(servidor: &mut Servidor, id: i32) -> Result<ClienteResponse, Code> {
+    servidor
+        .clientes
+        .anonimiza(IdClienteRequest { id })
+        .await
+        .map(|r| r.into_inner())
+        .map_err(|s| s.code())
+}
+
+async fn exporta_dados_do_cliente(backend: Backend) {
+    let mut servidor = inicia!(backend);
+    let destino = cadastra_com_docto(&mut servidor, "José da Silva", "111.111.111-11").await;
+    let origem = cadastra_com_docto(&mut servidor, "Jose da Silva", "222.222.222-22").await;
+    let outro = servidor.cadastra("Fulano").await.id;
+    servidor.cadastra_endereco(destino, "Rua A").await;
+    servidor.cadastra_endereco(origem, "Rua B").await;
+    servidor.cadastra_endereco(outro, "Rua C").await;
+    servidor
+        .clientes
+        .cadastra_contato(novo_contato(destino, EMAIL, "jose@exemplo.com"))
+        .await
+        .unwrap();
+    mescla(&mut servidor, origem, destino).await.unwrap();
+
+    let dados = exporta_dados(&mut servidor, destino).await.unwrap();
+    let cliente = dados.cliente.unwrap();
+    assert_eq!(cliente.id, destino);
+    assert_eq!(cliente.docto, "111.111.111-11");
+    let enderecos: Vec<&str> = dados
+        .enderecos
+        .iter()
+        .map(|e| e.logradouro.as_str())
+        .collect();
+    assert_eq!(enderecos, ["Rua A", "Rua B"]);
+    assert_eq!(dados.contatos.len(), 1);
+    assert_eq!(dados.contatos[0].valor, "jose@exemplo.com");
+
+    // O histórico inclui a mescla, que identifica o cliente de origem.
+    assert_eq!(dados.log.len(), 1);
+    assert_eq!(dados.log[0].registro, Some(destino));
+    assert_eq!(dados.log[0].operacao, OperacaoLog::Mescla as i32);
+    assert!(dados.log[0].datahora.ends_with('Z'));
+    assert!(dados.log[0]
+        .descricao
+        .as_deref()
+        .unwrap()
+        .contains(&format!("Cliente {} (Jose da Silva)", origem)));
+
+    let dados = exporta_dados(&mut servidor, outro).await.unwrap();
+    assert_eq!(dados.enderecos.len(), 1);
+    assert!(dados.contatos.is_empty());
+    assert!(dados.log.is_empty());
+
+    assert_eq!(
+        exporta_dados(&mut servidor, 42).await.err(),
+        Some(Code::NotFound)
+    );
+}
+
+async fn anonimiza_apaga_dados_pessoais(backend: Backend) {
+    let mut servidor = inicia!(backend);
+    let id = cadastra_com_docto(&mut servidor, "José da Silva", "111.111.111-11").await;
+    let duplicado = cadastra_com_docto(&mut servidor, "Jose da Silva", "111.111.111-11").await;
+    let outro = cadastra_com_docto(&mut servidor, "Maria Souza", "222.222.222-22").await;
+    servidor.cadastra_endereco(id, "Rua A").await;
+    servidor.cadastra_endereco(outro, "Rua B").await;
+    for cliente_id in [id, outro] {
+        servidor
+            .clientes
+            .cadastra_contato(novo_contato(cliente_id, TELEFONE, "(38) 99999-0000"))
+            .await
+            .unwrap();
+    }
+    mescla(&mut servidor, duplicado, id).await.unwrap();
+    define_limite(&mut servidor, id, Some(10_000))
+        .await
+        .unwrap();
+    movimenta(&mut servidor, id, 2_500).await.unwrap();
+
+    let cliente = anonimiza(&mut servidor, id).await.unwrap();
+    assert_eq!(cliente.id, id);
+    assert!(cliente.nome.is_empty());
+    assert!(cliente.docto.is_empty());
+    assert!(!cliente.ativo);
+    // Os dados de crédito são mantidos.
+    assert_eq!(cliente.limite_credito, Some(10_000));
+    assert_eq!(cliente.saldo_devedor, 2_500);
+
+    let dados = exporta_dados(&mut servidor, id).await.unwrap();
+    assert_eq!(dados.cliente.unwrap(), cliente);
+    assert_eq!(dados.enderecos.len(), 1);
+    let endereco = &dados.enderecos[0];
+    assert!(endereco.logradouro.is_empty());
+    assert!(endereco.numero.is_empty());
+    assert!(endereco.bairro.is_empty());
+    assert_eq!(endereco.cep, None);
+    assert_eq!(endereco.cidade, "Diamantina");
+    assert!(dados.contatos.is_empty());
+
+    // A mescla continua no histórico, sem os dados pessoais.
+    let log: Vec<(i32, Option<String>)> = dados
+        .log
+        .into_iter()
+        .map(|l| (l.operacao, l.descricao))
+        .collect();
+    assert_eq!(
+        log,
+        [
+            (OperacaoLog::Mescla as i32, None),
+            (
+                OperacaoLog::Anonimizacao as i32,
+                Some(format!("Cliente {} anonimizado", id))
+            ),
+        ]
+    );
+
+    // Os demais clientes não são alterados.
+    let dados = exporta_dados(&mut servidor, outro).await.unwrap();
+    assert_eq!(dados.cliente.unwrap().nome, "Maria Souza");
+    assert_eq!(dados.enderecos[0].logradouro, "Rua B");
+    assert_eq!(dados.contatos.len(), 1);
+
+    // Clientes anonimizados não são reportados como duplicados.
+    let outro_anonimo = servidor.cadastra("Fulano").await.id;
+    anonimiza(&mut servidor, outro_anonimo).await.unwrap();
+    assert!(servidor
+        .clientes
+        .detecta_duplicados(())
+        .await
+        .unwrap()
+        .into_inner()
+        .grupos
+        .is_empty());
+
+    assert_eq!(anonimiza(&mut servidor, 42).await, Err(Code::NotFound));
+}
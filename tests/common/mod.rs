@@ -0,0 +1,303 @@
+// mod.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Utilitários compartilhados pelos testes de integração.
+//!
+//! Cada teste inicia os serviços gRPC do Minerva.Lite em uma porta efêmera,
+//! usando um dos repositórios disponíveis:
+//!
+//! - em memória, sempre disponível;
+//! - PostgreSQL, caso a variável `TEST_DATABASE_URL` aponte para um banco
+//!   descartável (as tabelas `cliente`, `cep` e `logdb` serão esvaziadas, e
+//!   os tipos de endereço que não são padrão serão removidos, a cada teste);
+//! - SQLite em memória, caso a feature `sqlite` esteja habilitada.
+
+// Cada arquivo de testes usa apenas parte destes utilitários.
+#![allow(dead_code)]
+
+use bb8_diesel::DieselConnectionManager;
+use diesel::{PgConnection, RunQueryDsl};
+use lazy_static::lazy_static;
+use minerva_lite::cifra::Cifra;
+use minerva_lite::importacao::{self, ResumoImportacao};
+use minerva_lite::minerva_clientes_client::MinervaClientesClient;
+use minerva_lite::model::endereco::TIPOS_ENDERECO_PADRAO;
+use minerva_lite::repository::memory::MemRepository;
+use minerva_lite::repository::postgres::PgRepository;
+use minerva_lite::repository::Repository;
+use minerva_lite::service::acesso::{AcessoConfig, AcessoLayer};
+use minerva_lite::{migrations, service};
+use minerva_lite::{ClienteResponse, EnderecoResponse, NovoClienteRequest, NovoEnderecoRequest};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::{Mutex, MutexGuard};
+use tokio_stream::wrappers::TcpListenerStream;
+use tokio_stream::StreamExt;
+use tonic::transport::{Channel, Server};
+use tower::Layer;
+
+lazy_static! {
+    /// Trava que impede que dois testes usem o mesmo banco PostgreSQL ao
+    /// mesmo tempo.
+    static ref POSTGRES: Mutex<()> = Mutex::new(());
+}
+
+/// Repositório usado por um teste.
+#[derive(Debug, Clone, Copy)]
+pub enum Backend {
+    /// Repositório em memória.
+    Memoria,
+    /// Repositório no PostgreSQL definido em `TEST_DATABASE_URL`.
+    Postgres,
+    /// Repositório em um banco SQLite em memória.
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+}
+
+/// Servidor iniciado para um teste, junto com um cliente conectado a ele.
+pub struct Servidor {
+    /// Endereço do servidor, no formato `http://127.0.0.1:<porta>`.
+    pub addr: String,
+    /// Cliente do serviço de clientes.
+    pub clientes: MinervaClientesClient<Channel>,
+    /// Repositório usado pelo servidor.
+    pub repo: Arc<dyn Repository>,
+    reabre: Reabre,
+    _trava: Option<MutexGuard<'static, ()>>,
+}
+
+/// Cria um novo repositório sobre o banco de dados de um teste, com outras
+/// chaves de cifragem.
+type Reabre = Box<dyn Fn(Cifra) -> Arc<dyn Repository> + Send + Sync>;
+
+/// Cria o repositório de um teste, com o banco de dados vazio e os documentos
+/// cifrados com as chaves informadas. Retorna `None` caso o banco de dados
+/// não esteja disponível.
+async fn cria_repositorio(
+    backend: Backend,
+    cifra: Cifra,
+) -> Option<(Arc<dyn Repository>, Reabre, Option<MutexGuard<'static, ()>>)> {
+    match backend {
+        Backend::Memoria => {
+            // Os dados em memória nunca são cifrados.
+            let repo: Arc<dyn Repository> = Arc::new(MemRepository::new());
+            let reabre = repo.clone();
+            Some((repo, Box::new(move |_| reabre.clone()), None))
+        }
+        Backend::Postgres => {
+            let url = match std::env::var("TEST_DATABASE_URL") {
+                Ok(url) => url,
+                Err(_) => {
+                    eprintln!("TEST_DATABASE_URL não definido; teste ignorado");
+                    return None;
+                }
+            };
+
+            let trava = POSTGRES.lock().await;
+            let pool = bb8::Pool::builder()
+                .build(DieselConnectionManager::<PgConnection>::new(url))
+                .await
+                .expect("Impossível criar pool de conexões");
+
+            {
+                let conn = pool.get().await.expect("Impossível conectar ao banco");
+                migrations::up(&*conn, migrations::MIGRACOES, &mut std::io::sink())
+                    .expect("Impossível aplicar migrações");
+                diesel::sql_query("TRUNCATE cliente RESTART IDENTITY CASCADE")
+                    .execute(&*conn)
+                    .expect("Impossível esvaziar tabela de clientes");
+                diesel::sql_query(format!(
+                    "DELETE FROM tipo_endereco WHERE id >= {}",
+                    TIPOS_ENDERECO_PADRAO.len()
+                ))
+                .execute(&*conn)
+                .expect("Impossível restaurar tipos de endereço");
+                diesel::sql_query("DELETE FROM cep")
+                    .execute(&*conn)
+                    .expect("Impossível esvaziar tabela de CEPs");
+                diesel::sql_query("TRUNCATE logdb RESTART IDENTITY")
+                    .execute(&*conn)
+                    .expect("Impossível esvaziar log de operações");
+                diesel::sql_query("TRUNCATE usuario, produto RESTART IDENTITY CASCADE")
+                    .execute(&*conn)
+                    .expect("Impossível esvaziar tabelas de usuários e produtos");
+            }
+
+            let repo = PgRepository::new(pool);
+            let reabre = repo.clone();
+            Some((
+                Arc::new(repo.com_cifra(cifra)),
+                Box::new(move |cifra| Arc::new(reabre.clone().com_cifra(cifra))),
+                Some(trava),
+            ))
+        }
+        #[cfg(feature = "sqlite")]
+        Backend::Sqlite => {
+            use minerva_lite::repository::sqlite::SqliteRepository;
+
+            let pool = minerva_lite::db::make_sqlite_pool(":memory:").await;
+            {
+                let conn = pool.get().await.expect("Impossível conectar ao banco");
+                migrations::up(&*conn, migrations::MIGRACOES_SQLITE, &mut std::io::sink())
+                    .expect("Impossível aplicar migrações");
+            }
+            let repo = SqliteRepository::new(pool);
+            let reabre = repo.clone();
+            Some((
+                Arc::new(repo.com_cifra(cifra)),
+                Box::new(move |cifra| Arc::new(reabre.clone().com_cifra(cifra))),
+                None,
+            ))
+        }
+    }
+}
+
+/// Inicia os serviços gRPC em uma porta efêmera, retornando um cliente
+/// conectado aos mesmos. Retorna `None` caso o banco de dados não esteja
+/// disponível, e o teste deva ser ignorado.
+pub async fn inicia(backend: Backend) -> Option<Servidor> {
+    inicia_com_cifra(backend, Cifra::default()).await
+}
+
+/// Inicia os serviços gRPC como em [`inicia`], com os documentos dos clientes
+/// cifrados com as chaves informadas.
+pub async fn inicia_com_cifra(backend: Backend, cifra: Cifra) -> Option<Servidor> {
+    inicia_com(backend, cifra, AcessoConfig::default()).await
+}
+
+/// Inicia os serviços gRPC como em [`inicia`], exigindo os tokens de acesso
+/// informados.
+pub async fn inicia_com_acesso(backend: Backend, acesso: AcessoConfig) -> Option<Servidor> {
+    inicia_com(backend, Cifra::default(), acesso).await
+}
+
+/// Inicia os serviços gRPC com as chaves de cifragem e o controle de acesso
+/// informados.
+async fn inicia_com(backend: Backend, cifra: Cifra, acesso: AcessoConfig) -> Option<Servidor> {
+    let (repo, reabre, trava) = cria_repositorio(backend, cifra).await?;
+
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Impossível abrir porta efêmera");
+    let addr = listener.local_addr().unwrap();
+
+    let acesso = AcessoLayer::new(acesso);
+    let server = Server::builder()
+        .add_service(acesso.layer(service::base::make_service().await))
+        .add_service(acesso.layer(service::clientes::make_service(repo.clone()).await))
+        .serve_with_incoming(TcpListenerStream::new(listener));
+    tokio::spawn(server);
+
+    let addr = format!("http://{}", addr);
+    let clientes = MinervaClientesClient::connect(addr.clone())
+        .await
+        .expect("Impossível conectar ao servidor");
+
+    Some(Servidor {
+        addr,
+        clientes,
+        repo,
+        reabre,
+        _trava: trava,
+    })
+}
+
+impl Servidor {
+    /// Cria um novo repositório sobre o mesmo banco de dados do servidor,
+    /// com outras chaves de cifragem. No repositório em memória, retorna o
+    /// próprio repositório do servidor.
+    pub fn repositorio(&self, cifra: Cifra) -> Arc<dyn Repository> {
+        (self.reabre)(cifra)
+    }
+
+    /// Importa CEPs a partir de um arquivo CSV separado por vírgulas,
+    /// diretamente no repositório do servidor.
+    pub async fn importa_ceps(&self, csv: &str) -> ResumoImportacao {
+        importacao::importa_ceps(&*self.repo, csv.as_bytes(), b',')
+            .await
+            .expect("Impossível importar CEPs")
+    }
+
+    /// Cadastra um cliente com dados de teste, retornando o cliente cadastrado.
+    pub async fn cadastra(&mut self, nome: &str) -> ClienteResponse {
+        self.clientes
+            .cadastra(NovoClienteRequest {
+                nome: nome.to_string(),
+                pj: false,
+                docto: "000.000.000-00".to_string(),
+            })
+            .await
+            .expect("Impossível cadastrar cliente")
+            .into_inner()
+    }
+
+    /// Cadastra vários clientes, retornando seus IDs em ordem de cadastro.
+    pub async fn cadastra_varios(&mut self, quantidade: usize) -> Vec<i32> {
+        let mut ids = vec![];
+        for i in 0..quantidade {
+            ids.push(self.cadastra(&format!("Cliente {}", i)).await.id);
+        }
+        ids
+    }
+
+    /// Retorna os dados de um endereço de teste válido para um cliente.
+    pub fn novo_endereco(cliente_id: i32, logradouro: &str) -> NovoEnderecoRequest {
+        NovoEnderecoRequest {
+            cliente_id,
+            tipo: 0,
+            logradouro: logradouro.to_string(),
+            numero: "S/N".to_string(),
+            complemento: None,
+            bairro: "Centro".to_string(),
+            uf: "MG".to_string(),
+            cidade: "Diamantina".to_string(),
+            cep: "39100-000".to_string(),
+            principal: false,
+        }
+    }
+
+    /// Cadastra um endereço com dados de teste para um cliente, retornando o
+    /// endereço cadastrado.
+    pub async fn cadastra_endereco(
+        &mut self,
+        cliente_id: i32,
+        logradouro: &str,
+    ) -> EnderecoResponse {
+        self.clientes
+            .cadastra_endereco(Self::novo_endereco(cliente_id, logradouro))
+            .await
+            .expect("Impossível cadastrar endereço")
+            .into_inner()
+    }
+
+    /// Recebe todas as páginas da listagem de clientes, retornando os IDs
+    /// de cada página.
+    pub async fn lista(&mut self) -> Vec<Vec<i32>> {
+        let mut stream = self
+            .clientes
+            .lista(())
+            .await
+            .expect("Impossível listar clientes")
+            .into_inner();
+
+        let mut paginas = vec![];
+        while let Some(pagina) = stream.next().await {
+            let pagina = pagina.expect("Erro ao receber página de clientes");
+            paginas.push(pagina.clientes.into_iter().map(|c| c.id).collect());
+        }
+        paginas
+    }
+}
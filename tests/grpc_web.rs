@@ -0,0 +1,300 @@
+// grpc_web.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Testes de integração do gRPC-Web. As requisições são montadas da mesma
+//! forma que um navegador as enviaria: via HTTP/1.1, com as mensagens
+//! codificadas em quadros do gRPC-Web.
+
+use hyper::body::Bytes;
+use hyper::{Body, Client, Method, Request, StatusCode};
+use minerva_lite::minerva_clientes_client::MinervaClientesClient;
+use minerva_lite::repository::memory::MemRepository;
+use minerva_lite::service::acesso::{Acesso, AcessoConfig, AcessoLayer};
+use minerva_lite::service::{self, web::WebConfig};
+use minerva_lite::{ClientePageResponse, ClienteResponse, NovoClienteRequest};
+use prost::Message;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::Server;
+use tower::Layer;
+
+/// Origem permitida pelo servidor de testes.
+const ORIGEM: &str = "http://app.exemplo";
+
+/// Inicia o servidor com gRPC-Web habilitado, retornando seu endereço.
+async fn inicia() -> String {
+    inicia_com_acesso(AcessoConfig::default()).await
+}
+
+/// Inicia o servidor com gRPC-Web e controle de acesso habilitados, da mesma
+/// forma que o `liteserver`.
+async fn inicia_com_acesso(acesso: AcessoConfig) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let web = WebConfig {
+        origens: vec![ORIGEM.to_string()],
+        max_age: None,
+    }
+    .config();
+
+    let repo = Arc::new(MemRepository::new());
+    let acesso = AcessoLayer::new(acesso);
+    let base = acesso.layer(service::base::make_service().await);
+    let clientes = acesso.layer(service::clientes::make_service(repo).await);
+    let server = Server::builder()
+        .accept_http1(true)
+        .add_service(web.enable(base))
+        .add_service(web.enable(clientes))
+        .serve_with_incoming(TcpListenerStream::new(listener));
+    tokio::spawn(server);
+
+    format!("http://{}", addr)
+}
+
+/// Quadro de uma resposta gRPC-Web.
+enum Quadro {
+    Mensagem(Bytes),
+    Trailers(String),
+}
+
+/// Resposta de uma requisição gRPC-Web.
+struct Resposta {
+    status: StatusCode,
+    /// Código de status gRPC, enviado nos trailers ou, em respostas sem
+    /// mensagens, nos próprios cabeçalhos.
+    grpc_status: String,
+    quadros: Vec<Quadro>,
+}
+
+/// Envia uma requisição gRPC-Web, retornando a resposta decodificada.
+async fn chama(addr: &str, metodo: &str, mensagem: Vec<u8>) -> Resposta {
+    chama_com_token(addr, None, metodo, mensagem).await
+}
+
+/// Como [`chama`], mas enviando opcionalmente um token de acesso.
+async fn chama_com_token(
+    addr: &str,
+    token: Option<&str>,
+    metodo: &str,
+    mensagem: Vec<u8>,
+) -> Resposta {
+    let mut corpo = vec![0u8];
+    corpo.extend_from_slice(&(mensagem.len() as u32).to_be_bytes());
+    corpo.extend_from_slice(&mensagem);
+
+    let mut req = Request::builder()
+        .method(Method::POST)
+        .uri(format!("{}/Minerva.MinervaClientes/{}", addr, metodo))
+        .header("content-type", "application/grpc-web+proto")
+        .header("x-grpc-web", "1")
+        .header("origin", ORIGEM);
+    if let Some(token) = token {
+        req = req.header("authorization", format!("Bearer {}", token));
+    }
+    let req = req.body(Body::from(corpo)).unwrap();
+
+    let resposta = Client::new().request(req).await.unwrap();
+    let status = resposta.status();
+    assert_eq!(
+        resposta.headers()["access-control-allow-origin"],
+        ORIGEM,
+        "Origem não permitida na resposta"
+    );
+    let cabecalho = resposta
+        .headers()
+        .get("grpc-status")
+        .map(|v| v.to_str().unwrap().to_string());
+
+    let mut corpo = hyper::body::to_bytes(resposta.into_body()).await.unwrap();
+    let mut quadros = vec![];
+    while !corpo.is_empty() {
+        let tipo = corpo[0];
+        let tamanho = u32::from_be_bytes(corpo[1..5].try_into().unwrap()) as usize;
+        let conteudo = corpo.slice(5..5 + tamanho);
+        corpo = corpo.slice(5 + tamanho..);
+
+        quadros.push(if tipo & 0x80 != 0 {
+            Quadro::Trailers(String::from_utf8(conteudo.to_vec()).unwrap())
+        } else {
+            Quadro::Mensagem(conteudo)
+        });
+    }
+
+    let grpc_status = quadros
+        .iter()
+        .find_map(|q| match q {
+            Quadro::Trailers(t) => t
+                .lines()
+                .find_map(|l| l.strip_prefix("grpc-status:"))
+                .map(|s| s.trim().to_string()),
+            _ => None,
+        })
+        .or(cabecalho)
+        .expect("Resposta sem grpc-status");
+
+    Resposta {
+        status,
+        grpc_status,
+        quadros,
+    }
+}
+
+fn novo_cliente(nome: &str) -> Vec<u8> {
+    NovoClienteRequest {
+        nome: nome.to_string(),
+        pj: false,
+        docto: "000.000.000-00".to_string(),
+    }
+    .encode_to_vec()
+}
+
+#[tokio::test]
+async fn cadastra_e_lista_via_grpc_web() {
+    let addr = inicia().await;
+
+    for nome in ["Fulano", "Ciclano"] {
+        let resposta = chama(&addr, "Cadastra", novo_cliente(nome)).await;
+        assert_eq!(resposta.status, StatusCode::OK);
+        assert_eq!(resposta.grpc_status, "0");
+        match &resposta.quadros[0] {
+            Quadro::Mensagem(m) => {
+                assert_eq!(ClienteResponse::decode(m.clone()).unwrap().nome, nome)
+            }
+            Quadro::Trailers(_) => panic!("Resposta sem mensagem"),
+        }
+    }
+
+    // A listagem é enviada via streaming, em quadros seguidos pelos trailers.
+    let resposta = chama(&addr, "Lista", vec![]).await;
+    assert_eq!(resposta.status, StatusCode::OK);
+    assert_eq!(resposta.grpc_status, "0");
+
+    let nomes: Vec<String> = resposta
+        .quadros
+        .iter()
+        .filter_map(|q| match q {
+            Quadro::Mensagem(m) => Some(ClientePageResponse::decode(m.clone()).unwrap()),
+            Quadro::Trailers(_) => None,
+        })
+        .flat_map(|pagina| pagina.clientes)
+        .map(|c| c.nome)
+        .collect();
+    assert_eq!(nomes, ["Fulano", "Ciclano"]);
+}
+
+#[tokio::test]
+async fn erros_sao_enviados_como_status_grpc() {
+    let addr = inicia().await;
+
+    let id = minerva_lite::IdClienteRequest { id: 42 }.encode_to_vec();
+    let resposta = chama(&addr, "Consulta", id).await;
+    assert_eq!(resposta.status, StatusCode::OK);
+    assert_eq!(
+        resposta.grpc_status,
+        (tonic::Code::NotFound as i32).to_string()
+    );
+}
+
+/// Monta uma requisição preflight do CORS, como enviada por um navegador.
+fn preflight(addr: &str, origem: &str) -> Request<Body> {
+    Request::builder()
+        .method(Method::OPTIONS)
+        .uri(format!("{}/Minerva.MinervaClientes/Lista", addr))
+        .header("origin", origem)
+        .header("access-control-request-method", "POST")
+        .header(
+            "access-control-request-headers",
+            "authorization,content-type,x-grpc-web",
+        )
+        .body(Body::empty())
+        .unwrap()
+}
+
+#[tokio::test]
+async fn preflight_respeita_origens_permitidas() {
+    let addr = inicia().await;
+
+    let resposta = Client::new()
+        .request(preflight(&addr, ORIGEM))
+        .await
+        .unwrap();
+    assert!(resposta.status().is_success());
+    assert_eq!(resposta.headers()["access-control-allow-origin"], ORIGEM);
+    let expostos = resposta.headers()["access-control-expose-headers"]
+        .to_str()
+        .unwrap();
+    assert!(expostos.contains("x-request-id"), "{}", expostos);
+
+    let resposta = Client::new()
+        .request(preflight(&addr, "http://outro.exemplo"))
+        .await
+        .unwrap();
+    assert_eq!(resposta.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn grpc_nativo_continua_disponivel() {
+    let addr = inicia().await;
+
+    let mut client = MinervaClientesClient::connect(addr).await.unwrap();
+    let cliente = client
+        .cadastra(NovoClienteRequest {
+            nome: "Fulano".to_string(),
+            pj: false,
+            docto: "000.000.000-00".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(cliente.id, 1);
+}
+
+#[tokio::test]
+async fn controle_de_acesso_com_grpc_web() {
+    let config = AcessoConfig {
+        tokens: vec![(
+            "segredo".to_string(),
+            Acesso {
+                usuario: "ana".to_string(),
+                permissoes: vec![],
+            },
+        )],
+    };
+    let addr = inicia_com_acesso(config).await;
+
+    // O preflight não carrega o token de acesso, e não deve ser recusado.
+    let resposta = Client::new()
+        .request(preflight(&addr, ORIGEM))
+        .await
+        .unwrap();
+    assert!(resposta.status().is_success(), "{}", resposta.status());
+    assert_eq!(resposta.headers()["access-control-allow-origin"], ORIGEM);
+    assert!(resposta.headers().get("grpc-status").is_none());
+
+    // Requisições sem token são recusadas, com os cabeçalhos do CORS, para
+    // que o navegador possa ler o status.
+    let resposta = chama(&addr, "Cadastra", novo_cliente("Fulano")).await;
+    assert_eq!(
+        resposta.grpc_status,
+        (tonic::Code::Unauthenticated as i32).to_string()
+    );
+
+    let resposta =
+        chama_com_token(&addr, Some("segredo"), "Cadastra", novo_cliente("Fulano")).await;
+    assert_eq!(resposta.grpc_status, "0");
+}
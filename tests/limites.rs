@@ -0,0 +1,93 @@
+// limites.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Testes dos limites de requisições simultâneas por método gRPC.
+
+use hyper::{Body, Request, Response};
+use minerva_lite::service::limits::{LimitsConfig, LimitsLayer};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use tonic::body::BoxBody;
+use tower::util::BoxCloneService;
+use tower::{Layer, Service, ServiceExt};
+
+/// Cria um serviço com no máximo uma requisição simultânea por método, e
+/// duas para `Lista`. As respostas mantêm suas vagas enquanto existirem.
+fn servico() -> BoxCloneService<Request<Body>, Response<BoxBody>, Infallible> {
+    let config = LimitsConfig {
+        max_por_metodo: Some(1),
+        limites_metodos: HashMap::from([("Lista".to_string(), 2)]),
+        ..Default::default()
+    };
+    let interno = tower::service_fn(|_req: Request<Body>| async {
+        Ok::<_, Infallible>(Response::new(tonic::body::empty_body()))
+    });
+    BoxCloneService::new(LimitsLayer::new(config).layer(interno))
+}
+
+/// Requisita um caminho, retornando a resposta e o código de status gRPC
+/// informado nos cabeçalhos, caso a requisição tenha sido recusada.
+async fn requisita(
+    servico: &mut BoxCloneService<Request<Body>, Response<BoxBody>, Infallible>,
+    caminho: &str,
+) -> (Response<BoxBody>, Option<String>) {
+    let req = Request::builder()
+        .uri(format!("http://localhost{}", caminho))
+        .body(Body::empty())
+        .unwrap();
+    let resposta = servico.ready().await.unwrap().call(req).await.unwrap();
+    let status = resposta
+        .headers()
+        .get("grpc-status")
+        .map(|valor| valor.to_str().unwrap().to_string());
+    (resposta, status)
+}
+
+#[tokio::test]
+async fn limita_requisicoes_por_metodo() {
+    let mut servico = servico();
+    let recusado = Some((tonic::Code::ResourceExhausted as i32).to_string());
+
+    let (consulta, status) = requisita(&mut servico, "/Minerva.MinervaClientes/Consulta").await;
+    assert_eq!(status, None);
+    let (_, status) = requisita(&mut servico, "/Minerva.MinervaClientes/Consulta").await;
+    assert_eq!(status, recusado);
+
+    // Cada método possui seu próprio limite.
+    let (_lista, status) = requisita(&mut servico, "/Minerva.MinervaClientes/Lista").await;
+    assert_eq!(status, None);
+    let (_, status) = requisita(&mut servico, "/Minerva.MinervaClientes/Lista").await;
+    assert_eq!(status, None);
+
+    // A vaga é liberada quando a resposta é descartada.
+    drop(consulta);
+    let (_, status) = requisita(&mut servico, "/Minerva.MinervaClientes/Consulta").await;
+    assert_eq!(status, None);
+}
+
+#[tokio::test]
+async fn caminhos_desconhecidos_sem_limite_proprio() {
+    let mut servico = servico();
+
+    // Caminhos que não correspondem a métodos gRPC não criam novos limites.
+    let mut respostas = vec![];
+    for i in 0..3 {
+        let caminho = format!("/Minerva.MinervaClientes/Inexistente{}", i % 2);
+        let (resposta, status) = requisita(&mut servico, &caminho).await;
+        assert_eq!(status, None);
+        respostas.push(resposta);
+    }
+}
@@ -0,0 +1,105 @@
+// logging.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Testes do registro de requisições gRPC no log estruturado.
+
+use hyper::body::{Bytes, HttpBody};
+use hyper::header::HeaderValue;
+use hyper::{Body, HeaderMap, Request, Response};
+use minerva_lite::service::logging::RequestTraceLayer;
+use serde_json::Value;
+use std::convert::Infallible;
+use std::io;
+use std::sync::{Arc, Mutex};
+use tonic::body::BoxBody;
+use tower::{Layer, ServiceExt};
+
+/// Destino do log, compartilhado com o teste.
+#[derive(Clone, Default)]
+struct Escrita(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for Escrita {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Escrita {
+    /// Retorna os eventos registrados com a mensagem informada.
+    fn eventos(&self, mensagem: &str) -> Vec<Value> {
+        let log = self.0.lock().unwrap();
+        String::from_utf8_lossy(&log)
+            .lines()
+            .map(|linha| serde_json::from_str::<Value>(linha).unwrap())
+            .filter(|evento| evento["fields"]["message"] == mensagem)
+            .collect()
+    }
+}
+
+#[tokio::test]
+async fn status_do_stream_lido_dos_trailers() {
+    let escrita = Escrita::default();
+    let destino = escrita.clone();
+    let _guard = tracing::subscriber::set_default(
+        tracing_subscriber::fmt()
+            .json()
+            .with_writer(move || destino.clone())
+            .finish(),
+    );
+
+    let (mut tx, body) = Body::channel();
+    let body = Arc::new(Mutex::new(Some(body)));
+    let servico = tower::service_fn(move |_req: Request<Body>| {
+        let body = body.lock().unwrap().take().unwrap();
+        let body: BoxBody = body
+            .map_err(|e| tonic::Status::internal(e.to_string()))
+            .boxed_unsync();
+        async move { Ok::<_, Infallible>(Response::new(body)) }
+    });
+
+    let req = Request::builder()
+        .uri("http://localhost/Minerva.MinervaClientes/Lista")
+        .body(Body::empty())
+        .unwrap();
+    let mut corpo = RequestTraceLayer
+        .layer(servico)
+        .oneshot(req)
+        .await
+        .unwrap()
+        .into_body();
+
+    // Nada é registrado enquanto a resposta não termina.
+    assert!(escrita.eventos("Requisição atendida").is_empty());
+    assert!(escrita.eventos("Requisição com erro").is_empty());
+
+    tx.send_data(Bytes::from_static(b"pagina")).await.unwrap();
+    assert!(corpo.data().await.unwrap().is_ok());
+    let mut trailers = HeaderMap::new();
+    trailers.insert("grpc-status", HeaderValue::from_static("4"));
+    tx.send_trailers(trailers).await.unwrap();
+    corpo.trailers().await.unwrap();
+
+    assert!(escrita.eventos("Requisição atendida").is_empty());
+    let erros = escrita.eventos("Requisição com erro");
+    assert_eq!(erros.len(), 1);
+    assert_eq!(erros[0]["fields"]["status"], 4);
+    assert_eq!(erros[0]["span"]["metodo"], "/Minerva.MinervaClientes/Lista");
+}
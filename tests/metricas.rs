@@ -0,0 +1,166 @@
+// metricas.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Testes da contabilização de requisições gRPC nas métricas do Prometheus.
+
+use hyper::body::{Bytes, HttpBody};
+use hyper::header::HeaderValue;
+use hyper::{Body, HeaderMap, Request, Response};
+use minerva_lite::service::metrics::{MetricsLayer, METODO_DESCONHECIDO};
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use tonic::body::BoxBody;
+use tower::{Layer, Service, ServiceExt};
+
+/// Retorna o número de requisições contabilizadas para um método e código
+/// de status.
+fn requisicoes(metodo: &str, status: &str) -> u64 {
+    prometheus::gather()
+        .iter()
+        .filter(|familia| familia.get_name() == "minerva_rpc_requisicoes_total")
+        .flat_map(|familia| familia.get_metric())
+        .filter(|metrica| {
+            let rotulos = metrica.get_label();
+            rotulos
+                .iter()
+                .any(|r| r.get_name() == "metodo" && r.get_value() == metodo)
+                && rotulos
+                    .iter()
+                    .any(|r| r.get_name() == "status" && r.get_value() == status)
+        })
+        .map(|metrica| metrica.get_counter().get_value() as u64)
+        .sum()
+}
+
+/// Converte um corpo do hyper no corpo usado pelas camadas do servidor.
+fn boxed(body: Body) -> BoxBody {
+    body.map_err(|e| tonic::Status::internal(e.to_string()))
+        .boxed_unsync()
+}
+
+/// Requisita o caminho informado a um serviço cuja resposta é produzida
+/// pela função `resposta`, retornando o corpo da resposta.
+async fn requisita<F>(caminho: &str, resposta: F) -> BoxBody
+where
+    F: Fn() -> Response<BoxBody> + Clone + Send + 'static,
+{
+    let servico = tower::service_fn(move |_req: Request<Body>| {
+        let resposta = resposta.clone();
+        async move { Ok::<_, Infallible>(resposta()) }
+    });
+    let mut servico = MetricsLayer.layer(servico);
+    let req = Request::builder()
+        .uri(format!("http://localhost{}", caminho))
+        .body(Body::empty())
+        .unwrap();
+    servico
+        .ready()
+        .await
+        .unwrap()
+        .call(req)
+        .await
+        .unwrap()
+        .into_body()
+}
+
+/// Consome todo o corpo de uma resposta, incluindo seus trailers.
+async fn consome(mut body: BoxBody) {
+    while let Some(dados) = body.data().await {
+        dados.unwrap();
+    }
+    body.trailers().await.unwrap();
+}
+
+#[tokio::test]
+async fn status_lido_dos_trailers() {
+    const METODO: &str = "/Minerva.MinervaClientes/Lista";
+    let antes = requisicoes(METODO, "4");
+
+    let (mut tx, body) = Body::channel();
+    let body = Arc::new(Mutex::new(Some(body)));
+    let corpo = requisita(METODO, move || {
+        Response::new(boxed(body.lock().unwrap().take().unwrap()))
+    })
+    .await;
+
+    // A requisição só é contabilizada quando os trailers são enviados.
+    assert_eq!(requisicoes(METODO, "4"), antes);
+    let envio = tokio::spawn(async move {
+        tx.send_data(Bytes::from_static(b"mensagem")).await.unwrap();
+        let mut trailers = HeaderMap::new();
+        trailers.insert("grpc-status", HeaderValue::from_static("4"));
+        tx.send_trailers(trailers).await.unwrap();
+    });
+    consome(corpo).await;
+    envio.await.unwrap();
+
+    assert_eq!(requisicoes(METODO, "4"), antes + 1);
+    assert_eq!(requisicoes(METODO, "0"), 0);
+}
+
+#[tokio::test]
+async fn status_lido_dos_cabecalhos() {
+    const METODO: &str = "/Minerva.MinervaClientes/Consulta";
+    let antes = requisicoes(METODO, "5");
+
+    let corpo = requisita(METODO, || {
+        let mut resposta = Response::new(boxed(Body::empty()));
+        resposta
+            .headers_mut()
+            .insert("grpc-status", HeaderValue::from_static("5"));
+        resposta
+    })
+    .await;
+
+    assert_eq!(requisicoes(METODO, "5"), antes + 1);
+    consome(corpo).await;
+    assert_eq!(requisicoes(METODO, "5"), antes + 1);
+}
+
+#[tokio::test]
+async fn caminho_desconhecido_agrupado() {
+    const CAMINHO: &str = "/Minerva.MinervaClientes/Inexistente";
+    let antes = requisicoes(METODO_DESCONHECIDO, "12");
+
+    let corpo = requisita(CAMINHO, || {
+        let mut resposta = Response::new(boxed(Body::empty()));
+        resposta
+            .headers_mut()
+            .insert("grpc-status", HeaderValue::from_static("12"));
+        resposta
+    })
+    .await;
+    consome(corpo).await;
+
+    assert_eq!(requisicoes(METODO_DESCONHECIDO, "12"), antes + 1);
+    assert_eq!(requisicoes(CAMINHO, "12"), 0);
+}
+
+#[tokio::test]
+async fn resposta_interrompida_cancelada() {
+    const METODO: &str = "/Minerva.MinervaClientes/ListaDetalhada";
+    let antes = requisicoes(METODO, "1");
+
+    let (_tx, body) = Body::channel();
+    let body = Arc::new(Mutex::new(Some(body)));
+    let corpo = requisita(METODO, move || {
+        Response::new(boxed(body.lock().unwrap().take().unwrap()))
+    })
+    .await;
+    drop(corpo);
+
+    assert_eq!(requisicoes(METODO, "1"), antes + 1);
+}
@@ -0,0 +1,108 @@
+// migracoes.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Testes das migrações do banco de dados sobre dados existentes. Cada teste
+//! aplica as migrações até certo ponto, grava dados que as migrações
+//! seguintes devem preservar, e aplica as migrações restantes.
+//!
+//! No PostgreSQL, as migrações são aplicadas em um esquema próprio do banco
+//! definido em `TEST_DATABASE_URL`, removido ao final do teste.
+
+use diesel::connection::SimpleConnection;
+use diesel::prelude::*;
+use diesel_migrations::MigrationConnection;
+use minerva_lite::migrations::{self, Migracao};
+
+/// Tipos de endereço esperados após a migração de um endereço com um tipo
+/// fora do catálogo padrão.
+const TIPOS_MIGRADOS: &[(i16, &str)] = &[
+    (0, "Residencial"),
+    (1, "Comercial"),
+    (2, "Cobrança"),
+    (3, "Entrega"),
+    (7, "Tipo 7"),
+];
+
+/// Aplica as migrações anteriores à criação do catálogo de tipos de
+/// endereço, grava endereços com um tipo fora do catálogo padrão, e aplica
+/// as migrações restantes.
+fn migra_endereco_com_tipo_desconhecido<C: MigrationConnection>(conn: &C, migracoes: &[Migracao]) {
+    let n = migracoes
+        .iter()
+        .position(|m| m.nome == "2026-10-18-000001_valida_endereco")
+        .unwrap();
+    migrations::up(conn, &migracoes[..n], &mut std::io::sink()).unwrap();
+    conn.batch_execute(
+        "INSERT INTO cliente (id, nome, docto) VALUES (1, 'Fulano', '');
+         INSERT INTO endereco (cliente_id, tipo, logradouro, numero, bairro, uf, cidade)
+             VALUES (1, 7, 'Rua A', '1', 'Centro', 'MG', 'Diamantina'),
+                    (1, 7, 'Rua B', '2', 'Centro', 'MG', 'Diamantina'),
+                    (1, 1, 'Rua C', '3', 'Centro', 'MG', 'Diamantina');",
+    )
+    .unwrap();
+    migrations::up(conn, migracoes, &mut std::io::sink()).unwrap();
+}
+
+#[test]
+fn postgres_migra_endereco_com_tipo_desconhecido() {
+    use minerva_lite::model::schema::{endereco, tipo_endereco};
+
+    let url = match std::env::var("TEST_DATABASE_URL") {
+        Ok(url) => url,
+        Err(_) => {
+            eprintln!("TEST_DATABASE_URL não definido; teste ignorado");
+            return;
+        }
+    };
+    let conn = PgConnection::establish(&url).unwrap();
+    conn.batch_execute(
+        "DROP SCHEMA IF EXISTS teste_migracoes CASCADE;
+         CREATE SCHEMA teste_migracoes;
+         SET search_path TO teste_migracoes, public;",
+    )
+    .unwrap();
+
+    migra_endereco_com_tipo_desconhecido(&conn, migrations::MIGRACOES);
+    let tipos = tipo_endereco::table
+        .order(tipo_endereco::id)
+        .load::<(i16, String)>(&conn);
+    let enderecos = endereco::table.count().get_result::<i64>(&conn);
+    conn.batch_execute("DROP SCHEMA teste_migracoes CASCADE")
+        .unwrap();
+
+    let tipos = tipos.unwrap();
+    let tipos: Vec<(i16, &str)> = tipos.iter().map(|(id, d)| (*id, d.as_str())).collect();
+    assert_eq!(tipos, TIPOS_MIGRADOS);
+    assert_eq!(enderecos.unwrap(), 3);
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn sqlite_migra_endereco_com_tipo_desconhecido() {
+    use minerva_lite::model::schema_sqlite::{endereco, tipo_endereco};
+
+    let conn = SqliteConnection::establish(":memory:").unwrap();
+    conn.batch_execute("PRAGMA foreign_keys = ON").unwrap();
+
+    migra_endereco_com_tipo_desconhecido(&conn, migrations::MIGRACOES_SQLITE);
+    let tipos = tipo_endereco::table
+        .order(tipo_endereco::id)
+        .load::<(i16, String)>(&conn)
+        .unwrap();
+    let tipos: Vec<(i16, &str)> = tipos.iter().map(|(id, d)| (*id, d.as_str())).collect();
+    assert_eq!(tipos, TIPOS_MIGRADOS);
+    assert_eq!(endereco::table.count().get_result::<i64>(&conn).unwrap(), 3);
+}
@@ -0,0 +1,224 @@
+// repositorios.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Testes de integração dos repositórios de usuários, produtos e estoque.
+//! Cada cenário é executado diretamente sobre todos os repositórios
+//! disponíveis.
+
+mod common;
+
+use bigdecimal::BigDecimal;
+use common::Backend;
+use minerva_lite::model::estoque::NovoMovEstoque;
+use minerva_lite::model::produto::NovoProduto;
+use minerva_lite::model::usuario::NovoUsuario;
+use minerva_lite::repository::RepoError;
+use std::str::FromStr;
+
+/// Inicia o servidor de um cenário, encerrando o teste caso o banco de dados
+/// não esteja disponível.
+macro_rules! inicia {
+    ($backend:expr) => {
+        match common::inicia($backend).await {
+            Some(servidor) => servidor,
+            None => return,
+        }
+    };
+}
+
+/// Gera um teste para cada repositório, a partir das funções de cenário
+/// informadas.
+macro_rules! cenarios {
+    ($($cenario:ident),* $(,)?) => {
+        mod memoria {
+            $(
+                #[tokio::test(flavor = "multi_thread")]
+                async fn $cenario() {
+                    super::$cenario(super::Backend::Memoria).await
+                }
+            )*
+        }
+
+        mod postgres {
+            $(
+                #[tokio::test(flavor = "multi_thread")]
+                async fn $cenario() {
+                    super::$cenario(super::Backend::Postgres).await
+                }
+            )*
+        }
+
+        #[cfg(feature = "sqlite")]
+        mod sqlite {
+            $(
+                #[tokio::test(flavor = "multi_thread")]
+                async fn $cenario() {
+                    super::$cenario(super::Backend::Sqlite).await
+                }
+            )*
+        }
+    };
+}
+
+cenarios!(usuarios, produtos, movimenta_estoque);
+
+fn decimal(valor: &str) -> BigDecimal {
+    BigDecimal::from_str(valor).unwrap()
+}
+
+fn novo_usuario(login: &str) -> NovoUsuario {
+    NovoUsuario {
+        login: login.to_string(),
+        nome: format!("Usuário {}", login),
+        email: None,
+        senha_hash: vec![1, 2, 3],
+    }
+}
+
+fn movimento(produto_id: i32, quantidade: &str, preco: &str, frete: &str) -> NovoMovEstoque {
+    NovoMovEstoque {
+        produto_id,
+        docto: "NF 1".to_string(),
+        quantidade: decimal(quantidade),
+        preco_frete: decimal(frete),
+        preco_unitario: decimal(preco),
+    }
+}
+
+async fn usuarios(backend: Backend) {
+    let servidor = inicia!(backend);
+    let repo = &servidor.repo;
+
+    let admin = repo.cadastra_usuario(novo_usuario("admin")).await.unwrap();
+    let operador = repo
+        .cadastra_usuario(novo_usuario("operador"))
+        .await
+        .unwrap();
+    assert_eq!(admin.login, "admin");
+    assert_eq!(admin.senha_hash, vec![1, 2, 3]);
+
+    let duplicado = repo.cadastra_usuario(novo_usuario("admin")).await;
+    assert!(matches!(duplicado, Err(RepoError::Conflito(_))));
+
+    assert_eq!(repo.consulta_usuario(admin.id).await.unwrap(), admin);
+    assert_eq!(
+        repo.consulta_usuario_login("operador".to_string())
+            .await
+            .unwrap(),
+        operador
+    );
+    assert_eq!(
+        repo.lista_usuarios().await.unwrap(),
+        vec![admin.clone(), operador.clone()]
+    );
+
+    repo.remove_usuario(admin.id).await.unwrap();
+    assert!(matches!(
+        repo.consulta_usuario(admin.id).await,
+        Err(RepoError::NaoEncontrado)
+    ));
+    assert!(matches!(
+        repo.consulta_usuario_login("admin".to_string()).await,
+        Err(RepoError::NaoEncontrado)
+    ));
+    assert_eq!(repo.lista_usuarios().await.unwrap(), vec![operador]);
+}
+
+async fn produtos(backend: Backend) {
+    let servidor = inicia!(backend);
+    let repo = &servidor.repo;
+
+    let produto = repo
+        .cadastra_produto(NovoProduto {
+            descricao: "Parafuso".to_string(),
+            unidsaida: "UN".to_string(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(produto.descricao, "Parafuso");
+    assert_eq!(repo.consulta_produto(produto.id).await.unwrap(), produto);
+    assert_eq!(repo.lista_produtos().await.unwrap(), vec![produto.clone()]);
+
+    let estoque = repo.consulta_estoque(produto.id).await.unwrap();
+    assert_eq!(estoque.quantidade, BigDecimal::default());
+
+    repo.registra_movimento_estoque(movimento(produto.id, "5", "1", "0"))
+        .await
+        .unwrap();
+    repo.remove_produto(produto.id).await.unwrap();
+    assert!(matches!(
+        repo.consulta_produto(produto.id).await,
+        Err(RepoError::NaoEncontrado)
+    ));
+    assert!(matches!(
+        repo.consulta_estoque(produto.id).await,
+        Err(RepoError::NaoEncontrado)
+    ));
+    assert!(repo.lista_produtos().await.unwrap().is_empty());
+}
+
+async fn movimenta_estoque(backend: Backend) {
+    let servidor = inicia!(backend);
+    let repo = &servidor.repo;
+
+    let produto = repo
+        .cadastra_produto(NovoProduto {
+            descricao: "Arruela".to_string(),
+            unidsaida: "UN".to_string(),
+        })
+        .await
+        .unwrap();
+
+    let estoque = repo
+        .registra_movimento_estoque(movimento(produto.id, "10", "2", "0"))
+        .await
+        .unwrap();
+    assert_eq!(estoque.quantidade, decimal("10"));
+    assert_eq!(estoque.precounitario, decimal("2"));
+
+    // O custo médio é ponderado pelas quantidades e inclui o frete.
+    let estoque = repo
+        .registra_movimento_estoque(movimento(produto.id, "10", "3", "10"))
+        .await
+        .unwrap();
+    assert_eq!(estoque.quantidade, decimal("20"));
+    assert_eq!(estoque.precounitario, decimal("3"));
+
+    // Saídas reduzem a quantidade sem alterar o custo médio.
+    let estoque = repo
+        .registra_movimento_estoque(movimento(produto.id, "-5", "0", "0"))
+        .await
+        .unwrap();
+    assert_eq!(estoque.quantidade, decimal("15"));
+    assert_eq!(estoque.precounitario, decimal("3"));
+    assert_eq!(repo.consulta_estoque(produto.id).await.unwrap(), estoque);
+
+    let movimentos = repo.lista_movimentos_estoque(produto.id).await.unwrap();
+    let quantidades: Vec<_> = movimentos.iter().map(|m| m.quantidade.clone()).collect();
+    assert_eq!(
+        quantidades,
+        vec![decimal("10"), decimal("10"), decimal("-5")]
+    );
+
+    let inexistente = repo
+        .registra_movimento_estoque(movimento(produto.id + 1, "1", "1", "0"))
+        .await;
+    assert!(matches!(inexistente, Err(RepoError::Invalido(_))));
+    assert!(matches!(
+        repo.lista_movimentos_estoque(produto.id + 1).await,
+        Err(RepoError::NaoEncontrado)
+    ));
+}
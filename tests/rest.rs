@@ -0,0 +1,335 @@
+// rest.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Testes de integração do gateway REST. As rotas são chamadas diretamente,
+//! sem abrir uma porta, sobre o repositório em memória.
+
+use axum::body::Body;
+use axum::http::{header, Method, Request, StatusCode};
+use axum::Router;
+use minerva_lite::controller::cliente::CLIENTE_PAGE_SIZE;
+use minerva_lite::repository::memory::MemRepository;
+use minerva_lite::service::acesso::{Acesso, AcessoConfig, Permissao};
+use minerva_lite::service::rest;
+use minerva_lite::{ClienteDetalhadoResponse, ClienteResponse};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+/// Executa uma requisição nas rotas, retornando o status, o cabeçalho
+/// `Location` e o corpo JSON da resposta, caso exista.
+async fn requisita(
+    router: &Router,
+    metodo: Method,
+    caminho: &str,
+    corpo: Option<Value>,
+) -> (StatusCode, Option<String>, Value) {
+    requisita_com_token(router, None, metodo, caminho, corpo).await
+}
+
+/// Como [`requisita`], mas enviando opcionalmente um token de acesso.
+async fn requisita_com_token(
+    router: &Router,
+    token: Option<&str>,
+    metodo: Method,
+    caminho: &str,
+    corpo: Option<Value>,
+) -> (StatusCode, Option<String>, Value) {
+    let mut req = Request::builder().method(metodo).uri(caminho);
+    if let Some(token) = token {
+        req = req.header(header::AUTHORIZATION, format!("Bearer {}", token));
+    }
+    let body = match corpo {
+        Some(corpo) => {
+            req = req.header(header::CONTENT_TYPE, "application/json");
+            Body::from(corpo.to_string())
+        }
+        None => Body::empty(),
+    };
+
+    let resposta = router
+        .clone()
+        .oneshot(req.body(body).unwrap())
+        .await
+        .unwrap();
+
+    let status = resposta.status();
+    let local = resposta
+        .headers()
+        .get(header::LOCATION)
+        .map(|v| v.to_str().unwrap().to_string());
+    let bytes = hyper::body::to_bytes(resposta.into_body()).await.unwrap();
+    let corpo = if bytes.is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_slice(&bytes).unwrap()
+    };
+    (status, local, corpo)
+}
+
+fn router() -> Router {
+    rest::router(Arc::new(MemRepository::new()), AcessoConfig::default())
+}
+
+/// Cria as rotas com controle de acesso: o token `admin` pode ver os dados
+/// sensíveis dos clientes, e o token `atendente` não.
+fn router_com_acesso() -> Router {
+    let acesso = |usuario: &str, permissoes| Acesso {
+        usuario: usuario.to_string(),
+        permissoes,
+    };
+    let config = AcessoConfig {
+        tokens: vec![
+            (
+                "token-admin".to_string(),
+                acesso("admin", vec![Permissao::DadosSensiveis]),
+            ),
+            ("token-atendente".to_string(), acesso("atendente", vec![])),
+        ],
+    };
+    rest::router(Arc::new(MemRepository::new()), config)
+}
+
+async fn cadastra(router: &Router, nome: &str) -> Value {
+    let (status, _, corpo) = requisita(
+        router,
+        Method::POST,
+        "/clientes",
+        Some(json!({ "nome": nome, "pj": false, "docto": "000.000.000-00" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    corpo
+}
+
+#[tokio::test]
+async fn cadastra_e_consulta_cliente() {
+    let router = router();
+
+    let (status, local, cadastrado) = requisita(
+        &router,
+        Method::POST,
+        "/clientes",
+        Some(json!({ "nome": "Fulano", "pj": true, "docto": "00.000.000/0001-00" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(local.as_deref(), Some("/clientes/1"));
+    assert_eq!(
+        cadastrado,
+        json!({
+            "id": 1,
+            "tipo": 0,
+            "nome": "Fulano",
+            "pj": true,
+            "docto": "00.000.000/0001-00",
+            "ativo": true,
+            "bloqueado": false,
+            "saldoDevedor": "0",
+        })
+    );
+
+    let (status, _, consultado) = requisita(&router, Method::GET, "/clientes/1", None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(consultado, cadastrado);
+}
+
+#[test]
+fn serializa_mensagens_conforme_proto3() {
+    let cliente = ClienteResponse {
+        limite_credito: Some(150_000),
+        saldo_devedor: -2500,
+        ..Default::default()
+    };
+    let valor = serde_json::to_value(&cliente).unwrap();
+    assert_eq!(valor["limiteCredito"], "150000");
+    assert_eq!(valor["saldoDevedor"], "-2500");
+
+    // Campos opcionais ausentes são omitidos.
+    let valor = serde_json::to_value(ClienteDetalhadoResponse::default()).unwrap();
+    assert!(valor.get("cliente").is_none(), "{}", valor);
+
+    // Inteiros de 64 bits são aceitos tanto como strings quanto como números.
+    let lido: ClienteResponse =
+        serde_json::from_value(json!({ "limiteCredito": 100, "saldoDevedor": "-7" })).unwrap();
+    assert_eq!(lido.limite_credito, Some(100));
+    assert_eq!(lido.saldo_devedor, -7);
+    let lido: ClienteResponse = serde_json::from_value(json!({ "limiteCredito": null })).unwrap();
+    assert_eq!(lido.limite_credito, None);
+    assert!(serde_json::from_value::<ClienteResponse>(json!({ "saldoDevedor": "x" })).is_err());
+}
+
+#[tokio::test]
+async fn consulta_cliente_inexistente() {
+    let (status, _, corpo) = requisita(&router(), Method::GET, "/clientes/42", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(corpo["code"], tonic::Code::NotFound as i32);
+    assert_eq!(corpo["message"], "Usuário não encontrado");
+}
+
+#[tokio::test]
+async fn rejeita_corpo_e_id_invalidos() {
+    let router = router();
+
+    let (status, _, corpo) = requisita(
+        &router,
+        Method::POST,
+        "/clientes",
+        Some(json!({ "nome": 42 })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(corpo["code"], tonic::Code::InvalidArgument as i32);
+
+    let (status, _, _) = requisita(&router, Method::GET, "/clientes/abc", None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _, _) = requisita(&router, Method::GET, "/clientes?pagina=-1", None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn rejeita_docto_invalido() {
+    let router = router();
+
+    let novo = json!({ "nome": "Fulano", "pj": false, "docto": "cifra:v1:dead:AAAA" });
+    let (status, _, corpo) = requisita(&router, Method::POST, "/clientes", Some(novo)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(corpo["code"], tonic::Code::InvalidArgument as i32);
+
+    let (status, _, pagina) = requisita(&router, Method::GET, "/clientes", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let clientes = pagina["clientes"].as_array().cloned().unwrap_or_default();
+    assert!(clientes.is_empty(), "{}", pagina);
+}
+
+#[tokio::test]
+async fn lista_clientes_por_pagina() {
+    let router = router();
+    for i in 0..CLIENTE_PAGE_SIZE + 1 {
+        cadastra(&router, &format!("Cliente {}", i)).await;
+    }
+
+    let (status, _, primeira) = requisita(&router, Method::GET, "/clientes", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let primeira = primeira["clientes"].as_array().unwrap();
+    assert_eq!(primeira.len(), CLIENTE_PAGE_SIZE as usize);
+    assert_eq!(primeira[0]["id"], 1);
+
+    let (_, _, segunda) = requisita(&router, Method::GET, "/clientes?pagina=1", None).await;
+    let segunda = segunda["clientes"].as_array().unwrap();
+    assert_eq!(segunda.len(), 1);
+    assert_eq!(segunda[0]["id"], CLIENTE_PAGE_SIZE + 1);
+
+    let (_, _, vazia) = requisita(&router, Method::GET, "/clientes?pagina=2", None).await;
+    assert_eq!(vazia, json!({ "clientes": [] }));
+}
+
+#[tokio::test]
+async fn deleta_cliente() {
+    let router = router();
+    let cliente = cadastra(&router, "Fulano").await;
+    let caminho = format!("/clientes/{}", cliente["id"]);
+
+    let (status, _, corpo) = requisita(&router, Method::DELETE, &caminho, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert_eq!(corpo, Value::Null);
+
+    let (status, _, _) = requisita(&router, Method::GET, &caminho, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn documento_openapi_descreve_rotas() {
+    let (status, _, doc) = requisita(&router(), Method::GET, "/openapi.json", None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let paths = &doc["paths"];
+    assert_eq!(paths["/clientes"]["post"]["operationId"], "Cadastra");
+    assert_eq!(paths["/clientes"]["get"]["operationId"], "Lista");
+    assert_eq!(paths["/clientes/{id}"]["get"]["operationId"], "Consulta");
+    assert_eq!(paths["/clientes/{id}"]["delete"]["operationId"], "Deleta");
+
+    let cliente = &doc["components"]["schemas"]["ClienteResponse"]["properties"];
+    assert_eq!(cliente["id"]["type"], "integer");
+    assert_eq!(cliente["nome"]["type"], "string");
+    assert_eq!(cliente["bloqueado"]["type"], "boolean");
+    assert_eq!(cliente["saldoDevedor"]["type"], "string");
+}
+
+#[tokio::test]
+async fn recusa_requisicao_sem_token_valido() {
+    let router = router_com_acesso();
+
+    let (status, _, _) = requisita(&router, Method::GET, "/clientes/1", None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _, _) = requisita_com_token(
+        &router,
+        Some("token-errado"),
+        Method::GET,
+        "/clientes/1",
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // O documento OpenAPI não exige autenticação.
+    let (status, _, _) = requisita(&router, Method::GET, "/openapi.json", None).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn mascara_documento_sem_permissao() {
+    let router = router_com_acesso();
+    let novo = json!({ "nome": "Fulano", "pj": false, "docto": "123.456.789-00" });
+
+    let (status, _, criado) = requisita_com_token(
+        &router,
+        Some("token-atendente"),
+        Method::POST,
+        "/clientes",
+        Some(novo),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(criado["docto"], "***.456.789-**");
+
+    let caminho = format!("/clientes/{}", criado["id"]);
+    let (_, _, cliente) = requisita_com_token(
+        &router,
+        Some("token-atendente"),
+        Method::GET,
+        &caminho,
+        None,
+    )
+    .await;
+    assert_eq!(cliente["docto"], "***.456.789-**");
+
+    let (_, _, pagina) = requisita_com_token(
+        &router,
+        Some("token-atendente"),
+        Method::GET,
+        "/clientes",
+        None,
+    )
+    .await;
+    assert_eq!(pagina["clientes"][0]["docto"], "***.456.789-**");
+
+    let (_, _, cliente) =
+        requisita_com_token(&router, Some("token-admin"), Method::GET, &caminho, None).await;
+    assert_eq!(cliente["docto"], "123.456.789-00");
+}
@@ -0,0 +1,188 @@
+// sdk.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Testes de integração do cliente de alto nível. Como o cliente não depende
+//! do meio de armazenamento do servidor, apenas o repositório em memória é
+//! usado.
+
+// Os interceptadores do tonic retornam `Status` diretamente como erro.
+#![allow(clippy::result_large_err)]
+
+mod common;
+
+use common::Backend;
+use minerva_lite::controller::cliente::CLIENTE_PAGE_SIZE;
+use minerva_lite::sdk::{Erro, MinervaLite, NovoCliente, Retentativas};
+use minerva_lite::service;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::Server;
+use tonic::{Request, Status};
+
+/// Inicia o servidor em memória e conecta o cliente de alto nível a ele.
+async fn conecta() -> (common::Servidor, MinervaLite) {
+    let servidor = common::inicia(Backend::Memoria).await.unwrap();
+    let minerva = MinervaLite::conecta(servidor.addr.clone())
+        .await
+        .expect("Impossível conectar ao servidor");
+    (servidor, minerva)
+}
+
+/// Inicia o serviço base com um interceptador, retornando o endereço do
+/// servidor.
+async fn inicia_com_interceptador<F>(interceptador: F) -> String
+where
+    F: FnMut(Request<()>) -> Result<Request<()>, Status> + Clone + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = Server::builder()
+        .layer(tonic::service::interceptor(interceptador))
+        .add_service(service::base::make_service().await)
+        .serve_with_incoming(TcpListenerStream::new(listener));
+    tokio::spawn(server);
+
+    format!("http://{}", addr)
+}
+
+fn novo_cliente(nome: &str) -> NovoCliente {
+    NovoCliente {
+        nome: nome.to_string(),
+        pj: false,
+        docto: "000.000.000-00".to_string(),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn cadastra_e_consulta_cliente() {
+    let (_servidor, minerva) = conecta().await;
+
+    let cadastrado = minerva.cadastra(novo_cliente("Fulano")).await.unwrap();
+    let consultado = minerva.consulta(cadastrado.id).await.unwrap();
+    assert_eq!(cadastrado, consultado);
+    assert_eq!(consultado.nome, "Fulano");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn consulta_cliente_inexistente_retorna_erro_tipado() {
+    let (_servidor, minerva) = conecta().await;
+
+    let erro = minerva.consulta(42).await.unwrap_err();
+    assert!(matches!(erro, Erro::NaoEncontrado(_)), "{:?}", erro);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn lista_achata_paginas() {
+    let (mut servidor, minerva) = conecta().await;
+    let ids = servidor
+        .cadastra_varios(2 * CLIENTE_PAGE_SIZE as usize + 3)
+        .await;
+
+    let clientes = minerva.lista_todos().await.unwrap();
+    let recebidos: Vec<i32> = clientes.iter().map(|c| c.id).collect();
+    assert_eq!(recebidos, ids);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn deleta_cliente() {
+    let (_servidor, minerva) = conecta().await;
+
+    let cliente = minerva.cadastra(novo_cliente("Fulano")).await.unwrap();
+    minerva.deleta(cliente.id).await.unwrap();
+    assert!(minerva.lista_todos().await.unwrap().is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn conexao_recusada() {
+    // Reserva uma porta e a libera, para que ninguém esteja escutando nela.
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let erro = MinervaLite::conecta(format!("http://{}", addr))
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(erro, Erro::Conexao(_)), "{:?}", erro);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn envia_token_de_acesso() {
+    let addr = inicia_com_interceptador(|req: Request<()>| {
+        match req.metadata().get("authorization") {
+            Some(token) if token == "Bearer segredo" => Ok(req),
+            _ => Err(Status::unauthenticated("Token ausente")),
+        }
+    })
+    .await;
+
+    let sem_token = MinervaLite::conecta(addr.clone()).await.unwrap();
+    let erro = sem_token.ping().await.unwrap_err();
+    assert!(matches!(erro, Erro::NaoAutenticado(_)), "{:?}", erro);
+
+    let com_token = MinervaLite::builder(addr)
+        .token("segredo")
+        .conecta()
+        .await
+        .unwrap();
+    com_token.ping().await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn repete_requisicoes_recusadas_por_excesso_de_carga() {
+    // O servidor recusa as duas primeiras requisições.
+    let chamadas = Arc::new(AtomicUsize::new(0));
+    let contador = chamadas.clone();
+    let addr = inicia_com_interceptador(move |req: Request<()>| {
+        if contador.fetch_add(1, Ordering::SeqCst) < 2 {
+            Err(Status::resource_exhausted("Limite excedido"))
+        } else {
+            Ok(req)
+        }
+    })
+    .await;
+
+    let retentativas = Retentativas {
+        maximo: 2,
+        espera_inicial: Duration::from_millis(1),
+        espera_maxima: Duration::from_millis(10),
+    };
+
+    let minerva = MinervaLite::builder(addr.clone())
+        .retentativas(retentativas)
+        .conecta()
+        .await
+        .unwrap();
+    minerva.ping().await.unwrap();
+    assert_eq!(chamadas.load(Ordering::SeqCst), 3);
+
+    // Sem novas tentativas, o erro é entregue imediatamente.
+    chamadas.store(0, Ordering::SeqCst);
+    let minerva = MinervaLite::builder(addr)
+        .retentativas(Retentativas::nenhuma())
+        .conecta()
+        .await
+        .unwrap();
+    let erro = minerva.ping().await.unwrap_err();
+    assert!(matches!(erro, Erro::Esgotado(_)), "{:?}", erro);
+    assert_eq!(chamadas.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,185 @@
+// telemetria.rs -- Uma parte de Minerva.Lite
+// Copyright (C) 2022 Lucas S. Vieira
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Testes de integração da exportação de traces via OpenTelemetry. Um
+//! receptor OTLP falso recebe os spans exportados pelo servidor, no lugar de
+//! um coletor.
+//!
+//! Como o subscriber de logs é global, este arquivo deve conter apenas um
+//! teste.
+
+#![cfg(feature = "otel")]
+
+use futures::future::{self, BoxFuture, Ready};
+use hyper::Body;
+use minerva_lite::minerva_client::MinervaClient;
+use minerva_lite::service;
+use prost::Message;
+use std::convert::Infallible;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::body::BoxBody;
+use tonic::codec::ProstCodec;
+use tonic::server::{Grpc, UnaryService};
+use tonic::transport::{NamedService, Server};
+use tonic::{Request, Response, Status};
+use tower::Service;
+
+/// Contexto de trace W3C enviado na requisição.
+const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+const PARENT_ID: &str = "00f067aa0ba902b7";
+
+// Subconjunto das mensagens do serviço de traces do OTLP
+// (`opentelemetry/proto/collector/trace/v1/trace_service.proto`). Os campos
+// não declarados são ignorados na decodificação.
+
+#[derive(Clone, PartialEq, Message)]
+struct ExportTraceServiceRequest {
+    #[prost(message, repeated, tag = "1")]
+    resource_spans: Vec<ResourceSpans>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct ExportTraceServiceResponse {}
+
+#[derive(Clone, PartialEq, Message)]
+struct ResourceSpans {
+    #[prost(message, repeated, tag = "2")]
+    instrumentation_library_spans: Vec<InstrumentationLibrarySpans>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct InstrumentationLibrarySpans {
+    #[prost(message, repeated, tag = "2")]
+    spans: Vec<Span>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct Span {
+    #[prost(bytes = "vec", tag = "1")]
+    trace_id: Vec<u8>,
+    #[prost(bytes = "vec", tag = "4")]
+    parent_span_id: Vec<u8>,
+    #[prost(string, tag = "5")]
+    name: String,
+}
+
+/// Receptor OTLP que encaminha os spans recebidos para um canal.
+#[derive(Clone)]
+struct Receptor(mpsc::UnboundedSender<Span>);
+
+impl NamedService for Receptor {
+    const NAME: &'static str = "opentelemetry.proto.collector.trace.v1.TraceService";
+}
+
+impl UnaryService<ExportTraceServiceRequest> for Receptor {
+    type Response = ExportTraceServiceResponse;
+    type Future = Ready<Result<Response<Self::Response>, Status>>;
+
+    fn call(&mut self, req: Request<ExportTraceServiceRequest>) -> Self::Future {
+        let spans = req
+            .into_inner()
+            .resource_spans
+            .into_iter()
+            .flat_map(|r| r.instrumentation_library_spans)
+            .flat_map(|l| l.spans);
+        for span in spans {
+            let _ = self.0.send(span);
+        }
+        future::ready(Ok(Response::new(ExportTraceServiceResponse {})))
+    }
+}
+
+impl Service<hyper::Request<Body>> for Receptor {
+    type Response = hyper::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: hyper::Request<Body>) -> Self::Future {
+        let receptor = self.clone();
+        Box::pin(async move {
+            let mut grpc = Grpc::new(ProstCodec::default());
+            Ok(grpc.unary(receptor, req).await)
+        })
+    }
+}
+
+/// Inicia um servidor na porta efêmera, retornando seu endereço.
+async fn escuta() -> (TcpListenerStream, String) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = format!("http://{}", listener.local_addr().unwrap());
+    (TcpListenerStream::new(listener), addr)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn exporta_span_com_contexto_propagado() {
+    let (incoming, coletor) = escuta().await;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    tokio::spawn(
+        Server::builder()
+            .add_service(Receptor(tx))
+            .serve_with_incoming(incoming),
+    );
+
+    std::env::set_var("OTEL_EXPORTER_OTLP_ENDPOINT", &coletor);
+    service::logging::init();
+
+    let (incoming, addr) = escuta().await;
+    tokio::spawn(
+        Server::builder()
+            .layer(service::logging::RequestTraceLayer)
+            .add_service(service::base::make_service().await)
+            .serve_with_incoming(incoming),
+    );
+
+    let mut client = MinervaClient::connect(addr).await.unwrap();
+    let mut req = Request::new(());
+    req.metadata_mut().insert(
+        "traceparent",
+        format!("00-{}-{}-01", TRACE_ID, PARENT_ID).parse().unwrap(),
+    );
+    client.ping(req).await.unwrap();
+
+    // O encerramento envia os spans pendentes ao coletor.
+    tokio::task::spawn_blocking(service::logging::shutdown)
+        .await
+        .unwrap();
+
+    let span = tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            let span = rx.recv().await.expect("Nenhum span exportado");
+            if span.name == "rpc" {
+                return span;
+            }
+        }
+    })
+    .await
+    .expect("Span da requisição não exportado");
+
+    assert_eq!(hex(&span.trace_id), TRACE_ID);
+    assert_eq!(hex(&span.parent_span_id), PARENT_ID);
+}